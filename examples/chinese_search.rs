@@ -33,7 +33,8 @@ async fn main() -> anyhow::Result<()> {
 
     println!(
         "Found {} results in {}ms",
-        results.count, results.duration_ms
+        results.len(),
+        results.duration_ms
     );
     println!();
 
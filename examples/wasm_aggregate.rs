@@ -0,0 +1,47 @@
+//! Example: Merging engine results with the WASM-compatible core.
+//!
+//! Uses only the types that compile for `wasm32-unknown-unknown`, e.g. in a
+//! frontend that fetches per-engine results itself and ranks them locally:
+//!
+//! ```text
+//! cargo build --example wasm_aggregate --target wasm32-unknown-unknown \
+//!     --no-default-features --features wasm
+//! ```
+
+use a3s_search::{Aggregator, SearchQuery, SearchResult};
+
+fn main() {
+    let query = SearchQuery::new("rust programming").with_page(1);
+    println!("Query: {} (cache key {})", query.query, query.cache_key());
+
+    // Per-engine results, as a frontend might have collected them
+    let duckduckgo = vec![
+        SearchResult::new(
+            "https://www.rust-lang.org/",
+            "Rust Programming Language",
+            "A language empowering everyone to build reliable software.",
+        ),
+        SearchResult::new(
+            "https://doc.rust-lang.org/book/",
+            "The Rust Programming Language - The Rust Book",
+            "An introductory book about Rust.",
+        ),
+    ];
+    let wikipedia = vec![SearchResult::new(
+        "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+        "Rust (programming language) - Wikipedia",
+        "Rust is a general-purpose programming language.",
+    )];
+
+    let mut aggregator = Aggregator::new();
+    aggregator.set_engine_weight("wikipedia", 1.2);
+    let results = aggregator.aggregate(vec![
+        ("duckduckgo".to_string(), duckduckgo),
+        ("wikipedia".to_string(), wikipedia),
+    ]);
+
+    for (i, result) in results.items().iter().enumerate() {
+        println!("{}. {} ({:.2})", i + 1, result.title, result.score);
+        println!("   {}", result.url);
+    }
+}
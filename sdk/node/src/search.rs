@@ -4,7 +4,7 @@ use std::time::Duration;
 use napi::Result;
 use napi_derive::napi;
 
-use a3s_search::engines::{Brave, DuckDuckGo, So360, Sogou, Wikipedia};
+use a3s_search::engines::{Brave, DuckDuckGo, Marginalia, So360, Sogou, Wikipedia};
 use a3s_search::{HttpFetcher, Search, SearchQuery};
 
 use crate::types::{JsEngineError, JsSearchOptions, JsSearchResponse, JsSearchResult};
@@ -13,7 +13,7 @@ use crate::util::to_napi_error;
 /// Native search engine binding.
 ///
 /// Wraps the a3s-search Rust library, providing direct access to
-/// DuckDuckGo, Brave, Wikipedia, Sogou, and 360 search engines.
+/// DuckDuckGo, Brave, Wikipedia, Marginalia, Sogou, and 360 search engines.
 #[napi]
 pub struct JsSearch {}
 
@@ -49,11 +49,13 @@ impl JsSearch {
         let mut search = Search::new();
         search.set_timeout(Duration::from_secs(timeout_secs));
 
-        let http_fetcher: Arc<dyn a3s_search::PageFetcher> = if let Some(ref proxy) = opts.proxy {
-            Arc::new(HttpFetcher::with_proxy(proxy).map_err(to_napi_error)?)
+        // One client shared by every HTTP engine, including Wikipedia
+        let shared_http = if let Some(ref proxy) = opts.proxy {
+            HttpFetcher::with_proxy(proxy).map_err(to_napi_error)?
         } else {
-            Arc::new(HttpFetcher::new())
+            HttpFetcher::new()
         };
+        let http_fetcher: Arc<dyn a3s_search::PageFetcher> = Arc::new(shared_http.clone());
 
         for shortcut in &engine_shortcuts {
             match shortcut.as_str() {
@@ -64,12 +66,10 @@ impl JsSearch {
                     search.add_engine(Brave::with_fetcher(Arc::clone(&http_fetcher)));
                 }
                 "wiki" | "wikipedia" => {
-                    let fetcher = if let Some(ref proxy) = opts.proxy {
-                        HttpFetcher::with_proxy(proxy).map_err(to_napi_error)?
-                    } else {
-                        HttpFetcher::new()
-                    };
-                    search.add_engine(Wikipedia::with_http_fetcher(fetcher));
+                    search.add_engine(Wikipedia::with_http_fetcher(shared_http.clone()));
+                }
+                "mar" | "marginalia" => {
+                    search.add_engine(Marginalia::with_fetcher(Arc::clone(&http_fetcher)));
                 }
                 "sogou" => {
                     search.add_engine(Sogou::with_fetcher(Arc::clone(&http_fetcher)));
@@ -79,7 +79,7 @@ impl JsSearch {
                 }
                 unknown => {
                     return Err(to_napi_error(format!(
-                        "Unknown engine '{}'. Available: ddg, brave, wiki, sogou, 360",
+                        "Unknown engine '{}'. Available: ddg, brave, wiki, mar, sogou, 360",
                         unknown
                     )));
                 }
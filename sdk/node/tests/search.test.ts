@@ -362,6 +362,15 @@ describe("A3SSearch Integration", () => {
     expect(response).toBeDefined();
   });
 
+  it("should search with marginalia engine", async () => {
+    const search = new A3SSearch();
+    const response = await search.search("personal website", {
+      engines: ["mar"],
+    });
+
+    expect(response).toBeDefined();
+  });
+
   it("should search with sogou engine", async () => {
     const search = new A3SSearch();
     const response = await search.search("搜索引擎", {
@@ -3,7 +3,7 @@ use std::time::Duration;
 
 use pyo3::prelude::*;
 
-use a3s_search::engines::{Brave, DuckDuckGo, So360, Sogou, Wikipedia};
+use a3s_search::engines::{Brave, DuckDuckGo, Marginalia, So360, Sogou, Wikipedia};
 use a3s_search::{HttpFetcher, Search, SearchQuery};
 
 use crate::types::{PyEngineError, PySearchOptions, PySearchResponse, PySearchResult};
@@ -12,7 +12,7 @@ use crate::util::to_py_error;
 /// Native search engine binding.
 ///
 /// Wraps the a3s-search Rust library, providing direct access to
-/// DuckDuckGo, Brave, Wikipedia, Sogou, and 360 search engines.
+/// DuckDuckGo, Brave, Wikipedia, Marginalia, Sogou, and 360 search engines.
 #[pyclass]
 pub struct PySearch {}
 
@@ -50,29 +50,27 @@ impl PySearch {
             let mut search = Search::new();
             search.set_timeout(Duration::from_secs(timeout_secs));
 
-            let http_fetcher: Arc<dyn a3s_search::PageFetcher> =
-                if let Some(ref proxy) = opts.proxy {
-                    Arc::new(HttpFetcher::with_proxy(proxy).map_err(to_py_error)?)
-                } else {
-                    Arc::new(HttpFetcher::new())
-                };
+            // One client shared by every HTTP engine, including Wikipedia
+            let shared_http = if let Some(ref proxy) = opts.proxy {
+                HttpFetcher::with_proxy(proxy).map_err(to_py_error)?
+            } else {
+                HttpFetcher::new()
+            };
+            let http_fetcher: Arc<dyn a3s_search::PageFetcher> = Arc::new(shared_http.clone());
 
             for shortcut in &engine_shortcuts {
                 match shortcut.as_str() {
                     "ddg" | "duckduckgo" => {
-                        search
-                            .add_engine(DuckDuckGo::with_fetcher(Arc::clone(&http_fetcher)));
+                        search.add_engine(DuckDuckGo::with_fetcher(Arc::clone(&http_fetcher)));
                     }
                     "brave" => {
                         search.add_engine(Brave::with_fetcher(Arc::clone(&http_fetcher)));
                     }
                     "wiki" | "wikipedia" => {
-                        let fetcher = if let Some(ref proxy) = opts.proxy {
-                            HttpFetcher::with_proxy(proxy).map_err(to_py_error)?
-                        } else {
-                            HttpFetcher::new()
-                        };
-                        search.add_engine(Wikipedia::with_http_fetcher(fetcher));
+                        search.add_engine(Wikipedia::with_http_fetcher(shared_http.clone()));
+                    }
+                    "mar" | "marginalia" => {
+                        search.add_engine(Marginalia::with_fetcher(Arc::clone(&http_fetcher)));
                     }
                     "sogou" => {
                         search.add_engine(Sogou::with_fetcher(Arc::clone(&http_fetcher)));
@@ -82,7 +80,7 @@ impl PySearch {
                     }
                     unknown => {
                         return Err(to_py_error(format!(
-                            "Unknown engine '{}'. Available: ddg, brave, wiki, sogou, 360",
+                            "Unknown engine '{}'. Available: ddg, brave, wiki, mar, sogou, 360",
                             unknown
                         )));
                     }
@@ -356,6 +356,12 @@ class TestA3SSearchIntegration:
         response = await search.search("open source", engines=["brave"])
         assert response is not None
 
+    @pytest.mark.asyncio
+    async def test_search_marginalia(self):
+        search = A3SSearch()
+        response = await search.search("personal website", engines=["mar"])
+        assert response is not None
+
     @pytest.mark.asyncio
     async def test_search_sogou(self):
         search = A3SSearch()
@@ -2,7 +2,8 @@
 
 use std::collections::HashMap;
 
-use crate::{SearchResult, SearchResults};
+use crate::result::{clean_title, url_has_scheme, DEFAULT_RESULT_SCHEMES};
+use crate::{ResultType, SearchResult, SearchResults};
 
 /// Result priority for ranking.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -14,11 +15,57 @@ pub enum ResultPriority {
     Low,
 }
 
+/// How much a result's score grows with the number of engines that found it.
+///
+/// The factor is 1 for a result found by a single engine in every mode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConsensusMode {
+    /// Multiply by the engine count `n`, as SearXNG does.
+    #[default]
+    Linear,
+    /// Multiply by `√n`.
+    Sqrt,
+    /// Multiply by `1 + ln n`.
+    Log,
+}
+
+impl ConsensusMode {
+    /// Returns the score factor of a result found by `engines` engines.
+    pub fn factor(&self, engines: usize) -> f64 {
+        let n = engines.max(1) as f64;
+        match self {
+            ConsensusMode::Linear => n,
+            ConsensusMode::Sqrt => n.sqrt(),
+            ConsensusMode::Log => 1.0 + n.ln(),
+        }
+    }
+}
+
 /// Aggregates and ranks search results from multiple engines.
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Aggregator {
     /// Engine weights for scoring.
     engine_weights: HashMap<String, f64>,
+    /// URL schemes results must use; empty allows any URL.
+    allowed_schemes: Vec<String>,
+    /// Whether titles lose a trailing site-name segment.
+    strip_site_names: bool,
+    /// How multi-engine agreement boosts scores.
+    consensus: ConsensusMode,
+}
+
+impl Default for Aggregator {
+    fn default() -> Self {
+        Self {
+            engine_weights: HashMap::new(),
+            allowed_schemes: DEFAULT_RESULT_SCHEMES
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            strip_site_names: false,
+            consensus: ConsensusMode::default(),
+        }
+    }
 }
 
 impl Aggregator {
@@ -27,6 +74,46 @@ impl Aggregator {
         Self::default()
     }
 
+    /// Sets the URL schemes results must use (default: `http` and `https`).
+    ///
+    /// Results with relative URLs or other schemes, such as `javascript:` or
+    /// `mailto:` links, are dropped. An empty list keeps every result.
+    pub fn set_allowed_schemes(&mut self, schemes: &[&str]) {
+        self.allowed_schemes = schemes.iter().map(|s| s.to_string()).collect();
+    }
+
+    /// Strips trailing site names such as " - Wikipedia" from titles
+    /// (default: disabled).
+    ///
+    /// Whitespace in titles is always collapsed and trimmed; this also
+    /// drops the segment after the last `|`, `-`, `—`, `–`, or `·`
+    /// separator, which helps when comparing or displaying titles from
+    /// engines that keep it.
+    pub fn set_strip_site_names(&mut self, strip: bool) {
+        self.strip_site_names = strip;
+    }
+
+    /// Sets how results found by several engines are boosted (default:
+    /// [`ConsensusMode::Linear`]).
+    ///
+    /// Linear boosting over-rewards results that many low-quality engines
+    /// agree on; `Sqrt` and `Log` give diminishing returns.
+    pub fn set_consensus(&mut self, mode: ConsensusMode) {
+        self.consensus = mode;
+    }
+
+    /// Returns the aggregator with the given consensus mode; see
+    /// [`set_consensus`](Self::set_consensus).
+    pub fn with_consensus(mut self, mode: ConsensusMode) -> Self {
+        self.set_consensus(mode);
+        self
+    }
+
+    /// Returns true if `result`'s URL passes the scheme allowlist.
+    fn is_allowed(&self, result: &SearchResult) -> bool {
+        self.allowed_schemes.is_empty() || url_has_scheme(&result.url, &self.allowed_schemes)
+    }
+
     /// Sets the weight for an engine.
     pub fn set_engine_weight(&mut self, engine: impl Into<String>, weight: f64) {
         self.engine_weights.insert(engine.into(), weight);
@@ -35,45 +122,82 @@ impl Aggregator {
     /// Aggregates results from multiple engines.
     ///
     /// This performs:
-    /// 1. Deduplication based on normalized URL
-    /// 2. Merging of duplicate results (combining engines and positions)
-    /// 3. Score calculation
-    /// 4. Sorting by score
+    /// 1. Title cleanup (see [`set_strip_site_names`](Self::set_strip_site_names))
+    /// 2. Deduplication based on normalized URL and result type
+    /// 3. Merging of duplicate results (combining engines and positions)
+    /// 4. Score calculation
+    /// 5. Sorting by score
     pub fn aggregate(&self, engine_results: Vec<(String, Vec<SearchResult>)>) -> SearchResults {
-        let mut url_map: HashMap<String, SearchResult> = HashMap::new();
+        // Results are keyed by type as well as URL so that, e.g., a video
+        // result is not folded into a web result linking to the same page.
+        let mut url_map: HashMap<(ResultType, String), SearchResult> = HashMap::new();
 
         for (engine_name, results) in engine_results {
             for (position, mut result) in results.into_iter().enumerate() {
-                let normalized = result.normalized_url();
+                // Positions count dropped results, as the engine ranked them
+                if !self.is_allowed(&result) {
+                    continue;
+                }
+                result.title = clean_title(&result.title, self.strip_site_names);
+                let key = (result.result_type, result.normalized_url());
                 let position = (position + 1) as u32;
 
-                if let Some(existing) = url_map.get_mut(&normalized) {
+                if let Some(existing) = url_map.get_mut(&key) {
                     self.merge_results(existing, result, &engine_name, position);
                 } else {
                     result.engines.insert(engine_name.clone());
                     result.positions.push(position);
-                    url_map.insert(normalized, result);
+                    url_map.insert(key, result);
                 }
             }
         }
 
-        let mut results: Vec<SearchResult> = url_map.into_values().collect();
+        let results = self.rank(url_map.into_values().collect());
+
+        let mut search_results = SearchResults::new();
+        for result in results {
+            search_results.add_result(result);
+        }
+        search_results
+    }
+
+    /// Re-aggregates results that were already aggregated, e.g. the combined
+    /// output of two separate searches.
+    ///
+    /// Duplicates are merged by unioning their engines and positions, then
+    /// every result is rescored and the list re-sorted.
+    pub(crate) fn reaggregate(&self, results: Vec<SearchResult>) -> Vec<SearchResult> {
+        let mut url_map: HashMap<(ResultType, String), SearchResult> = HashMap::new();
+
+        for result in results.into_iter().filter(|r| self.is_allowed(r)) {
+            let key = (result.result_type, result.normalized_url());
+            if let Some(existing) = url_map.get_mut(&key) {
+                existing.engines.extend(result.engines.iter().cloned());
+                existing.positions.extend(result.positions.iter().copied());
+                Self::merge_fields(existing, result);
+            } else {
+                url_map.insert(key, result);
+            }
+        }
+
+        self.rank(url_map.into_values().collect())
+    }
 
+    /// Scores results and sorts them by descending score.
+    fn rank(&self, mut results: Vec<SearchResult>) -> Vec<SearchResult> {
         for result in &mut results {
             result.score = self.calculate_score(result, ResultPriority::Normal);
         }
 
+        // Featured snippets stay on top whatever their score
         results.sort_by(|a, b| {
-            b.score
-                .partial_cmp(&a.score)
-                .unwrap_or(std::cmp::Ordering::Equal)
+            b.is_featured.cmp(&a.is_featured).then_with(|| {
+                b.score
+                    .partial_cmp(&a.score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
         });
-
-        let mut search_results = SearchResults::new();
-        for result in results {
-            search_results.add_result(result);
-        }
-        search_results
+        results
     }
 
     /// Merges a new result into an existing one.
@@ -86,7 +210,13 @@ impl Aggregator {
     ) {
         existing.engines.insert(engine.to_string());
         existing.positions.push(position);
+        Self::merge_fields(existing, new);
+    }
 
+    /// Keeps the richer title, content, thumbnail, and date of two duplicates.
+    /// The merged result is featured if either one was.
+    fn merge_fields(existing: &mut SearchResult, new: SearchResult) {
+        existing.is_featured |= new.is_featured;
         if new.title.len() > existing.title.len() {
             existing.title = new.title;
         }
@@ -105,7 +235,8 @@ impl Aggregator {
     ///
     /// The scoring algorithm is based on SearXNG:
     /// - Weight is multiplied by engine weights
-    /// - Weight is multiplied by number of engines that found the result
+    /// - Weight is multiplied by the consensus factor of the number of
+    ///   engines that found the result (see [`ConsensusMode`])
     /// - Score is sum of (weight / position) for each position
     fn calculate_score(&self, result: &SearchResult, priority: ResultPriority) -> f64 {
         let mut weight = 1.0;
@@ -114,7 +245,7 @@ impl Aggregator {
             weight *= self.engine_weights.get(engine).copied().unwrap_or(1.0);
         }
 
-        weight *= result.engines.len() as f64;
+        weight *= self.consensus.factor(result.engines.len());
 
         let mut score = 0.0;
         for &position in &result.positions {
@@ -159,7 +290,7 @@ mod tests {
         let aggregator = Aggregator::new();
         let engine_results: Vec<(String, Vec<SearchResult>)> = vec![];
         let aggregated = aggregator.aggregate(engine_results);
-        assert_eq!(aggregated.count, 0);
+        assert_eq!(aggregated.len(), 0);
     }
 
     #[test]
@@ -168,7 +299,7 @@ mod tests {
         let results = vec![SearchResult::new("https://example.com", "Title", "Content")];
         let engine_results = vec![("engine1".to_string(), results)];
         let aggregated = aggregator.aggregate(engine_results);
-        assert_eq!(aggregated.count, 1);
+        assert_eq!(aggregated.len(), 1);
         assert!(aggregated.items()[0].engines.contains("engine1"));
     }
 
@@ -193,7 +324,7 @@ mod tests {
 
         let aggregated = aggregator.aggregate(engine_results);
 
-        assert_eq!(aggregated.items().len(), 2);
+        assert_eq!(aggregated.len(), 2);
 
         let example_result = aggregated
             .items()
@@ -206,6 +337,50 @@ mod tests {
         assert_eq!(example_result.title, "Title 2 Longer");
     }
 
+    #[test]
+    fn test_aggregate_keeps_videos_distinct() {
+        let aggregator = Aggregator::new();
+
+        let web = vec![SearchResult::new(
+            "https://www.youtube.com/watch?v=abc",
+            "Video page",
+            "Web result",
+        )];
+        let videos1 =
+            vec![
+                SearchResult::new("https://www.youtube.com/watch?v=abc", "Video", "[3:15]")
+                    .with_type(ResultType::Video),
+            ];
+        let videos2 =
+            vec![
+                SearchResult::new("http://www.youtube.com/watch?v=abc/", "Video", "[3:15]")
+                    .with_type(ResultType::Video),
+            ];
+
+        let engine_results = vec![
+            ("web_engine".to_string(), web),
+            ("video1".to_string(), videos1),
+            ("video2".to_string(), videos2),
+        ];
+
+        let aggregated = aggregator.aggregate(engine_results);
+
+        // Videos merge with each other but not with the web result
+        assert_eq!(aggregated.len(), 2);
+        let video = aggregated
+            .items()
+            .iter()
+            .find(|r| r.result_type == ResultType::Video)
+            .unwrap();
+        assert_eq!(video.engines.len(), 2);
+        let web = aggregated
+            .items()
+            .iter()
+            .find(|r| r.result_type == ResultType::Web)
+            .unwrap();
+        assert_eq!(web.engines.len(), 1);
+    }
+
     #[test]
     fn test_aggregate_merges_longer_content() {
         let aggregator = Aggregator::new();
@@ -294,6 +469,117 @@ mod tests {
         assert_eq!(result.engines.len(), 2);
     }
 
+    /// Returns the scores of a result found by one engine and of one found
+    /// by four engines, all at position 1.
+    fn consensus_scores(aggregator: &Aggregator) -> (f64, f64) {
+        let engine_results = ["a", "b", "c", "d"]
+            .iter()
+            .map(|engine| {
+                let mut results = vec![SearchResult::new("https://shared.com", "Shared", "")];
+                if *engine == "a" {
+                    results.insert(0, SearchResult::new("https://single.com", "Single", ""));
+                }
+                (engine.to_string(), results)
+            })
+            .collect();
+        let aggregated = aggregator.aggregate(engine_results);
+        let score = |url: &str| aggregated.iter().find(|r| r.url == url).unwrap().score;
+        // "shared" is at position 2 for engine a and 1 for the others
+        (score("https://single.com"), score("https://shared.com"))
+    }
+
+    #[test]
+    fn test_consensus_modes() {
+        // Position sum for "shared": 1/2 + 1 + 1 + 1
+        let positions = 3.5;
+        for (mode, factor) in [
+            (ConsensusMode::Linear, 4.0),
+            (ConsensusMode::Sqrt, 2.0),
+            (ConsensusMode::Log, 1.0 + 4f64.ln()),
+        ] {
+            let aggregator = Aggregator::new().with_consensus(mode);
+            let (single, shared) = consensus_scores(&aggregator);
+            assert_eq!(single, 1.0, "{:?}", mode);
+            assert!(
+                (shared - factor * positions).abs() < 1e-9,
+                "{:?}: {}",
+                mode,
+                shared
+            );
+        }
+    }
+
+    #[test]
+    fn test_consensus_default_is_linear() {
+        assert_eq!(ConsensusMode::default(), ConsensusMode::Linear);
+        assert_eq!(
+            consensus_scores(&Aggregator::new()),
+            consensus_scores(&Aggregator::new().with_consensus(ConsensusMode::Linear))
+        );
+    }
+
+    #[test]
+    fn test_consensus_factor() {
+        for mode in [
+            ConsensusMode::Linear,
+            ConsensusMode::Sqrt,
+            ConsensusMode::Log,
+        ] {
+            assert_eq!(mode.factor(1), 1.0);
+            assert_eq!(mode.factor(0), 1.0);
+            assert!(mode.factor(2) < mode.factor(3));
+        }
+        assert_eq!(ConsensusMode::Linear.factor(9), 9.0);
+        assert_eq!(ConsensusMode::Sqrt.factor(9), 3.0);
+        assert!((ConsensusMode::Log.factor(9) - (1.0 + 9f64.ln())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_featured_results_rank_first() {
+        let aggregator = Aggregator::new();
+
+        let results1 = vec![
+            SearchResult::new("https://top.com", "Top", "Organic"),
+            SearchResult::new("https://both.com", "Both", "Organic"),
+        ];
+        let results2 = vec![
+            SearchResult::new("https://both.com", "Both", "Organic"),
+            SearchResult::new("https://answer.com", "Answer", "Featured").with_featured(true),
+        ];
+        let results3 = vec![SearchResult::new("https://both.com", "Both", "Organic")];
+
+        let aggregated = aggregator.aggregate(vec![
+            ("engine1".to_string(), results1),
+            ("engine2".to_string(), results2),
+            ("engine3".to_string(), results3),
+        ]);
+
+        let urls: Vec<&str> = aggregated.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec!["https://answer.com", "https://both.com", "https://top.com"]
+        );
+        assert!(aggregated.items()[0].score < aggregated.items()[1].score);
+        assert!(aggregated.items()[0].is_featured);
+        assert!(!aggregated.items()[1].is_featured);
+    }
+
+    #[test]
+    fn test_aggregate_merges_featured_flag() {
+        let aggregator = Aggregator::new();
+
+        let results1 = vec![SearchResult::new("https://example.com", "Title", "Content")];
+        let results2 =
+            vec![SearchResult::new("https://example.com", "Title", "Content").with_featured(true)];
+
+        let aggregated = aggregator.aggregate(vec![
+            ("engine1".to_string(), results1),
+            ("engine2".to_string(), results2),
+        ]);
+
+        assert!(aggregated.items()[0].is_featured);
+    }
+
     #[test]
     fn test_results_sorted_by_score() {
         let mut aggregator = Aggregator::new();
@@ -484,4 +770,65 @@ mod tests {
         let aggregated = aggregator.aggregate(engine_results);
         assert_eq!(aggregated.items()[0].title, "Much Longer Title");
     }
+
+    #[test]
+    fn test_aggregate_cleans_titles() {
+        let aggregator = Aggregator::new();
+        let results = aggregator.aggregate(vec![(
+            "engine".to_string(),
+            vec![SearchResult::new(
+                "https://example.com",
+                "\n  Example\n   Domain - Example  ",
+                "",
+            )],
+        )]);
+        assert_eq!(results.items()[0].title, "Example Domain - Example");
+
+        let mut aggregator = Aggregator::new();
+        aggregator.set_strip_site_names(true);
+        let results = aggregator.aggregate(vec![(
+            "engine".to_string(),
+            vec![SearchResult::new(
+                "https://example.com",
+                "Example Domain | Example",
+                "",
+            )],
+        )]);
+        assert_eq!(results.items()[0].title, "Example Domain");
+    }
+
+    #[test]
+    fn test_aggregate_drops_disallowed_schemes() {
+        let aggregator = Aggregator::new();
+        let results = vec![
+            SearchResult::new("javascript:void(0)", "Script", ""),
+            SearchResult::new("mailto:someone@example.com", "Mail", ""),
+            SearchResult::new("/relative", "Relative", ""),
+            SearchResult::new("https://example.com/", "Web", ""),
+        ];
+
+        let aggregated = aggregator.aggregate(vec![("engine".to_string(), results)]);
+
+        assert_eq!(aggregated.len(), 1);
+        assert_eq!(aggregated.items()[0].url, "https://example.com/");
+        // The engine ranked it fourth
+        assert_eq!(aggregated.items()[0].positions, vec![4]);
+    }
+
+    #[test]
+    fn test_aggregate_custom_schemes() {
+        let mut aggregator = Aggregator::new();
+        aggregator.set_allowed_schemes(&["https", "ftp"]);
+        let results = vec![
+            SearchResult::new("ftp://files.example.com/a", "Files", ""),
+            SearchResult::new("http://example.com/", "Plain", ""),
+        ];
+        let aggregated = aggregator.aggregate(vec![("engine".to_string(), results.clone())]);
+        assert_eq!(aggregated.len(), 1);
+        assert_eq!(aggregated.items()[0].url, "ftp://files.example.com/a");
+
+        aggregator.set_allowed_schemes(&[]);
+        let aggregated = aggregator.aggregate(vec![("engine".to_string(), results)]);
+        assert_eq!(aggregated.len(), 2);
+    }
 }
@@ -4,10 +4,17 @@
 //! detect an existing Chrome/Chromium installation or automatically download
 //! Chrome for Testing from Google's official CDN.
 //!
-//! Downloaded binaries are cached in `~/.a3s/chromium/<version>/`.
+//! Downloaded binaries are cached in `<cache dir>/<version>/`; see
+//! [`set_cache_dir`] for how the cache directory is chosen.
 
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
 
+use futures::StreamExt;
+use sha2::{Digest, Sha256};
 use tracing::{debug, info, warn};
 
 use crate::{Result, SearchError};
@@ -16,6 +23,73 @@ use crate::{Result, SearchError};
 const CHROME_VERSIONS_URL: &str =
     "https://googlechromelabs.github.io/chrome-for-testing/last-known-good-versions-with-downloads.json";
 
+/// Base URL of the Chrome for Testing downloads, by version and platform.
+const CHROME_DOWNLOAD_BASE: &str = "https://storage.googleapis.com/chrome-for-testing-public";
+
+/// Default mirror of Chromium snapshots for Linux arm64, for which Chrome
+/// for Testing has no builds.
+const CHROMIUM_ARM64_MIRROR: &str =
+    "https://commondatastorage.googleapis.com/chromium-browser-snapshots/Linux_ARM";
+
+/// Environment variable overriding [`CHROMIUM_ARM64_MIRROR`]. A mirror
+/// serves the latest revision number at `<mirror>/LAST_CHANGE` and each
+/// build at `<mirror>/<revision>/chrome-linux.zip`.
+pub const CHROMIUM_MIRROR_ENV: &str = "A3S_CHROMIUM_MIRROR";
+
+/// Platform identifier of Linux arm64, served from a Chromium snapshot
+/// mirror instead of Chrome for Testing.
+const LINUX_ARM64: &str = "linux-arm64";
+
+/// File in a version directory holding the SHA-256 of the zip it was
+/// extracted from.
+const CHECKSUM_FILE: &str = ".zip-sha256";
+
+/// Lock file in the cache directory, held by the process that downloads
+/// Chrome so concurrent processes don't install into the same directory.
+const LOCK_FILE: &str = ".lock";
+
+/// Serializes downloads within this process, since [`LOCK_FILE`] is held
+/// per process.
+static DOWNLOAD_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// Stage of a Chrome installation reported through [`DownloadProgress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadPhase {
+    /// Downloading the zip; progress counts bytes.
+    Downloading,
+    /// Extracting the zip; progress counts files.
+    Extracting,
+}
+
+/// Progress of a Chrome installation, reported by
+/// [`ensure_chrome_with_progress`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadProgress {
+    /// Current stage.
+    pub phase: DownloadPhase,
+    /// Bytes downloaded, or files extracted, so far.
+    pub downloaded: u64,
+    /// Size of the download if the server sent it, or the number of files
+    /// in the archive.
+    pub total: Option<u64>,
+}
+
+impl DownloadProgress {
+    /// Returns the completed share of the current stage, from 0 to 100.
+    pub fn percent(&self) -> Option<f64> {
+        self.total
+            .filter(|&total| total > 0)
+            .map(|total| (self.downloaded as f64 / total as f64 * 100.0).min(100.0))
+    }
+}
+
+/// Callback receiving [`DownloadProgress`] updates.
+type Progress<'a> = &'a (dyn Fn(DownloadProgress) + Send + Sync);
+
+/// Environment variable pinning the Chrome for Testing version that
+/// [`ensure_chrome`] uses.
+pub const CHROME_VERSION_ENV: &str = "CHROME_VERSION";
+
 /// Well-known Chrome/Chromium executable paths per platform.
 #[cfg(target_os = "macos")]
 const KNOWN_PATHS: &[&str] = &[
@@ -34,6 +108,12 @@ const KNOWN_PATHS: &[&str] = &[
     "/usr/bin/chromium",
     "/usr/bin/chromium-browser",
     "/snap/bin/chromium",
+    // Distribution packages, common on arm64 (Debian, Alpine, Ubuntu and
+    // Raspberry Pi OS, Fedora)
+    "/usr/lib/chromium/chromium",
+    "/usr/lib/chromium/chrome",
+    "/usr/lib/chromium-browser/chromium-browser",
+    "/usr/lib64/chromium-browser/chromium-browser",
 ];
 
 /// Well-known command names to search in PATH.
@@ -59,18 +139,37 @@ fn platform_id() -> Result<&'static str> {
     {
         Ok("linux64")
     }
+    #[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+    {
+        Ok(LINUX_ARM64)
+    }
     #[cfg(not(any(
         all(target_os = "macos", target_arch = "aarch64"),
         all(target_os = "macos", target_arch = "x86_64"),
         all(target_os = "linux", target_arch = "x86_64"),
+        all(target_os = "linux", target_arch = "aarch64"),
     )))]
     {
-        Err(SearchError::Browser(
-            "Unsupported platform for automatic Chrome download".to_string(),
+        Err(no_chrome_error(
+            "automatic download is not supported on this platform",
         ))
     }
 }
 
+/// Start of the message of [`no_chrome_error`].
+const NO_CHROME: &str = "No usable Chrome found";
+
+/// Error for when no Chrome could be found or downloaded, explaining how
+/// to point to one manually.
+fn no_chrome_error(reason: impl std::fmt::Display) -> SearchError {
+    SearchError::Browser(format!(
+        "{} ({}). Install Chrome or Chromium (e.g. \
+         `apt install chromium`), or set the CHROME environment variable to \
+         its executable, e.g. CHROME=/usr/bin/chromium",
+        NO_CHROME, reason
+    ))
+}
+
 /// Returns the relative path to the Chrome executable inside the extracted zip.
 #[cfg(target_os = "macos")]
 fn chrome_executable_in_zip(platform: &str) -> String {
@@ -82,20 +181,93 @@ fn chrome_executable_in_zip(platform: &str) -> String {
 
 #[cfg(all(unix, not(target_os = "macos")))]
 fn chrome_executable_in_zip(platform: &str) -> String {
+    // Chromium snapshots are not named after the platform
+    if platform == LINUX_ARM64 {
+        return "chrome-linux/chrome".to_string();
+    }
     format!("chrome-{}/chrome", platform)
 }
 
+/// Environment variable that, set to `1` (or `true`), forbids downloading
+/// Chrome: only a system installation or the cache is used.
+pub const NO_DOWNLOAD_ENV: &str = "A3S_NO_DOWNLOAD";
+
+/// Returns whether [`NO_DOWNLOAD_ENV`] forbids downloads.
+fn downloads_disabled() -> bool {
+    std::env::var(NO_DOWNLOAD_ENV)
+        .is_ok_and(|value| matches!(value.trim(), "1") || value.trim().eq_ignore_ascii_case("true"))
+}
+
+/// Fails if [`NO_DOWNLOAD_ENV`] forbids downloads. Checked before any
+/// request is made.
+fn check_downloads_allowed() -> Result<()> {
+    if downloads_disabled() {
+        return Err(no_chrome_error(format!(
+            "downloads are disabled by {}",
+            NO_DOWNLOAD_ENV
+        )));
+    }
+    Ok(())
+}
+
+/// Attempts at a download, set with [`set_download_attempts`].
+static DOWNLOAD_ATTEMPTS: AtomicU32 = AtomicU32::new(4);
+
+/// Delay before the first retry of a failed download, doubling after each
+/// further failure up to [`MAX_RETRY_DELAY`].
+const INITIAL_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest delay between two download attempts.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Sets how many times a Chrome download is attempted before giving up
+/// (default: 4, at least 1).
+///
+/// A failed attempt is retried after an exponential backoff and resumes
+/// where it stopped if the server supports range requests.
+pub fn set_download_attempts(attempts: u32) {
+    DOWNLOAD_ATTEMPTS.store(attempts.max(1), Ordering::Relaxed);
+}
+
+/// Environment variable naming the directory for Chrome downloads.
+pub const CHROME_DIR_ENV: &str = "A3S_CHROME_DIR";
+
+/// Cache directory set with [`set_cache_dir`].
+static CACHE_DIR: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// Sets the directory where Chrome for Testing is cached and downloaded,
+/// for the rest of the process.
+///
+/// Without it, the directory is, in order of precedence:
+/// 1. `A3S_CHROME_DIR` environment variable
+/// 2. `$XDG_CACHE_HOME/.a3s/chromium`
+/// 3. `$HOME/.a3s/chromium`
+pub fn set_cache_dir(path: impl Into<PathBuf>) {
+    *CACHE_DIR.write().unwrap_or_else(|e| e.into_inner()) = Some(path.into());
+}
+
 /// Base directory for cached Chrome downloads.
 fn cache_dir() -> Result<PathBuf> {
-    let home = dirs_path()?;
-    Ok(home.join(".a3s").join("chromium"))
+    if let Some(dir) = CACHE_DIR.read().unwrap_or_else(|e| e.into_inner()).clone() {
+        return Ok(dir);
+    }
+    cache_dir_from(|name| std::env::var_os(name).filter(|value| !value.is_empty()))
 }
 
-/// Returns the user's home directory.
-fn dirs_path() -> Result<PathBuf> {
-    std::env::var("HOME")
-        .map(PathBuf::from)
-        .map_err(|_| SearchError::Browser("Cannot determine home directory".to_string()))
+/// Resolves the cache directory from the environment variables `var` returns.
+fn cache_dir_from(var: impl Fn(&str) -> Option<std::ffi::OsString>) -> Result<PathBuf> {
+    if let Some(dir) = var(CHROME_DIR_ENV) {
+        return Ok(PathBuf::from(dir));
+    }
+    let base = var("XDG_CACHE_HOME")
+        .or_else(|| var("HOME"))
+        .ok_or_else(|| {
+            SearchError::Browser(format!(
+                "Cannot determine the Chrome cache directory; set {} or HOME",
+                CHROME_DIR_ENV
+            ))
+        })?;
+    Ok(PathBuf::from(base).join(".a3s").join("chromium"))
 }
 
 /// Detect an existing Chrome/Chromium installation on the system.
@@ -139,11 +311,69 @@ pub fn detect_chrome() -> Option<PathBuf> {
 /// Ensure Chrome is available, downloading it if necessary.
 ///
 /// 1. If Chrome is already installed on the system, returns its path.
-/// 2. If a cached download exists in `~/.a3s/chromium/`, returns that path.
+/// 2. If a cached download exists in the cache directory (see
+///    [`set_cache_dir`]), returns that path.
 /// 3. Otherwise, downloads Chrome for Testing and caches it.
 ///
+/// With the `CHROME_VERSION` environment variable set, returns that
+/// version of Chrome for Testing instead, as
+/// [`ensure_chrome_version`] does. With [`NO_DOWNLOAD_ENV`] set, nothing
+/// is downloaded, as with [`find_chrome`].
+///
 /// Returns the path to the Chrome executable.
 pub async fn ensure_chrome() -> Result<PathBuf> {
+    ensure_chrome_with_progress(|_| {}).await
+}
+
+/// Like [`ensure_chrome`], but reports the progress of a download and its
+/// extraction to `progress`, e.g. to draw a progress bar.
+pub async fn ensure_chrome_with_progress(
+    progress: impl Fn(DownloadProgress) + Send + Sync,
+) -> Result<PathBuf> {
+    ensure_chrome_from(CHROME_VERSIONS_URL, &progress).await
+}
+
+/// Find Chrome like [`ensure_chrome`] does, without ever downloading it:
+/// a system installation, or else a cached download (the `CHROME_VERSION`
+/// one if that is set).
+///
+/// This is what [`ensure_chrome`] does when [`NO_DOWNLOAD_ENV`] is set, and
+/// what a [`BrowserPool`](crate::BrowserPool) with
+/// [`auto_download`](crate::BrowserPoolConfig::auto_download) off uses.
+/// Fails with an error explaining how to install Chrome or set `CHROME`.
+pub fn find_chrome() -> Result<PathBuf> {
+    if let Some(version) = pinned_version() {
+        validate_version(&version)?;
+        return find_cached_chrome_version(&version)
+            .map_err(|_| no_chrome_error(format!("Chrome {} is not cached", version)));
+    }
+
+    if let Some(path) = detect_chrome() {
+        info!("Using system Chrome: {}", path.display());
+        return Ok(path);
+    }
+    find_cached_chrome()
+        .inspect(|path| info!("Using cached Chrome: {}", path.display()))
+        .map_err(|_| no_chrome_error("no system or cached Chrome, and downloads are disabled"))
+}
+
+/// The version set by [`CHROME_VERSION_ENV`], if any.
+fn pinned_version() -> Option<String> {
+    std::env::var(CHROME_VERSION_ENV)
+        .ok()
+        .filter(|v| !v.is_empty())
+}
+
+/// [`ensure_chrome_with_progress`], looking up the latest stable version
+/// at `versions_url`.
+async fn ensure_chrome_from(versions_url: &str, progress: Progress<'_>) -> Result<PathBuf> {
+    if downloads_disabled() {
+        return find_chrome();
+    }
+    if let Some(version) = pinned_version() {
+        return ensure_version(&version, progress).await;
+    }
+
     // 1. Check system installation
     if let Some(path) = detect_chrome() {
         info!("Using system Chrome: {}", path.display());
@@ -158,7 +388,196 @@ pub async fn ensure_chrome() -> Result<PathBuf> {
 
     // 3. Download Chrome for Testing
     info!("No Chrome installation found, downloading Chrome for Testing...");
-    download_chrome().await
+    locked_download(find_cached_chrome, download_chrome(versions_url, progress))
+        .await
+        .map_err(|e| match e {
+            SearchError::Browser(reason) if reason.starts_with(NO_CHROME) => {
+                SearchError::Browser(reason)
+            }
+            SearchError::Browser(reason) => no_chrome_error(reason),
+            e => no_chrome_error(e),
+        })
+}
+
+/// Ensure a specific version of Chrome for Testing is available, e.g.
+/// `"130.0.6723.58"`, downloading it into the cache directory if needed.
+///
+/// System installations are ignored, so every environment runs the same
+/// build. Fails with the download URL if the version does not exist for
+/// the current platform.
+pub async fn ensure_chrome_version(version: &str) -> Result<PathBuf> {
+    ensure_version(version, &|_| {}).await
+}
+
+/// [`ensure_chrome_version`] reporting to `progress`.
+async fn ensure_version(version: &str, progress: Progress<'_>) -> Result<PathBuf> {
+    ensure_version_from(&download_base(platform_id()?), version, progress).await
+}
+
+/// [`ensure_version`], downloading from `base` (see [`chrome_download_url`]).
+async fn ensure_version_from(base: &str, version: &str, progress: Progress<'_>) -> Result<PathBuf> {
+    validate_version(version)?;
+    if let Ok(path) = find_cached_chrome_version(version) {
+        info!("Using cached Chrome {}: {}", version, path.display());
+        return Ok(path);
+    }
+
+    info!(
+        "Chrome {} not cached, downloading Chrome for Testing...",
+        version
+    );
+    locked_download(
+        || find_cached_chrome_version(version),
+        download_chrome_version(base, version, None, progress),
+    )
+    .await
+}
+
+/// Like [`ensure_chrome_version`], but only accepts a download whose zip
+/// has the SHA-256 `sha256` (hex).
+///
+/// A cached install is used only if it was verified against the same
+/// checksum when it was downloaded; otherwise it is downloaded again.
+pub async fn ensure_chrome_with_checksum(version: &str, sha256: &str) -> Result<PathBuf> {
+    validate_version(version)?;
+    let sha256 = sha256.trim();
+    if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(SearchError::Browser(format!(
+            "Invalid SHA-256 checksum '{}', expected 64 hex digits",
+            sha256
+        )));
+    }
+
+    if let Ok(path) = find_verified_chrome(version, sha256) {
+        info!(
+            "Using verified cached Chrome {}: {}",
+            version,
+            path.display()
+        );
+        return Ok(path);
+    }
+    if find_cached_chrome_version(version).is_ok() {
+        info!(
+            "Cached Chrome {} does not match the checksum, downloading it again",
+            version
+        );
+    }
+
+    locked_download(
+        || find_verified_chrome(version, sha256),
+        download_chrome_version(
+            &download_base(platform_id()?),
+            version,
+            Some(sha256),
+            &|_| {},
+        ),
+    )
+    .await
+}
+
+/// Finds the cached `version` if it was verified against `sha256` when it
+/// was downloaded.
+fn find_verified_chrome(version: &str, sha256: &str) -> Result<PathBuf> {
+    let path = find_cached_chrome_version(version)?;
+    let recorded = std::fs::read_to_string(cache_dir()?.join(version).join(CHECKSUM_FILE));
+    if recorded.is_ok_and(|recorded| recorded.trim().eq_ignore_ascii_case(sha256)) {
+        Ok(path)
+    } else {
+        Err(SearchError::Browser(format!(
+            "Cached Chrome {} was not verified against {}",
+            version, sha256
+        )))
+    }
+}
+
+/// Runs `download` holding [`DOWNLOAD_LOCK`] and the cache directory's
+/// [`LOCK_FILE`], unless `cached` finds Chrome once both are held, e.g.
+/// because another task or process installed it in the meantime.
+async fn locked_download(
+    cached: impl Fn() -> Result<PathBuf>,
+    download: impl std::future::Future<Output = Result<PathBuf>>,
+) -> Result<PathBuf> {
+    let _guard = DOWNLOAD_LOCK.lock().await;
+    let _lock = lock_cache_dir().await?;
+    if let Ok(path) = cached() {
+        info!("Using Chrome installed concurrently: {}", path.display());
+        return Ok(path);
+    }
+    download.await
+}
+
+/// Takes an exclusive lock on the cache directory's [`LOCK_FILE`], waiting
+/// for any other process holding it. Dropping the file releases the lock.
+async fn lock_cache_dir() -> Result<std::fs::File> {
+    let base = cache_dir()?;
+    let path = base.join(LOCK_FILE);
+    let lock = tokio::task::spawn_blocking(move || {
+        std::fs::create_dir_all(&base)?;
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+        file.lock()?;
+        Ok::<_, std::io::Error>(file)
+    })
+    .await
+    .map_err(|e| SearchError::Browser(format!("Failed to lock Chrome cache: {}", e)))?;
+    lock.map_err(|e| SearchError::Browser(format!("Failed to lock Chrome cache: {}", e)))
+}
+
+/// Rejects version strings that are not dot-separated numbers, since they
+/// end up in a URL and a cache path.
+fn validate_version(version: &str) -> Result<()> {
+    let valid = !version.is_empty()
+        && version
+            .split('.')
+            .all(|part| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()));
+    if valid {
+        Ok(())
+    } else {
+        Err(SearchError::Browser(format!(
+            "Invalid Chrome version '{}', expected e.g. 130.0.6723.58",
+            version
+        )))
+    }
+}
+
+/// Where downloads for `platform` come from: Chrome for Testing, or the
+/// Chromium snapshot mirror on Linux arm64.
+fn download_base(platform: &str) -> String {
+    if platform != LINUX_ARM64 {
+        return CHROME_DOWNLOAD_BASE.to_string();
+    }
+    std::env::var(CHROMIUM_MIRROR_ENV)
+        .ok()
+        .filter(|mirror| !mirror.is_empty())
+        .unwrap_or_else(|| CHROMIUM_ARM64_MIRROR.to_string())
+}
+
+/// Download URL of a Chrome for Testing `version` for `platform`, or of a
+/// Chromium snapshot revision on Linux arm64.
+fn chrome_download_url(base: &str, version: &str, platform: &str) -> String {
+    let base = base.trim_end_matches('/');
+    if platform == LINUX_ARM64 {
+        return format!("{}/{}/chrome-linux.zip", base, version);
+    }
+    format!("{}/{}/{}/chrome-{}.zip", base, version, platform, platform)
+}
+
+/// Look for a previously downloaded Chrome `version` in the cache directory.
+fn find_cached_chrome_version(version: &str) -> Result<PathBuf> {
+    let exe_path = cache_dir()?
+        .join(version)
+        .join(chrome_executable_in_zip(platform_id()?));
+    if exe_path.exists() {
+        Ok(exe_path)
+    } else {
+        Err(SearchError::Browser(format!(
+            "No cached Chrome {} found",
+            version
+        )))
+    }
 }
 
 /// Look for a previously downloaded Chrome in the cache directory.
@@ -168,19 +587,10 @@ fn find_cached_chrome() -> Result<PathBuf> {
         return Err(SearchError::Browser("No cached Chrome found".to_string()));
     }
 
-    // Find the latest version directory
-    let mut versions: Vec<_> = std::fs::read_dir(&base)
-        .map_err(|e| SearchError::Browser(format!("Failed to read cache dir: {}", e)))?
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.path().is_dir())
-        .collect();
-
-    // Sort by name descending (latest version first)
-    versions.sort_by_key(|b| std::cmp::Reverse(b.file_name()));
-
+    // Use the latest version directory that has an executable
     let platform = platform_id()?;
-    for version_dir in versions {
-        let exe_path = version_dir.path().join(chrome_executable_in_zip(platform));
+    for version_dir in cached_version_dirs(&base)? {
+        let exe_path = version_dir.join(chrome_executable_in_zip(platform));
         if exe_path.exists() {
             return Ok(exe_path);
         }
@@ -189,18 +599,79 @@ fn find_cached_chrome() -> Result<PathBuf> {
     Err(SearchError::Browser("No cached Chrome found".to_string()))
 }
 
+/// Number of cached versions kept when a new stable Chrome is downloaded.
+const KEEP_VERSIONS_AFTER_DOWNLOAD: usize = 2;
+
+/// Remove all but the newest `keep` Chrome versions from the cache
+/// directory, returning the directories that were removed.
+///
+/// Versions are compared numerically, so `130.0.6723.58` is newer than
+/// `99.0.4844.51`. Only directories named like a version are considered;
+/// unfinished installs and other files are left alone. A version still in
+/// use by a running browser is removed all the same, so call this when no
+/// browser started from the cache is running.
+pub fn cleanup_old_versions(keep: usize) -> Result<Vec<PathBuf>> {
+    cleanup_versions_in(&cache_dir()?, keep)
+}
+
+/// [`cleanup_old_versions`] in the cache directory `base`.
+fn cleanup_versions_in(base: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    if !base.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut removed = Vec::new();
+    for version_dir in cached_version_dirs(base)?.into_iter().skip(keep) {
+        std::fs::remove_dir_all(&version_dir).map_err(|e| {
+            SearchError::Browser(format!("Failed to remove {}: {}", version_dir.display(), e))
+        })?;
+        info!("Removed cached Chrome {}", version_dir.display());
+        removed.push(version_dir);
+    }
+    Ok(removed)
+}
+
+/// Version directories in the cache directory `base`, newest first.
+///
+/// Hidden directories (unfinished installs) and names that are not a
+/// version are skipped.
+fn cached_version_dirs(base: &Path) -> Result<Vec<PathBuf>> {
+    let mut versions: Vec<(Vec<u64>, PathBuf)> = std::fs::read_dir(base)
+        .map_err(|e| SearchError::Browser(format!("Failed to read cache dir: {}", e)))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let version = parse_version(&entry.file_name().to_string_lossy())?;
+            Some((version, entry.path()))
+        })
+        .collect();
+
+    versions.sort_by(|a, b| b.0.cmp(&a.0));
+    Ok(versions.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Parses a dot-separated version such as `130.0.6723.58` into its
+/// numeric parts, for comparison.
+fn parse_version(name: &str) -> Option<Vec<u64>> {
+    name.split('.').map(|part| part.parse().ok()).collect()
+}
+
 /// Download Chrome for Testing from Google's official CDN.
 ///
-/// Downloads the stable version for the current platform and extracts it
-/// to `~/.a3s/chromium/<version>/`.
-async fn download_chrome() -> Result<PathBuf> {
+/// Downloads the stable version listed at `versions_url` for the current
+/// platform and extracts it to `<cache dir>/<version>/`.
+async fn download_chrome(versions_url: &str, progress: Progress<'_>) -> Result<PathBuf> {
+    check_downloads_allowed()?;
     let platform = platform_id()?;
+    if platform == LINUX_ARM64 {
+        return download_chromium_snapshot(&download_base(platform), progress).await;
+    }
 
     // Fetch version metadata
     eprintln!("Fetching Chrome for Testing version info...");
     let client = reqwest::Client::new();
     let resp = client
-        .get(CHROME_VERSIONS_URL)
+        .get(versions_url)
         .send()
         .await
         .map_err(|e| SearchError::Browser(format!("Failed to fetch Chrome versions: {}", e)))?;
@@ -239,88 +710,476 @@ async fn download_chrome() -> Result<PathBuf> {
             ))
         })?;
 
-    // Prepare cache directory
-    let version_dir = cache_dir()?.join(version);
-    std::fs::create_dir_all(&version_dir).map_err(|e| {
+    let exe_path = install_chrome(&client, version, platform, download_url, None, progress).await?;
+
+    // A new stable release supersedes older ones, so prune them
+    if let Err(e) = cleanup_old_versions(KEEP_VERSIONS_AFTER_DOWNLOAD) {
+        warn!("Failed to clean up old Chrome versions: {}", e);
+    }
+    Ok(exe_path)
+}
+
+/// Download the latest Chromium snapshot from the Linux arm64 `mirror`
+/// (see [`CHROMIUM_MIRROR_ENV`]) into `<cache dir>/<revision>/`.
+async fn download_chromium_snapshot(mirror: &str, progress: Progress<'_>) -> Result<PathBuf> {
+    let client = reqwest::Client::new();
+    let revision = latest_snapshot_revision(&client, mirror).await?;
+    let exe_path = install_chrome(
+        &client,
+        &revision,
+        LINUX_ARM64,
+        &chrome_download_url(mirror, &revision, LINUX_ARM64),
+        None,
+        progress,
+    )
+    .await?;
+
+    if let Err(e) = cleanup_old_versions(KEEP_VERSIONS_AFTER_DOWNLOAD) {
+        warn!("Failed to clean up old Chrome versions: {}", e);
+    }
+    Ok(exe_path)
+}
+
+/// Reads the latest revision from `<mirror>/LAST_CHANGE`.
+async fn latest_snapshot_revision(client: &reqwest::Client, mirror: &str) -> Result<String> {
+    let url = format!("{}/LAST_CHANGE", mirror.trim_end_matches('/'));
+    eprintln!("Fetching latest Chromium snapshot from {}...", url);
+    let resp = client
+        .get(&url)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|e| {
+            SearchError::Browser(format!(
+                "Failed to fetch Chromium revision from {}: {}",
+                url, e
+            ))
+        })?;
+    let body = resp.text().await.map_err(|e| {
         SearchError::Browser(format!(
-            "Failed to create cache directory {}: {}",
-            version_dir.display(),
-            e
+            "Failed to read Chromium revision from {}: {}",
+            url, e
         ))
     })?;
 
+    let revision = body.trim().to_string();
+    validate_version(&revision).map_err(|_| {
+        SearchError::Browser(format!(
+            "Invalid Chromium revision '{}' from {}",
+            revision, url
+        ))
+    })?;
+    Ok(revision)
+}
+
+/// Download Chrome for Testing `version` from `base` (see
+/// [`chrome_download_url`]) into `<cache dir>/<version>/`, verifying the
+/// zip against `sha256` if given.
+async fn download_chrome_version(
+    base: &str,
+    version: &str,
+    sha256: Option<&str>,
+    progress: Progress<'_>,
+) -> Result<PathBuf> {
+    check_downloads_allowed()?;
+    let platform = platform_id()?;
+    let download_url = chrome_download_url(base, version, platform);
+    install_chrome(
+        &reqwest::Client::new(),
+        version,
+        platform,
+        &download_url,
+        sha256,
+        progress,
+    )
+    .await
+}
+
+/// Download the Chrome zip at `download_url`, verify it, and install it
+/// into `<cache dir>/<version>/`.
+async fn install_chrome(
+    client: &reqwest::Client,
+    version: &str,
+    platform: &str,
+    download_url: &str,
+    sha256: Option<&str>,
+    progress: Progress<'_>,
+) -> Result<PathBuf> {
     // Download the zip
-    eprintln!(
-        "Downloading Chrome for Testing v{} ({})...",
-        version, platform
-    );
-    let zip_bytes = client
-        .get(download_url)
-        .send()
-        .await
-        .map_err(|e| SearchError::Browser(format!("Failed to download Chrome: {}", e)))?
-        .bytes()
-        .await
-        .map_err(|e| SearchError::Browser(format!("Failed to read Chrome download: {}", e)))?;
+    let product = if platform == LINUX_ARM64 {
+        "Chromium"
+    } else {
+        "Chrome for Testing"
+    };
+    eprintln!("Downloading {} v{} ({})...", product, version, platform);
+
+    // The zip is kept in the cache so a later run can resume it
+    let base = cache_dir()?;
+    let zip_path = base.join(format!(".download-{}-{}.zip", version, platform));
+    let attempts = DOWNLOAD_ATTEMPTS.load(Ordering::Relaxed);
+
+    let mut fresh = false;
+    let exe_path = loop {
+        let download = download_to_file(
+            client,
+            download_url,
+            &zip_path,
+            attempts,
+            INITIAL_RETRY_DELAY,
+            progress,
+        )
+        .await?;
+        let Download::Complete { size, resumed } = download else {
+            return Err(SearchError::Browser(format!(
+                "Chrome for Testing {} is not available for platform '{}' (tried {})",
+                version, platform, download_url
+            )));
+        };
+
+        eprintln!(
+            "Downloaded {:.1} MB, extracting...",
+            size as f64 / 1_048_576.0
+        );
+        let installed = std::fs::read(&zip_path)
+            .map_err(|e| SearchError::Browser(format!("Failed to read Chrome download: {}", e)))
+            .and_then(|zip_bytes| {
+                let checksum = verify_download(&zip_bytes, Some(size), sha256)?;
+                install_zip(&zip_bytes, &checksum, &base, version, platform, progress)
+            });
+        std::fs::remove_file(&zip_path).ok();
+
+        match installed {
+            Ok(exe_path) => break exe_path,
+            // The part downloaded earlier may be stale or corrupt
+            Err(e) if resumed && !fresh => {
+                warn!("Resumed Chrome download is corrupt ({}), starting over", e);
+                fresh = true;
+            }
+            Err(e) => return Err(e),
+        }
+    };
 
-    eprintln!(
-        "Downloaded {:.1} MB, extracting...",
-        zip_bytes.len() as f64 / 1_048_576.0
-    );
+    eprintln!("{} v{} installed successfully!", product, version);
+    info!("Chrome installed at: {}", exe_path.display());
 
-    // Extract the zip
-    extract_zip(&zip_bytes, &version_dir)?;
+    Ok(exe_path)
+}
 
-    // Find the executable
-    let exe_path = version_dir.join(chrome_executable_in_zip(platform));
+/// Outcome of [`download_to_file`].
+#[derive(Debug, PartialEq, Eq)]
+enum Download {
+    /// The file holds the whole body.
+    Complete {
+        /// Size of the body.
+        size: u64,
+        /// Whether part of it was downloaded by an earlier attempt or run.
+        resumed: bool,
+    },
+    /// The server answered 404.
+    NotFound,
+}
 
-    // Make executable on Unix
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        if exe_path.exists() {
-            let mut perms = std::fs::metadata(&exe_path)
-                .map_err(|e| {
-                    SearchError::Browser(format!("Failed to read Chrome permissions: {}", e))
-                })?
-                .permissions();
-            perms.set_mode(0o755);
-            std::fs::set_permissions(&exe_path, perms).map_err(|e| {
-                SearchError::Browser(format!("Failed to set Chrome permissions: {}", e))
-            })?;
-        }
-    }
+/// Outcome of one [`download_attempt`].
+enum Attempt {
+    Done(Download),
+    /// A failure worth retrying, such as a dropped connection.
+    Retry(SearchError),
+}
 
-    if !exe_path.exists() {
-        // List what was actually extracted for debugging
-        let contents: Vec<_> = std::fs::read_dir(&version_dir)
-            .map(|rd| rd.filter_map(|e| e.ok()).map(|e| e.path()).collect())
-            .unwrap_or_default();
+/// Downloads `url` into `path`, making up to `attempts` attempts with an
+/// exponential backoff starting at `initial_delay`.
+///
+/// A partial file, from a failed attempt or an earlier run, is resumed
+/// with a `Range` request; if the server does not honor it, or the ranges
+/// do not line up, the file is downloaded again from the start.
+async fn download_to_file(
+    client: &reqwest::Client,
+    url: &str,
+    path: &Path,
+    attempts: u32,
+    initial_delay: Duration,
+    progress: Progress<'_>,
+) -> Result<Download> {
+    let mut delay = initial_delay;
+    let mut attempt = 1;
+    loop {
+        let error = match download_attempt(client, url, path, progress).await? {
+            Attempt::Done(download) => return Ok(download),
+            Attempt::Retry(error) => error,
+        };
+        if attempt >= attempts {
+            return Err(error);
+        }
         warn!(
-            "Expected Chrome at {} but not found. Extracted contents: {:?}",
-            exe_path.display(),
-            contents
+            "Chrome download attempt {}/{} failed: {}; retrying in {:?}",
+            attempt, attempts, error, delay
         );
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(MAX_RETRY_DELAY);
+        attempt += 1;
+    }
+}
+
+/// Makes one request for `url`, appending to the partial file at `path`
+/// if there is one. Errors are not worth retrying.
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    path: &Path,
+    progress: Progress<'_>,
+) -> Result<Attempt> {
+    let io_error =
+        |e: std::io::Error| SearchError::Browser(format!("Failed to write Chrome download: {}", e));
+    let retry = |message: String| Ok(Attempt::Retry(SearchError::Browser(message)));
+
+    let offset = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut request = client.get(url);
+    if offset > 0 {
+        request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+    let resp = match request.send().await {
+        Ok(resp) => resp,
+        Err(e) => return retry(format!("Failed to download Chrome: {}", e)),
+    };
+
+    let status = resp.status();
+    let (start, total) = match status {
+        reqwest::StatusCode::OK => (0, resp.content_length()),
+        reqwest::StatusCode::PARTIAL_CONTENT => {
+            match content_range(resp.headers()) {
+                Some((start, total)) if start == offset => (start, Some(total)),
+                // Not the part we asked for
+                _ => {
+                    std::fs::remove_file(path).ok();
+                    return retry(format!("Unexpected Content-Range from {}", url));
+                }
+            }
+        }
+        reqwest::StatusCode::NOT_FOUND => return Ok(Attempt::Done(Download::NotFound)),
+        // The partial file is at least as large as the whole body
+        reqwest::StatusCode::RANGE_NOT_SATISFIABLE => {
+            std::fs::remove_file(path).ok();
+            return retry(format!("Partial Chrome download does not match {}", url));
+        }
+        status if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS => {
+            return retry(format!(
+                "Failed to download Chrome: HTTP {} from {}",
+                status, url
+            ));
+        }
+        status => {
+            return Err(SearchError::Browser(format!(
+                "Failed to download Chrome: HTTP {} from {}",
+                status, url
+            )))
+        }
+    };
+    if start == 0 && offset > 0 {
+        debug!("Server ignored the range request, downloading from the start");
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(io_error)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(start > 0)
+        .truncate(start == 0)
+        .open(path)
+        .map_err(io_error)?;
+    let report = |downloaded: u64| {
+        progress(DownloadProgress {
+            phase: DownloadPhase::Downloading,
+            downloaded,
+            total,
+        })
+    };
+
+    let mut downloaded = start;
+    let mut stream = resp.bytes_stream();
+    report(downloaded);
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                file.sync_all().ok();
+                return retry(format!("Failed to read Chrome download: {}", e));
+            }
+        };
+        file.write_all(&chunk).map_err(io_error)?;
+        downloaded += chunk.len() as u64;
+        report(downloaded);
+    }
+    file.sync_all().map_err(io_error)?;
+
+    match total {
+        Some(total) if downloaded < total => retry(format!(
+            "Chrome download truncated: got {} of {} bytes",
+            downloaded, total
+        )),
+        Some(total) if downloaded > total => {
+            std::fs::remove_file(path).ok();
+            retry(format!(
+                "Chrome download too long: got {} of {} bytes",
+                downloaded, total
+            ))
+        }
+        _ => Ok(Attempt::Done(Download::Complete {
+            size: downloaded,
+            resumed: start > 0,
+        })),
+    }
+}
+
+/// Parses the start and total size of a `Content-Range: bytes
+/// <start>-<end>/<total>` header.
+fn content_range(headers: &reqwest::header::HeaderMap) -> Option<(u64, u64)> {
+    let value = headers.get(reqwest::header::CONTENT_RANGE)?.to_str().ok()?;
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()?))
+}
+
+/// Checks a downloaded zip against the response's `Content-Length` and an
+/// expected SHA-256, and returns its SHA-256 as lowercase hex.
+fn verify_download(
+    zip_bytes: &[u8],
+    content_length: Option<u64>,
+    sha256: Option<&str>,
+) -> Result<String> {
+    if let Some(expected) = content_length {
+        if zip_bytes.len() as u64 != expected {
+            return Err(SearchError::Browser(format!(
+                "Chrome download truncated: got {} of {} bytes",
+                zip_bytes.len(),
+                expected
+            )));
+        }
+    }
+
+    let checksum = format!("{:x}", Sha256::digest(zip_bytes));
+    if let Some(expected) = sha256 {
+        if !checksum.eq_ignore_ascii_case(expected.trim()) {
+            return Err(SearchError::Browser(format!(
+                "Chrome download checksum mismatch: expected SHA-256 {}, got {}",
+                expected.trim(),
+                checksum
+            )));
+        }
+    }
+    Ok(checksum)
+}
+
+/// Extracts a Chrome zip into `<base>/<version>/`.
+///
+/// The archive is extracted into a hidden staging directory, synced to
+/// disk, and renamed into place only once complete, so an interrupted
+/// install never leaves a version directory that [`find_cached_chrome`]
+/// would pick up. The zip's `checksum` is recorded next to the executable.
+fn install_zip(
+    zip_bytes: &[u8],
+    checksum: &str,
+    base: &Path,
+    version: &str,
+    platform: &str,
+    progress: Progress<'_>,
+) -> Result<PathBuf> {
+    // Reject truncated or corrupt archives before writing anything
+    let exe_in_zip = chrome_executable_in_zip(platform);
+    let archive = zip::ZipArchive::new(std::io::Cursor::new(zip_bytes))
+        .map_err(|e| SearchError::Browser(format!("Failed to open zip archive: {}", e)))?;
+    if archive.index_for_name(&exe_in_zip).is_none() {
         return Err(SearchError::Browser(format!(
-            "Chrome executable not found after extraction at {}",
-            exe_path.display()
+            "Chrome zip does not contain {}",
+            exe_in_zip
         )));
     }
 
-    eprintln!("Chrome for Testing v{} installed successfully!", version);
-    info!("Chrome installed at: {}", exe_path.display());
+    let io_err = |what: &str, path: &Path, e: std::io::Error| {
+        SearchError::Browser(format!("Failed to {} {}: {}", what, path.display(), e))
+    };
+    std::fs::create_dir_all(base).map_err(|e| io_err("create cache directory", base, e))?;
+    let staging = base.join(format!(".partial-{}-{}", version, std::process::id()));
+    std::fs::remove_dir_all(&staging).ok();
+
+    let staged = extract_zip(zip_bytes, &staging, progress).and_then(|()| {
+        let exe_path = staging.join(&exe_in_zip);
+
+        // Make executable on Unix
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if exe_path.exists() {
+                let mut perms = std::fs::metadata(&exe_path)
+                    .map_err(|e| {
+                        SearchError::Browser(format!("Failed to read Chrome permissions: {}", e))
+                    })?
+                    .permissions();
+                perms.set_mode(0o755);
+                std::fs::set_permissions(&exe_path, perms).map_err(|e| {
+                    SearchError::Browser(format!("Failed to set Chrome permissions: {}", e))
+                })?;
+            }
+        }
 
-    Ok(exe_path)
+        if !exe_path.exists() {
+            // List what was actually extracted for debugging
+            let contents: Vec<_> = std::fs::read_dir(&staging)
+                .map(|rd| rd.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+                .unwrap_or_default();
+            warn!(
+                "Expected Chrome at {} but not found. Extracted contents: {:?}",
+                exe_path.display(),
+                contents
+            );
+            return Err(SearchError::Browser(format!(
+                "Chrome executable not found after extraction at {}",
+                exe_path.display()
+            )));
+        }
+
+        let marker = staging.join(CHECKSUM_FILE);
+        let mut file =
+            std::fs::File::create(&marker).map_err(|e| io_err("create file", &marker, e))?;
+        std::io::Write::write_all(&mut file, checksum.as_bytes())
+            .and_then(|()| file.sync_all())
+            .map_err(|e| io_err("write file", &marker, e))
+    });
+    if let Err(e) = staged {
+        std::fs::remove_dir_all(&staging).ok();
+        return Err(e);
+    }
+
+    // Replace an older install of the same version, e.g. one that failed
+    // checksum verification
+    let version_dir = base.join(version);
+    if version_dir.exists() {
+        std::fs::remove_dir_all(&version_dir)
+            .map_err(|e| io_err("remove old install", &version_dir, e))?;
+    }
+    if let Err(e) = std::fs::rename(&staging, &version_dir) {
+        std::fs::remove_dir_all(&staging).ok();
+        return Err(io_err("move Chrome into", &version_dir, e));
+    }
+    // Persist the rename itself
+    if let Ok(dir) = std::fs::File::open(base) {
+        dir.sync_all().ok();
+    }
+
+    Ok(version_dir.join(exe_in_zip))
 }
 
-/// Extract a zip archive to the target directory.
-fn extract_zip(zip_bytes: &[u8], target_dir: &Path) -> Result<()> {
+/// Extract a zip archive to the target directory, reporting each extracted
+/// entry to `progress`.
+fn extract_zip(zip_bytes: &[u8], target_dir: &Path, progress: Progress<'_>) -> Result<()> {
     use std::io::{Cursor, Read};
 
     let reader = Cursor::new(zip_bytes);
     let mut archive = zip::ZipArchive::new(reader)
         .map_err(|e| SearchError::Browser(format!("Failed to open zip archive: {}", e)))?;
 
+    let entries = archive.len() as u64;
     for i in 0..archive.len() {
         let mut file = archive
             .by_index(i)
@@ -360,13 +1219,15 @@ fn extract_zip(zip_bytes: &[u8], target_dir: &Path) -> Result<()> {
             file.read_to_end(&mut buf)
                 .map_err(|e| SearchError::Browser(format!("Failed to read zip entry: {}", e)))?;
 
-            std::io::Write::write_all(&mut outfile, &buf).map_err(|e| {
-                SearchError::Browser(format!(
-                    "Failed to write file {}: {}",
-                    out_path.display(),
-                    e
-                ))
-            })?;
+            std::io::Write::write_all(&mut outfile, &buf)
+                .and_then(|()| outfile.sync_all())
+                .map_err(|e| {
+                    SearchError::Browser(format!(
+                        "Failed to write file {}: {}",
+                        out_path.display(),
+                        e
+                    ))
+                })?;
 
             // Preserve Unix permissions from zip
             #[cfg(unix)]
@@ -377,6 +1238,12 @@ fn extract_zip(zip_bytes: &[u8], target_dir: &Path) -> Result<()> {
                 }
             }
         }
+
+        progress(DownloadProgress {
+            phase: DownloadPhase::Extracting,
+            downloaded: i as u64 + 1,
+            total: Some(entries),
+        });
     }
 
     Ok(())
@@ -385,6 +1252,48 @@ fn extract_zip(zip_bytes: &[u8], target_dir: &Path) -> Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_server::{spawn_connection_server, spawn_status_server};
+
+    /// Serializes the tests that change the cache directory settings.
+    static ENV_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// Runs `f` with `HOME` set to `home` and the other cache directory
+    /// variables set to `vars` (unset otherwise), restoring all of them
+    /// afterwards.
+    fn with_env<T>(home: &str, vars: &[(&str, &str)], f: impl FnOnce() -> T) -> T {
+        let _guard = ENV_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let names = [
+            "HOME",
+            CHROME_DIR_ENV,
+            "XDG_CACHE_HOME",
+            CHROME_VERSION_ENV,
+            CHROMIUM_MIRROR_ENV,
+            NO_DOWNLOAD_ENV,
+        ];
+        let saved: Vec<_> = names
+            .iter()
+            .map(|name| (*name, std::env::var_os(name)))
+            .collect();
+        std::env::set_var("HOME", home);
+        std::env::remove_var(CHROME_DIR_ENV);
+        std::env::remove_var("XDG_CACHE_HOME");
+        std::env::remove_var(CHROME_VERSION_ENV);
+        std::env::remove_var(CHROMIUM_MIRROR_ENV);
+        std::env::remove_var(NO_DOWNLOAD_ENV);
+        for (name, value) in vars {
+            std::env::set_var(name, value);
+        }
+
+        let result = f();
+
+        for (name, value) in saved {
+            match value {
+                Some(value) => std::env::set_var(name, value),
+                None => std::env::remove_var(name),
+            }
+        }
+        result
+    }
 
     #[test]
     fn test_platform_id() {
@@ -392,7 +1301,7 @@ mod tests {
         assert!(platform.is_ok());
         let id = platform.unwrap();
         assert!(
-            ["mac-arm64", "mac-x64", "linux64"].contains(&id),
+            ["mac-arm64", "mac-x64", "linux64", LINUX_ARM64].contains(&id),
             "Unexpected platform: {}",
             id
         );
@@ -407,7 +1316,7 @@ mod tests {
 
     #[test]
     fn test_cache_dir() {
-        let dir = cache_dir();
+        let dir = with_env("/tmp/a3s_test_home", &[], cache_dir);
         assert!(dir.is_ok());
         let path = dir.unwrap();
         assert!(path.to_string_lossy().contains(".a3s/chromium"));
@@ -442,10 +1351,8 @@ mod tests {
     #[test]
     fn test_find_cached_chrome_no_cache() {
         // With no cache directory, should return error
-        std::env::set_var("HOME", "/tmp/a3s_test_nonexistent_home");
-        let result = find_cached_chrome();
+        let result = with_env("/tmp/a3s_test_nonexistent_home", &[], find_cached_chrome);
         assert!(result.is_err());
-        std::env::remove_var("HOME");
     }
 
     #[test]
@@ -460,34 +1367,109 @@ mod tests {
 
     #[test]
     fn test_extract_zip_invalid_data() {
-        let result = extract_zip(b"not a zip file", Path::new("/tmp"));
+        let result = extract_zip(b"not a zip file", Path::new("/tmp"), &|_| {});
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
         assert!(err.contains("zip"), "Error should mention zip: {}", err);
     }
 
     #[test]
-    fn test_dirs_path_returns_home() {
-        let original_home = std::env::var("HOME").ok();
-        std::env::set_var("HOME", "/tmp/test_home_dir");
-        let result = dirs_path();
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap(), PathBuf::from("/tmp/test_home_dir"));
-        // Restore
-        if let Some(home) = original_home {
-            std::env::set_var("HOME", home);
-        }
+    fn test_cache_dir_from_precedence() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.into())
+            }
+        };
+
+        let all = env(&[
+            ("A3S_CHROME_DIR", "/opt/chrome"),
+            ("XDG_CACHE_HOME", "/xdg"),
+            ("HOME", "/home/a"),
+        ]);
+        assert_eq!(cache_dir_from(all).unwrap(), PathBuf::from("/opt/chrome"));
+
+        let xdg = env(&[("XDG_CACHE_HOME", "/xdg"), ("HOME", "/home/a")]);
+        assert_eq!(
+            cache_dir_from(xdg).unwrap(),
+            PathBuf::from("/xdg/.a3s/chromium")
+        );
+
+        let home = env(&[("HOME", "/home/a")]);
+        assert_eq!(
+            cache_dir_from(home).unwrap(),
+            PathBuf::from("/home/a/.a3s/chromium")
+        );
+
+        let err = cache_dir_from(env(&[])).unwrap_err();
+        assert!(err.to_string().contains("A3S_CHROME_DIR"), "{}", err);
     }
 
     #[test]
     fn test_cache_dir_structure() {
-        let original_home = std::env::var("HOME").ok();
-        std::env::set_var("HOME", "/tmp/test_cache_home");
-        let dir = cache_dir().unwrap();
+        let dir = with_env("/tmp/test_cache_home", &[], cache_dir).unwrap();
+        assert_eq!(dir, PathBuf::from("/tmp/test_cache_home/.a3s/chromium"));
+
+        // An empty variable counts as unset
+        let dir = with_env(
+            "/tmp/test_cache_home",
+            &[(CHROME_DIR_ENV, ""), ("XDG_CACHE_HOME", "")],
+            cache_dir,
+        )
+        .unwrap();
         assert_eq!(dir, PathBuf::from("/tmp/test_cache_home/.a3s/chromium"));
-        if let Some(home) = original_home {
-            std::env::set_var("HOME", home);
-        }
+    }
+
+    #[test]
+    fn test_find_cached_chrome_env_precedence() {
+        // A cached Chrome in each candidate directory
+        let tmp = std::env::temp_dir().join("a3s_test_cache_precedence");
+        let exe = chrome_executable_in_zip(platform_id().unwrap());
+        let install = |dir: &Path| {
+            let path = dir.join("130.0.6723.58").join(&exe);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, b"").unwrap();
+            path
+        };
+        let home = tmp.join("home");
+        let xdg = tmp.join("xdg");
+        let custom = tmp.join("custom");
+        let in_home = install(&home.join(".a3s").join("chromium"));
+        let in_xdg = install(&xdg.join(".a3s").join("chromium"));
+        let in_custom = install(&custom);
+
+        let home_str = home.to_str().unwrap();
+        let xdg_str = xdg.to_str().unwrap();
+        let custom_str = custom.to_str().unwrap();
+        assert_eq!(
+            with_env(home_str, &[], find_cached_chrome).unwrap(),
+            in_home
+        );
+        assert_eq!(
+            with_env(home_str, &[("XDG_CACHE_HOME", xdg_str)], find_cached_chrome).unwrap(),
+            in_xdg
+        );
+        assert_eq!(
+            with_env(
+                home_str,
+                &[("XDG_CACHE_HOME", xdg_str), (CHROME_DIR_ENV, custom_str)],
+                find_cached_chrome
+            )
+            .unwrap(),
+            in_custom
+        );
+
+        // set_cache_dir beats the environment
+        let overridden = with_env(home_str, &[(CHROME_DIR_ENV, custom_str)], || {
+            set_cache_dir(xdg.join(".a3s").join("chromium"));
+            let found = find_cached_chrome();
+            *CACHE_DIR.write().unwrap() = None;
+            found
+        });
+        assert_eq!(overridden.unwrap(), in_xdg);
+
+        std::fs::remove_dir_all(&tmp).ok();
     }
 
     #[test]
@@ -496,6 +1478,19 @@ mod tests {
         assert!(path.contains("chrome-linux64"));
     }
 
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_chrome_executable_in_zip_linux_arm64_format() {
+        assert_eq!(chrome_executable_in_zip(LINUX_ARM64), "chrome-linux/chrome");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_known_paths_include_distro_chromium() {
+        assert!(KNOWN_PATHS.contains(&"/usr/lib/chromium/chromium"));
+        assert!(KNOWN_PATHS.contains(&"/usr/lib/chromium-browser/chromium-browser"));
+    }
+
     #[test]
     fn test_chrome_executable_in_zip_mac_x64_format() {
         let path = chrome_executable_in_zip("mac-x64");
@@ -509,15 +1504,10 @@ mod tests {
         let cache = tmp.join(".a3s").join("chromium");
         std::fs::create_dir_all(&cache).ok();
 
-        let original_home = std::env::var("HOME").ok();
-        std::env::set_var("HOME", tmp.to_str().unwrap());
-        let result = find_cached_chrome();
+        let result = with_env(tmp.to_str().unwrap(), &[], find_cached_chrome);
         assert!(result.is_err());
 
         // Cleanup
-        if let Some(home) = original_home {
-            std::env::set_var("HOME", home);
-        }
         std::fs::remove_dir_all(&tmp).ok();
     }
 
@@ -528,15 +1518,10 @@ mod tests {
         let version_dir = tmp.join(".a3s").join("chromium").join("130.0.6723.58");
         std::fs::create_dir_all(&version_dir).ok();
 
-        let original_home = std::env::var("HOME").ok();
-        std::env::set_var("HOME", tmp.to_str().unwrap());
-        let result = find_cached_chrome();
+        let result = with_env(tmp.to_str().unwrap(), &[], find_cached_chrome);
         assert!(result.is_err());
 
         // Cleanup
-        if let Some(home) = original_home {
-            std::env::set_var("HOME", home);
-        }
         std::fs::remove_dir_all(&tmp).ok();
     }
 
@@ -550,6 +1535,880 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_chrome_download_url() {
+        assert_eq!(
+            chrome_download_url(CHROME_DOWNLOAD_BASE, "130.0.6723.58", "linux64"),
+            "https://storage.googleapis.com/chrome-for-testing-public/130.0.6723.58/linux64/chrome-linux64.zip"
+        );
+        assert_eq!(
+            chrome_download_url(CHROMIUM_ARM64_MIRROR, "1381234", LINUX_ARM64),
+            "https://commondatastorage.googleapis.com/chromium-browser-snapshots/Linux_ARM/1381234/chrome-linux.zip"
+        );
+        assert_eq!(
+            chrome_download_url("https://mirror.example/chromium/", "1381234", LINUX_ARM64),
+            "https://mirror.example/chromium/1381234/chrome-linux.zip"
+        );
+    }
+
+    #[test]
+    fn test_download_base() {
+        assert_eq!(download_base("linux64"), CHROME_DOWNLOAD_BASE);
+        let bases = with_env(
+            "/tmp/a3s_test_home",
+            &[(CHROMIUM_MIRROR_ENV, "https://mirror.example/chromium")],
+            || (download_base(LINUX_ARM64), download_base("mac-arm64")),
+        );
+        assert_eq!(
+            bases,
+            (
+                "https://mirror.example/chromium".to_string(),
+                CHROME_DOWNLOAD_BASE.to_string()
+            )
+        );
+        let base = with_env("/tmp/a3s_test_home", &[], || download_base(LINUX_ARM64));
+        assert_eq!(base, CHROMIUM_ARM64_MIRROR);
+    }
+
+    #[test]
+    fn test_no_chrome_error_explains_chrome_env() {
+        let err = no_chrome_error("download failed").to_string();
+        assert!(err.contains("download failed"), "{}", err);
+        assert!(err.contains("CHROME=/usr/bin/chromium"), "{}", err);
+    }
+
+    /// Serves `body` with `status` and returns the base URL.
+    async fn serve_status(status: &'static str, body: &'static str) -> String {
+        let (addr, _) = spawn_status_server(status, "", body).await;
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_latest_snapshot_revision() {
+        let client = reqwest::Client::new();
+
+        let mirror = serve_status("200 OK", "1381234\n").await;
+        let revision = latest_snapshot_revision(&client, &mirror).await.unwrap();
+        assert_eq!(revision, "1381234");
+
+        let mirror = serve_status("200 OK", "<html>not found</html>").await;
+        let err = latest_snapshot_revision(&client, &mirror)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Invalid Chromium revision"), "{}", err);
+
+        let mirror = serve_status("404 Not Found", "").await;
+        let err = latest_snapshot_revision(&client, &mirror)
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(&format!("{}/LAST_CHANGE", mirror)), "{}", err);
+    }
+
+    #[test]
+    fn test_validate_version() {
+        assert!(validate_version("130.0.6723.58").is_ok());
+        assert!(validate_version("130").is_ok());
+        for version in ["", "latest", "130..58", "../130", "130.0/x"] {
+            assert!(validate_version(version).is_err(), "{:?}", version);
+        }
+    }
+
+    #[test]
+    fn test_find_cached_chrome_version() {
+        let tmp = std::env::temp_dir().join("a3s_test_pinned_cache");
+        let exe = chrome_executable_in_zip(platform_id().unwrap());
+        let pinned = tmp.join("130.0.6723.58").join(&exe);
+        std::fs::create_dir_all(pinned.parent().unwrap()).unwrap();
+        std::fs::write(&pinned, b"").unwrap();
+        // A newer version that ensure_chrome would otherwise pick
+        let newer = tmp.join("131.0.6778.85").join(&exe);
+        std::fs::create_dir_all(newer.parent().unwrap()).unwrap();
+        std::fs::write(&newer, b"").unwrap();
+
+        let dir = tmp.to_str().unwrap();
+        let (found, missing) = with_env("/tmp/a3s_test_home", &[(CHROME_DIR_ENV, dir)], || {
+            (
+                find_cached_chrome_version("130.0.6723.58"),
+                find_cached_chrome_version("129.0.6668.100"),
+            )
+        });
+        assert_eq!(found.unwrap(), pinned);
+        assert!(missing.is_err());
+
+        // CHROME_VERSION makes ensure_chrome use the pinned build
+        let ensured = with_env(
+            "/tmp/a3s_test_home",
+            &[(CHROME_DIR_ENV, dir), (CHROME_VERSION_ENV, "130.0.6723.58")],
+            || {
+                tokio::runtime::Builder::new_current_thread()
+                    .build()
+                    .unwrap()
+                    .block_on(ensure_chrome())
+            },
+        );
+        assert_eq!(ensured.unwrap(), pinned);
+
+        std::fs::remove_dir_all(&tmp).ok();
+    }
+
+    #[tokio::test]
+    async fn test_download_chrome_version_unknown() {
+        let (addr, server) = spawn_status_server("404 Not Found", "", "").await;
+
+        let base = format!("http://{}", addr);
+        let err = download_chrome_version(&base, "1.2.3.4", None, &|_| {})
+            .await
+            .unwrap_err()
+            .to_string();
+
+        let url = chrome_download_url(&base, "1.2.3.4", platform_id().unwrap());
+        assert!(err.contains("1.2.3.4"), "{}", err);
+        assert!(err.contains(&url), "{}", err);
+        server.abort();
+    }
+
+    /// A zip holding a fake Chrome executable for the current platform.
+    fn chrome_zip() -> Vec<u8> {
+        use std::io::Write;
+        let mut zip_writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+        zip_writer
+            .start_file(chrome_executable_in_zip(platform_id().unwrap()), options)
+            .unwrap();
+        zip_writer.write_all(&[0x7f; 4096]).unwrap();
+        zip_writer.finish().unwrap().into_inner()
+    }
+
+    /// Names of the entries in `dir`.
+    fn entries(dir: &Path) -> Vec<String> {
+        let mut names: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_verify_download() {
+        let zip = chrome_zip();
+        let checksum = verify_download(&zip, Some(zip.len() as u64), None).unwrap();
+        assert_eq!(checksum.len(), 64);
+
+        // Shorter than announced
+        let err = verify_download(&zip[..100], Some(zip.len() as u64), None).unwrap_err();
+        assert!(err.to_string().contains("truncated"), "{}", err);
+
+        // Checksums compare case-insensitively
+        let upper = checksum.to_uppercase();
+        assert!(verify_download(&zip, None, Some(&upper)).is_ok());
+        let err = verify_download(&zip, None, Some(&"0".repeat(64))).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"), "{}", err);
+    }
+
+    #[test]
+    fn test_install_zip_rejects_truncated_zip() {
+        let base = std::env::temp_dir().join("a3s_test_install_truncated");
+        std::fs::remove_dir_all(&base).ok();
+        let platform = platform_id().unwrap();
+        let zip = chrome_zip();
+
+        // Cut off the central directory at the end of the archive
+        let truncated = &zip[..zip.len() - 30];
+        let result = install_zip(truncated, "", &base, "130.0.6723.58", platform, &|_| {});
+
+        assert!(result.is_err());
+        // Nothing was written
+        assert!(!base.exists() || entries(&base).is_empty());
+        std::fs::remove_dir_all(&base).ok();
+    }
+
+    #[test]
+    fn test_install_zip_renames_into_place() {
+        let base = std::env::temp_dir().join("a3s_test_install_rename");
+        std::fs::remove_dir_all(&base).ok();
+        let platform = platform_id().unwrap();
+        let zip = chrome_zip();
+
+        let exe = install_zip(&zip, "abc", &base, "130.0.6723.58", platform, &|_| {}).unwrap();
+        assert_eq!(
+            exe,
+            base.join("130.0.6723.58")
+                .join(chrome_executable_in_zip(platform))
+        );
+        assert!(exe.exists());
+        // The staging directory was renamed, not copied
+        assert_eq!(entries(&base), vec!["130.0.6723.58"]);
+        let marker = base.join("130.0.6723.58").join(CHECKSUM_FILE);
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), "abc");
+
+        // Reinstalling replaces the old install
+        install_zip(&zip, "def", &base, "130.0.6723.58", platform, &|_| {}).unwrap();
+        assert_eq!(entries(&base), vec!["130.0.6723.58"]);
+        assert_eq!(std::fs::read_to_string(&marker).unwrap(), "def");
+
+        std::fs::remove_dir_all(&base).ok();
+    }
+
+    #[test]
+    fn test_find_cached_chrome_skips_partial_install() {
+        let base = std::env::temp_dir().join("a3s_test_partial_cache");
+        std::fs::remove_dir_all(&base).ok();
+        let exe = chrome_executable_in_zip(platform_id().unwrap());
+        // An install killed before its rename, with the executable written
+        let partial = base.join(".partial-131.0.6778.85-4242").join(&exe);
+        std::fs::create_dir_all(partial.parent().unwrap()).unwrap();
+        std::fs::write(&partial, b"").unwrap();
+
+        let dir = base.to_str().unwrap();
+        let found = with_env(
+            "/tmp/a3s_test_home",
+            &[(CHROME_DIR_ENV, dir)],
+            find_cached_chrome,
+        );
+        assert!(found.is_err());
+
+        let complete = base.join("130.0.6723.58").join(&exe);
+        std::fs::create_dir_all(complete.parent().unwrap()).unwrap();
+        std::fs::write(&complete, b"").unwrap();
+        let found = with_env(
+            "/tmp/a3s_test_home",
+            &[(CHROME_DIR_ENV, dir)],
+            find_cached_chrome,
+        );
+        assert_eq!(found.unwrap(), complete);
+
+        std::fs::remove_dir_all(&base).ok();
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("130.0.6723.58"), Some(vec![130, 0, 6723, 58]));
+        assert!(parse_version("99.0.4844.51") < parse_version("130.0.6723.58"));
+        assert_eq!(parse_version(".partial-130.0.6723.58-42"), None);
+        assert_eq!(parse_version("130..58"), None);
+        assert_eq!(parse_version("latest"), None);
+    }
+
+    /// Creates a cache layout with fake Chrome versions, a partial install,
+    /// and unrelated entries under `base`.
+    fn fake_cache(base: &Path) {
+        std::fs::remove_dir_all(base).ok();
+        for version in [
+            "99.0.4844.51",
+            "131.0.6778.85",
+            "129.0.6668.100",
+            "130.0.6723.58",
+            ".partial-132.0.6834.83-4242",
+            "notes",
+        ] {
+            std::fs::create_dir_all(base.join(version).join("chrome")).unwrap();
+        }
+        std::fs::write(base.join("1.0"), b"not a directory").unwrap();
+    }
+
+    #[test]
+    fn test_cleanup_versions_keeps_newest() {
+        let base = std::env::temp_dir().join("a3s_test_cleanup_versions");
+        fake_cache(&base);
+
+        let mut removed = cleanup_versions_in(&base, 2).unwrap();
+        removed.sort();
+        assert_eq!(
+            removed,
+            vec![base.join("129.0.6668.100"), base.join("99.0.4844.51")]
+        );
+
+        let mut left: Vec<String> = std::fs::read_dir(&base)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(
+            left,
+            vec![
+                ".partial-132.0.6834.83-4242",
+                "1.0",
+                "130.0.6723.58",
+                "131.0.6778.85",
+                "notes"
+            ]
+        );
+
+        // Nothing more to remove
+        assert!(cleanup_versions_in(&base, 2).unwrap().is_empty());
+        std::fs::remove_dir_all(&base).ok();
+    }
+
+    #[test]
+    fn test_cleanup_versions_keep_zero_and_missing_dir() {
+        let base = std::env::temp_dir().join("a3s_test_cleanup_all");
+        fake_cache(&base);
+
+        assert_eq!(cleanup_versions_in(&base, 0).unwrap().len(), 4);
+        assert!(cached_version_dirs(&base).unwrap().is_empty());
+        assert!(base.join(".partial-132.0.6834.83-4242").exists());
+
+        std::fs::remove_dir_all(&base).ok();
+        assert!(cleanup_versions_in(&base, 0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_find_cached_chrome_compares_versions_numerically() {
+        let base = std::env::temp_dir().join("a3s_test_numeric_versions");
+        std::fs::remove_dir_all(&base).ok();
+        let exe = chrome_executable_in_zip(platform_id().unwrap());
+        for version in ["99.0.4844.51", "130.0.6723.58"] {
+            let path = base.join(version).join(&exe);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, b"").unwrap();
+        }
+
+        let found = with_env(
+            "/tmp/a3s_test_home",
+            &[(CHROME_DIR_ENV, base.to_str().unwrap())],
+            find_cached_chrome,
+        );
+        assert_eq!(found.unwrap(), base.join("130.0.6723.58").join(&exe));
+
+        std::fs::remove_dir_all(&base).ok();
+    }
+
+    #[test]
+    fn test_download_progress_percent() {
+        let progress = |downloaded, total| DownloadProgress {
+            phase: DownloadPhase::Downloading,
+            downloaded,
+            total,
+        };
+        assert_eq!(progress(50, Some(200)).percent(), Some(25.0));
+        assert_eq!(progress(50, None).percent(), None);
+        assert_eq!(progress(0, Some(0)).percent(), None);
+    }
+
+    #[test]
+    fn test_download_chrome_version_reports_progress() {
+        use std::sync::Mutex;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let cache = std::env::temp_dir().join("a3s_test_download_progress");
+        std::fs::remove_dir_all(&cache).ok();
+        let zip = chrome_zip();
+        let events = Mutex::new(Vec::new());
+        let record = |progress: DownloadProgress| events.lock().unwrap().push(progress);
+
+        let exe = with_env(
+            "/tmp/a3s_test_home",
+            &[(CHROME_DIR_ENV, cache.to_str().unwrap())],
+            || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                runtime.block_on(async {
+                    let body = zip.clone();
+                    let (addr, server) = spawn_connection_server(move |mut socket| {
+                        let body = body.clone();
+                        async move {
+                            let mut buf = [0u8; 1024];
+                            let _ = socket.read(&mut buf).await;
+                            let head = format!(
+                                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                                body.len()
+                            );
+                            socket.write_all(head.as_bytes()).await.unwrap();
+                            // In pieces, so the download reports several steps
+                            for piece in body.chunks(1024) {
+                                socket.write_all(piece).await.unwrap();
+                                socket.flush().await.unwrap();
+                                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                            }
+                        }
+                    })
+                    .await;
+                    let exe = download_chrome_version(
+                        &format!("http://{}", addr),
+                        "130.0.6723.58",
+                        None,
+                        &record,
+                    )
+                    .await;
+                    server.abort();
+                    exe
+                })
+            },
+        );
+        assert!(exe.unwrap().exists());
+
+        let events = events.into_inner().unwrap();
+        let total = zip.len() as u64;
+        let (download, extract): (Vec<&DownloadProgress>, Vec<_>) = events
+            .iter()
+            .partition(|p| p.phase == DownloadPhase::Downloading);
+        // Downloading comes first, counting up to the full size
+        assert_eq!(events[0].phase, DownloadPhase::Downloading);
+        assert_eq!(download[0].downloaded, 0);
+        assert!(download.len() > 2, "{:?}", download);
+        assert!(download
+            .windows(2)
+            .all(|w| w[0].downloaded <= w[1].downloaded));
+        assert!(download.iter().all(|p| p.total == Some(total)));
+        assert_eq!(download.last().unwrap().downloaded, total);
+        // Then one event per extracted file
+        assert_eq!(
+            extract,
+            vec![&DownloadProgress {
+                phase: DownloadPhase::Extracting,
+                downloaded: 1,
+                total: Some(1),
+            }]
+        );
+
+        std::fs::remove_dir_all(&cache).ok();
+    }
+
+    /// How [`range_server`] answers a request.
+    #[derive(Clone, Copy)]
+    enum Reply {
+        /// The whole body with 200.
+        Full,
+        /// The first bytes of the body with 200 and the full
+        /// `Content-Length`, then the connection drops.
+        Drop(usize),
+        /// The rest of the body from the requested offset with 206.
+        Range,
+        /// An empty response with this status line.
+        Status(&'static str),
+    }
+
+    /// Serves `body`, answering the n-th request (from 0) as `reply(n)`,
+    /// and records the `Range` start of each request.
+    async fn range_server(
+        body: Vec<u8>,
+        reply: fn(usize) -> Reply,
+    ) -> (String, std::sync::Arc<std::sync::Mutex<Vec<Option<u64>>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let body = std::sync::Arc::new(body);
+        let ranges = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = std::sync::Arc::clone(&ranges);
+        let requests = std::sync::atomic::AtomicUsize::new(0);
+        let (addr, _) = spawn_connection_server(move |mut socket| {
+            let n = requests.fetch_add(1, Ordering::SeqCst);
+            let body = std::sync::Arc::clone(&body);
+            let recorded = std::sync::Arc::clone(&recorded);
+            async move {
+                let mut buf = [0u8; 4096];
+                let len = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..len]).to_lowercase();
+                let start = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .and_then(|range| range.trim_end_matches('-').parse::<u64>().ok());
+                recorded.lock().unwrap().push(start);
+
+                let response = match reply(n) {
+                    Reply::Full => {
+                        let mut r =
+                            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len())
+                                .into_bytes();
+                        r.extend_from_slice(&body);
+                        r
+                    }
+                    Reply::Drop(sent) => {
+                        let mut r =
+                            format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", body.len())
+                                .into_bytes();
+                        r.extend_from_slice(&body[..sent]);
+                        r
+                    }
+                    Reply::Range => {
+                        let from = start.unwrap_or(0) as usize;
+                        let mut r = format!(
+                            "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\n\
+                             Content-Range: bytes {}-{}/{}\r\n\r\n",
+                            body.len() - from,
+                            from,
+                            body.len() - 1,
+                            body.len()
+                        )
+                        .into_bytes();
+                        r.extend_from_slice(&body[from..]);
+                        r
+                    }
+                    Reply::Status(status) => {
+                        format!("HTTP/1.1 {}\r\nContent-Length: 0\r\n\r\n", status).into_bytes()
+                    }
+                };
+                let _ = socket.write_all(&response).await;
+                let _ = socket.shutdown().await;
+            }
+        })
+        .await;
+        (format!("http://{}/chrome.zip", addr), ranges)
+    }
+
+    /// A body of distinct bytes, so misplaced ranges show.
+    fn numbered_body() -> Vec<u8> {
+        (0..20_000u32).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn test_download_to_file_resumes_after_drop() {
+        let body = numbered_body();
+        let (url, ranges) = range_server(body.clone(), |n| match n {
+            0 => Reply::Drop(8_000),
+            _ => Reply::Range,
+        })
+        .await;
+        let path = std::env::temp_dir().join("a3s_test_resume_drop.zip");
+        std::fs::remove_file(&path).ok();
+
+        let download = download_to_file(
+            &reqwest::Client::new(),
+            &url,
+            &path,
+            3,
+            Duration::from_millis(10),
+            &|_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            download,
+            Download::Complete {
+                size: body.len() as u64,
+                resumed: true
+            }
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(*ranges.lock().unwrap(), vec![None, Some(8_000)]);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_download_to_file_restarts_when_range_ignored() {
+        let body = numbered_body();
+        let (url, ranges) = range_server(body.clone(), |_| Reply::Full).await;
+        let path = std::env::temp_dir().join("a3s_test_resume_ignored.zip");
+        std::fs::write(&path, [0xff; 500]).unwrap();
+
+        let download = download_to_file(
+            &reqwest::Client::new(),
+            &url,
+            &path,
+            1,
+            Duration::ZERO,
+            &|_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            download,
+            Download::Complete {
+                size: body.len() as u64,
+                resumed: false
+            }
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(*ranges.lock().unwrap(), vec![Some(500)]);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_download_to_file_restarts_oversized_partial() {
+        let body = numbered_body();
+        let (url, ranges) = range_server(body.clone(), |n| match n {
+            0 => Reply::Status("416 Range Not Satisfiable"),
+            _ => Reply::Full,
+        })
+        .await;
+        let path = std::env::temp_dir().join("a3s_test_resume_oversized.zip");
+        std::fs::write(&path, vec![0u8; 30_000]).unwrap();
+
+        download_to_file(
+            &reqwest::Client::new(),
+            &url,
+            &path,
+            2,
+            Duration::from_millis(10),
+            &|_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), body);
+        assert_eq!(*ranges.lock().unwrap(), vec![Some(30_000), None]);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[tokio::test]
+    async fn test_download_to_file_gives_up_after_attempts() {
+        let (url, ranges) =
+            range_server(Vec::new(), |_| Reply::Status("503 Service Unavailable")).await;
+        let path = std::env::temp_dir().join("a3s_test_resume_give_up.zip");
+        std::fs::remove_file(&path).ok();
+
+        let err = download_to_file(
+            &reqwest::Client::new(),
+            &url,
+            &path,
+            3,
+            Duration::from_millis(10),
+            &|_| {},
+        )
+        .await
+        .unwrap_err()
+        .to_string();
+
+        assert!(err.contains("503"), "{}", err);
+        assert_eq!(ranges.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_content_range() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(content_range(&headers), None);
+        headers.insert(
+            reqwest::header::CONTENT_RANGE,
+            "bytes 8000-19999/20000".parse().unwrap(),
+        );
+        assert_eq!(content_range(&headers), Some((8000, 20000)));
+        headers.insert(
+            reqwest::header::CONTENT_RANGE,
+            "bytes */20000".parse().unwrap(),
+        );
+        assert_eq!(content_range(&headers), None);
+    }
+
+    #[test]
+    fn test_install_chrome_restarts_corrupt_partial() {
+        let cache = std::env::temp_dir().join("a3s_test_corrupt_partial");
+        std::fs::remove_dir_all(&cache).ok();
+        let platform = platform_id().unwrap();
+        let zip = chrome_zip();
+        // A stale partial download whose bytes do not belong to this zip
+        std::fs::create_dir_all(&cache).unwrap();
+        let partial = cache.join(format!(".download-130.0.6723.58-{}.zip", platform));
+        std::fs::write(&partial, vec![0x5a; zip.len() / 2]).unwrap();
+
+        let (exe, ranges) = with_env(
+            "/tmp/a3s_test_home",
+            &[(CHROME_DIR_ENV, cache.to_str().unwrap())],
+            || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                runtime.block_on(async {
+                    let (url, ranges) = range_server(zip.clone(), |n| match n {
+                        0 => Reply::Range,
+                        _ => Reply::Full,
+                    })
+                    .await;
+                    let exe = install_chrome(
+                        &reqwest::Client::new(),
+                        "130.0.6723.58",
+                        platform,
+                        &url,
+                        None,
+                        &|_| {},
+                    )
+                    .await;
+                    (exe, ranges)
+                })
+            },
+        );
+
+        assert!(exe.unwrap().exists());
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            vec![Some(zip.len() as u64 / 2), None]
+        );
+        assert!(!partial.exists());
+        std::fs::remove_dir_all(&cache).ok();
+    }
+
+    #[test]
+    fn test_concurrent_ensure_version_downloads_once() {
+        let cache = std::env::temp_dir().join("a3s_test_concurrent_download");
+        std::fs::remove_dir_all(&cache).ok();
+        let zip = chrome_zip();
+
+        let (results, ranges) = with_env(
+            "/tmp/a3s_test_home",
+            &[(CHROME_DIR_ENV, cache.to_str().unwrap())],
+            || {
+                let runtime = tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(4)
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                runtime.block_on(async {
+                    let (url, ranges) = range_server(zip.clone(), |_| Reply::Full).await;
+                    let tasks: Vec<_> = (0..4)
+                        .map(|_| {
+                            let url = url.clone();
+                            tokio::spawn(async move {
+                                ensure_version_from(&url, "130.0.6723.58", &|_| {}).await
+                            })
+                        })
+                        .collect();
+                    let results = futures::future::join_all(tasks).await;
+                    (results, ranges)
+                })
+            },
+        );
+
+        let paths: Vec<PathBuf> = results
+            .into_iter()
+            .map(|result| result.unwrap().unwrap())
+            .collect();
+        assert!(paths[0].exists());
+        assert!(paths.iter().all(|path| *path == paths[0]));
+        assert_eq!(ranges.lock().unwrap().len(), 1);
+        std::fs::remove_dir_all(&cache).ok();
+    }
+
+    #[test]
+    fn test_lock_cache_dir_excludes_other_handles() {
+        let cache = std::env::temp_dir().join("a3s_test_cache_lock");
+        std::fs::remove_dir_all(&cache).ok();
+
+        with_env(
+            "/tmp/a3s_test_home",
+            &[(CHROME_DIR_ENV, cache.to_str().unwrap())],
+            || {
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .unwrap();
+                let lock = runtime.block_on(lock_cache_dir()).unwrap();
+                // Another process would open the file separately
+                let other = std::fs::File::open(cache.join(LOCK_FILE)).unwrap();
+                assert!(other.try_lock().is_err());
+                drop(lock);
+                assert!(other.try_lock().is_ok());
+            },
+        );
+        std::fs::remove_dir_all(&cache).ok();
+    }
+
+    #[test]
+    fn test_downloads_disabled() {
+        for (value, disabled) in [
+            ("1", true),
+            ("true", true),
+            ("TRUE", true),
+            ("0", false),
+            ("", false),
+            ("no", false),
+        ] {
+            let actual = with_env("/tmp/a3s_test_home", &[(NO_DOWNLOAD_ENV, value)], || {
+                downloads_disabled()
+            });
+            assert_eq!(actual, disabled, "{:?}", value);
+        }
+        assert!(!with_env("/tmp/a3s_test_home", &[], downloads_disabled));
+    }
+
+    /// Binds a server that only counts the connections it gets.
+    fn counting_server(
+        runtime: &tokio::runtime::Runtime,
+    ) -> (String, std::sync::Arc<std::sync::atomic::AtomicUsize>) {
+        let connections = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = std::sync::Arc::clone(&connections);
+        let (addr, _) = runtime.block_on(spawn_connection_server(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async {}
+        }));
+        (format!("http://{}", addr), connections)
+    }
+
+    #[test]
+    fn test_no_download_never_contacts_servers() {
+        let cache = std::env::temp_dir().join("a3s_test_no_download");
+        std::fs::remove_dir_all(&cache).ok();
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        let (url, connections) = counting_server(&runtime);
+        let env = [
+            (CHROME_DIR_ENV, cache.to_str().unwrap()),
+            (NO_DOWNLOAD_ENV, "1"),
+        ];
+
+        let latest = with_env("/tmp/a3s_test_home", &env, || {
+            runtime.block_on(ensure_chrome_from(&url, &|_| {}))
+        });
+        match detect_chrome() {
+            Some(system) => assert_eq!(latest.unwrap(), system),
+            None => {
+                let err = latest.unwrap_err().to_string();
+                assert!(err.contains("CHROME="), "{}", err);
+            }
+        }
+
+        let pinned = with_env("/tmp/a3s_test_home", &env, || {
+            runtime.block_on(download_chrome_version(
+                &url,
+                "130.0.6723.58",
+                None,
+                &|_| {},
+            ))
+        });
+        let err = pinned.unwrap_err().to_string();
+        assert!(err.contains(NO_DOWNLOAD_ENV), "{}", err);
+        assert!(err.contains("CHROME="), "{}", err);
+
+        assert_eq!(connections.load(Ordering::SeqCst), 0);
+        assert!(!cache.exists());
+    }
+
+    #[test]
+    fn test_find_chrome_uses_pinned_cache() {
+        let cache = std::env::temp_dir().join("a3s_test_find_pinned");
+        std::fs::remove_dir_all(&cache).ok();
+        let exe = cache
+            .join("130.0.6723.58")
+            .join(chrome_executable_in_zip(platform_id().unwrap()));
+        std::fs::create_dir_all(exe.parent().unwrap()).unwrap();
+        std::fs::write(&exe, b"").unwrap();
+
+        let dir = cache.to_str().unwrap();
+        let found = with_env(
+            "/tmp/a3s_test_home",
+            &[(CHROME_DIR_ENV, dir), (CHROME_VERSION_ENV, "130.0.6723.58")],
+            find_chrome,
+        );
+        assert_eq!(found.unwrap(), exe);
+
+        let missing = with_env(
+            "/tmp/a3s_test_home",
+            &[(CHROME_DIR_ENV, dir), (CHROME_VERSION_ENV, "131.0.6778.85")],
+            find_chrome,
+        );
+        let err = missing.unwrap_err().to_string();
+        assert!(err.contains("131.0.6778.85 is not cached"), "{}", err);
+
+        std::fs::remove_dir_all(&cache).ok();
+    }
+
+    #[test]
+    fn test_set_download_attempts_minimum() {
+        set_download_attempts(0);
+        assert_eq!(DOWNLOAD_ATTEMPTS.load(Ordering::Relaxed), 1);
+        set_download_attempts(4);
+        assert_eq!(DOWNLOAD_ATTEMPTS.load(Ordering::Relaxed), 4);
+    }
+
     #[test]
     fn test_chrome_versions_url_is_valid() {
         assert!(CHROME_VERSIONS_URL.starts_with("https://"));
@@ -572,7 +2431,7 @@ mod tests {
         let tmp_dir = std::env::temp_dir().join("a3s_test_extract_valid");
         std::fs::create_dir_all(&tmp_dir).ok();
 
-        let result = extract_zip(&zip_bytes, &tmp_dir);
+        let result = extract_zip(&zip_bytes, &tmp_dir, &|_| {});
         assert!(result.is_ok());
 
         // Verify the file was extracted
@@ -602,7 +2461,7 @@ mod tests {
         let tmp_dir = std::env::temp_dir().join("a3s_test_extract_dir");
         std::fs::create_dir_all(&tmp_dir).ok();
 
-        let result = extract_zip(&zip_bytes, &tmp_dir);
+        let result = extract_zip(&zip_bytes, &tmp_dir, &|_| {});
         assert!(result.is_ok());
 
         let nested = tmp_dir.join("subdir").join("file.txt");
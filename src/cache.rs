@@ -0,0 +1,362 @@
+//! Cache backends for fetched pages and engine results.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::query::fnv1a;
+use crate::{Result, SearchError};
+
+/// A key-value store with expiry, used to cache pages and engine results.
+///
+/// Backends are best effort: a failed write is dropped and a failed read is
+/// a miss, so a broken cache never fails a search.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Returns the value stored under `key`, unless missing or expired.
+    async fn get(&self, key: &str) -> Option<String>;
+
+    /// Stores `value` under `key`, replacing any previous value.
+    async fn insert(&self, key: &str, value: String);
+
+    /// Removes the value stored under `key`, if any.
+    async fn remove(&self, key: &str);
+
+    /// Removes all values.
+    async fn clear(&self);
+}
+
+/// A cached value.
+struct CacheEntry {
+    value: String,
+    /// When the value was stored, for TTL expiry.
+    stored_at: Instant,
+    /// Access counter value at the last hit, for LRU eviction.
+    last_used: u64,
+}
+
+/// Cache state guarded by a single lock.
+#[derive(Default)]
+struct CacheState {
+    entries: HashMap<String, CacheEntry>,
+    /// Monotonic counter bumped on every access.
+    clock: u64,
+}
+
+/// An in-memory cache with TTL expiry and LRU eviction.
+///
+/// Entries expire after `ttl` and the least recently used entry is evicted
+/// once `max_entries` is reached. The cache is lost when the process exits;
+/// use [`DiskCache`] to keep it across restarts.
+pub struct MemoryCache {
+    ttl: Duration,
+    max_entries: usize,
+    state: Mutex<CacheState>,
+}
+
+impl MemoryCache {
+    /// Creates an empty in-memory cache.
+    ///
+    /// A `max_entries` of zero is treated as one.
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries: max_entries.max(1),
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// Returns the number of entries, including expired ones not yet evicted.
+    pub async fn len(&self) -> usize {
+        self.state.lock().await.entries.len()
+    }
+
+    /// Returns true if nothing is cached.
+    pub async fn is_empty(&self) -> bool {
+        self.state.lock().await.entries.is_empty()
+    }
+
+    /// Removes every entry whose key satisfies `predicate`.
+    pub(crate) async fn remove_where(&self, predicate: impl Fn(&str) -> bool) {
+        self.state
+            .lock()
+            .await
+            .entries
+            .retain(|key, _| !predicate(key));
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    async fn get(&self, key: &str) -> Option<String> {
+        let mut state = self.state.lock().await;
+        state.clock += 1;
+        let clock = state.clock;
+
+        let expired = match state.entries.get_mut(key) {
+            Some(entry) if entry.stored_at.elapsed() < self.ttl => {
+                entry.last_used = clock;
+                return Some(entry.value.clone());
+            }
+            Some(_) => true,
+            None => false,
+        };
+        if expired {
+            state.entries.remove(key);
+        }
+        None
+    }
+
+    async fn insert(&self, key: &str, value: String) {
+        let mut state = self.state.lock().await;
+        state.clock += 1;
+        let clock = state.clock;
+
+        if !state.entries.contains_key(key) && state.entries.len() >= self.max_entries {
+            // Drop expired entries first, then the least recently used one
+            let ttl = self.ttl;
+            state
+                .entries
+                .retain(|_, entry| entry.stored_at.elapsed() < ttl);
+            if state.entries.len() >= self.max_entries {
+                let oldest = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    state.entries.remove(&oldest);
+                }
+            }
+        }
+
+        state.entries.insert(
+            key.to_string(),
+            CacheEntry {
+                value,
+                stored_at: Instant::now(),
+                last_used: clock,
+            },
+        );
+    }
+
+    async fn remove(&self, key: &str) {
+        self.state.lock().await.entries.remove(key);
+    }
+
+    async fn clear(&self) {
+        self.state.lock().await.entries.clear();
+    }
+}
+
+/// A cache that persists values as files in a directory.
+///
+/// Each value is stored with its key in its own file named after a hash of
+/// the key, and expires `ttl` after the file was last written. Expired files
+/// are removed when read, and a file holding a different key whose hash
+/// collides is a miss. Files are read and written with `tokio::fs`, off the
+/// async workers.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use a3s_search::{DiskCache, Search};
+///
+/// # fn run() -> a3s_search::Result<()> {
+/// let mut search = Search::new();
+/// search.set_cache(DiskCache::new("/var/cache/a3s-search", Duration::from_secs(3600))?);
+/// # Ok(())
+/// # }
+/// ```
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl DiskCache {
+    /// Creates a disk cache in `dir`, which is created if missing. Values
+    /// already in `dir` are kept.
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| {
+            SearchError::Other(format!("Failed to create {}: {}", dir.display(), e))
+        })?;
+        Ok(Self { dir, ttl })
+    }
+
+    /// Returns the cache directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir
+            .join(format!("{:016x}.json", fnv1a(key.as_bytes())))
+    }
+
+    fn is_fresh(&self, modified: SystemTime) -> bool {
+        SystemTime::now()
+            .duration_since(modified)
+            .is_ok_and(|age| age < self.ttl)
+    }
+}
+
+/// The contents of a [`DiskCache`] file.
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    key: String,
+    value: String,
+}
+
+#[async_trait]
+impl CacheBackend for DiskCache {
+    async fn get(&self, key: &str) -> Option<String> {
+        let path = self.path(key);
+        let meta = tokio::fs::metadata(&path).await.ok()?;
+        if !meta
+            .modified()
+            .is_ok_and(|modified| self.is_fresh(modified))
+        {
+            let _ = tokio::fs::remove_file(&path).await;
+            return None;
+        }
+        let contents = tokio::fs::read_to_string(&path).await.ok()?;
+        let entry: DiskEntry = serde_json::from_str(&contents).ok()?;
+        (entry.key == key).then_some(entry.value)
+    }
+
+    async fn insert(&self, key: &str, value: String) {
+        let entry = DiskEntry {
+            key: key.to_string(),
+            value,
+        };
+        let Ok(contents) = serde_json::to_string(&entry) else {
+            return;
+        };
+        // Write to a temporary file first so readers never see a partial value
+        let path = self.path(key);
+        let tmp = path.with_extension("tmp");
+        if tokio::fs::write(&tmp, contents).await.is_err()
+            || tokio::fs::rename(&tmp, &path).await.is_err()
+        {
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+    }
+
+    async fn remove(&self, key: &str) {
+        let _ = tokio::fs::remove_file(self.path(key)).await;
+    }
+
+    async fn clear(&self) {
+        let Ok(mut entries) = tokio::fs::read_dir(&self.dir).await else {
+            return;
+        };
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                let _ = tokio::fs::remove_file(path).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("a3s-search-cache-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_memory_cache_roundtrip() {
+        let cache = MemoryCache::new(Duration::from_secs(60), 10);
+        assert!(cache.get("a").await.is_none());
+
+        cache.insert("a", "one".to_string()).await;
+        assert_eq!(cache.get("a").await.as_deref(), Some("one"));
+
+        cache.remove("a").await;
+        assert!(cache.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_roundtrip() {
+        let dir = temp_dir("roundtrip");
+        let cache = DiskCache::new(&dir, Duration::from_secs(60)).unwrap();
+        assert!(cache.get("duckduckgo rust").await.is_none());
+
+        cache.insert("duckduckgo rust", "[1,2,3]".to_string()).await;
+        assert_eq!(
+            cache.get("duckduckgo rust").await.as_deref(),
+            Some("[1,2,3]")
+        );
+        assert!(cache.get("duckduckgo go").await.is_none());
+
+        // A new instance on the same directory sees the stored value
+        let reopened = DiskCache::new(&dir, Duration::from_secs(60)).unwrap();
+        assert_eq!(
+            reopened.get("duckduckgo rust").await.as_deref(),
+            Some("[1,2,3]")
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_expiry() {
+        let dir = temp_dir("expiry");
+        let cache = DiskCache::new(&dir, Duration::from_millis(50)).unwrap();
+
+        cache.insert("key", "value".to_string()).await;
+        assert!(cache.get("key").await.is_some());
+        tokio::time::sleep(Duration::from_millis(80)).await;
+
+        assert!(cache.get("key").await.is_none());
+        assert!(!cache.path("key").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_checks_stored_key() {
+        let dir = temp_dir("collision");
+        let cache = DiskCache::new(&dir, Duration::from_secs(60)).unwrap();
+
+        // Another key's value in this key's file, as after a hash collision
+        cache.insert("other", "theirs".to_string()).await;
+        fs::rename(cache.path("other"), cache.path("key")).unwrap();
+        assert!(cache.get("key").await.is_none());
+
+        // Files without a stored key are misses too
+        fs::write(cache.path("key"), "[1,2,3]").unwrap();
+        assert!(cache.get("key").await.is_none());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_remove_and_clear() {
+        let dir = temp_dir("clear");
+        let cache = DiskCache::new(&dir, Duration::from_secs(60)).unwrap();
+
+        cache.insert("a", "1".to_string()).await;
+        cache.insert("b", "2".to_string()).await;
+        cache.remove("a").await;
+        assert!(cache.get("a").await.is_none());
+        assert!(cache.get("b").await.is_some());
+
+        cache.clear().await;
+        assert!(cache.get("b").await.is_none());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,193 @@
+//! DNS resolution cache for the HTTP fetcher.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use futures::future::{join_all, BoxFuture};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+/// Resolves a host name to socket addresses.
+type LookupFn =
+    Arc<dyn Fn(String) -> BoxFuture<'static, io::Result<Vec<SocketAddr>>> + Send + Sync>;
+
+struct DnsCacheInner {
+    ttl: Duration,
+    lookup: LookupFn,
+    entries: Mutex<HashMap<String, (Instant, Vec<SocketAddr>)>>,
+}
+
+/// A reqwest resolver that remembers the addresses of each host for a fixed
+/// TTL.
+///
+/// The OS resolver does not report record TTLs, so every host is kept for
+/// the same duration. Failed lookups are not cached. Clones share entries.
+#[derive(Clone)]
+pub(crate) struct DnsCache {
+    inner: Arc<DnsCacheInner>,
+}
+
+impl DnsCache {
+    /// Creates a cache backed by the system resolver.
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self::with_lookup(
+            ttl,
+            Arc::new(
+                |host: String| -> BoxFuture<'static, io::Result<Vec<SocketAddr>>> {
+                    Box::pin(async move {
+                        Ok(tokio::net::lookup_host((host.as_str(), 0)).await?.collect())
+                    })
+                },
+            ),
+        )
+    }
+
+    /// Creates a cache backed by a custom lookup function.
+    pub(crate) fn with_lookup(ttl: Duration, lookup: LookupFn) -> Self {
+        Self {
+            inner: Arc::new(DnsCacheInner {
+                ttl,
+                lookup,
+                entries: Mutex::new(HashMap::new()),
+            }),
+        }
+    }
+
+    /// Returns the addresses of `host`, looking them up on a miss.
+    pub(crate) async fn lookup(&self, host: &str) -> io::Result<Vec<SocketAddr>> {
+        let host = host.to_ascii_lowercase();
+        if let Some(addrs) = self.cached(&host) {
+            return Ok(addrs);
+        }
+
+        let addrs = (self.inner.lookup)(host.clone()).await?;
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No addresses found for {}", host),
+            ));
+        }
+        self.inner
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(host, (Instant::now(), addrs.clone()));
+        Ok(addrs)
+    }
+
+    /// Looks up every host concurrently and returns how many resolved.
+    pub(crate) async fn preresolve<I, S>(&self, hosts: I) -> usize
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let hosts: Vec<String> = hosts.into_iter().map(|h| h.as_ref().to_string()).collect();
+        join_all(hosts.iter().map(|host| self.lookup(host)))
+            .await
+            .iter()
+            .filter(|result| result.is_ok())
+            .count()
+    }
+
+    fn cached(&self, host: &str) -> Option<Vec<SocketAddr>> {
+        let mut entries = self.inner.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(host) {
+            Some((at, addrs)) if at.elapsed() < self.inner.ttl => Some(addrs.clone()),
+            Some(_) => {
+                entries.remove(host);
+                None
+            }
+            None => None,
+        }
+    }
+}
+
+impl fmt::Debug for DnsCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DnsCache")
+            .field("ttl", &self.inner.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Resolve for DnsCache {
+    fn resolve(&self, name: Name) -> Resolving {
+        let cache = self.clone();
+        Box::pin(async move {
+            let addrs = cache.lookup(name.as_str()).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Returns a cache whose lookups resolve every host to localhost,
+    /// except "missing.test", and the lookup counter.
+    fn counting_cache(ttl: Duration) -> (DnsCache, Arc<AtomicUsize>) {
+        let count = Arc::new(AtomicUsize::new(0));
+        let counter = count.clone();
+        let cache = DnsCache::with_lookup(
+            ttl,
+            Arc::new(
+                move |host: String| -> BoxFuture<'static, io::Result<Vec<SocketAddr>>> {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    Box::pin(async move {
+                        if host == "missing.test" {
+                            return Err(io::Error::new(io::ErrorKind::NotFound, "no such host"));
+                        }
+                        Ok(vec![SocketAddr::from(([127, 0, 0, 1], 0))])
+                    })
+                },
+            ),
+        );
+        (cache, count)
+    }
+
+    #[tokio::test]
+    async fn test_dns_cache_hit_skips_lookup() {
+        let (cache, count) = counting_cache(Duration::from_secs(60));
+
+        cache.lookup("engine.test").await.unwrap();
+        cache.lookup("ENGINE.test").await.unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+
+        cache.lookup("other.test").await.unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dns_cache_expiry() {
+        let (cache, count) = counting_cache(Duration::ZERO);
+
+        cache.lookup("engine.test").await.unwrap();
+        cache.lookup("engine.test").await.unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dns_cache_failures_not_cached() {
+        let (cache, count) = counting_cache(Duration::from_secs(60));
+
+        assert!(cache.lookup("missing.test").await.is_err());
+        assert!(cache.lookup("missing.test").await.is_err());
+        assert_eq!(count.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_dns_cache_preresolve() {
+        let (cache, count) = counting_cache(Duration::from_secs(60));
+
+        let resolved = cache.preresolve(["a.test", "b.test", "missing.test"]).await;
+        assert_eq!(resolved, 2);
+
+        cache.lookup("a.test").await.unwrap();
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+}
@@ -3,7 +3,7 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 
-use crate::{Result, SearchQuery, SearchResult};
+use crate::{FetcherHint, Result, SearchQuery, SearchResult};
 
 /// Categories for search engines.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -104,6 +104,15 @@ pub trait Engine: Send + Sync {
     fn is_enabled(&self) -> bool {
         self.config().enabled
     }
+
+    /// Returns which fetcher path should serve the fetches for `query`
+    /// (default: [`FetcherHint::Auto`]).
+    ///
+    /// `Search` passes it on to the fetchers the engine uses, e.g. to go
+    /// straight to the browser for queries known to get blocked over HTTP.
+    fn preferred_fetcher(&self, _query: &SearchQuery) -> FetcherHint {
+        FetcherHint::Auto
+    }
 }
 
 #[cfg(test)]
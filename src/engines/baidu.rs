@@ -6,10 +6,10 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use scraper::{Html, Selector};
 
 use crate::fetcher::PageFetcher;
-use crate::{Engine, EngineCategory, EngineConfig, Result, SearchError, SearchQuery, SearchResult};
+use crate::html::{HtmlDocument, HtmlSelector};
+use crate::{Engine, EngineCategory, EngineConfig, Result, SearchQuery, SearchResult};
 
 /// Baidu search engine (百度).
 ///
@@ -21,19 +21,24 @@ pub struct Baidu {
 }
 
 impl Baidu {
+    /// Returns the configuration a new engine starts with.
+    pub(crate) fn default_config() -> EngineConfig {
+        EngineConfig {
+            name: "Baidu".to_string(),
+            shortcut: "baidu".to_string(),
+            categories: vec![EngineCategory::General],
+            weight: 1.0,
+            timeout: 10,
+            enabled: true,
+            paging: true,
+            safesearch: false,
+        }
+    }
+
     /// Creates a new Baidu engine with the given page fetcher.
     pub fn new(fetcher: Arc<dyn PageFetcher>) -> Self {
         Self {
-            config: EngineConfig {
-                name: "Baidu".to_string(),
-                shortcut: "baidu".to_string(),
-                categories: vec![EngineCategory::General],
-                weight: 1.0,
-                timeout: 10,
-                enabled: true,
-                paging: true,
-                safesearch: false,
-            },
+            config: Self::default_config(),
             fetcher,
         }
     }
@@ -45,15 +50,12 @@ impl Baidu {
     }
 
     fn parse_results(&self, html: &str) -> Result<Vec<SearchResult>> {
-        let document = Html::parse_document(html);
+        let document = HtmlDocument::parse(html);
 
-        let result_selector = Selector::parse("div.result, div.c-container")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let title_selector = Selector::parse("h3 a, .t a")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
+        let result_selector = HtmlSelector::parse("div.result, div.c-container")?;
+        let title_selector = HtmlSelector::parse("h3 a, .t a")?;
         let snippet_selector =
-            Selector::parse(".c-abstract, .c-span-last, .content-right_8Zs40")
-                .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
+            HtmlSelector::parse(".c-abstract, .c-span-last, .content-right_8Zs40")?;
 
         let mut results = Vec::new();
 
@@ -63,20 +65,16 @@ impl Baidu {
                 None => continue,
             };
 
-            let title = title_elem.text().collect::<String>().trim().to_string();
-            let url = title_elem
-                .value()
-                .attr("href")
-                .unwrap_or_default()
-                .to_string();
+            let title = title_elem.text().trim().to_string();
+            let url = title_elem.attr("href").unwrap_or_default().to_string();
 
             let content = element
                 .select(&snippet_selector)
                 .next()
-                .map(|e| e.text().collect::<String>().trim().to_string())
+                .map(|e| e.text().trim().to_string())
                 .unwrap_or_default();
 
-            if !url.is_empty() && !title.is_empty() {
+            if !title.is_empty() && super::is_web_url(&url) {
                 results.push(SearchResult::new(url, title, content));
             }
         }
@@ -92,10 +90,14 @@ impl Engine for Baidu {
     }
 
     async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
-        let url = format!(
+        let mut url = format!(
             "https://www.baidu.com/s?wd={}",
             urlencoding::encode(&query.query)
         );
+        // Baidu pages hold ten results; `pn` skips those of earlier pages
+        if query.page > 1 {
+            url.push_str(&format!("&pn={}", query.page_offset(10)));
+        }
 
         let html = self.fetcher.fetch(&url).await?;
         self.parse_results(&html)
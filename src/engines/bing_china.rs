@@ -6,10 +6,12 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use scraper::{Html, Selector};
 
-use crate::fetcher::PageFetcher;
-use crate::{Engine, EngineCategory, EngineConfig, Result, SearchError, SearchQuery, SearchResult};
+use crate::fetcher::{FetchRequest, PageFetcher};
+use crate::html::{HtmlDocument, HtmlSelector};
+use crate::{
+    Engine, EngineCategory, EngineConfig, Result, ResultType, SafeSearch, SearchQuery, SearchResult,
+};
 
 /// Bing China search engine (必应中国).
 ///
@@ -21,19 +23,24 @@ pub struct BingChina {
 }
 
 impl BingChina {
+    /// Returns the configuration a new engine starts with.
+    pub(crate) fn default_config() -> EngineConfig {
+        EngineConfig {
+            name: "Bing China".to_string(),
+            shortcut: "bing_cn".to_string(),
+            categories: vec![EngineCategory::General],
+            weight: 1.0,
+            timeout: 10,
+            enabled: true,
+            paging: true,
+            safesearch: true,
+        }
+    }
+
     /// Creates a new Bing China engine with the given page fetcher.
     pub fn new(fetcher: Arc<dyn PageFetcher>) -> Self {
         Self {
-            config: EngineConfig {
-                name: "Bing China".to_string(),
-                shortcut: "bing_cn".to_string(),
-                categories: vec![EngineCategory::General],
-                weight: 1.0,
-                timeout: 10,
-                enabled: true,
-                paging: true,
-                safesearch: true,
-            },
+            config: Self::default_config(),
             fetcher,
         }
     }
@@ -44,39 +51,68 @@ impl BingChina {
         self
     }
 
-    fn parse_results(&self, html: &str) -> Result<Vec<SearchResult>> {
-        let document = Html::parse_document(html);
+    /// Parses web results, plus the cards of video and news answers if
+    /// `categories` asks for them (see [`super::snippet_result_type`]).
+    fn parse_results(
+        &self,
+        html: &str,
+        categories: &[EngineCategory],
+    ) -> Result<Vec<SearchResult>> {
+        let document = HtmlDocument::parse(html);
 
-        let result_selector = Selector::parse("li.b_algo")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let title_selector = Selector::parse("h2 a")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let snippet_selector = Selector::parse(".b_caption p, .b_algoSlug")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
+        let result_selector =
+            HtmlSelector::parse(&format!("li.b_algo, {}, {}", VIDEO_CARDS, NEWS_CARDS))?;
+        let video_selector = HtmlSelector::parse(VIDEO_CARDS)?;
+        let news_selector = HtmlSelector::parse(NEWS_CARDS)?;
+        let title_selector = HtmlSelector::parse("h2 a, a.title, a.mc_vtvc_link")?;
+        let card_title_selector = HtmlSelector::parse(".mc_vtvc_title")?;
+        let snippet_selector =
+            HtmlSelector::parse(".b_caption p, .b_algoSlug, .snippet, .mc_vtvc_meta")?;
+        let thumbnail_selector = HtmlSelector::parse(r#"img[src^="http"]"#)?;
 
         let mut results = Vec::new();
 
         for element in document.select(&result_selector) {
+            let kind = if video_selector.matches(&element) {
+                "video"
+            } else if news_selector.matches(&element) {
+                "news"
+            } else {
+                "web"
+            };
+            let Some(result_type) = super::snippet_result_type(kind, categories) else {
+                continue;
+            };
+
             let title_elem = match element.select(&title_selector).next() {
                 Some(el) => el,
                 None => continue,
             };
 
-            let title = title_elem.text().collect::<String>().trim().to_string();
-            let url = title_elem
-                .value()
-                .attr("href")
-                .unwrap_or_default()
+            // Video cards keep their title apart from the link's other text
+            let title = element
+                .select(&card_title_selector)
+                .next()
+                .unwrap_or(title_elem)
+                .text()
+                .trim()
                 .to_string();
+            let url = title_elem.attr("href").unwrap_or_default().to_string();
 
             let content = element
                 .select(&snippet_selector)
                 .next()
-                .map(|e| e.text().collect::<String>().trim().to_string())
+                .map(|e| e.text().trim().to_string())
                 .unwrap_or_default();
 
-            if !url.is_empty() && !title.is_empty() && url.starts_with("http") {
-                results.push(SearchResult::new(url, title, content));
+            if !title.is_empty() && super::is_web_url(&url) {
+                let mut result = SearchResult::new(url, title, content).with_type(result_type);
+                if result_type != ResultType::Web {
+                    if let Some(src) = element.select(&thumbnail_selector).next() {
+                        result = result.with_thumbnail(src.attr("src").unwrap_or_default());
+                    }
+                }
+                results.push(result);
             }
         }
 
@@ -84,6 +120,41 @@ impl BingChina {
     }
 }
 
+/// Video cards of a video answer.
+const VIDEO_CARDS: &str = ".b_vidAns .mc_vtvc";
+
+/// Article cards of a news answer.
+const NEWS_CARDS: &str = ".b_nwsAns .news-card";
+
+/// Bing's cap on `count`.
+const MAX_RESULTS: u32 = 50;
+
+/// Results on a page when `count` is not set.
+const PAGE_SIZE: u32 = 10;
+
+/// Builds the results page URL, asking for `count` results when the query
+/// sets a count and starting later pages at their `first` result.
+fn search_url(query: &SearchQuery) -> String {
+    let mut url = format!(
+        "https://cn.bing.com/search?q={}",
+        urlencoding::encode(&query.query)
+    );
+    let count = query.result_count(MAX_RESULTS);
+    if let Some(count) = count {
+        url.push_str(&format!("&count={}", count));
+    }
+    if query.page > 1 {
+        let first = query.page_offset(count.unwrap_or(PAGE_SIZE)) + 1;
+        url.push_str(&format!("&first={}", first));
+    }
+    match query.safesearch {
+        SafeSearch::Off => {}
+        SafeSearch::Moderate => url.push_str("&adlt=moderate"),
+        SafeSearch::Strict => url.push_str("&adlt=strict"),
+    }
+    url
+}
+
 #[async_trait]
 impl Engine for BingChina {
     fn config(&self) -> &EngineConfig {
@@ -91,13 +162,15 @@ impl Engine for BingChina {
     }
 
     async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
-        let url = format!(
-            "https://cn.bing.com/search?q={}",
-            urlencoding::encode(&query.query)
-        );
+        let url = search_url(query);
 
-        let html = self.fetcher.fetch(&url).await?;
-        self.parse_results(&html)
+        let mut request = FetchRequest::get(url);
+        if let Some(language) = query.accept_language() {
+            request = request.with_header("Accept-Language", language);
+        }
+        let response = self.fetcher.fetch_request(&request).await?;
+
+        self.parse_results(&response.body, &query.categories)
     }
 }
 
@@ -123,6 +196,25 @@ mod tests {
         assert!(engine.config.safesearch);
     }
 
+    #[test]
+    fn test_search_url_results_per_engine() {
+        let query = SearchQuery::new("rust");
+        assert_eq!(search_url(&query), "https://cn.bing.com/search?q=rust");
+        let query = query.with_results_per_engine(30);
+        assert!(search_url(&query).ends_with("&count=30"));
+        let query = query.with_results_per_engine(200);
+        assert!(search_url(&query).ends_with("&count=50"));
+    }
+
+    #[test]
+    fn test_search_url_safesearch() {
+        let query = SearchQuery::new("rust").with_safesearch(SafeSearch::Moderate);
+        assert_eq!(
+            search_url(&query),
+            "https://cn.bing.com/search?q=rust&adlt=moderate"
+        );
+    }
+
     #[test]
     fn test_bing_china_with_config() {
         let custom_config = EngineConfig {
@@ -149,7 +241,9 @@ mod tests {
     #[test]
     fn test_parse_results_empty_html() {
         let engine = make_bing_china();
-        let results = engine.parse_results("<html><body></body></html>").unwrap();
+        let results = engine
+            .parse_results("<html><body></body></html>", &[])
+            .unwrap();
         assert!(results.is_empty());
     }
 
@@ -172,7 +266,9 @@ mod tests {
             </body>
             </html>
         "#;
-        let results = engine.parse_results(html).unwrap();
+        let results = engine
+            .parse_results(html, &[EngineCategory::General])
+            .unwrap();
         assert_eq!(results.len(), 2);
         assert_eq!(results[0].title, "Rust Programming Language");
         assert_eq!(results[0].url, "https://www.rust-lang.org/");
@@ -180,6 +276,61 @@ mod tests {
         assert_eq!(results[1].title, "The Rust Book");
     }
 
+    #[test]
+    fn test_parse_results_typed_cards() {
+        let engine = make_bing_china();
+        let html = include_str!("../../tests/fixtures/bing_serp_mixed.html");
+
+        // Web-only queries skip video and news cards
+        let results = engine
+            .parse_results(html, &[EngineCategory::General])
+            .unwrap();
+        let urls: Vec<&str> = results.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://rust-lang.github.io/async-book/",
+                "https://tokio.rs/tokio/tutorial"
+            ]
+        );
+
+        let results = engine
+            .parse_results(
+                html,
+                &[
+                    EngineCategory::General,
+                    EngineCategory::Videos,
+                    EngineCategory::News,
+                ],
+            )
+            .unwrap();
+        let types: Vec<ResultType> = results.iter().map(|r| r.result_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                ResultType::Web,
+                ResultType::Video,
+                ResultType::News,
+                ResultType::Web
+            ]
+        );
+
+        let video = &results[1];
+        assert_eq!(video.url, "https://www.bilibili.com/video/BV1Ki4y1X7Z8");
+        assert_eq!(video.title, "Rust 异步编程入门");
+        assert_eq!(video.content, "bilibili · 2.1万次观看");
+        assert_eq!(
+            video.thumbnail.as_deref(),
+            Some("https://tse1.mm.bing.net/th?id=OVP.rust-async")
+        );
+
+        let news = &results[2];
+        assert_eq!(news.url, "https://www.infoq.cn/article/rust-async-closures");
+        assert_eq!(news.title, "Rust 异步闭包进入稳定版");
+        assert_eq!(news.content, "Rust 1.85 稳定了异步闭包。");
+        assert!(news.thumbnail.is_none());
+    }
+
     #[test]
     fn test_parse_results_skips_non_http_urls() {
         let engine = make_bing_china();
@@ -192,7 +343,9 @@ mod tests {
             </body>
             </html>
         "#;
-        let results = engine.parse_results(html).unwrap();
+        let results = engine
+            .parse_results(html, &[EngineCategory::General])
+            .unwrap();
         assert!(results.is_empty());
     }
 
@@ -208,7 +361,9 @@ mod tests {
             </body>
             </html>
         "#;
-        let results = engine.parse_results(html).unwrap();
+        let results = engine
+            .parse_results(html, &[EngineCategory::General])
+            .unwrap();
         assert!(results.is_empty());
     }
 
@@ -225,8 +380,34 @@ mod tests {
             </body>
             </html>
         "#;
-        let results = engine.parse_results(html).unwrap();
+        let results = engine
+            .parse_results(html, &[EngineCategory::General])
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].content, "Snippet from algo slug.");
     }
+
+    #[tokio::test]
+    async fn test_bing_china_sends_accept_language() {
+        let fetcher = crate::engines::CapturingFetcher::new("");
+        let engine = BingChina::new(fetcher.clone());
+        engine
+            .search(&SearchQuery::new("rust").with_language("en-US"))
+            .await
+            .unwrap();
+        engine.search(&SearchQuery::new("rust")).await.unwrap();
+
+        let languages: Vec<Option<String>> = fetcher
+            .requests()
+            .iter()
+            .map(|request| {
+                request
+                    .headers
+                    .iter()
+                    .find(|(name, _)| name == "Accept-Language")
+                    .map(|(_, value)| value.clone())
+            })
+            .collect();
+        assert_eq!(languages, vec![Some("en-US,en;q=0.9".to_string()), None]);
+    }
 }
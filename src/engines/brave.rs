@@ -3,18 +3,24 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use scraper::{Html, Selector};
 
-use crate::fetcher::PageFetcher;
+use crate::fetcher::{FetchRequest, PageFetcher};
+use crate::html::{HtmlDocument, HtmlSelector};
 use crate::{
-    Engine, EngineCategory, EngineConfig, HttpFetcher, Result, SearchError, SearchQuery,
-    SearchResult,
+    Engine, EngineCategory, EngineConfig, HttpFetcher, Result, ResultType, SafeSearch, SearchQuery,
+    SearchResult, TimeRange,
 };
 
+/// Origin requests are sent to unless overridden with
+/// [`Brave::with_base_url`].
+const DEFAULT_BASE_URL: &str = "https://search.brave.com";
+
 /// Brave search engine.
 pub struct Brave {
     config: EngineConfig,
     fetcher: Arc<dyn PageFetcher>,
+    /// Origin of the engine's endpoints.
+    base_url: String,
 }
 
 impl Brave {
@@ -23,23 +29,36 @@ impl Brave {
         Self::with_fetcher(Arc::new(HttpFetcher::new()))
     }
 
+    /// Returns the configuration a new engine starts with.
+    pub(crate) fn default_config() -> EngineConfig {
+        EngineConfig {
+            name: "Brave".to_string(),
+            shortcut: "brave".to_string(),
+            categories: vec![EngineCategory::General],
+            weight: 1.0,
+            timeout: 5,
+            enabled: true,
+            paging: true,
+            safesearch: true,
+        }
+    }
+
     /// Creates a new Brave engine with a custom page fetcher.
     pub fn with_fetcher(fetcher: Arc<dyn PageFetcher>) -> Self {
         Self {
-            config: EngineConfig {
-                name: "Brave".to_string(),
-                shortcut: "brave".to_string(),
-                categories: vec![EngineCategory::General],
-                weight: 1.0,
-                timeout: 5,
-                enabled: true,
-                paging: true,
-                safesearch: true,
-            },
+            config: Self::default_config(),
             fetcher,
+            base_url: DEFAULT_BASE_URL.to_string(),
         }
     }
 
+    /// Sends requests to `base_url` instead of `https://search.brave.com`, e.g. a
+    /// mirror or a local test server.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
     /// Creates with custom configuration.
     pub fn with_config(mut self, config: EngineConfig) -> Self {
         self.config = config;
@@ -60,54 +79,96 @@ impl Engine for Brave {
     }
 
     async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
-        let url = format!(
-            "https://search.brave.com/search?q={}",
+        let mut url = format!(
+            "{}/search?q={}",
+            self.base_url,
             urlencoding::encode(&query.query)
         );
+        if let Some(range) = query.time_range {
+            url.push_str(&format!("&tf={}", time_range_code(range)));
+        }
+        // Brave's `offset` counts pages from zero
+        if query.page > 1 {
+            url.push_str(&format!("&offset={}", query.page_offset(1)));
+        }
 
-        let html = self.fetcher.fetch(&url).await?;
+        let mut request = FetchRequest::get(url);
+        if let Some(language) = query.accept_language() {
+            request = request.with_header("Accept-Language", language);
+        }
+        // Brave reads the filter level from its settings cookie
+        match query.safesearch {
+            SafeSearch::Off => {}
+            SafeSearch::Moderate => request = request.with_header("Cookie", "safesearch=moderate"),
+            SafeSearch::Strict => request = request.with_header("Cookie", "safesearch=strict"),
+        }
+        let response = self.fetcher.fetch_request(&request).await?;
 
-        self.parse_results(&html)
+        self.parse_results(&response.body, &query.categories)
+    }
+}
+
+/// Brave's `tf` value for a time range ("past day", ...).
+fn time_range_code(range: TimeRange) -> &'static str {
+    match range {
+        TimeRange::Day => "pd",
+        TimeRange::Week => "pw",
+        TimeRange::Month => "pm",
+        TimeRange::Year => "py",
     }
 }
 
 impl Brave {
-    fn parse_results(&self, html: &str) -> Result<Vec<SearchResult>> {
-        let document = Html::parse_document(html);
-
-        let result_selector = Selector::parse(r#"div.snippet[data-type="web"]"#)
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let title_selector = Selector::parse(".search-snippet-title")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let desc_selector = Selector::parse(".generic-snippet .content, .snippet-description")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let url_selector = Selector::parse(r#"a[href^="http"]"#)
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
+    /// Parses web snippets, plus video and news snippets if `categories`
+    /// asks for them (see [`super::snippet_result_type`]).
+    fn parse_results(
+        &self,
+        html: &str,
+        categories: &[EngineCategory],
+    ) -> Result<Vec<SearchResult>> {
+        let document = HtmlDocument::parse(html);
+
+        let result_selector = HtmlSelector::parse("div.snippet[data-type]")?;
+        let title_selector = HtmlSelector::parse(".search-snippet-title")?;
+        let desc_selector = HtmlSelector::parse(".generic-snippet .content, .snippet-description")?;
+        let url_selector = HtmlSelector::parse(r#"a[href^="http"]"#)?;
+        let thumbnail_selector = HtmlSelector::parse(r#"img[src^="http"]"#)?;
 
         let mut results = Vec::new();
 
         for element in document.select(&result_selector) {
+            let kind = element.attr("data-type").unwrap_or_default();
+            let Some(result_type) = super::snippet_result_type(kind, categories) else {
+                continue;
+            };
+
             let title = element
                 .select(&title_selector)
                 .next()
-                .map(|e| e.text().collect::<String>().trim().to_string())
+                .map(|e| e.text().trim().to_string())
                 .unwrap_or_default();
 
             let url = element
                 .select(&url_selector)
                 .next()
-                .and_then(|e| e.value().attr("href"))
+                .and_then(|e| e.attr("href"))
                 .unwrap_or_default()
                 .to_string();
 
             let content = element
                 .select(&desc_selector)
                 .next()
-                .map(|e| e.text().collect::<String>().trim().to_string())
+                .map(|e| e.text().trim().to_string())
                 .unwrap_or_default();
 
-            if !url.is_empty() && !title.is_empty() && url.starts_with("http") {
-                results.push(SearchResult::new(url, title, content));
+            if !title.is_empty() && super::is_web_url(&url) {
+                let mut result = SearchResult::new(url, title, content).with_type(result_type);
+                if result_type != ResultType::Web {
+                    if let Some(src) = element.select(&thumbnail_selector).next() {
+                        result = result.with_thumbnail(src.attr("src").unwrap_or_default());
+                    }
+                }
+                results.push(result);
             }
         }
 
@@ -162,7 +223,9 @@ mod tests {
     #[test]
     fn test_brave_parse_results_empty() {
         let engine = Brave::new();
-        let results = engine.parse_results("<html><body></body></html>").unwrap();
+        let results = engine
+            .parse_results("<html><body></body></html>", &[])
+            .unwrap();
         assert!(results.is_empty());
     }
 
@@ -181,7 +244,9 @@ mod tests {
         </div>
         </body></html>
         "#;
-        let results = engine.parse_results(html).unwrap();
+        let results = engine
+            .parse_results(html, &[EngineCategory::General])
+            .unwrap();
         assert_eq!(results.len(), 2);
         assert_eq!(results[0].title, "Rust Programming Language");
         assert_eq!(results[0].url, "https://www.rust-lang.org/");
@@ -207,8 +272,138 @@ mod tests {
         </div>
         </body></html>
         "#;
-        let results = engine.parse_results(html).unwrap();
+        let results = engine
+            .parse_results(html, &[EngineCategory::General])
+            .unwrap();
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].title, "A Page");
     }
+
+    #[test]
+    fn test_brave_parse_results_typed_snippets() {
+        let engine = Brave::new();
+        let html = include_str!("../../tests/fixtures/brave_serp_mixed.html");
+
+        // Web-only queries skip video and news snippets
+        let results = engine
+            .parse_results(html, &[EngineCategory::General])
+            .unwrap();
+        let urls: Vec<&str> = results.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://rust-lang.github.io/async-book/",
+                "https://tokio.rs/tokio/tutorial"
+            ]
+        );
+        assert!(results.iter().all(|r| r.result_type == ResultType::Web));
+
+        let results = engine
+            .parse_results(
+                html,
+                &[
+                    EngineCategory::General,
+                    EngineCategory::Videos,
+                    EngineCategory::News,
+                ],
+            )
+            .unwrap();
+        let types: Vec<ResultType> = results.iter().map(|r| r.result_type).collect();
+        assert_eq!(
+            types,
+            vec![
+                ResultType::Web,
+                ResultType::News,
+                ResultType::Video,
+                ResultType::Web
+            ]
+        );
+        assert_eq!(results[1].title, "Async closures land in Rust nightly");
+        assert_eq!(
+            results[1].content,
+            "The long-awaited feature is ready for testing."
+        );
+        assert_eq!(
+            results[2].url,
+            "https://www.youtube.com/watch?v=ThjvMReOXYM"
+        );
+        assert_eq!(
+            results[2].thumbnail.as_deref(),
+            Some("https://imgs.search.brave.com/thumb/rust-async.jpg")
+        );
+        assert!(results[0].thumbnail.is_none());
+
+        // Only the requested kind is captured
+        let results = engine
+            .parse_results(html, &[EngineCategory::Videos])
+            .unwrap();
+        let types: Vec<ResultType> = results.iter().map(|r| r.result_type).collect();
+        assert_eq!(
+            types,
+            vec![ResultType::Web, ResultType::Video, ResultType::Web]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_brave_sends_accept_language() {
+        let fetcher = crate::engines::CapturingFetcher::new("");
+        let engine = Brave::with_fetcher(fetcher.clone());
+        engine
+            .search(&SearchQuery::new("rust").with_language("en-US"))
+            .await
+            .unwrap();
+        engine.search(&SearchQuery::new("rust")).await.unwrap();
+
+        let languages: Vec<Option<String>> = fetcher
+            .requests()
+            .iter()
+            .map(|request| {
+                request
+                    .headers
+                    .iter()
+                    .find(|(name, _)| name == "Accept-Language")
+                    .map(|(_, value)| value.clone())
+            })
+            .collect();
+        assert_eq!(languages, vec![Some("en-US,en;q=0.9".to_string()), None]);
+    }
+
+    #[tokio::test]
+    async fn test_brave_time_range() {
+        let fetcher = crate::engines::CapturingFetcher::new("");
+        let query = SearchQuery::new("rust").with_time_range(TimeRange::Month);
+        Brave::with_fetcher(fetcher.clone())
+            .search(&query)
+            .await
+            .unwrap();
+        assert_eq!(
+            fetcher.urls(),
+            vec!["https://search.brave.com/search?q=rust&tf=pm"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_brave_safesearch_cookie() {
+        let fetcher = crate::engines::CapturingFetcher::new("");
+        let engine = Brave::with_fetcher(fetcher.clone());
+        for level in [SafeSearch::Off, SafeSearch::Strict] {
+            engine
+                .search(&SearchQuery::new("rust").with_safesearch(level))
+                .await
+                .unwrap();
+        }
+
+        let cookies: Vec<Option<String>> = fetcher
+            .requests()
+            .iter()
+            .map(|request| {
+                request
+                    .headers
+                    .iter()
+                    .find(|(name, _)| name == "Cookie")
+                    .map(|(_, value)| value.clone())
+            })
+            .collect();
+        assert_eq!(cookies, vec![None, Some("safesearch=strict".to_string())]);
+    }
 }
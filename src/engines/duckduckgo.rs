@@ -3,18 +3,24 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use scraper::{Html, Selector};
 
-use crate::fetcher::PageFetcher;
+use crate::fetcher::{FetchRequest, PageFetcher};
+use crate::html::{HtmlDocument, HtmlSelector};
 use crate::{
-    Engine, EngineCategory, EngineConfig, HttpFetcher, Result, SearchError, SearchQuery,
-    SearchResult,
+    Engine, EngineCategory, EngineConfig, HttpFetcher, Result, SafeSearch, SearchQuery,
+    SearchResult, TimeRange,
 };
 
+/// Origin requests are sent to unless overridden with
+/// [`DuckDuckGo::with_base_url`].
+const DEFAULT_BASE_URL: &str = "https://html.duckduckgo.com";
+
 /// DuckDuckGo search engine.
 pub struct DuckDuckGo {
     config: EngineConfig,
     fetcher: Arc<dyn PageFetcher>,
+    /// Origin of the engine's endpoints.
+    base_url: String,
 }
 
 impl DuckDuckGo {
@@ -23,23 +29,36 @@ impl DuckDuckGo {
         Self::with_fetcher(Arc::new(HttpFetcher::new()))
     }
 
+    /// Returns the configuration a new engine starts with.
+    pub(crate) fn default_config() -> EngineConfig {
+        EngineConfig {
+            name: "DuckDuckGo".to_string(),
+            shortcut: "ddg".to_string(),
+            categories: vec![EngineCategory::General],
+            weight: 1.0,
+            timeout: 5,
+            enabled: true,
+            paging: true,
+            safesearch: true,
+        }
+    }
+
     /// Creates a new DuckDuckGo engine with a custom page fetcher.
     pub fn with_fetcher(fetcher: Arc<dyn PageFetcher>) -> Self {
         Self {
-            config: EngineConfig {
-                name: "DuckDuckGo".to_string(),
-                shortcut: "ddg".to_string(),
-                categories: vec![EngineCategory::General],
-                weight: 1.0,
-                timeout: 5,
-                enabled: true,
-                paging: true,
-                safesearch: true,
-            },
+            config: Self::default_config(),
             fetcher,
+            base_url: DEFAULT_BASE_URL.to_string(),
         }
     }
 
+    /// Sends requests to `base_url` instead of `https://html.duckduckgo.com`, e.g. a
+    /// mirror or a local test server.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
     /// Creates with custom configuration.
     pub fn with_config(mut self, config: EngineConfig) -> Self {
         self.config = config;
@@ -60,26 +79,81 @@ impl Engine for DuckDuckGo {
     }
 
     async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
-        let url = format!(
-            "https://html.duckduckgo.com/html/?q={}",
-            urlencoding::encode(&query.query)
-        );
+        let endpoint = format!("{}/html/", self.base_url);
+        let fields = form_fields(query);
+        let language = query.accept_language();
+
+        // The first page is a plain GET, which every fetcher and cache can
+        // serve; later pages are form POSTs, like the endpoint's "Next" button
+        let request = if query.page > 1 {
+            FetchRequest::post_form(endpoint, fields)
+                .with_header("Referer", format!("{}/", self.base_url))
+        } else {
+            let url = format!("{}?{}", endpoint, query_string(&fields));
+            if language.is_none() {
+                return self.parse_results(&self.fetcher.fetch(&url).await?);
+            }
+            FetchRequest::get(url)
+        };
+        let request = match language {
+            Some(language) => request.with_header("Accept-Language", language),
+            None => request,
+        };
 
-        let html = self.fetcher.fetch(&url).await?;
+        let response = self.fetcher.fetch_request(&request).await?;
 
-        self.parse_results(&html)
+        self.parse_results(&response.body)
+    }
+}
+
+/// Builds the search form, including the result offset for pages after the
+/// first (the first page holds 20 results, later pages 50).
+fn form_fields(query: &SearchQuery) -> Vec<(String, String)> {
+    let mut fields = vec![("q".to_string(), query.query.clone())];
+    if query.page > 1 {
+        let offset = 20 + (query.page - 2) * 50;
+        fields.push(("s".to_string(), offset.to_string()));
+        fields.push(("dc".to_string(), (offset + 1).to_string()));
+        fields.push(("v".to_string(), "l".to_string()));
+        fields.push(("o".to_string(), "json".to_string()));
+        fields.push(("api".to_string(), "d.js".to_string()));
+    }
+    if let Some(range) = query.time_range {
+        fields.push(("df".to_string(), time_range_code(range).to_string()));
+    }
+    match query.safesearch {
+        SafeSearch::Off => {}
+        SafeSearch::Moderate => fields.push(("kp".to_string(), "-1".to_string())),
+        SafeSearch::Strict => fields.push(("kp".to_string(), "1".to_string())),
+    }
+    fields
+}
+
+/// Encodes form fields as a URL query string.
+fn query_string(fields: &[(String, String)]) -> String {
+    fields
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, urlencoding::encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// DuckDuckGo's `df` value for a time range.
+fn time_range_code(range: TimeRange) -> &'static str {
+    match range {
+        TimeRange::Day => "d",
+        TimeRange::Week => "w",
+        TimeRange::Month => "m",
+        TimeRange::Year => "y",
     }
 }
 
 impl DuckDuckGo {
     fn parse_results(&self, html: &str) -> Result<Vec<SearchResult>> {
-        let document = Html::parse_document(html);
-        let result_selector = Selector::parse(".result")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let title_selector = Selector::parse(".result__title a")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let snippet_selector = Selector::parse(".result__snippet")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
+        let document = HtmlDocument::parse(html);
+        let result_selector = HtmlSelector::parse(".result")?;
+        let title_selector = HtmlSelector::parse(".result__title a")?;
+        let snippet_selector = HtmlSelector::parse(".result__snippet")?;
 
         let mut results = Vec::new();
 
@@ -88,8 +162,8 @@ impl DuckDuckGo {
             let snippet_elem = element.select(&snippet_selector).next();
 
             if let Some(title_elem) = title_elem {
-                let title = title_elem.text().collect::<String>().trim().to_string();
-                let url = title_elem.value().attr("href").unwrap_or_default();
+                let title = title_elem.text().trim().to_string();
+                let url = title_elem.attr("href").unwrap_or_default();
 
                 let url = if url.starts_with("//duckduckgo.com/l/") {
                     extract_redirect_url(url).unwrap_or_else(|| url.to_string())
@@ -98,7 +172,7 @@ impl DuckDuckGo {
                 };
 
                 let content = snippet_elem
-                    .map(|e| e.text().collect::<String>().trim().to_string())
+                    .map(|e| e.text().trim().to_string())
                     .unwrap_or_default();
 
                 if !url.is_empty() && !title.is_empty() {
@@ -328,4 +402,203 @@ mod tests {
         let result = extract_redirect_url(url);
         assert!(result.is_some());
     }
+
+    #[test]
+    fn test_form_fields_first_page() {
+        let fields = form_fields(&SearchQuery::new("rust"));
+        assert_eq!(fields, vec![("q".to_string(), "rust".to_string())]);
+    }
+
+    #[test]
+    fn test_form_fields_later_pages() {
+        let fields = form_fields(&SearchQuery::new("rust").with_page(3));
+        assert!(fields.contains(&("s".to_string(), "70".to_string())));
+        assert!(fields.contains(&("dc".to_string(), "71".to_string())));
+    }
+
+    #[test]
+    fn test_form_fields_safesearch() {
+        let kp = |level| {
+            form_fields(&SearchQuery::new("rust").with_safesearch(level))
+                .into_iter()
+                .find(|(name, _)| name == "kp")
+                .map(|(_, value)| value)
+        };
+        assert_eq!(kp(SafeSearch::Off), None);
+        assert_eq!(kp(SafeSearch::Moderate).as_deref(), Some("-1"));
+        assert_eq!(kp(SafeSearch::Strict).as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_form_fields_time_range() {
+        let fields = form_fields(&SearchQuery::new("rust").with_time_range(TimeRange::Week));
+        assert_eq!(
+            fields,
+            vec![
+                ("q".to_string(), "rust".to_string()),
+                ("df".to_string(), "w".to_string())
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_posts_form() {
+        use crate::fetcher::{FetchBody, FetchMethod, FetchResponse};
+        use std::sync::Mutex;
+
+        struct FormFetcher {
+            requests: Mutex<Vec<FetchRequest>>,
+        }
+
+        #[async_trait]
+        impl PageFetcher for FormFetcher {
+            async fn fetch(&self, _url: &str) -> Result<String> {
+                panic!("DuckDuckGo should POST through fetch_request");
+            }
+
+            async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+                self.requests.lock().unwrap().push(request.clone());
+                Ok(FetchResponse {
+                    status: 200,
+                    final_url: request.url.clone(),
+                    headers: Vec::new(),
+                    body: r#"<div class="result"><h2 class="result__title">
+                        <a href="https://www.rust-lang.org/">Rust</a></h2></div>"#
+                        .to_string(),
+                })
+            }
+        }
+
+        let fetcher = Arc::new(FormFetcher {
+            requests: Mutex::new(Vec::new()),
+        });
+        let engine = DuckDuckGo::with_fetcher(fetcher.clone());
+        let results = engine
+            .search(&SearchQuery::new("rust").with_page(2))
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        let requests = fetcher.requests.lock().unwrap();
+        assert_eq!(requests[0].method, FetchMethod::Post);
+        assert_eq!(requests[0].url, "https://html.duckduckgo.com/html/");
+        match requests[0].body {
+            Some(FetchBody::Form(ref fields)) => {
+                assert!(fields.contains(&("q".to_string(), "rust".to_string())));
+                assert!(fields.contains(&("s".to_string(), "20".to_string())));
+            }
+            ref other => panic!("Expected form body, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_duckduckgo_sends_accept_language() {
+        use crate::fetcher::{FetchMethod, FetchResponse};
+        use std::sync::Mutex;
+
+        struct HeaderFetcher {
+            requests: Mutex<Vec<FetchRequest>>,
+        }
+
+        #[async_trait]
+        impl PageFetcher for HeaderFetcher {
+            async fn fetch(&self, _url: &str) -> Result<String> {
+                panic!("DuckDuckGo should send headers through fetch_request");
+            }
+
+            async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+                self.requests.lock().unwrap().push(request.clone());
+                Ok(FetchResponse {
+                    status: 200,
+                    final_url: request.url.clone(),
+                    headers: Vec::new(),
+                    body: String::new(),
+                })
+            }
+        }
+
+        let fetcher = Arc::new(HeaderFetcher {
+            requests: Mutex::new(Vec::new()),
+        });
+        let engine = DuckDuckGo::with_fetcher(fetcher.clone());
+        let query = SearchQuery::new("rust").with_language("de-DE");
+        engine.search(&query).await.unwrap();
+        engine.search(&query.with_page(2)).await.unwrap();
+        engine
+            .search(&SearchQuery::new("rust").with_page(2))
+            .await
+            .unwrap();
+
+        let requests = fetcher.requests.lock().unwrap();
+        let accept_language = |request: &FetchRequest| {
+            request
+                .headers
+                .iter()
+                .find(|(name, _)| name == "Accept-Language")
+                .map(|(_, value)| value.clone())
+        };
+        assert_eq!(requests[0].method, FetchMethod::Get);
+        assert_eq!(
+            accept_language(&requests[0]),
+            Some("de-DE,de;q=0.9".to_string())
+        );
+        assert_eq!(requests[1].method, FetchMethod::Post);
+        assert_eq!(
+            accept_language(&requests[1]),
+            Some("de-DE,de;q=0.9".to_string())
+        );
+        assert_eq!(accept_language(&requests[2]), None);
+    }
+
+    #[tokio::test]
+    async fn test_duckduckgo_first_page_through_get_only_fetcher() {
+        use std::sync::Mutex;
+
+        /// Fetcher serving GETs by URL only, like most custom fetchers.
+        struct GetOnlyFetcher {
+            urls: Mutex<Vec<String>>,
+        }
+
+        #[async_trait]
+        impl PageFetcher for GetOnlyFetcher {
+            async fn fetch(&self, url: &str) -> Result<String> {
+                self.urls.lock().unwrap().push(url.to_string());
+                Ok(r#"<div class="result"><h2 class="result__title">
+                    <a href="https://www.rust-lang.org/">Rust</a></h2></div>"#
+                    .to_string())
+            }
+        }
+
+        let fetcher = Arc::new(GetOnlyFetcher {
+            urls: Mutex::new(Vec::new()),
+        });
+        let engine = DuckDuckGo::with_fetcher(fetcher.clone());
+        let query = SearchQuery::new("rust lang").with_time_range(TimeRange::Day);
+        assert_eq!(engine.search(&query).await.unwrap().len(), 1);
+        let query = query.with_language("de-DE");
+        assert_eq!(engine.search(&query).await.unwrap().len(), 1);
+
+        assert_eq!(
+            *fetcher.urls.lock().unwrap(),
+            vec!["https://html.duckduckgo.com/html/?q=rust%20lang&df=d"; 2]
+        );
+        // Only later pages need a fetcher that can POST
+        assert!(engine.search(&query.with_page(2)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_duckduckgo_base_url_later_pages() {
+        let fetcher = crate::engines::CapturingFetcher::new("");
+        DuckDuckGo::with_fetcher(fetcher.clone())
+            .with_base_url("http://127.0.0.1:8080/")
+            .search(&SearchQuery::new("rust").with_page(2))
+            .await
+            .unwrap();
+
+        let requests = fetcher.requests();
+        assert_eq!(requests[0].url, "http://127.0.0.1:8080/html/");
+        assert!(requests[0]
+            .headers
+            .contains(&("Referer".to_string(), "http://127.0.0.1:8080/".to_string())));
+    }
 }
@@ -6,10 +6,13 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use scraper::{Html, Selector};
 
-use crate::fetcher::PageFetcher;
-use crate::{Engine, EngineCategory, EngineConfig, Result, SearchError, SearchQuery, SearchResult};
+use crate::fetcher::{FetchRequest, PageFetcher};
+use crate::html::{HtmlDocument, HtmlSelector};
+use crate::{
+    Engine, EngineCategory, EngineConfig, Result, SafeSearch, SearchError, SearchQuery,
+    SearchResult, TimeRange,
+};
 
 /// Google search engine.
 ///
@@ -21,19 +24,24 @@ pub struct Google {
 }
 
 impl Google {
+    /// Returns the configuration a new engine starts with.
+    pub(crate) fn default_config() -> EngineConfig {
+        EngineConfig {
+            name: "Google".to_string(),
+            shortcut: "g".to_string(),
+            categories: vec![EngineCategory::General],
+            weight: 1.5,
+            timeout: 10,
+            enabled: true,
+            paging: true,
+            safesearch: true,
+        }
+    }
+
     /// Creates a new Google engine with the given page fetcher.
     pub fn new(fetcher: Arc<dyn PageFetcher>) -> Self {
         Self {
-            config: EngineConfig {
-                name: "Google".to_string(),
-                shortcut: "g".to_string(),
-                categories: vec![EngineCategory::General],
-                weight: 1.5,
-                timeout: 10,
-                enabled: true,
-                paging: true,
-                safesearch: true,
-            },
+            config: Self::default_config(),
             fetcher,
         }
     }
@@ -45,50 +53,61 @@ impl Google {
     }
 
     fn parse_results(&self, html: &str) -> Result<Vec<SearchResult>> {
-        let document = Html::parse_document(html);
+        let document = HtmlDocument::parse(html);
 
-        let container_selector = Selector::parse("div.g")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let title_selector = Selector::parse("h3")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let link_selector = Selector::parse("a[href]")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let snippet_selector = Selector::parse("div[data-sncf], div.VwiC3b")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
+        let container_selector = HtmlSelector::parse("div.g")?;
+        let title_selector = HtmlSelector::parse("h3")?;
+        let link_selector = HtmlSelector::parse("a[href]")?;
+        let snippet_selector = HtmlSelector::parse("div[data-sncf], div.VwiC3b")?;
+        let widget_selector = HtmlSelector::parse(NON_RESULT_WIDGETS)?;
+        let featured_selector = HtmlSelector::parse(FEATURED_SNIPPET)?;
+        let answer_selector = HtmlSelector::parse(FEATURED_ANSWER)?;
 
         let mut results = Vec::new();
 
         for element in document.select(&container_selector) {
-            let title = match element.select(&title_selector).next() {
-                Some(el) => el.text().collect::<String>().trim().to_string(),
-                None => continue,
+            // Skip blocks inside "People also ask", sitelinks, or another result
+            let nested = element.ancestors().any(|ancestor| {
+                widget_selector.matches(&ancestor) || container_selector.matches(&ancestor)
+            });
+            if nested {
+                continue;
+            }
+
+            // The result link is the anchor wrapping the title
+            let Some((link, title)) = element.select(&link_selector).find_map(|link| {
+                link.select(&title_selector)
+                    .next()
+                    .map(|h3| (link, h3.text().trim().to_string()))
+            }) else {
+                continue;
             };
 
-            let url = match element.select(&link_selector).next() {
-                Some(el) => {
-                    let href = el.value().attr("href").unwrap_or_default();
-                    // Skip Google's internal links
-                    if href.starts_with('/') && !href.starts_with("/url?") {
-                        continue;
-                    }
-                    // Extract actual URL from /url?q= redirects
-                    if let Some(q) = href.strip_prefix("/url?q=") {
-                        q.split('&').next().unwrap_or(q).to_string()
-                    } else {
-                        href.to_string()
-                    }
-                }
+            let url = match external_url(link.attr("href").unwrap_or_default()) {
+                Some(url) => url,
                 None => continue,
             };
 
-            let content = element
-                .select(&snippet_selector)
-                .next()
-                .map(|el| el.text().collect::<String>().trim().to_string())
-                .unwrap_or_default();
-
-            if !url.is_empty() && !title.is_empty() {
-                results.push(SearchResult::new(url, title, content));
+            // A featured snippet's answer sits beside its source link
+            let featured = element
+                .ancestors()
+                .find(|ancestor| featured_selector.matches(ancestor));
+            let answer = featured
+                .and_then(|block| block.select(&answer_selector).next())
+                .map(|el| el.text().trim().to_string())
+                .filter(|text| !text.is_empty());
+
+            let content = answer.unwrap_or_else(|| {
+                element
+                    .select(&snippet_selector)
+                    .next()
+                    .map(|el| el.text().trim().to_string())
+                    .unwrap_or_default()
+            });
+
+            if !title.is_empty() {
+                results
+                    .push(SearchResult::new(url, title, content).with_featured(featured.is_some()));
             }
         }
 
@@ -96,6 +115,76 @@ impl Google {
     }
 }
 
+/// Widgets whose `div.g` blocks are not organic results: "People also ask"
+/// accordions and sitelink tables.
+const NON_RESULT_WIDGETS: &str =
+    ".related-question-pair, [data-initq], g-accordion-expander, .HiHjCd, table.jmjoTe";
+
+/// Containers of a featured snippet (the answer box above organic results).
+const FEATURED_SNIPPET: &str = "block-component, .xpdopen";
+
+/// The answer text of a featured snippet.
+const FEATURED_ANSWER: &str = ".hgKElc, [data-attrid='wa:/description']";
+
+/// Returns the destination of a result link if it points to an external
+/// http(s) page, unwrapping Google's `/url?q=` redirects.
+fn external_url(href: &str) -> Option<String> {
+    let href = match href.strip_prefix("/url?q=") {
+        Some(q) => urlencoding::decode(q.split('&').next().unwrap_or(q))
+            .ok()?
+            .into_owned(),
+        None => href.to_string(),
+    };
+
+    let parsed = url::Url::parse(&href).ok()?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return None;
+    }
+    let host = parsed.host_str()?;
+    if host == "google.com" || host.ends_with(".google.com") || host.starts_with("www.google.") {
+        return None;
+    }
+    Some(href)
+}
+
+/// Google's cap on `num`.
+const MAX_RESULTS: u32 = 100;
+
+/// Results on a page when `num` is not set.
+const PAGE_SIZE: u32 = 10;
+
+/// Builds the results page URL, asking for `num` results when the query
+/// sets a count and skipping earlier pages' results with `start`.
+fn search_url(query: &SearchQuery) -> String {
+    let mut url = format!(
+        "https://www.google.com/search?q={}&hl=en",
+        urlencoding::encode(&query.query)
+    );
+    let count = query.result_count(MAX_RESULTS);
+    if let Some(count) = count {
+        url.push_str(&format!("&num={}", count));
+    }
+    if query.page > 1 {
+        let start = query.page_offset(count.unwrap_or(PAGE_SIZE));
+        url.push_str(&format!("&start={}", start));
+    }
+    if let Some(range) = query.time_range {
+        let code = match range {
+            TimeRange::Day => "d",
+            TimeRange::Week => "w",
+            TimeRange::Month => "m",
+            TimeRange::Year => "y",
+        };
+        url.push_str(&format!("&tbs=qdr:{}", code));
+    }
+    match query.safesearch {
+        SafeSearch::Off => {}
+        SafeSearch::Moderate => url.push_str("&safe=medium"),
+        SafeSearch::Strict => url.push_str("&safe=active"),
+    }
+    url
+}
+
 #[async_trait]
 impl Engine for Google {
     fn config(&self) -> &EngineConfig {
@@ -103,16 +192,41 @@ impl Engine for Google {
     }
 
     async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
-        let url = format!(
-            "https://www.google.com/search?q={}&hl=en",
-            urlencoding::encode(&query.query)
-        );
-
-        let html = self.fetcher.fetch(&url).await?;
+        let url = search_url(query);
 
-        // Detect CAPTCHA / bot-block pages before parsing
-        if html.contains("/sorry/index") || html.contains("recaptcha") {
+        // Lets a browser fetcher emulate the query's locale as well
+        let mut request = FetchRequest::get(url);
+        if let Some(language) = query.accept_language() {
+            request = request.with_header("Accept-Language", language);
+        }
+        let response = self.fetcher.fetch_request(&request).await?;
+        let html = response.body;
+
+        // A consent interstitial would otherwise parse as zero results
+        let final_url = url::Url::parse(&response.final_url).ok();
+        if final_url
+            .as_ref()
+            .and_then(|url| url.host_str())
+            .is_some_and(|host| host.starts_with("consent."))
+        {
             return Err(SearchError::Other(
+                "Google returned a cookie consent page. Use a BrowserFetcher with consent handling (the default)."
+                    .to_string(),
+            ));
+        }
+
+        // Detect CAPTCHA / bot-block pages before parsing: a redirect to
+        // /sorry/ or a 429 is conclusive, the markup check covers fetchers
+        // that don't report where they ended up
+        let redirected_to_sorry = final_url
+            .as_ref()
+            .is_some_and(|url| url.path().starts_with("/sorry/"));
+        if redirected_to_sorry
+            || response.status == 429
+            || html.contains("/sorry/index")
+            || html.contains("recaptcha")
+        {
+            return Err(SearchError::Captcha(
                 "Google returned a CAPTCHA page (bot detected). Try again later or use a proxy (-p)."
                     .to_string(),
             ));
@@ -262,6 +376,128 @@ mod tests {
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn test_parse_results_skips_people_also_ask_and_sitelinks() {
+        let engine = make_google();
+        let html = include_str!("../../tests/fixtures/google_serp_paa.html");
+        let results = engine.parse_results(html).unwrap();
+
+        let urls: Vec<&str> = results.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://www.rust-lang.org/",
+                "https://doc.rust-lang.org/book/",
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+            ]
+        );
+        assert_eq!(results[0].title, "Rust Programming Language");
+        assert_eq!(
+            results[0].content,
+            "A language empowering everyone to build reliable and efficient software."
+        );
+        assert_eq!(results[2].title, "Rust (programming language) - Wikipedia");
+    }
+
+    #[test]
+    fn test_parse_results_flags_featured_snippet() {
+        let engine = make_google();
+        let html = include_str!("../../tests/fixtures/google_serp_featured.html");
+        let results = engine.parse_results(html).unwrap();
+
+        assert_eq!(results.len(), 3);
+        let featured = &results[0];
+        assert!(featured.is_featured);
+        assert_eq!(
+            featured.url,
+            "https://www.sleepfoundation.org/animals-and-sleep/how-much-do-cats-sleep"
+        );
+        assert_eq!(
+            featured.content,
+            "Cats sleep for 12 to 16 hours a day on average, and kittens and older cats even longer."
+        );
+        assert!(results[1..].iter().all(|r| !r.is_featured));
+
+        // Another engine ranking an organic result higher does not unseat it
+        let organic = SearchResult::new(
+            "https://en.wikipedia.org/wiki/Cat_behavior",
+            "Cat behavior",
+            "",
+        );
+        let aggregated = crate::Aggregator::new().aggregate(vec![
+            ("google".to_string(), results),
+            ("bing".to_string(), vec![organic]),
+        ]);
+        assert_eq!(
+            aggregated.items()[0].url,
+            "https://www.sleepfoundation.org/animals-and-sleep/how-much-do-cats-sleep"
+        );
+        assert!(aggregated.items()[0].is_featured);
+        assert!(aggregated.items()[1].score > aggregated.items()[0].score);
+    }
+
+    #[test]
+    fn test_parse_results_requires_title_inside_link() {
+        let engine = make_google();
+        let html = r#"
+            <html>
+            <body>
+                <div class="g">
+                    <h3><a href="https://example.com/sitelink">Sitelink</a></h3>
+                </div>
+            </body>
+            </html>
+        "#;
+        let results = engine.parse_results(html).unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_search_url_results_per_engine() {
+        let query = SearchQuery::new("rust lang");
+        assert_eq!(
+            search_url(&query),
+            "https://www.google.com/search?q=rust%20lang&hl=en"
+        );
+        let query = query.with_results_per_engine(30);
+        assert!(search_url(&query).ends_with("&num=30"));
+        let query = query.with_results_per_engine(500);
+        assert!(search_url(&query).ends_with("&num=100"));
+    }
+
+    #[test]
+    fn test_search_url_safesearch() {
+        let query = SearchQuery::new("rust");
+        assert!(!search_url(&query).contains("safe="));
+        let query = query.with_safesearch(SafeSearch::Strict);
+        assert!(search_url(&query).ends_with("&safe=active"));
+    }
+
+    #[test]
+    fn test_search_url_time_range() {
+        let query = SearchQuery::new("rust").with_time_range(TimeRange::Day);
+        assert_eq!(
+            search_url(&query),
+            "https://www.google.com/search?q=rust&hl=en&tbs=qdr:d"
+        );
+    }
+
+    #[test]
+    fn test_external_url() {
+        assert_eq!(
+            external_url("https://example.com/a"),
+            Some("https://example.com/a".to_string())
+        );
+        assert_eq!(
+            external_url("/url?q=https://example.com/a%3Fb%3Dc&sa=U"),
+            Some("https://example.com/a?b=c".to_string())
+        );
+        assert_eq!(external_url("/search?q=rust"), None);
+        assert_eq!(external_url("javascript:void(0)"), None);
+        assert_eq!(external_url("https://maps.google.com/maps?q=x"), None);
+        assert_eq!(external_url("https://www.google.co.uk/search?q=x"), None);
+    }
+
     #[tokio::test]
     async fn test_search_detects_captcha_sorry_page() {
         use crate::fetcher::PageFetcher;
@@ -340,4 +576,98 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap().len(), 1);
     }
+
+    #[tokio::test]
+    async fn test_search_sends_accept_language() {
+        use crate::fetcher::{FetchResponse, PageFetcher};
+        use std::sync::Mutex;
+
+        #[derive(Default)]
+        struct RecordingFetcher(Mutex<Vec<FetchRequest>>);
+        #[async_trait]
+        impl PageFetcher for RecordingFetcher {
+            async fn fetch(&self, _url: &str) -> crate::Result<String> {
+                unreachable!("Google should fetch through fetch_request")
+            }
+
+            async fn fetch_request(&self, request: &FetchRequest) -> crate::Result<FetchResponse> {
+                self.0.lock().unwrap().push(request.clone());
+                Ok(FetchResponse::default())
+            }
+        }
+
+        let fetcher = Arc::new(RecordingFetcher::default());
+        let engine = Google::new(fetcher.clone());
+        engine
+            .search(&SearchQuery::new("rust").with_language("de-DE"))
+            .await
+            .unwrap();
+        engine.search(&SearchQuery::new("rust")).await.unwrap();
+
+        let requests = fetcher.0.lock().unwrap();
+        assert_eq!(
+            requests[0].headers,
+            vec![("Accept-Language".to_string(), "de-DE,de;q=0.9".to_string())]
+        );
+        assert!(requests[1].headers.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_reports_consent_page() {
+        use crate::fetcher::{FetchResponse, PageFetcher};
+
+        struct ConsentFetcher;
+        #[async_trait]
+        impl PageFetcher for ConsentFetcher {
+            async fn fetch(&self, _url: &str) -> crate::Result<String> {
+                unreachable!("Google should fetch through fetch_request")
+            }
+
+            async fn fetch_request(&self, _request: &FetchRequest) -> crate::Result<FetchResponse> {
+                Ok(FetchResponse {
+                    status: 200,
+                    final_url: "https://consent.google.com/ml?continue=https://www.google.com/search"
+                        .to_string(),
+                    headers: Vec::new(),
+                    body: "<html><body><form action=\"https://consent.google.com/save\"></form></body></html>"
+                        .to_string(),
+                })
+            }
+        }
+
+        let engine = Google::new(Arc::new(ConsentFetcher));
+        let err = engine.search(&SearchQuery::new("rust")).await.unwrap_err();
+        assert!(err.to_string().contains("consent"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_search_detects_sorry_redirect_by_final_url() {
+        use crate::fetcher::{FetchResponse, PageFetcher};
+
+        struct SorryFetcher(u16);
+        #[async_trait]
+        impl PageFetcher for SorryFetcher {
+            async fn fetch(&self, _url: &str) -> crate::Result<String> {
+                unreachable!("Google should fetch through fetch_request")
+            }
+
+            async fn fetch_request(&self, _request: &FetchRequest) -> crate::Result<FetchResponse> {
+                Ok(FetchResponse {
+                    status: self.0,
+                    final_url: "https://www.google.com/sorry/?continue=https://www.google.com/search"
+                        .to_string(),
+                    headers: Vec::new(),
+                    // Nothing in the markup gives the block page away
+                    body: "<html><body><p>Our systems have detected unusual traffic.</p></body></html>"
+                        .to_string(),
+                })
+            }
+        }
+
+        for status in [200, 429] {
+            let engine = Google::new(Arc::new(SorryFetcher(status)));
+            let err = engine.search(&SearchQuery::new("rust")).await.unwrap_err();
+            assert!(err.to_string().contains("CAPTCHA"), "{}", err);
+        }
+    }
 }
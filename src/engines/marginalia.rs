@@ -0,0 +1,226 @@
+//! Marginalia search engine implementation.
+//!
+//! Marginalia indexes the small, independent web: non-commercial,
+//! text-heavy pages that rarely surface in mainstream engines. Results come
+//! from its public JSON API, so no JavaScript rendering is required.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::fetcher::PageFetcher;
+use crate::{
+    Engine, EngineCategory, EngineConfig, HttpFetcher, Result, SearchError, SearchQuery,
+    SearchResult,
+};
+
+/// Public API key with shared rate limits.
+const PUBLIC_API_KEY: &str = "public";
+
+/// Number of results requested per query.
+const RESULT_COUNT: u32 = 20;
+
+/// Origin requests are sent to unless overridden with
+/// [`Marginalia::with_base_url`].
+const DEFAULT_BASE_URL: &str = "https://api.marginalia.nu";
+
+/// Marginalia search engine for small-web results.
+pub struct Marginalia {
+    config: EngineConfig,
+    fetcher: Arc<dyn PageFetcher>,
+    /// Origin of the engine's endpoints.
+    base_url: String,
+    api_key: String,
+}
+
+impl Marginalia {
+    /// Creates a new Marginalia engine with a default HTTP fetcher.
+    pub fn new() -> Self {
+        Self::with_fetcher(Arc::new(HttpFetcher::new()))
+    }
+
+    /// Returns the configuration a new engine starts with.
+    pub(crate) fn default_config() -> EngineConfig {
+        EngineConfig {
+            name: "Marginalia".to_string(),
+            shortcut: "mar".to_string(),
+            categories: vec![EngineCategory::General],
+            weight: 0.8,
+            timeout: 5,
+            enabled: true,
+            paging: false,
+            safesearch: false,
+        }
+    }
+
+    /// Creates a new Marginalia engine with a custom page fetcher.
+    pub fn with_fetcher(fetcher: Arc<dyn PageFetcher>) -> Self {
+        Self {
+            config: Self::default_config(),
+            fetcher,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            api_key: PUBLIC_API_KEY.to_string(),
+        }
+    }
+
+    /// Sets a dedicated API key instead of the shared public key.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    /// Sends requests to `base_url` instead of `https://api.marginalia.nu`, e.g. a
+    /// mirror or a local test server.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Creates with custom configuration.
+    pub fn with_config(mut self, config: EngineConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+impl Default for Marginalia {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Deserialize)]
+struct MarginaliaResponse {
+    #[serde(default)]
+    results: Vec<MarginaliaResult>,
+}
+
+#[derive(Deserialize)]
+struct MarginaliaResult {
+    url: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[async_trait]
+impl Engine for Marginalia {
+    fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+        let url = format!(
+            "{}/{}/search/{}?count={}",
+            self.base_url,
+            urlencoding::encode(&self.api_key),
+            urlencoding::encode(&query.query),
+            RESULT_COUNT
+        );
+
+        let body = self.fetcher.fetch(&url).await?;
+
+        self.parse_results(&body)
+    }
+}
+
+impl Marginalia {
+    fn parse_results(&self, body: &str) -> Result<Vec<SearchResult>> {
+        let response: MarginaliaResponse = serde_json::from_str(body)
+            .map_err(|e| SearchError::Parse(format!("Invalid Marginalia response: {}", e)))?;
+
+        let results = response
+            .results
+            .into_iter()
+            .filter(|item| super::is_web_url(&item.url))
+            .map(|item| {
+                let title = if item.title.trim().is_empty() {
+                    item.url.clone()
+                } else {
+                    item.title.trim().to_string()
+                };
+                SearchResult::new(item.url, title, item.description.trim())
+            })
+            .collect();
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/marginalia_search.json");
+
+    #[test]
+    fn test_marginalia_new() {
+        let engine = Marginalia::new();
+        assert_eq!(engine.config.name, "Marginalia");
+        assert_eq!(engine.config.shortcut, "mar");
+        assert_eq!(engine.config.categories, vec![EngineCategory::General]);
+        assert_eq!(engine.config.weight, 0.8);
+        assert_eq!(engine.api_key, PUBLIC_API_KEY);
+    }
+
+    #[test]
+    fn test_marginalia_default() {
+        let engine = Marginalia::default();
+        assert_eq!(engine.name(), "Marginalia");
+    }
+
+    #[test]
+    fn test_marginalia_with_api_key() {
+        let engine = Marginalia::new().with_api_key("my-key");
+        assert_eq!(engine.api_key, "my-key");
+    }
+
+    #[test]
+    fn test_marginalia_with_config() {
+        let custom_config = EngineConfig {
+            name: "Custom Marginalia".to_string(),
+            weight: 1.5,
+            ..Default::default()
+        };
+        let engine = Marginalia::new().with_config(custom_config);
+        assert_eq!(engine.name(), "Custom Marginalia");
+        assert_eq!(engine.weight(), 1.5);
+    }
+
+    #[test]
+    fn test_marginalia_parse_fixture() {
+        let engine = Marginalia::new();
+        let results = engine.parse_results(FIXTURE).unwrap();
+
+        // The non-HTTP entry is skipped
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].url, "https://www.lesswrong.com/rust-notes");
+        assert_eq!(results[0].title, "Notes on learning Rust");
+        assert_eq!(
+            results[0].content,
+            "A long-form write-up of the ownership model from a hobbyist."
+        );
+        assert_eq!(results[1].title, "Rust and the small web");
+        // Missing titles fall back to the URL
+        assert_eq!(results[2].title, "http://example.org/~ann/rust.html");
+        assert_eq!(results[2].content, "");
+    }
+
+    #[test]
+    fn test_marginalia_parse_no_results() {
+        let engine = Marginalia::new();
+        let results = engine
+            .parse_results(r#"{"license": "CC-BY-NC-SA 4.0", "query": "x", "results": []}"#)
+            .unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_marginalia_parse_invalid_json() {
+        let engine = Marginalia::new();
+        let result = engine.parse_results("Too Many Requests");
+        assert!(matches!(result, Err(SearchError::Parse(_))));
+    }
+}
@@ -3,12 +3,17 @@
 // International engines
 mod brave;
 mod duckduckgo;
+mod marginalia;
 mod wikipedia;
+mod youtube;
 
 // Chinese engines
 mod so360;
 mod sogou;
 
+// Shared helpers
+mod redirect;
+
 // Headless browser engines (require JavaScript rendering)
 #[cfg(feature = "headless")]
 mod baidu;
@@ -19,7 +24,9 @@ mod google;
 
 pub use brave::Brave;
 pub use duckduckgo::DuckDuckGo;
-pub use wikipedia::Wikipedia;
+pub use marginalia::Marginalia;
+pub use wikipedia::{WikiProject, Wikipedia};
+pub use youtube::{YouTube, INVIDIOUS_INSTANCE_ENV};
 
 pub use so360::So360;
 pub use sogou::Sogou;
@@ -30,3 +37,331 @@ pub use baidu::Baidu;
 pub use bing_china::BingChina;
 #[cfg(feature = "headless")]
 pub use google::Google;
+
+use std::sync::Arc;
+
+use crate::{Engine, EngineConfig, HttpFetcher, PageFetcher};
+
+/// Shortcuts of the engines [`all_http`] returns.
+pub const HTTP_ENGINES: &[&str] = &["ddg", "brave", "wiki", "mar", "yt", "sogou", "360"];
+
+/// Builds the engine that works over plain HTTP with `shortcut`, or one of
+/// its aliases (such as `duckduckgo` for `ddg`), on `fetcher`.
+///
+/// Besides [`HTTP_ENGINES`], knows Wikipedia's sister projects (`wikt`,
+/// `wikinews`, `wikibooks`). Returns `None` for any other shortcut,
+/// including those of the headless engines.
+///
+/// Takes an [`HttpFetcher`] rather than any [`PageFetcher`] because
+/// Wikipedia needs one; clones share its connection pool and proxy.
+pub fn by_shortcut(shortcut: &str, fetcher: &HttpFetcher) -> Option<Box<dyn Engine>> {
+    let shared = || -> Arc<dyn PageFetcher> { Arc::new(fetcher.clone()) };
+    let wiki = |project| Wikipedia::with_http_fetcher(fetcher.clone()).with_project(project);
+    let engine: Box<dyn Engine> = match shortcut {
+        "ddg" | "duckduckgo" => Box::new(DuckDuckGo::with_fetcher(shared())),
+        "brave" => Box::new(Brave::with_fetcher(shared())),
+        "wiki" | "wikipedia" => Box::new(wiki(WikiProject::Wikipedia)),
+        "wikt" | "wiktionary" => Box::new(wiki(WikiProject::Wiktionary)),
+        "wikinews" => Box::new(wiki(WikiProject::Wikinews)),
+        "wikibooks" => Box::new(wiki(WikiProject::Wikibooks)),
+        "mar" | "marginalia" => Box::new(Marginalia::with_fetcher(shared())),
+        "yt" | "youtube" => Box::new(YouTube::with_fetcher(shared())),
+        "sogou" => Box::new(Sogou::with_fetcher(shared())),
+        "360" | "so360" => Box::new(So360::with_fetcher(shared())),
+        _ => return None,
+    };
+    Some(engine)
+}
+
+/// Returns every engine that works over plain HTTP, sharing `fetcher`
+/// (and so its connection pool and proxy): DuckDuckGo, Brave, Wikipedia,
+/// Marginalia, YouTube, Sogou and 360 Search.
+///
+/// Wikipedia's sister projects are left out. Add the engines with
+/// [`Search::add_engines`](crate::Search::add_engines).
+pub fn all_http(fetcher: HttpFetcher) -> Vec<Box<dyn Engine>> {
+    HTTP_ENGINES
+        .iter()
+        .filter_map(|shortcut| by_shortcut(shortcut, &fetcher))
+        .collect()
+}
+
+/// Returns every engine that renders its pages in `pool`'s browser:
+/// Google, Baidu and Bing China, each waiting for its results to appear.
+#[cfg(feature = "headless")]
+pub fn all_headless(pool: Arc<crate::BrowserPool>) -> Vec<Box<dyn Engine>> {
+    use crate::{BrowserFetcher, WaitStrategy};
+
+    let fetcher = |wait: WaitStrategy| -> Arc<dyn PageFetcher> {
+        Arc::new(BrowserFetcher::new(Arc::clone(&pool)).with_wait(wait))
+    };
+    vec![
+        Box::new(Google::new(fetcher(WaitStrategy::Selector {
+            css: "div.g".to_string(),
+            timeout_ms: 5000,
+        }))),
+        Box::new(Baidu::new(fetcher(WaitStrategy::Selector {
+            css: "div.c-container".to_string(),
+            timeout_ms: 5000,
+        }))),
+        Box::new(BingChina::new(fetcher(WaitStrategy::Delay { ms: 2000 }))),
+    ]
+}
+
+/// Returns the default configuration of every engine [`all_http`] and
+/// [`all_headless`] return, in the same order, without building any
+/// engine, fetcher or browser.
+pub fn configs() -> Vec<EngineConfig> {
+    let configs = vec![
+        DuckDuckGo::default_config(),
+        Brave::default_config(),
+        Wikipedia::default_config(),
+        Marginalia::default_config(),
+        YouTube::default_config(),
+        Sogou::default_config(),
+        So360::default_config(),
+    ];
+    #[cfg(feature = "headless")]
+    let configs = [
+        configs,
+        vec![
+            Google::default_config(),
+            Baidu::default_config(),
+            BingChina::default_config(),
+        ],
+    ]
+    .concat();
+    configs
+}
+
+/// Maps the kind of snippet on a result page (`"web"`, `"video"` or
+/// `"news"`) to the [`ResultType`](crate::ResultType) it is reported as.
+///
+/// Web snippets are always wanted; video and news snippets only when
+/// `categories` asks for videos or news, and `None` means skip the snippet.
+pub(crate) fn snippet_result_type(
+    kind: &str,
+    categories: &[crate::EngineCategory],
+) -> Option<crate::ResultType> {
+    use crate::{EngineCategory, ResultType};
+
+    let (result_type, category) = match kind {
+        "web" => return Some(ResultType::Web),
+        "video" => (ResultType::Video, EngineCategory::Videos),
+        "news" => (ResultType::News, EngineCategory::News),
+        _ => return None,
+    };
+    categories.contains(&category).then_some(result_type)
+}
+
+/// Returns true for absolute `http`/`https` URLs, the only links engines
+/// report as results.
+pub(crate) fn is_web_url(url: &str) -> bool {
+    crate::result::url_has_scheme(url, crate::result::DEFAULT_RESULT_SCHEMES)
+}
+
+/// Test fetcher that records every request and answers each with `body`.
+#[cfg(test)]
+pub(crate) struct CapturingFetcher {
+    body: String,
+    requests: std::sync::Mutex<Vec<crate::FetchRequest>>,
+}
+
+#[cfg(test)]
+impl CapturingFetcher {
+    pub(crate) fn new(body: impl Into<String>) -> Arc<Self> {
+        Arc::new(Self {
+            body: body.into(),
+            requests: std::sync::Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Returns the requests made so far.
+    pub(crate) fn requests(&self) -> Vec<crate::FetchRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// Returns the URLs requested so far.
+    pub(crate) fn urls(&self) -> Vec<String> {
+        self.requests().into_iter().map(|r| r.url).collect()
+    }
+}
+
+#[cfg(test)]
+#[async_trait::async_trait]
+impl PageFetcher for CapturingFetcher {
+    async fn fetch(&self, url: &str) -> crate::Result<String> {
+        self.fetch_request(&crate::FetchRequest::get(url))
+            .await
+            .map(|response| response.body)
+    }
+
+    async fn fetch_request(
+        &self,
+        request: &crate::FetchRequest,
+    ) -> crate::Result<crate::FetchResponse> {
+        self.requests.lock().unwrap().push(request.clone());
+        Ok(crate::FetchResponse {
+            status: 200,
+            final_url: request.url.clone(),
+            headers: Vec::new(),
+            body: self.body.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{spawn_server, Response};
+    use crate::SearchQuery;
+
+    #[test]
+    fn test_all_http_shortcuts() {
+        let engines = all_http(HttpFetcher::new());
+        let shortcuts: Vec<&str> = engines.iter().map(|e| e.shortcut()).collect();
+        assert_eq!(
+            shortcuts,
+            vec!["ddg", "brave", "wiki", "mar", "yt", "sogou", "360"]
+        );
+    }
+
+    #[test]
+    fn test_by_shortcut_aliases() {
+        let fetcher = HttpFetcher::new();
+        let shortcut = |name: &str| by_shortcut(name, &fetcher).map(|e| e.shortcut().to_string());
+        assert_eq!(shortcut("duckduckgo").as_deref(), Some("ddg"));
+        assert_eq!(shortcut("wiktionary").as_deref(), Some("wikt"));
+        assert_eq!(shortcut("so360").as_deref(), Some("360"));
+        assert_eq!(shortcut("g"), None);
+        assert_eq!(shortcut("nope"), None);
+    }
+
+    #[tokio::test]
+    async fn test_http_engines_with_base_url() {
+        let targets = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let (addr, server) = {
+            let targets = Arc::clone(&targets);
+            spawn_server(move |request| {
+                targets.lock().unwrap().push(request.path().to_string());
+                async { Response::ok("") }
+            })
+            .await
+        };
+        let base_url = format!("http://{}/", addr);
+        let fetcher = || -> Arc<dyn PageFetcher> { Arc::new(HttpFetcher::new()) };
+
+        let cases: Vec<(Box<dyn Engine>, &str)> = vec![
+            (
+                Box::new(DuckDuckGo::with_fetcher(fetcher()).with_base_url(&base_url)),
+                "/html/?q=rust",
+            ),
+            (
+                Box::new(Brave::with_fetcher(fetcher()).with_base_url(&base_url)),
+                "/search?q=rust",
+            ),
+            (
+                Box::new(Wikipedia::new().with_base_url(&base_url)),
+                "/w/api.php?action=query&list=search&srsearch=rust&format=json&srlimit=10",
+            ),
+            (
+                Box::new(Marginalia::with_fetcher(fetcher()).with_base_url(&base_url)),
+                "/public/search/rust?count=20",
+            ),
+            (
+                Box::new(YouTube::with_fetcher(fetcher()).with_base_url(&base_url)),
+                "/api/v1/search?q=rust&type=video&page=1",
+            ),
+            (
+                Box::new(Sogou::with_fetcher(fetcher()).with_base_url(&base_url)),
+                "/web?query=rust",
+            ),
+            (
+                Box::new(So360::with_fetcher(fetcher()).with_base_url(&base_url)),
+                "/s?q=rust",
+            ),
+        ];
+        for (engine, target) in cases {
+            // The empty page may not parse; only the request matters
+            let _ = engine.search(&SearchQuery::new("rust")).await;
+            let sent = targets.lock().unwrap().pop();
+            assert_eq!(sent.as_deref(), Some(target), "{}", engine.name());
+        }
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_paging_engines_request_later_pages() {
+        let fetcher = CapturingFetcher::new("");
+        let shared = || -> Arc<dyn PageFetcher> { fetcher.clone() };
+
+        #[allow(unused_mut)]
+        let mut cases: Vec<(Box<dyn Engine>, &str)> = vec![
+            (Box::new(DuckDuckGo::with_fetcher(shared())), "&s=70"),
+            (Box::new(Brave::with_fetcher(shared())), "&offset=2"),
+            (Box::new(YouTube::with_fetcher(shared())), "&page=3"),
+            (Box::new(Sogou::with_fetcher(shared())), "&page=3"),
+            (Box::new(So360::with_fetcher(shared())), "&pn=3"),
+        ];
+        #[cfg(feature = "headless")]
+        cases.extend([
+            (
+                Box::new(Google::new(shared())) as Box<dyn Engine>,
+                "&start=20",
+            ),
+            (Box::new(Baidu::new(shared())), "&pn=20"),
+            (Box::new(BingChina::new(shared())), "&first=21"),
+        ]);
+
+        // Every engine claiming to page is covered
+        for engine in all_http(HttpFetcher::new()) {
+            let covered = cases.iter().any(|(e, _)| e.shortcut() == engine.shortcut());
+            assert_eq!(covered, engine.config().paging, "{}", engine.name());
+        }
+
+        let query = SearchQuery::new("rust").with_page(3);
+        for (engine, param) in cases {
+            assert!(engine.config().paging, "{}", engine.name());
+            // The empty page may not parse; only the request matters
+            let _ = engine.search(&query).await;
+            let request = fetcher.requests().pop().unwrap();
+            let mut sent = request.url;
+            if let Some(crate::FetchBody::Form(fields)) = request.body {
+                for (name, value) in fields {
+                    sent.push_str(&format!("&{}={}", name, value));
+                }
+            }
+            assert!(sent.contains(param), "{}: {}", engine.name(), sent);
+        }
+    }
+
+    #[cfg(feature = "headless")]
+    #[test]
+    fn test_all_headless_shortcuts() {
+        let pool = Arc::new(crate::BrowserPool::new(crate::BrowserPoolConfig::default()));
+        let engines = all_headless(pool);
+        let shortcuts: Vec<&str> = engines.iter().map(|e| e.shortcut()).collect();
+        assert_eq!(shortcuts, vec!["g", "baidu", "bing_cn"]);
+    }
+
+    #[test]
+    fn test_configs_match_engines() {
+        let engines = all_http(HttpFetcher::new());
+        #[cfg(feature = "headless")]
+        let engines: Vec<_> = engines
+            .into_iter()
+            .chain(all_headless(Arc::new(crate::BrowserPool::new(
+                crate::BrowserPoolConfig::default(),
+            ))))
+            .collect();
+
+        let configs = configs();
+        assert_eq!(configs.len(), engines.len());
+        for (config, engine) in configs.iter().zip(&engines) {
+            assert_eq!(
+                serde_json::to_value(config).unwrap(),
+                serde_json::to_value(engine.config()).unwrap()
+            );
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! Redirect resolution for engines that link through redirectors.
+//!
+//! Sogou and 360 Search link results through their own redirect endpoints
+//! (`sogou.com/link?url=...`, `so.com/link?m=...`) instead of the real
+//! destination. Resolving those links makes deduplication across engines
+//! work and saves users an extra hop.
+
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use futures::stream::{self, StreamExt};
+use regex::Regex;
+
+use crate::fetcher::{FetchRequest, PageFetcher};
+use crate::SearchResult;
+
+/// Maximum number of redirect lookups in flight per search.
+const RESOLVE_CONCURRENCY: usize = 4;
+
+/// Timeout for a single redirect lookup.
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Resolves redirector links to their final destination.
+///
+/// Lookups go through the engine's own fetcher, so they share its proxy,
+/// user agent and rate limits. They ask it not to follow redirects: the
+/// redirector's `Location` header (or, for redirectors that answer 200, the
+/// target in a meta refresh or `location.replace` script) is taken as the
+/// destination, so with an [`HttpFetcher`](crate::HttpFetcher) the target
+/// page is never downloaded. Fetchers that follow redirects anyway yield
+/// the URL they ended up on.
+pub(crate) struct RedirectResolver {
+    fetcher: Arc<dyn PageFetcher>,
+}
+
+impl RedirectResolver {
+    /// Creates a resolver looking links up through `fetcher`.
+    pub(crate) fn new(fetcher: Arc<dyn PageFetcher>) -> Self {
+        Self { fetcher }
+    }
+
+    /// Rewrites the URL of every result matching `is_redirector` to its
+    /// resolved destination. Results that fail to resolve keep their
+    /// original URL.
+    pub(crate) async fn resolve_all(
+        &self,
+        results: &mut [SearchResult],
+        is_redirector: impl Fn(&str) -> bool,
+    ) {
+        let lookups: Vec<(usize, String)> = results
+            .iter()
+            .enumerate()
+            .filter(|(_, result)| is_redirector(&result.url))
+            .map(|(i, result)| (i, result.url.clone()))
+            .collect();
+
+        let resolved: Vec<(usize, Option<String>)> = stream::iter(lookups)
+            .map(|(i, url)| async move { (i, self.resolve(&url).await) })
+            .buffer_unordered(RESOLVE_CONCURRENCY)
+            .collect()
+            .await;
+
+        for (i, target) in resolved {
+            if let Some(target) = target {
+                results[i].url = target;
+            }
+        }
+    }
+
+    /// Resolves a single redirector URL, returning `None` on failure.
+    async fn resolve(&self, url: &str) -> Option<String> {
+        let request = FetchRequest::get(url).without_redirects();
+        let response = tokio::time::timeout(RESOLVE_TIMEOUT, self.fetcher.fetch_request(&request))
+            .await
+            .ok()?
+            .ok()?;
+        let base = reqwest::Url::parse(&response.final_url).ok()?;
+        if (300..400).contains(&response.status) {
+            let location = response.header("Location")?;
+            return base.join(location).ok().map(|u| u.to_string());
+        }
+        if !(200..300).contains(&response.status) {
+            return None;
+        }
+        if reqwest::Url::parse(url).ok().as_ref() != Some(&base) {
+            return Some(response.final_url);
+        }
+
+        let target = extract_script_redirect(&response.body)?;
+        base.join(&target).ok().map(|u| u.to_string())
+    }
+}
+
+/// Extracts the target of a meta refresh or `location.replace` redirect page.
+fn extract_script_redirect(body: &str) -> Option<String> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(
+            r#"(?i)(?:location\.replace\(\s*|location\.href\s*=\s*|url\s*=\s*)["']?(https?://[^"')\s>]+)"#,
+        )
+        .expect("valid redirect pattern")
+    });
+    pattern
+        .captures(body)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_server::{spawn_server, Response};
+    use crate::HttpFetcher;
+
+    /// Spawns a local redirector: `/link?url=302` answers with a Location
+    /// header, `/link?url=local` redirects to `/dest` on the same server,
+    /// `/link?url=js` with a script redirect page, anything else 404.
+    async fn spawn_redirector() -> (std::net::SocketAddr, tokio::task::JoinHandle<()>) {
+        spawn_server(|request| {
+            let response = match request.path() {
+                "/link?url=302" => Response::redirect("302 Found", "https://www.rust-lang.org/"),
+                "/link?url=local" => Response::redirect("302 Found", "/dest"),
+                "/dest" => Response::ok("ok"),
+                "/link?url=js" => Response::ok(
+                    "<meta http-equiv=\"refresh\" \
+                     content=\"0;URL='https://doc.rust-lang.org/book/'\">\
+                     <script>window.location.replace(\
+                     \"https://doc.rust-lang.org/book/\")</script>",
+                ),
+                _ => Response::new("404 Not Found"),
+            };
+            async move { response }
+        })
+        .await
+    }
+
+    fn is_local_redirector(url: &str) -> bool {
+        url.contains("/link?url=")
+    }
+
+    #[tokio::test]
+    async fn test_resolve_all_rewrites_redirectors() {
+        let (addr, server) = spawn_redirector().await;
+        let mut results = vec![
+            SearchResult::new(format!("http://{}/link?url=302", addr), "Rust", ""),
+            SearchResult::new("https://example.com/direct", "Direct", ""),
+            SearchResult::new(format!("http://{}/link?url=js", addr), "Book", ""),
+            SearchResult::new(format!("http://{}/link?url=missing", addr), "Gone", ""),
+            SearchResult::new(format!("http://{}/link?url=local", addr), "Local", ""),
+        ];
+
+        RedirectResolver::new(Arc::new(HttpFetcher::new()))
+            .resolve_all(&mut results, is_local_redirector)
+            .await;
+
+        assert_eq!(results[0].url, "https://www.rust-lang.org/");
+        assert_eq!(results[1].url, "https://example.com/direct");
+        assert_eq!(results[2].url, "https://doc.rust-lang.org/book/");
+        // Unresolvable links keep their original URL
+        assert_eq!(results[3].url, format!("http://{}/link?url=missing", addr));
+        assert_eq!(results[4].url, format!("http://{}/dest", addr));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_resolve_all_with_fetcher_following_redirects() {
+        let (addr, server) = spawn_redirector().await;
+        let mut results = vec![SearchResult::new(
+            format!("http://{}/link?url=local", addr),
+            "Local",
+            "",
+        )];
+
+        // A custom client follows redirects whatever the request asks
+        let fetcher = HttpFetcher::with_client(reqwest::Client::new());
+        RedirectResolver::new(Arc::new(fetcher))
+            .resolve_all(&mut results, is_local_redirector)
+            .await;
+
+        assert_eq!(results[0].url, format!("http://{}/dest", addr));
+        server.abort();
+    }
+
+    #[test]
+    fn test_extract_script_redirect() {
+        assert_eq!(
+            extract_script_redirect(
+                r#"<script>window.location.replace("https://a.example/x")</script>"#
+            ),
+            Some("https://a.example/x".to_string())
+        );
+        assert_eq!(
+            extract_script_redirect(
+                r#"<meta http-equiv="refresh" content="0;URL='https://b.example/'">"#
+            ),
+            Some("https://b.example/".to_string())
+        );
+        assert_eq!(extract_script_redirect("<html>no redirect</html>"), None);
+    }
+}
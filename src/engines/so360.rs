@@ -3,18 +3,24 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use scraper::{Html, Selector};
 
+use super::redirect::RedirectResolver;
 use crate::fetcher::PageFetcher;
-use crate::{
-    Engine, EngineCategory, EngineConfig, HttpFetcher, Result, SearchError, SearchQuery,
-    SearchResult,
-};
+use crate::html::{HtmlDocument, HtmlSelector};
+use crate::{Engine, EngineCategory, EngineConfig, HttpFetcher, Result, SearchQuery, SearchResult};
+
+/// Origin requests are sent to unless overridden with
+/// [`So360::with_base_url`].
+const DEFAULT_BASE_URL: &str = "https://www.so.com";
 
 /// 360 Search engine (360搜索).
 pub struct So360 {
     config: EngineConfig,
     fetcher: Arc<dyn PageFetcher>,
+    /// Origin of the engine's endpoints.
+    base_url: String,
+    /// Resolves redirector links when redirect resolution is enabled.
+    resolver: Option<RedirectResolver>,
 }
 
 impl So360 {
@@ -23,23 +29,48 @@ impl So360 {
         Self::with_fetcher(Arc::new(HttpFetcher::new()))
     }
 
+    /// Returns the configuration a new engine starts with.
+    pub(crate) fn default_config() -> EngineConfig {
+        EngineConfig {
+            name: "360 Search".to_string(),
+            shortcut: "360".to_string(),
+            categories: vec![EngineCategory::General],
+            weight: 1.0,
+            timeout: 5,
+            enabled: true,
+            paging: true,
+            safesearch: false,
+        }
+    }
+
     /// Creates a new 360 Search engine with a custom page fetcher.
     pub fn with_fetcher(fetcher: Arc<dyn PageFetcher>) -> Self {
         Self {
-            config: EngineConfig {
-                name: "360 Search".to_string(),
-                shortcut: "360".to_string(),
-                categories: vec![EngineCategory::General],
-                weight: 1.0,
-                timeout: 5,
-                enabled: true,
-                paging: true,
-                safesearch: false,
-            },
+            config: Self::default_config(),
             fetcher,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            resolver: None,
         }
     }
 
+    /// Resolves `so.com/link?m=...` redirector links to their final
+    /// destination URLs (default: disabled).
+    ///
+    /// Adds one short request per redirector link, at most a few at a time,
+    /// after the results page is fetched. Lookups go through the engine's
+    /// fetcher, so call this after choosing it.
+    pub fn resolve_redirects(mut self, enabled: bool) -> Self {
+        self.resolver = enabled.then(|| RedirectResolver::new(Arc::clone(&self.fetcher)));
+        self
+    }
+
+    /// Sends requests to `base_url` instead of `https://www.so.com`, e.g. a
+    /// mirror or a local test server.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
     /// Creates with custom configuration.
     pub fn with_config(mut self, config: EngineConfig) -> Self {
         self.config = config;
@@ -60,27 +91,32 @@ impl Engine for So360 {
     }
 
     async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
-        let url = format!(
-            "https://www.so.com/s?q={}",
+        let mut url = format!(
+            "{}/s?q={}",
+            self.base_url,
             urlencoding::encode(&query.query)
         );
+        if query.page > 1 {
+            url.push_str(&format!("&pn={}", query.page));
+        }
 
         let html = self.fetcher.fetch(&url).await?;
 
-        self.parse_results(&html)
+        let mut results = self.parse_results(&html)?;
+        if let Some(ref resolver) = self.resolver {
+            resolver.resolve_all(&mut results, is_so360_redirect).await;
+        }
+        Ok(results)
     }
 }
 
 impl So360 {
     fn parse_results(&self, html: &str) -> Result<Vec<SearchResult>> {
-        let document = Html::parse_document(html);
+        let document = HtmlDocument::parse(html);
 
-        let result_selector = Selector::parse("li.res-list")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let title_selector = Selector::parse("h3 a")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let snippet_selector = Selector::parse(".res-desc, .res-rich")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
+        let result_selector = HtmlSelector::parse("li.res-list")?;
+        let title_selector = HtmlSelector::parse("h3 a")?;
+        let snippet_selector = HtmlSelector::parse(".res-desc, .res-rich")?;
 
         let mut results = Vec::new();
 
@@ -88,20 +124,19 @@ impl So360 {
             let title_elem = element.select(&title_selector).next();
 
             if let Some(title_elem) = title_elem {
-                let title = title_elem.text().collect::<String>().trim().to_string();
+                let title = title_elem.text().trim().to_string();
 
                 // 360 Search stores the real URL in data-mdurl, falling back to href
                 let url = title_elem
-                    .value()
                     .attr("data-mdurl")
-                    .or_else(|| title_elem.value().attr("href"))
+                    .or_else(|| title_elem.attr("href"))
                     .unwrap_or_default()
                     .to_string();
 
                 let content = element
                     .select(&snippet_selector)
                     .next()
-                    .map(|e| e.text().collect::<String>().trim().to_string())
+                    .map(|e| e.text().trim().to_string())
                     .unwrap_or_default();
 
                 if !url.is_empty() && !title.is_empty() {
@@ -114,6 +149,11 @@ impl So360 {
     }
 }
 
+/// Returns true for 360 Search's `/link?m=...` redirector URLs.
+fn is_so360_redirect(url: &str) -> bool {
+    url.starts_with("https://www.so.com/link?") || url.starts_with("http://www.so.com/link?")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,4 +249,18 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].url, "https://example.com/page");
     }
+
+    #[test]
+    fn test_so360_resolve_redirects() {
+        let engine = So360::new();
+        assert!(engine.resolver.is_none());
+        let engine = engine.resolve_redirects(true);
+        assert!(engine.resolver.is_some());
+    }
+
+    #[test]
+    fn test_is_so360_redirect() {
+        assert!(is_so360_redirect("https://www.so.com/link?m=abc"));
+        assert!(!is_so360_redirect("https://www.rust-lang.org/"));
+    }
 }
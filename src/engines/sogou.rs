@@ -3,18 +3,24 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use scraper::{Html, Selector};
 
+use super::redirect::RedirectResolver;
 use crate::fetcher::PageFetcher;
-use crate::{
-    Engine, EngineCategory, EngineConfig, HttpFetcher, Result, SearchError, SearchQuery,
-    SearchResult,
-};
+use crate::html::{HtmlDocument, HtmlSelector};
+use crate::{Engine, EngineCategory, EngineConfig, HttpFetcher, Result, SearchQuery, SearchResult};
+
+/// Origin requests are sent to unless overridden with
+/// [`Sogou::with_base_url`].
+const DEFAULT_BASE_URL: &str = "https://www.sogou.com";
 
 /// Sogou search engine (搜狗).
 pub struct Sogou {
     config: EngineConfig,
     fetcher: Arc<dyn PageFetcher>,
+    /// Origin of the engine's endpoints.
+    base_url: String,
+    /// Resolves redirector links when redirect resolution is enabled.
+    resolver: Option<RedirectResolver>,
 }
 
 impl Sogou {
@@ -23,23 +29,48 @@ impl Sogou {
         Self::with_fetcher(Arc::new(HttpFetcher::new()))
     }
 
+    /// Returns the configuration a new engine starts with.
+    pub(crate) fn default_config() -> EngineConfig {
+        EngineConfig {
+            name: "Sogou".to_string(),
+            shortcut: "sogou".to_string(),
+            categories: vec![EngineCategory::General],
+            weight: 1.0,
+            timeout: 5,
+            enabled: true,
+            paging: true,
+            safesearch: false,
+        }
+    }
+
     /// Creates a new Sogou engine with a custom page fetcher.
     pub fn with_fetcher(fetcher: Arc<dyn PageFetcher>) -> Self {
         Self {
-            config: EngineConfig {
-                name: "Sogou".to_string(),
-                shortcut: "sogou".to_string(),
-                categories: vec![EngineCategory::General],
-                weight: 1.0,
-                timeout: 5,
-                enabled: true,
-                paging: true,
-                safesearch: false,
-            },
+            config: Self::default_config(),
             fetcher,
+            base_url: DEFAULT_BASE_URL.to_string(),
+            resolver: None,
         }
     }
 
+    /// Resolves `sogou.com/link?url=...` redirector links to their final
+    /// destination URLs (default: disabled).
+    ///
+    /// Adds one short request per redirector link, at most a few at a time,
+    /// after the results page is fetched. Lookups go through the engine's
+    /// fetcher, so call this after choosing it.
+    pub fn resolve_redirects(mut self, enabled: bool) -> Self {
+        self.resolver = enabled.then(|| RedirectResolver::new(Arc::clone(&self.fetcher)));
+        self
+    }
+
+    /// Sends requests to `base_url` instead of `https://www.sogou.com`, e.g. a
+    /// mirror or a local test server.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
     /// Creates with custom configuration.
     pub fn with_config(mut self, config: EngineConfig) -> Self {
         self.config = config;
@@ -60,27 +91,34 @@ impl Engine for Sogou {
     }
 
     async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
-        let url = format!(
-            "https://www.sogou.com/web?query={}",
+        let mut url = format!(
+            "{}/web?query={}",
+            self.base_url,
             urlencoding::encode(&query.query)
         );
+        if query.page > 1 {
+            url.push_str(&format!("&page={}", query.page));
+        }
 
         let html = self.fetcher.fetch(&url).await?;
 
-        self.parse_results(&html)
+        let mut results = self.parse_results(&html)?;
+        if let Some(ref resolver) = self.resolver {
+            resolver
+                .resolve_all(&mut results, |url| is_sogou_redirect(&self.base_url, url))
+                .await;
+        }
+        Ok(results)
     }
 }
 
 impl Sogou {
     fn parse_results(&self, html: &str) -> Result<Vec<SearchResult>> {
-        let document = Html::parse_document(html);
+        let document = HtmlDocument::parse(html);
 
-        let result_selector = Selector::parse("div.vrwrap, div.rb")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let title_selector = Selector::parse("h3 a, .vr-title a")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
-        let snippet_selector = Selector::parse(".str-text, .str_info, .space-txt")
-            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))?;
+        let result_selector = HtmlSelector::parse("div.vrwrap, div.rb")?;
+        let title_selector = HtmlSelector::parse("h3 a, .vr-title a")?;
+        let snippet_selector = HtmlSelector::parse(".str-text, .str_info, .space-txt")?;
 
         let mut results = Vec::new();
 
@@ -88,12 +126,12 @@ impl Sogou {
             let title_elem = element.select(&title_selector).next();
 
             if let Some(title_elem) = title_elem {
-                let title = title_elem.text().collect::<String>().trim().to_string();
-                let raw_url = title_elem.value().attr("href").unwrap_or_default();
+                let title = title_elem.text().trim().to_string();
+                let raw_url = title_elem.attr("href").unwrap_or_default();
 
                 // Sogou returns relative redirect URLs like /link?url=...
                 let url = if raw_url.starts_with('/') {
-                    format!("https://www.sogou.com{}", raw_url)
+                    format!("{}{}", self.base_url, raw_url)
                 } else {
                     raw_url.to_string()
                 };
@@ -101,7 +139,7 @@ impl Sogou {
                 let content = element
                     .select(&snippet_selector)
                     .next()
-                    .map(|e| e.text().collect::<String>().trim().to_string())
+                    .map(|e| e.text().trim().to_string())
                     .unwrap_or_default();
 
                 if !url.is_empty() && !title.is_empty() {
@@ -114,6 +152,12 @@ impl Sogou {
     }
 }
 
+/// Returns true for Sogou's `/link?url=...` redirector URLs on `base_url`.
+fn is_sogou_redirect(base_url: &str, url: &str) -> bool {
+    url.strip_prefix(base_url)
+        .is_some_and(|path| path.starts_with("/link?"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,4 +249,51 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].url, "https://www.sogou.com/link?url=xyz789");
     }
+
+    #[test]
+    fn test_sogou_resolve_redirects() {
+        let engine = Sogou::new();
+        assert!(engine.resolver.is_none());
+        let engine = engine.resolve_redirects(true);
+        assert!(engine.resolver.is_some());
+        let engine = engine.resolve_redirects(false);
+        assert!(engine.resolver.is_none());
+    }
+
+    #[test]
+    fn test_is_sogou_redirect() {
+        assert!(is_sogou_redirect(
+            DEFAULT_BASE_URL,
+            "https://www.sogou.com/link?url=abc123"
+        ));
+        assert!(!is_sogou_redirect(
+            DEFAULT_BASE_URL,
+            "https://example.com/page"
+        ));
+        assert!(is_sogou_redirect(
+            "http://127.0.0.1:8080",
+            "http://127.0.0.1:8080/link?url=abc123"
+        ));
+        assert!(!is_sogou_redirect(
+            "http://127.0.0.1:8080",
+            "https://www.sogou.com/link?url=abc123"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sogou_resolves_redirects_through_engine_fetcher() {
+        let html = r#"<div class="vrwrap"><h3><a href="/link?url=abc">Rust</a></h3></div>"#;
+        let fetcher = crate::engines::CapturingFetcher::new(html);
+        Sogou::with_fetcher(fetcher.clone())
+            .with_base_url("http://127.0.0.1:8080")
+            .resolve_redirects(true)
+            .search(&SearchQuery::new("rust"))
+            .await
+            .unwrap();
+
+        let requests = fetcher.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].url, "http://127.0.0.1:8080/link?url=abc");
+        assert!(requests[1].no_redirects);
+    }
 }
@@ -5,14 +5,72 @@ use serde::Deserialize;
 
 use crate::{Engine, EngineCategory, EngineConfig, HttpFetcher, Result, SearchQuery, SearchResult};
 
+/// Results per request when the query doesn't ask for a count.
+const DEFAULT_RESULTS: u32 = 10;
+
+/// The API's `srlimit` cap for anonymous clients.
+const MAX_RESULTS: u32 = 500;
+
+/// A Wikimedia project searchable through the MediaWiki API.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WikiProject {
+    /// The encyclopedia (`{lang}.wikipedia.org`).
+    #[default]
+    Wikipedia,
+    /// The dictionary (`{lang}.wiktionary.org`).
+    Wiktionary,
+    /// News articles (`{lang}.wikinews.org`).
+    Wikinews,
+    /// Textbooks and manuals (`{lang}.wikibooks.org`).
+    Wikibooks,
+}
+
+impl WikiProject {
+    /// Second-level domain of the project, e.g. `wiktionary.org`.
+    pub fn domain(&self) -> &'static str {
+        match self {
+            WikiProject::Wikipedia => "wikipedia.org",
+            WikiProject::Wiktionary => "wiktionary.org",
+            WikiProject::Wikinews => "wikinews.org",
+            WikiProject::Wikibooks => "wikibooks.org",
+        }
+    }
+
+    /// Engine name used for the project.
+    pub fn name(&self) -> &'static str {
+        match self {
+            WikiProject::Wikipedia => "Wikipedia",
+            WikiProject::Wiktionary => "Wiktionary",
+            WikiProject::Wikinews => "Wikinews",
+            WikiProject::Wikibooks => "Wikibooks",
+        }
+    }
+
+    /// Engine shortcut used for the project.
+    pub fn shortcut(&self) -> &'static str {
+        match self {
+            WikiProject::Wikipedia => "wiki",
+            WikiProject::Wiktionary => "wikt",
+            WikiProject::Wikinews => "wikinews",
+            WikiProject::Wikibooks => "wikibooks",
+        }
+    }
+}
+
 /// Wikipedia search engine using the MediaWiki API.
 ///
+/// Sister projects such as Wiktionary share the same API and can be
+/// searched with [`Wikipedia::with_project`].
+///
 /// Unlike other engines, Wikipedia uses a JSON API rather than HTML scraping,
 /// so it holds an `HttpFetcher` directly to access the underlying reqwest client.
 pub struct Wikipedia {
     config: EngineConfig,
     fetcher: HttpFetcher,
     language: String,
+    project: WikiProject,
+    /// Origin overriding the project's `https://{lang}.{domain}`.
+    base_url: Option<String>,
 }
 
 impl Wikipedia {
@@ -21,23 +79,30 @@ impl Wikipedia {
         Self::with_http_fetcher(HttpFetcher::new())
     }
 
+    /// Returns the configuration a new engine starts with.
+    pub(crate) fn default_config() -> EngineConfig {
+        EngineConfig {
+            name: "Wikipedia".to_string(),
+            shortcut: "wiki".to_string(),
+            categories: vec![EngineCategory::General],
+            weight: 1.2,
+            timeout: 5,
+            enabled: true,
+            paging: false,
+            safesearch: false,
+        }
+    }
+
     /// Creates a new Wikipedia engine with a custom HTTP fetcher.
     ///
     /// Use this to provide a fetcher configured with proxy support.
     pub fn with_http_fetcher(fetcher: HttpFetcher) -> Self {
         Self {
-            config: EngineConfig {
-                name: "Wikipedia".to_string(),
-                shortcut: "wiki".to_string(),
-                categories: vec![EngineCategory::General],
-                weight: 1.2,
-                timeout: 5,
-                enabled: true,
-                paging: false,
-                safesearch: false,
-            },
+            config: Self::default_config(),
             fetcher,
             language: "en".to_string(),
+            project: WikiProject::Wikipedia,
+            base_url: None,
         }
     }
 
@@ -47,6 +112,25 @@ impl Wikipedia {
         self
     }
 
+    /// Searches a sister project instead of Wikipedia.
+    ///
+    /// Also sets the engine name and shortcut to the project's (e.g.
+    /// `Wiktionary` / `wikt`), so several projects can run side by side.
+    pub fn with_project(mut self, project: WikiProject) -> Self {
+        self.project = project;
+        self.config.name = project.name().to_string();
+        self.config.shortcut = project.shortcut().to_string();
+        self
+    }
+
+    /// Sends API requests to `base_url` instead of the project's
+    /// `https://{lang}.{domain}`, e.g. a mirror or a local test server.
+    /// Result links point there too.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into().trim_end_matches('/').to_string());
+        self
+    }
+
     /// Creates with custom configuration.
     pub fn with_config(mut self, config: EngineConfig) -> Self {
         self.config = config;
@@ -54,6 +138,28 @@ impl Wikipedia {
     }
 }
 
+impl Wikipedia {
+    fn origin(&self) -> String {
+        match self.base_url {
+            Some(ref base_url) => base_url.clone(),
+            None => format!("https://{}.{}", self.language, self.project.domain()),
+        }
+    }
+
+    fn api_url(&self, query: &SearchQuery) -> String {
+        format!(
+            "{}/w/api.php?action=query&list=search&srsearch={}&format=json&srlimit={}",
+            self.origin(),
+            urlencoding::encode(&query.query),
+            query.result_count(MAX_RESULTS).unwrap_or(DEFAULT_RESULTS)
+        )
+    }
+
+    fn result_url(&self, title: &str) -> String {
+        format!("{}/wiki/{}", self.origin(), title.replace(' ', "_"))
+    }
+}
+
 impl Default for Wikipedia {
     fn default() -> Self {
         Self::new()
@@ -85,13 +191,10 @@ impl Engine for Wikipedia {
     }
 
     async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
-        let url = format!(
-            "https://{}.wikipedia.org/w/api.php?action=query&list=search&srsearch={}&format=json&srlimit=10",
-            self.language,
-            urlencoding::encode(&query.query)
-        );
+        let url = self.api_url(query);
 
-        let response = self.fetcher.client().get(&url).send().await?;
+        let response = self.fetcher.request(&url).send().await?;
+        let response = self.fetcher.check_status(response).await?;
         let wiki_response: WikiResponse = response.json().await?;
 
         let results = wiki_response
@@ -100,11 +203,7 @@ impl Engine for Wikipedia {
                 q.search
                     .into_iter()
                     .map(|item| {
-                        let url = format!(
-                            "https://{}.wikipedia.org/wiki/{}",
-                            self.language,
-                            item.title.replace(' ', "_")
-                        );
+                        let url = self.result_url(&item.title);
                         let content = strip_html_tags(&item.snippet);
                         SearchResult::new(url, item.title, content)
                     })
@@ -272,4 +371,87 @@ mod tests {
         assert_eq!(engine.language, "zh");
         assert_eq!(engine.name(), "Wikipedia");
     }
+
+    #[test]
+    fn test_wikipedia_urls() {
+        let engine = Wikipedia::new();
+        assert_eq!(engine.project, WikiProject::Wikipedia);
+        assert_eq!(
+            engine.api_url(&SearchQuery::new("rust lang")),
+            "https://en.wikipedia.org/w/api.php?action=query&list=search&srsearch=rust%20lang&format=json&srlimit=10"
+        );
+        assert_eq!(
+            engine.result_url("Rust (programming language)"),
+            "https://en.wikipedia.org/wiki/Rust_(programming_language)"
+        );
+    }
+
+    #[test]
+    fn test_wikipedia_base_url_result_links() {
+        let engine = Wikipedia::new()
+            .with_language("de")
+            .with_base_url("http://127.0.0.1:8080/");
+        assert_eq!(engine.result_url("Rust"), "http://127.0.0.1:8080/wiki/Rust");
+    }
+
+    #[test]
+    fn test_wikipedia_results_per_engine() {
+        let engine = Wikipedia::new();
+        let query = SearchQuery::new("rust").with_results_per_engine(50);
+        assert!(engine.api_url(&query).ends_with("&srlimit=50"));
+        let query = SearchQuery::new("rust").with_results_per_engine(1000);
+        assert!(engine.api_url(&query).ends_with("&srlimit=500"));
+    }
+
+    #[test]
+    fn test_wikipedia_with_project_wiktionary() {
+        let engine = Wikipedia::new()
+            .with_language("de")
+            .with_project(WikiProject::Wiktionary);
+        assert_eq!(engine.name(), "Wiktionary");
+        assert_eq!(engine.shortcut(), "wikt");
+        assert_eq!(
+            engine.api_url(&SearchQuery::new("haus")),
+            "https://de.wiktionary.org/w/api.php?action=query&list=search&srsearch=haus&format=json&srlimit=10"
+        );
+        assert_eq!(
+            engine.result_url("Haus am See"),
+            "https://de.wiktionary.org/wiki/Haus_am_See"
+        );
+    }
+
+    #[test]
+    fn test_wiki_project_hosts() {
+        for (project, domain, name, shortcut) in [
+            (WikiProject::Wikipedia, "wikipedia.org", "Wikipedia", "wiki"),
+            (
+                WikiProject::Wiktionary,
+                "wiktionary.org",
+                "Wiktionary",
+                "wikt",
+            ),
+            (
+                WikiProject::Wikinews,
+                "wikinews.org",
+                "Wikinews",
+                "wikinews",
+            ),
+            (
+                WikiProject::Wikibooks,
+                "wikibooks.org",
+                "Wikibooks",
+                "wikibooks",
+            ),
+        ] {
+            assert_eq!(project.domain(), domain);
+            let engine = Wikipedia::new().with_project(project);
+            assert_eq!(engine.name(), name);
+            assert_eq!(engine.shortcut(), shortcut);
+            assert_eq!(
+                engine.result_url("Main Page"),
+                format!("https://en.{}/wiki/Main_Page", domain)
+            );
+        }
+        assert_eq!(WikiProject::default(), WikiProject::Wikipedia);
+    }
 }
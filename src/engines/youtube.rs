@@ -0,0 +1,325 @@
+//! YouTube video search engine implementation.
+//!
+//! YouTube's own results page is rendered with JavaScript, so this engine
+//! queries an Invidious instance instead. Invidious exposes a JSON search API
+//! that mirrors YouTube's results and works with plain HTTP requests.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use crate::fetcher::PageFetcher;
+use crate::{
+    Engine, EngineCategory, EngineConfig, HttpFetcher, Result, ResultType, SearchError,
+    SearchQuery, SearchResult,
+};
+
+/// Default Invidious instance used for API requests.
+const DEFAULT_INSTANCE: &str = "https://inv.nadeko.net";
+
+/// Environment variable naming the Invidious instance engines start with,
+/// e.g. `https://yewtu.be`, instead of the built-in default.
+/// [`YouTube::with_instance`] still overrides it.
+pub const INVIDIOUS_INSTANCE_ENV: &str = "A3S_INVIDIOUS_INSTANCE";
+
+/// Returns the instance `configured` by [`INVIDIOUS_INSTANCE_ENV`], or the
+/// default one if it is unset or blank.
+fn default_instance(configured: Option<String>) -> String {
+    configured
+        .map(|instance| instance.trim().trim_end_matches('/').to_string())
+        .filter(|instance| !instance.is_empty())
+        .unwrap_or_else(|| DEFAULT_INSTANCE.to_string())
+}
+
+/// YouTube video search engine backed by the Invidious API.
+pub struct YouTube {
+    config: EngineConfig,
+    fetcher: Arc<dyn PageFetcher>,
+    instance: String,
+}
+
+impl YouTube {
+    /// Creates a new YouTube engine with a default HTTP fetcher.
+    pub fn new() -> Self {
+        Self::with_fetcher(Arc::new(HttpFetcher::new()))
+    }
+
+    /// Returns the configuration a new engine starts with.
+    pub(crate) fn default_config() -> EngineConfig {
+        EngineConfig {
+            name: "YouTube".to_string(),
+            shortcut: "yt".to_string(),
+            categories: vec![EngineCategory::Videos],
+            weight: 1.0,
+            timeout: 5,
+            enabled: true,
+            paging: true,
+            safesearch: false,
+        }
+    }
+
+    /// Creates a new YouTube engine with a custom page fetcher.
+    pub fn with_fetcher(fetcher: Arc<dyn PageFetcher>) -> Self {
+        Self {
+            config: Self::default_config(),
+            fetcher,
+            instance: default_instance(std::env::var(INVIDIOUS_INSTANCE_ENV).ok()),
+        }
+    }
+
+    /// Returns the Invidious instance base URL requests go to.
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+
+    /// Sets the Invidious instance base URL (e.g., `https://yewtu.be`).
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = instance.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Same as [`with_instance`](Self::with_instance), matching the other
+    /// HTTP engines' builder for mirrors and local test servers.
+    pub fn with_base_url(self, base_url: impl Into<String>) -> Self {
+        self.with_instance(base_url)
+    }
+
+    /// Creates with custom configuration.
+    pub fn with_config(mut self, config: EngineConfig) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+impl Default for YouTube {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InvidiousItem {
+    #[serde(rename = "type")]
+    item_type: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    video_id: String,
+    #[serde(default)]
+    author: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    length_seconds: u64,
+    #[serde(default)]
+    published_text: Option<String>,
+    #[serde(default)]
+    video_thumbnails: Vec<InvidiousThumbnail>,
+}
+
+#[derive(Deserialize)]
+struct InvidiousThumbnail {
+    quality: String,
+    url: String,
+}
+
+#[async_trait]
+impl Engine for YouTube {
+    fn config(&self) -> &EngineConfig {
+        &self.config
+    }
+
+    async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+        let url = format!(
+            "{}/api/v1/search?q={}&type=video&page={}",
+            self.instance,
+            urlencoding::encode(&query.query),
+            query.page.max(1)
+        );
+
+        let body = self.fetcher.fetch(&url).await?;
+
+        self.parse_results(&body)
+    }
+}
+
+impl YouTube {
+    fn parse_results(&self, body: &str) -> Result<Vec<SearchResult>> {
+        let items: Vec<InvidiousItem> = serde_json::from_str(body)
+            .map_err(|e| SearchError::Parse(format!("Invalid Invidious response: {}", e)))?;
+
+        let results = items
+            .into_iter()
+            .filter(|item| item.item_type == "video" && !item.video_id.is_empty())
+            .filter(|item| !item.title.is_empty())
+            .map(|item| {
+                let url = format!("https://www.youtube.com/watch?v={}", item.video_id);
+                let content = video_content(&item);
+                let mut result =
+                    SearchResult::new(url, item.title.trim(), content).with_type(ResultType::Video);
+                if let Some(thumbnail) = self.pick_thumbnail(&item) {
+                    result = result.with_thumbnail(thumbnail);
+                }
+                if let Some(published) = item.published_text.filter(|p| !p.is_empty()) {
+                    result = result.with_published_date(published);
+                }
+                result
+            })
+            .collect();
+
+        Ok(results)
+    }
+
+    /// Picks a medium-sized thumbnail, falling back to the first one listed.
+    ///
+    /// Some instances return thumbnail paths relative to the instance, so
+    /// those are resolved against the instance base URL.
+    fn pick_thumbnail(&self, item: &InvidiousItem) -> Option<String> {
+        let thumbnail = item
+            .video_thumbnails
+            .iter()
+            .find(|t| t.quality == "medium")
+            .or_else(|| item.video_thumbnails.first())?;
+
+        if thumbnail.url.starts_with('/') {
+            Some(format!("{}{}", self.instance, thumbnail.url))
+        } else {
+            Some(thumbnail.url.clone())
+        }
+    }
+}
+
+/// Builds the result snippet: duration, channel, and description.
+fn video_content(item: &InvidiousItem) -> String {
+    let mut parts = vec![format!("[{}]", format_duration(item.length_seconds))];
+    if !item.author.is_empty() {
+        parts.push(item.author.trim().to_string());
+    }
+    let description = item.description.trim();
+    if !description.is_empty() {
+        parts.push(description.to_string());
+    }
+    parts.join(" ")
+}
+
+/// Formats a duration in seconds as `m:ss` or `h:mm:ss`.
+fn format_duration(seconds: u64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    let secs = seconds % 60;
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{}:{:02}", minutes, secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = include_str!("../../tests/fixtures/invidious_search.json");
+
+    #[test]
+    fn test_youtube_new() {
+        let engine = YouTube::new();
+        assert_eq!(engine.config.name, "YouTube");
+        assert_eq!(engine.config.shortcut, "yt");
+        assert_eq!(engine.config.categories, vec![EngineCategory::Videos]);
+        assert!(engine.config.paging);
+    }
+
+    #[test]
+    fn test_youtube_default_instance() {
+        assert_eq!(default_instance(None), DEFAULT_INSTANCE);
+        assert_eq!(default_instance(Some("  ".to_string())), DEFAULT_INSTANCE);
+        assert_eq!(
+            default_instance(Some(" https://yewtu.be/ ".to_string())),
+            "https://yewtu.be"
+        );
+    }
+
+    #[test]
+    fn test_youtube_default() {
+        let engine = YouTube::default();
+        assert_eq!(engine.name(), "YouTube");
+    }
+
+    #[test]
+    fn test_youtube_with_instance() {
+        let engine = YouTube::new().with_instance("https://yewtu.be/");
+        assert_eq!(engine.instance(), "https://yewtu.be");
+    }
+
+    #[test]
+    fn test_youtube_with_config() {
+        let custom_config = EngineConfig {
+            name: "Custom YouTube".to_string(),
+            weight: 0.8,
+            ..Default::default()
+        };
+        let engine = YouTube::new().with_config(custom_config);
+        assert_eq!(engine.name(), "Custom YouTube");
+        assert_eq!(engine.weight(), 0.8);
+    }
+
+    #[test]
+    fn test_youtube_parse_fixture() {
+        let engine = YouTube::new().with_instance("https://invidious.example");
+        let results = engine.parse_results(FIXTURE).unwrap();
+
+        // The channel and playlist entries are skipped
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|r| r.result_type == ResultType::Video));
+        assert!(results.iter().all(|r| r.thumbnail.is_some()));
+
+        assert_eq!(
+            results[0].url,
+            "https://www.youtube.com/watch?v=zF34dRivLOw"
+        );
+        assert_eq!(results[0].title, "Rust in 100 Seconds");
+        assert_eq!(
+            results[0].thumbnail.as_deref(),
+            Some("https://i.ytimg.com/vi/zF34dRivLOw/mqdefault.jpg")
+        );
+        assert!(results[0].content.starts_with("[2:29] Fireship"));
+        assert_eq!(results[0].published_date.as_deref(), Some("3 years ago"));
+
+        // Hour-long video and relative thumbnail path
+        assert!(results[1].content.starts_with("[1:02:05]"));
+        assert_eq!(
+            results[1].thumbnail.as_deref(),
+            Some("https://invidious.example/vi/BpPEoZW5IiY/mqdefault.jpg")
+        );
+
+        // Falls back to the first thumbnail when no medium quality exists
+        assert_eq!(
+            results[2].thumbnail.as_deref(),
+            Some("https://i.ytimg.com/vi/5C_HPTJg5ek/hqdefault.jpg")
+        );
+    }
+
+    #[test]
+    fn test_youtube_parse_empty_array() {
+        let engine = YouTube::new();
+        let results = engine.parse_results("[]").unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_youtube_parse_invalid_json() {
+        let engine = YouTube::new();
+        let result = engine.parse_results("<html>blocked</html>");
+        assert!(matches!(result, Err(SearchError::Parse(_))));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0:00");
+        assert_eq!(format_duration(59), "0:59");
+        assert_eq!(format_duration(149), "2:29");
+        assert_eq!(format_duration(3725), "1:02:05");
+    }
+}
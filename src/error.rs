@@ -28,6 +28,10 @@ pub enum SearchError {
     #[error("No search engines configured")]
     NoEngines,
 
+    /// Engine shortcuts that match no built-in engine.
+    #[error("Unknown engines: {}", .0.join(", "))]
+    UnknownEngines(Vec<String>),
+
     /// Invalid query.
     #[error("Invalid query: {0}")]
     InvalidQuery(String),
@@ -36,6 +40,40 @@ pub enum SearchError {
     #[error("URL parsing error: {0}")]
     UrlParse(#[from] url::ParseError),
 
+    /// Server rejected the request with HTTP 429 Too Many Requests.
+    ///
+    /// Carries the raw `Retry-After` header value when the server sent one.
+    #[error("Rate limited by server{}", retry_after_suffix(.0))]
+    RateLimited(Option<String>),
+
+    /// Server refused the request with HTTP 403, typically a bot block.
+    #[error("Request blocked by server (HTTP 403): {0}")]
+    Blocked(String),
+
+    /// The engine served a CAPTCHA or bot-check page instead of results.
+    #[error("{0}")]
+    Captcha(String),
+
+    /// Server returned a non-success HTTP status.
+    ///
+    /// Carries the status code and the start of the response body.
+    #[error("HTTP {0}: {1}")]
+    HttpStatus(u16, String),
+
+    /// Server answered with a redirect that the fetcher did not follow.
+    ///
+    /// Carries the absolute redirect target from the `Location` header.
+    #[error("Redirected to {0}")]
+    Redirected(String),
+
+    /// Response body exceeded the fetcher's size limit (in bytes).
+    #[error("Response body exceeds the {0} byte limit")]
+    ResponseTooLarge(usize),
+
+    /// The site's robots.txt disallows fetching the URL.
+    #[error("Disallowed by robots.txt: {0}")]
+    DisallowedByRobots(String),
+
     /// Browser operation failed.
     #[error("Browser error: {0}")]
     Browser(String),
@@ -45,6 +83,44 @@ pub enum SearchError {
     Other(String),
 }
 
+impl SearchError {
+    /// Returns a copy of this error for callers sharing one failed request.
+    ///
+    /// `reqwest::Error` cannot be cloned, so `Http` errors become `Other`
+    /// with the same message.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn duplicate(&self) -> SearchError {
+        match self {
+            SearchError::Http(e) => SearchError::Other(format!("HTTP request failed: {}", e)),
+            SearchError::Parse(s) => SearchError::Parse(s.clone()),
+            SearchError::EngineSuspended(name, until) => {
+                SearchError::EngineSuspended(name.clone(), until.clone())
+            }
+            SearchError::Timeout => SearchError::Timeout,
+            SearchError::NoEngines => SearchError::NoEngines,
+            SearchError::UnknownEngines(names) => SearchError::UnknownEngines(names.clone()),
+            SearchError::InvalidQuery(s) => SearchError::InvalidQuery(s.clone()),
+            SearchError::UrlParse(e) => SearchError::UrlParse(*e),
+            SearchError::RateLimited(r) => SearchError::RateLimited(r.clone()),
+            SearchError::Blocked(s) => SearchError::Blocked(s.clone()),
+            SearchError::Captcha(s) => SearchError::Captcha(s.clone()),
+            SearchError::HttpStatus(code, body) => SearchError::HttpStatus(*code, body.clone()),
+            SearchError::Redirected(s) => SearchError::Redirected(s.clone()),
+            SearchError::ResponseTooLarge(n) => SearchError::ResponseTooLarge(*n),
+            SearchError::DisallowedByRobots(s) => SearchError::DisallowedByRobots(s.clone()),
+            SearchError::Browser(s) => SearchError::Browser(s.clone()),
+            SearchError::Other(s) => SearchError::Other(s.clone()),
+        }
+    }
+}
+
+fn retry_after_suffix(retry_after: &Option<String>) -> String {
+    match retry_after {
+        Some(value) => format!(" (retry after {})", value),
+        None => String::new(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -76,12 +152,63 @@ mod tests {
         assert_eq!(err.to_string(), "No search engines configured");
     }
 
+    #[test]
+    fn test_error_display_unknown_engines() {
+        let err = SearchError::UnknownEngines(vec!["foo".to_string(), "bar".to_string()]);
+        assert_eq!(err.to_string(), "Unknown engines: foo, bar");
+    }
+
     #[test]
     fn test_error_display_invalid_query() {
         let err = SearchError::InvalidQuery("empty query".to_string());
         assert_eq!(err.to_string(), "Invalid query: empty query");
     }
 
+    #[test]
+    fn test_error_display_rate_limited() {
+        let err = SearchError::RateLimited(Some("120".to_string()));
+        assert_eq!(err.to_string(), "Rate limited by server (retry after 120)");
+
+        let err = SearchError::RateLimited(None);
+        assert_eq!(err.to_string(), "Rate limited by server");
+    }
+
+    #[test]
+    fn test_error_display_blocked() {
+        let err = SearchError::Blocked("Access denied".to_string());
+        assert_eq!(
+            err.to_string(),
+            "Request blocked by server (HTTP 403): Access denied"
+        );
+    }
+
+    #[test]
+    fn test_error_display_http_status() {
+        let err = SearchError::HttpStatus(503, "Service Unavailable".to_string());
+        assert_eq!(err.to_string(), "HTTP 503: Service Unavailable");
+    }
+
+    #[test]
+    fn test_error_display_redirected() {
+        let err = SearchError::Redirected("https://example.com/final".to_string());
+        assert_eq!(err.to_string(), "Redirected to https://example.com/final");
+    }
+
+    #[test]
+    fn test_error_display_response_too_large() {
+        let err = SearchError::ResponseTooLarge(1024);
+        assert_eq!(err.to_string(), "Response body exceeds the 1024 byte limit");
+    }
+
+    #[test]
+    fn test_error_display_disallowed_by_robots() {
+        let err = SearchError::DisallowedByRobots("https://example.com/search".to_string());
+        assert_eq!(
+            err.to_string(),
+            "Disallowed by robots.txt: https://example.com/search"
+        );
+    }
+
     #[test]
     fn test_error_display_browser() {
         let err = SearchError::Browser("chrome crashed".to_string());
@@ -94,6 +221,15 @@ mod tests {
         assert_eq!(err.to_string(), "something went wrong");
     }
 
+    #[test]
+    fn test_error_duplicate() {
+        let err = SearchError::RateLimited(Some("60".to_string())).duplicate();
+        assert!(matches!(err, SearchError::RateLimited(Some(ref v)) if v == "60"));
+
+        let err = SearchError::HttpStatus(503, "down".to_string()).duplicate();
+        assert_eq!(err.to_string(), "HTTP 503: down");
+    }
+
     #[test]
     fn test_error_debug() {
         let err = SearchError::Timeout;
@@ -117,7 +253,15 @@ mod tests {
             SearchError::EngineSuspended("engine".to_string(), "date".to_string()),
             SearchError::Timeout,
             SearchError::NoEngines,
+            SearchError::UnknownEngines(vec!["engine".to_string()]),
             SearchError::InvalidQuery("bad query".to_string()),
+            SearchError::RateLimited(None),
+            SearchError::Blocked("blocked".to_string()),
+            SearchError::Captcha("captcha".to_string()),
+            SearchError::HttpStatus(500, "error".to_string()),
+            SearchError::Redirected("https://example.com".to_string()),
+            SearchError::ResponseTooLarge(1024),
+            SearchError::DisallowedByRobots("https://example.com".to_string()),
             SearchError::Browser("browser error".to_string()),
             SearchError::Other("other error".to_string()),
         ];
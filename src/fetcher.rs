@@ -2,7 +2,7 @@
 
 use async_trait::async_trait;
 
-use crate::Result;
+use crate::{Result, SearchError};
 
 /// Strategy for waiting until a page is considered fully loaded.
 #[derive(Debug, Clone, Default)]
@@ -10,7 +10,12 @@ pub enum WaitStrategy {
     /// Wait for the page load event only.
     #[default]
     Load,
-    /// Wait until network activity settles for the given duration.
+    /// Wait for the page load event, then until no requests have been in
+    /// flight for the given duration.
+    ///
+    /// `BrowserFetcher` can tolerate a few lingering requests and caps the
+    /// wait; see its `with_network_idle_threshold` and
+    /// `with_network_idle_timeout`.
     NetworkIdle {
         /// Milliseconds of network inactivity to wait for.
         idle_ms: u64,
@@ -29,6 +34,180 @@ pub enum WaitStrategy {
     },
 }
 
+/// Which path of a fetcher that can both send plain HTTP requests and
+/// render pages in a browser should serve an engine's fetches.
+///
+/// An engine returns it from
+/// [`Engine::preferred_fetcher`](crate::Engine::preferred_fetcher), and
+/// `HybridFetcher` follows it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FetcherHint {
+    /// Plain HTTP only, without falling back to the browser.
+    Http,
+    /// Straight to the browser.
+    Browser,
+    /// HTTP first, the browser only when HTTP is blocked.
+    #[default]
+    Auto,
+}
+
+/// HTTP method of a [`FetchRequest`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FetchMethod {
+    #[default]
+    Get,
+    Head,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl FetchMethod {
+    /// Returns the method name as sent on the wire (e.g. `"POST"`).
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FetchMethod::Get => "GET",
+            FetchMethod::Head => "HEAD",
+            FetchMethod::Post => "POST",
+            FetchMethod::Put => "PUT",
+            FetchMethod::Patch => "PATCH",
+            FetchMethod::Delete => "DELETE",
+        }
+    }
+}
+
+/// Body of a [`FetchRequest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FetchBody {
+    /// URL-encoded form fields (`application/x-www-form-urlencoded`).
+    Form(Vec<(String, String)>),
+    /// Raw body sent as-is; set `Content-Type` with a header.
+    Raw(String),
+}
+
+/// A request with an explicit method, headers, and optional body.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchRequest {
+    /// HTTP method.
+    pub method: FetchMethod,
+    /// Target URL.
+    pub url: String,
+    /// Extra headers, sent in addition to the fetcher's defaults.
+    pub headers: Vec<(String, String)>,
+    /// Request body.
+    pub body: Option<FetchBody>,
+    /// Whether a 3xx response is returned instead of followed.
+    ///
+    /// The response keeps the redirect's status and `Location` header and is
+    /// not reported as [`SearchError::Redirected`](crate::SearchError::Redirected).
+    /// A hint: fetchers that cannot stop at a redirect, such as a browser
+    /// tab, follow it and report the destination as the final URL.
+    pub no_redirects: bool,
+}
+
+impl FetchRequest {
+    /// Creates a GET request.
+    pub fn get(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Creates a POST request with URL-encoded form fields.
+    pub fn post_form<K, V>(url: impl Into<String>, fields: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<String>,
+    {
+        Self {
+            method: FetchMethod::Post,
+            url: url.into(),
+            headers: Vec::new(),
+            body: Some(FetchBody::Form(
+                fields
+                    .into_iter()
+                    .map(|(k, v)| (k.into(), v.into()))
+                    .collect(),
+            )),
+            no_redirects: false,
+        }
+    }
+
+    /// Sets the HTTP method.
+    pub fn with_method(mut self, method: FetchMethod) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Adds a header.
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the `User-Agent` header, replacing any set before.
+    ///
+    /// Overrides the fetcher's own user agent, including a rotated one, for
+    /// this request only.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case("user-agent"));
+        self.with_header("User-Agent", user_agent)
+    }
+
+    /// Returns the request's `User-Agent` header, if set.
+    pub fn user_agent(&self) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("user-agent"))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Sets the body.
+    pub fn with_body(mut self, body: FetchBody) -> Self {
+        self.body = Some(body);
+        self
+    }
+
+    /// Asks the fetcher to return a 3xx response instead of following it,
+    /// e.g. to read a redirector's `Location` without loading the target.
+    pub fn without_redirects(mut self) -> Self {
+        self.no_redirects = true;
+        self
+    }
+
+    /// Returns true for a plain GET without a body, which any fetcher can
+    /// serve by navigating to the URL.
+    pub fn is_plain_get(&self) -> bool {
+        self.method == FetchMethod::Get && self.body.is_none()
+    }
+}
+
+/// Response to a [`FetchRequest`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchResponse {
+    /// HTTP status code.
+    pub status: u16,
+    /// URL the response was served from, after any redirects.
+    pub final_url: String,
+    /// Response headers with lowercase names.
+    pub headers: Vec<(String, String)>,
+    /// Response body.
+    pub body: String,
+}
+
+impl FetchResponse {
+    /// Returns the first value of a header, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
 /// Trait for fetching the full HTML content of a URL.
 ///
 /// Implementations may use plain HTTP requests or a headless browser.
@@ -38,6 +217,75 @@ pub enum WaitStrategy {
 pub trait PageFetcher: Send + Sync {
     /// Fetches the HTML content of the given URL.
     async fn fetch(&self, url: &str) -> Result<String>;
+
+    /// Performs a request with an explicit method, headers, and body.
+    ///
+    /// The default implementation serves plain GETs through [`fetch`]
+    /// (ignoring extra headers, and reporting status 200 with the requested
+    /// URL as the final URL) and rejects any other request. Fetchers that speak HTTP directly should override it.
+    ///
+    /// [`fetch`]: PageFetcher::fetch
+    async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        if !request.is_plain_get() {
+            return Err(SearchError::Other(format!(
+                "Only plain GET requests are supported by this fetcher, got a {} request",
+                request.method.as_str()
+            )));
+        }
+        let body = self.fetch(&request.url).await?;
+        Ok(FetchResponse {
+            status: 200,
+            final_url: request.url.clone(),
+            headers: Vec::new(),
+            body,
+        })
+    }
+}
+
+cfg_native! {
+    tokio::task_local! {
+        /// User agent the search running the current task's engine set.
+        pub(crate) static USER_AGENT: std::sync::Arc<str>;
+
+        /// Fetcher the engine running on the current task prefers.
+        pub(crate) static FETCHER_HINT: FetcherHint;
+    }
+
+    /// Runs `search`, an engine's search, with the fetch context fetchers
+    /// read back: the engine's fetcher `hint` and the search's `user_agent`.
+    pub(crate) async fn with_fetch_context<F: std::future::Future>(
+        hint: FetcherHint,
+        user_agent: Option<std::sync::Arc<str>>,
+        search: F,
+    ) -> F::Output {
+        let search = FETCHER_HINT.scope(hint, search);
+        match user_agent {
+            Some(user_agent) => USER_AGENT.scope(user_agent, search).await,
+            None => search.await,
+        }
+    }
+
+    /// Returns the user agent the search running the current task's engine
+    /// set, if any.
+    pub(crate) fn current_user_agent() -> Option<String> {
+        USER_AGENT
+            .try_with(|user_agent| user_agent.to_string())
+            .ok()
+    }
+
+    /// Returns the fetcher hint of the engine running on the current task,
+    /// or [`FetcherHint::Auto`] outside a search.
+    pub(crate) fn current_fetcher_hint() -> FetcherHint {
+        FETCHER_HINT.try_with(|hint| *hint).unwrap_or_default()
+    }
+
+    /// Marks the engine span the current fetch runs in as having sent a
+    /// request through a proxy. Does nothing without the `otel` feature or
+    /// outside a search.
+    pub(crate) fn record_proxy_use() {
+        #[cfg(feature = "otel")]
+        tracing::Span::current().record("proxy_used", true);
+    }
 }
 
 #[cfg(test)]
@@ -146,4 +394,89 @@ mod tests {
             panic!("Expected Delay");
         }
     }
+
+    struct StaticFetcher;
+
+    #[async_trait]
+    impl PageFetcher for StaticFetcher {
+        async fn fetch(&self, url: &str) -> Result<String> {
+            Ok(format!("page for {}", url))
+        }
+    }
+
+    #[test]
+    fn test_fetch_request_get() {
+        let request = FetchRequest::get("https://example.com").with_header("Accept", "text/html");
+        assert_eq!(request.method, FetchMethod::Get);
+        assert_eq!(request.url, "https://example.com");
+        assert_eq!(request.headers.len(), 1);
+        assert!(request.is_plain_get());
+        assert!(!request.no_redirects);
+        // Fetchers that always follow redirects can still serve it
+        assert!(request.without_redirects().is_plain_get());
+    }
+
+    #[test]
+    fn test_fetch_request_user_agent() {
+        let request = FetchRequest::get("https://example.com");
+        assert!(request.user_agent().is_none());
+
+        let request = request
+            .with_header("user-agent", "First/1.0")
+            .with_header("Accept", "text/html")
+            .with_user_agent("Second/2.0");
+        assert_eq!(request.user_agent(), Some("Second/2.0"));
+        assert_eq!(request.headers.len(), 2);
+    }
+
+    #[test]
+    fn test_fetch_request_post_form() {
+        let request = FetchRequest::post_form("https://example.com", [("q", "rust")]);
+        assert_eq!(request.method, FetchMethod::Post);
+        assert_eq!(
+            request.body,
+            Some(FetchBody::Form(vec![("q".to_string(), "rust".to_string())]))
+        );
+        assert!(!request.is_plain_get());
+    }
+
+    #[test]
+    fn test_fetch_method_as_str() {
+        assert_eq!(FetchMethod::default().as_str(), "GET");
+        assert_eq!(FetchMethod::Post.as_str(), "POST");
+        assert_eq!(FetchMethod::Delete.as_str(), "DELETE");
+    }
+
+    #[test]
+    fn test_fetch_response_header_case_insensitive() {
+        let response = FetchResponse {
+            headers: vec![("content-type".to_string(), "text/html".to_string())],
+            ..Default::default()
+        };
+        assert_eq!(response.header("Content-Type"), Some("text/html"));
+        assert_eq!(response.header("Location"), None);
+    }
+
+    #[tokio::test]
+    async fn test_default_fetch_request_delegates_get() {
+        let response = StaticFetcher
+            .fetch_request(&FetchRequest::get("https://example.com"))
+            .await
+            .unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.final_url, "https://example.com");
+        assert_eq!(response.body, "page for https://example.com");
+    }
+
+    #[tokio::test]
+    async fn test_default_fetch_request_rejects_post() {
+        let result = StaticFetcher
+            .fetch_request(&FetchRequest::post_form(
+                "https://example.com",
+                [("q", "x")],
+            ))
+            .await;
+        let err = result.unwrap_err().to_string();
+        assert!(err.contains("POST"), "{}", err);
+    }
 }
@@ -0,0 +1,381 @@
+//! Caching page fetcher decorator.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::cache::{CacheBackend, MemoryCache};
+use crate::fetcher::{FetchRequest, FetchResponse, PageFetcher};
+use crate::Result;
+
+/// A page fetcher that caches another fetcher's responses by URL.
+///
+/// Entries expire after `ttl` and the least recently used entry is evicted
+/// once `max_entries` is reached. Only successful fetches are cached. Since
+/// engines build URLs that contain the query, wrapping an engine's fetcher
+/// caches its result pages per query.
+///
+/// Plain GETs made with [`fetch_request`](PageFetcher::fetch_request) are
+/// cached too, keyed by URL and request headers, as engines sending an
+/// `Accept-Language` or cookie use them; responses with a status of 400 or
+/// above are not. Requests with another method or a body always reach the
+/// inner fetcher.
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use a3s_search::{CachingFetcher, HttpFetcher};
+/// use a3s_search::engines::DuckDuckGo;
+///
+/// let fetcher = CachingFetcher::new(Arc::new(HttpFetcher::new()), Duration::from_secs(300), 100);
+/// let engine = DuckDuckGo::with_fetcher(Arc::new(fetcher));
+/// ```
+pub struct CachingFetcher {
+    inner: Arc<dyn PageFetcher>,
+    cache: MemoryCache,
+}
+
+impl CachingFetcher {
+    /// Creates a caching fetcher around `inner`.
+    ///
+    /// A `max_entries` of zero is treated as one.
+    pub fn new(inner: Arc<dyn PageFetcher>, ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            inner,
+            cache: MemoryCache::new(ttl, max_entries),
+        }
+    }
+
+    /// Removes the cached responses for `url`, whatever headers they were
+    /// requested with.
+    pub async fn invalidate(&self, url: &str) {
+        self.cache.remove(url).await;
+        let prefix = format!("{}\n", url);
+        self.cache
+            .remove_where(|key| key.starts_with(&prefix))
+            .await;
+    }
+
+    /// Removes all cached bodies.
+    pub async fn clear(&self) {
+        self.cache.clear().await;
+    }
+
+    /// Returns the number of cached entries, including expired ones not yet
+    /// evicted.
+    pub async fn len(&self) -> usize {
+        self.cache.len().await
+    }
+
+    /// Returns true if nothing is cached.
+    pub async fn is_empty(&self) -> bool {
+        self.cache.is_empty().await
+    }
+}
+
+#[async_trait]
+impl PageFetcher for CachingFetcher {
+    async fn fetch(&self, url: &str) -> Result<String> {
+        if let Some(body) = self.cache.get(url).await {
+            return Ok(body);
+        }
+
+        let body = self.inner.fetch(url).await?;
+        self.cache.insert(url, body.clone()).await;
+        Ok(body)
+    }
+
+    /// Caches plain GETs by URL and headers; other requests are passed
+    /// through, since their response depends on the body as well.
+    async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        if !request.is_plain_get() {
+            return self.inner.fetch_request(request).await;
+        }
+
+        let key = request_key(request);
+        let cached = self.cache.get(&key).await;
+        if let Some(response) = cached.and_then(|value| serde_json::from_str(&value).ok()) {
+            return Ok(CachedResponse::into_response(response));
+        }
+
+        let response = self.inner.fetch_request(request).await?;
+        if response.status < 400 {
+            if let Ok(value) = serde_json::to_string(&CachedResponse::from(&response)) {
+                self.cache.insert(&key, value).await;
+            }
+        }
+        Ok(response)
+    }
+}
+
+/// A [`FetchResponse`] as stored in the cache.
+#[derive(Serialize, Deserialize)]
+struct CachedResponse {
+    status: u16,
+    final_url: String,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+
+impl CachedResponse {
+    fn into_response(self) -> FetchResponse {
+        FetchResponse {
+            status: self.status,
+            final_url: self.final_url,
+            headers: self.headers,
+            body: self.body,
+        }
+    }
+}
+
+impl From<&FetchResponse> for CachedResponse {
+    fn from(response: &FetchResponse) -> Self {
+        Self {
+            status: response.status,
+            final_url: response.final_url.clone(),
+            headers: response.headers.clone(),
+            body: response.body.clone(),
+        }
+    }
+}
+
+/// Returns the cache key of a plain GET: its URL on the first line, then
+/// whether redirects are followed and every header, as both change the
+/// response. Header names are lowercased and sorted, so the same headers in
+/// another order share an entry.
+fn request_key(request: &FetchRequest) -> String {
+    let mut headers: Vec<String> = request
+        .headers
+        .iter()
+        .map(|(name, value)| format!("{}: {}", name.to_ascii_lowercase(), value))
+        .collect();
+    headers.sort();
+
+    let mut key = format!("{}\n", request.url);
+    if request.no_redirects {
+        key.push_str("no-redirects\n");
+    }
+    for header in headers {
+        key.push_str(&header);
+        key.push('\n');
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::SearchError;
+
+    /// Returns `"<url>#<n>"` where `n` counts calls, failing for URLs
+    /// containing "fail".
+    struct CountingFetcher {
+        calls: AtomicUsize,
+    }
+
+    impl CountingFetcher {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl PageFetcher for CountingFetcher {
+        async fn fetch(&self, url: &str) -> Result<String> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            if url.contains("fail") {
+                return Err(SearchError::Other("fetch failed".to_string()));
+            }
+            Ok(format!("{}#{}", url, n))
+        }
+
+        async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+            let n = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            let status = if request.url.contains("missing") {
+                404
+            } else {
+                200
+            };
+            Ok(FetchResponse {
+                status,
+                final_url: request.url.clone(),
+                headers: vec![("content-type".to_string(), "text/html".to_string())],
+                body: format!("{}#{}", request.url, n),
+            })
+        }
+    }
+
+    fn caching(inner: &Arc<CountingFetcher>, ttl: Duration, max: usize) -> CachingFetcher {
+        CachingFetcher::new(inner.clone(), ttl, max)
+    }
+
+    #[tokio::test]
+    async fn test_caching_fetcher_hit_skips_inner() {
+        let inner = CountingFetcher::new();
+        let fetcher = caching(&inner, Duration::from_secs(60), 10);
+
+        let first = fetcher.fetch("https://a.example/").await.unwrap();
+        let second = fetcher.fetch("https://a.example/").await.unwrap();
+
+        assert_eq!(first, "https://a.example/#1");
+        assert_eq!(second, first);
+        assert_eq!(inner.calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_caching_fetcher_miss_per_url() {
+        let inner = CountingFetcher::new();
+        let fetcher = caching(&inner, Duration::from_secs(60), 10);
+
+        fetcher.fetch("https://a.example/?q=rust").await.unwrap();
+        fetcher.fetch("https://a.example/?q=go").await.unwrap();
+
+        assert_eq!(inner.calls(), 2);
+        assert_eq!(fetcher.len().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_caching_fetcher_expiry() {
+        let inner = CountingFetcher::new();
+        let fetcher = caching(&inner, Duration::from_millis(50), 10);
+
+        fetcher.fetch("https://a.example/").await.unwrap();
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        let refetched = fetcher.fetch("https://a.example/").await.unwrap();
+
+        assert_eq!(refetched, "https://a.example/#2");
+        assert_eq!(inner.calls(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_caching_fetcher_lru_eviction() {
+        let inner = CountingFetcher::new();
+        let fetcher = caching(&inner, Duration::from_secs(60), 2);
+
+        fetcher.fetch("https://a.example/").await.unwrap();
+        fetcher.fetch("https://b.example/").await.unwrap();
+        // Touch `a` so `b` becomes the least recently used entry
+        fetcher.fetch("https://a.example/").await.unwrap();
+        fetcher.fetch("https://c.example/").await.unwrap();
+        assert_eq!(inner.calls(), 3);
+        assert_eq!(fetcher.len().await, 2);
+
+        fetcher.fetch("https://a.example/").await.unwrap();
+        assert_eq!(inner.calls(), 3);
+        fetcher.fetch("https://b.example/").await.unwrap();
+        assert_eq!(inner.calls(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_caching_fetcher_errors_not_cached() {
+        let inner = CountingFetcher::new();
+        let fetcher = caching(&inner, Duration::from_secs(60), 10);
+
+        assert!(fetcher.fetch("https://fail.example/").await.is_err());
+        assert!(fetcher.fetch("https://fail.example/").await.is_err());
+
+        assert_eq!(inner.calls(), 2);
+        assert!(fetcher.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_caching_fetcher_invalidate() {
+        let inner = CountingFetcher::new();
+        let fetcher = caching(&inner, Duration::from_secs(60), 10);
+
+        fetcher.fetch("https://a.example/").await.unwrap();
+        fetcher.fetch("https://b.example/").await.unwrap();
+        fetcher.invalidate("https://a.example/").await;
+
+        fetcher.fetch("https://a.example/").await.unwrap();
+        fetcher.fetch("https://b.example/").await.unwrap();
+        assert_eq!(inner.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_caching_fetcher_clear() {
+        let inner = CountingFetcher::new();
+        let fetcher = caching(&inner, Duration::from_secs(60), 10);
+
+        fetcher.fetch("https://a.example/").await.unwrap();
+        fetcher.fetch("https://b.example/").await.unwrap();
+        fetcher.clear().await;
+        assert!(fetcher.is_empty().await);
+
+        fetcher.fetch("https://a.example/").await.unwrap();
+        assert_eq!(inner.calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_caching_fetcher_caches_plain_get_requests() {
+        let inner = CountingFetcher::new();
+        let fetcher = caching(&inner, Duration::from_secs(60), 10);
+        let request = FetchRequest::get("https://a.example/")
+            .with_header("Accept-Language", "de")
+            .with_header("Cookie", "safesearch=strict");
+
+        let first = fetcher.fetch_request(&request).await.unwrap();
+        let reordered = FetchRequest::get("https://a.example/")
+            .with_header("cookie", "safesearch=strict")
+            .with_header("accept-language", "de");
+        let second = fetcher.fetch_request(&reordered).await.unwrap();
+        assert_eq!(second, first);
+        assert_eq!(inner.calls(), 1);
+
+        // Other headers, or no headers, get their own entries
+        let english = FetchRequest::get("https://a.example/").with_header("Accept-Language", "en");
+        assert_eq!(
+            fetcher.fetch_request(&english).await.unwrap().body,
+            "https://a.example/#2"
+        );
+        fetcher
+            .fetch_request(&FetchRequest::get("https://a.example/"))
+            .await
+            .unwrap();
+        fetcher
+            .fetch_request(&FetchRequest::get("https://a.example/").without_redirects())
+            .await
+            .unwrap();
+        assert_eq!(inner.calls(), 4);
+
+        fetcher.invalidate("https://a.example/").await;
+        assert!(fetcher.is_empty().await);
+        fetcher.fetch_request(&request).await.unwrap();
+        assert_eq!(inner.calls(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_caching_fetcher_passes_other_requests_through() {
+        let inner = CountingFetcher::new();
+        let fetcher = caching(&inner, Duration::from_secs(60), 10);
+        let post = FetchRequest::post_form("https://a.example/", [("q", "rust")]);
+        let missing = FetchRequest::get("https://a.example/missing");
+
+        for _ in 0..2 {
+            fetcher.fetch_request(&post).await.unwrap();
+            assert_eq!(fetcher.fetch_request(&missing).await.unwrap().status, 404);
+        }
+        assert_eq!(inner.calls(), 4);
+        assert!(fetcher.is_empty().await);
+    }
+
+    #[tokio::test]
+    async fn test_caching_fetcher_zero_capacity() {
+        let inner = CountingFetcher::new();
+        let fetcher = caching(&inner, Duration::from_secs(60), 0);
+
+        fetcher.fetch("https://a.example/").await.unwrap();
+        fetcher.fetch("https://a.example/").await.unwrap();
+        assert_eq!(inner.calls(), 1);
+    }
+}
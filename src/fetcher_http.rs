@@ -1,52 +1,480 @@
 //! HTTP-based page fetcher using reqwest.
 
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
 use async_trait::async_trait;
-use reqwest::Client;
+use encoding_rs::{Encoding, UTF_8};
+use futures::StreamExt;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use regex::bytes::Regex;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use reqwest::{Client, RequestBuilder};
+use tokio::sync::{watch, OwnedSemaphorePermit, Semaphore, SemaphorePermit};
 
-use crate::fetcher::PageFetcher;
-use crate::Result;
+use crate::dns::DnsCache;
+use crate::fetcher::{
+    current_user_agent, record_proxy_use, FetchBody, FetchMethod, FetchRequest, FetchResponse,
+    PageFetcher,
+};
+use crate::{Result, SearchError};
 
 /// Default user agent for HTTP requests.
 const DEFAULT_USER_AGENT: &str =
     "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 \
      (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
 
+/// Realistic desktop browser user agents used for rotation when no custom
+/// list is given.
+pub const DEFAULT_USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+     (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 \
+     (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:133.0) Gecko/20100101 Firefox/133.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 14.7; rv:133.0) Gecko/20100101 Firefox/133.0",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 \
+     (KHTML, like Gecko) Version/18.1 Safari/605.1.15",
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+     (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36 Edg/131.0.0.0",
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 \
+     (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36",
+];
+
+/// Default total request timeout for HTTP requests.
+pub(crate) const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default number of idle keep-alive connections kept per host.
+const DEFAULT_MAX_IDLE_PER_HOST: usize = 8;
+
+/// Default number of requests in flight to a single host.
+const DEFAULT_MAX_CONNECTIONS_PER_HOST: usize = 8;
+
+/// Default TCP keepalive probe interval for pooled connections.
+const DEFAULT_TCP_KEEPALIVE: Duration = Duration::from_secs(60);
+
+/// Default maximum response body size (5 MB).
+pub(crate) const DEFAULT_MAX_BODY_SIZE: usize = 5 * 1024 * 1024;
+
+/// Number of leading body bytes searched for a `<meta charset>` declaration.
+const CHARSET_SNIFF_BYTES: usize = 1024;
+
+/// Maximum number of body bytes included in HTTP status errors.
+const ERROR_BODY_PREVIEW_BYTES: usize = 200;
+
+/// `Accept` header Chromium browsers send when navigating to a page.
+const CHROMIUM_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,\
+     image/avif,image/webp,image/apng,*/*;q=0.8,application/signed-exchange;v=b3;q=0.7";
+
+/// `Accept` header Firefox and Safari send when navigating to a page.
+const GECKO_ACCEPT: &str = "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8";
+
+/// User agent selection strategy for rotation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UserAgentStrategy {
+    /// Cycle through the agents in order
+    #[default]
+    RoundRobin,
+    /// Pick an agent at random for each request
+    Random,
+}
+
+/// How an [`HttpFetcher`] handles 3xx redirects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RedirectPolicy {
+    /// Follow up to 10 redirects
+    #[default]
+    Limited,
+    /// Follow up to the given number of redirects
+    Max(usize),
+    /// Never follow redirects; `fetch` fails with [`SearchError::Redirected`]
+    None,
+}
+
+impl RedirectPolicy {
+    /// Maximum number of redirects followed under [`RedirectPolicy::Limited`].
+    pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+    fn to_reqwest(self) -> reqwest::redirect::Policy {
+        match self {
+            RedirectPolicy::Limited => {
+                reqwest::redirect::Policy::limited(Self::DEFAULT_MAX_REDIRECTS)
+            }
+            RedirectPolicy::Max(max) => reqwest::redirect::Policy::limited(max),
+            RedirectPolicy::None => reqwest::redirect::Policy::none(),
+        }
+    }
+}
+
+/// A list of user agents rotated across requests.
+struct UserAgentRotation {
+    agents: Vec<String>,
+    strategy: UserAgentStrategy,
+    current_index: AtomicUsize,
+    rng: Mutex<StdRng>,
+}
+
+impl UserAgentRotation {
+    fn new(agents: Vec<String>, strategy: UserAgentStrategy) -> Self {
+        Self {
+            agents,
+            strategy,
+            current_index: AtomicUsize::new(0),
+            rng: Mutex::new(StdRng::from_os_rng()),
+        }
+    }
+
+    fn next(&self) -> &str {
+        let index = match self.strategy {
+            UserAgentStrategy::RoundRobin => {
+                self.current_index.fetch_add(1, Ordering::Relaxed) % self.agents.len()
+            }
+            UserAgentStrategy::Random => self
+                .rng
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .random_range(0..self.agents.len()),
+        };
+        &self.agents[index]
+    }
+}
+
+/// Caps the requests in flight to each host, with one semaphore per host.
+struct HostLimiter {
+    max: usize,
+    hosts: Mutex<HashMap<String, Arc<Semaphore>>>,
+}
+
+impl HostLimiter {
+    fn new(max: usize) -> Self {
+        Self {
+            max,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the semaphore of the host `url` points to, if it has one.
+    fn semaphore(&self, url: &str) -> Option<Arc<Semaphore>> {
+        let url = reqwest::Url::parse(url).ok()?;
+        let key = format!("{}:{}", url.host_str()?, url.port_or_known_default()?);
+        let mut hosts = self.hosts.lock().unwrap_or_else(|e| e.into_inner());
+        if !hosts.contains_key(&key) {
+            // Forget hosts without requests in flight or waiting
+            hosts.retain(|_, semaphore| Arc::strong_count(semaphore) > 1);
+        }
+        let semaphore = hosts
+            .entry(key)
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max)));
+        Some(Arc::clone(semaphore))
+    }
+}
+
+/// Client for requests asking not to follow redirects, built on first use
+/// from the settings of the fetcher's own client.
+struct NoRedirectClient {
+    config: HttpFetcherBuilder,
+    headers: HeaderMap,
+    client: OnceLock<Option<Client>>,
+}
+
+impl NoRedirectClient {
+    fn get(&self) -> Option<&Client> {
+        self.client
+            .get_or_init(|| {
+                self.config
+                    .client(&self.headers, RedirectPolicy::None)
+                    .map_err(|e| tracing::warn!("No-redirect client unavailable: {}", e))
+                    .ok()
+            })
+            .as_ref()
+    }
+}
+
+/// Permits held by a request while it is in flight.
+struct Permits<'a> {
+    _host: Option<OwnedSemaphorePermit>,
+    _global: Option<SemaphorePermit<'a>>,
+}
+
+/// Receives the outcome of an in-flight `fetch`, once it completes.
+type FlightReceiver = watch::Receiver<Option<Result<String>>>;
+
+/// The user agent sent in place of the client's default, if any, and the
+/// URL of a `fetch`. Only fetches sending the same user agent share a
+/// response, since sites may answer each agent differently.
+type FlightKey = (Option<String>, String);
+
+/// In-flight `fetch` calls keyed by user agent and URL.
+type InFlight = Arc<Mutex<HashMap<FlightKey, FlightReceiver>>>;
+
+/// Removes a fetch from the in-flight map when it finishes or is cancelled.
+struct FlightGuard<'a> {
+    in_flight: &'a InFlight,
+    key: FlightKey,
+}
+
+impl Drop for FlightGuard<'_> {
+    fn drop(&mut self) {
+        self.in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.key);
+    }
+}
+
 /// A page fetcher that uses plain HTTP requests via reqwest.
 ///
 /// Suitable for engines that return server-rendered HTML. For engines
 /// that require JavaScript rendering, use `BrowserFetcher` instead.
+///
+/// Cloning is cheap: clones share the connection pool, TLS sessions, and
+/// connection limit, so one fetcher can back every HTTP engine.
+///
+/// Concurrent `fetch` calls for the same URL, from this fetcher or any of
+/// its clones, share a single request: the first caller sends it and the
+/// others receive a copy of its body or error.
+#[derive(Clone)]
 pub struct HttpFetcher {
     client: Client,
+    /// Whether the client sends requests through a proxy.
+    proxied: bool,
+    /// Client for [`FetchRequest::no_redirects`] requests, when built by
+    /// [`HttpFetcherBuilder`]; other fetchers follow redirects regardless.
+    no_redirects: Option<Arc<NoRedirectClient>>,
+    /// Bounds the number of requests in flight when a global limit is set.
+    limiter: Option<Arc<Semaphore>>,
+    /// Bounds the number of requests in flight to each host.
+    host_limiter: Option<Arc<HostLimiter>>,
+    /// Whether non-success statuses are returned as bodies instead of errors.
+    lenient: bool,
+    /// Per-request user agents overriding the client-level user agent.
+    user_agents: Option<Arc<UserAgentRotation>>,
+    /// Largest response body `fetch` will read, in bytes.
+    max_body_size: usize,
+    /// DNS cache the client resolves through, when enabled.
+    dns: Option<DnsCache>,
+    /// `fetch` calls currently waiting on the network.
+    in_flight: InFlight,
+    /// User agent the client sends, when built by [`HttpFetcherBuilder`].
+    default_user_agent: Option<String>,
+    /// Default headers of the client, which browser headers don't replace.
+    default_headers: HeaderMap,
+    /// Whether requests carry the header set of the browser they claim to be.
+    browser_headers: bool,
+    /// Middleware client requests are sent through, when set.
+    #[cfg(feature = "middleware")]
+    middleware: Option<reqwest_middleware::ClientWithMiddleware>,
 }
 
 impl HttpFetcher {
     /// Creates a new `HttpFetcher` with default settings.
     pub fn new() -> Self {
-        Self {
-            client: Client::builder()
-                .user_agent(DEFAULT_USER_AGENT)
-                .build()
-                .expect("Failed to create HTTP client"),
-        }
+        Self::builder()
+            .build()
+            .expect("Failed to create HTTP client")
+    }
+
+    /// Returns a builder for configuring an `HttpFetcher`.
+    pub fn builder() -> HttpFetcherBuilder {
+        HttpFetcherBuilder::new()
+    }
+
+    /// Creates an `HttpFetcher` whose requests fail once `timeout` elapses.
+    ///
+    /// The same budget is applied to connection establishment and to the
+    /// whole request, so a server that accepts but never answers cannot
+    /// hold the connection open past the deadline.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self::builder()
+            .timeout(timeout)
+            .build()
+            .expect("Failed to create HTTP client")
     }
 
     /// Creates an `HttpFetcher` with proxy support.
     pub fn with_proxy(proxy_url: &str) -> crate::Result<Self> {
-        let proxy = reqwest::Proxy::all(proxy_url)
-            .map_err(|e| crate::SearchError::Other(format!("Failed to create proxy: {}", e)))?;
-        let client = Client::builder()
-            .user_agent(DEFAULT_USER_AGENT)
-            .proxy(proxy)
-            .build()
-            .map_err(|e| {
-                crate::SearchError::Other(format!("Failed to create HTTP client: {}", e))
-            })?;
-        Ok(Self { client })
+        Self::builder().proxy(proxy_url).build()
     }
 
     /// Creates an `HttpFetcher` with a custom reqwest client.
     pub fn with_client(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            proxied: false,
+            no_redirects: None,
+            limiter: None,
+            host_limiter: None,
+            lenient: false,
+            user_agents: None,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            dns: None,
+            in_flight: InFlight::default(),
+            default_user_agent: None,
+            default_headers: HeaderMap::new(),
+            browser_headers: false,
+            #[cfg(feature = "middleware")]
+            middleware: None,
+        }
+    }
+
+    /// Sets the largest response body `fetch` will read (default: 5 MB).
+    ///
+    /// Responses whose `Content-Length` exceeds the limit are rejected before
+    /// the body is read; streamed bodies are aborted as soon as they pass it.
+    /// Both fail with [`SearchError::ResponseTooLarge`].
+    pub fn with_max_body_size(mut self, bytes: usize) -> Self {
+        self.max_body_size = bytes;
+        self
+    }
+
+    /// Rotates the `User-Agent` header across requests (round-robin by
+    /// default).
+    ///
+    /// Each request carries one agent from `agents`, overriding the
+    /// client-level user agent. An empty list uses [`DEFAULT_USER_AGENTS`].
+    /// Rotation is independent of proxy settings, so it can be combined with
+    /// [`with_proxy`](Self::with_proxy).
+    pub fn with_user_agent_rotation(mut self, agents: Vec<String>) -> Self {
+        let agents = if agents.is_empty() {
+            DEFAULT_USER_AGENTS
+                .iter()
+                .map(|ua| ua.to_string())
+                .collect()
+        } else {
+            agents
+        };
+        let strategy = self
+            .user_agents
+            .as_ref()
+            .map(|rotation| rotation.strategy)
+            .unwrap_or_default();
+        self.user_agents = Some(Arc::new(UserAgentRotation::new(agents, strategy)));
+        self
+    }
+
+    /// Sets how rotated user agents are picked.
+    ///
+    /// Has no effect unless rotation is enabled with
+    /// [`with_user_agent_rotation`](Self::with_user_agent_rotation).
+    pub fn with_user_agent_strategy(mut self, strategy: UserAgentStrategy) -> Self {
+        if let Some(rotation) = self.user_agents.take() {
+            self.user_agents = Some(Arc::new(UserAgentRotation::new(
+                rotation.agents.clone(),
+                strategy,
+            )));
+        }
+        self
+    }
+
+    /// Sends the headers a real browser sends with the user agent of each
+    /// request: `Accept`, `Accept-Language`, the `Sec-Fetch-*` navigation
+    /// headers and, for Chrome and Edge, the `Sec-CH-UA` client hints.
+    ///
+    /// Sites that fingerprint headers flag a bare request with a browser
+    /// user agent as automated. The set is derived per request, so it stays
+    /// consistent with rotated user agents. Headers set on a
+    /// [`FetchRequest`] or as builder defaults take precedence.
+    pub fn with_browser_headers(mut self) -> Self {
+        self.browser_headers = true;
+        self
+    }
+
+    /// Sends requests through a [`reqwest_middleware`] client, for request
+    /// signing, logging, or custom retries (requires the `middleware`
+    /// feature).
+    ///
+    /// Requests are still built by this fetcher, with its user agent
+    /// rotation and per-request headers, and responses still go through its
+    /// status and body size checks. Connection settings such as timeouts,
+    /// proxy, default headers, and redirects come from the client wrapped
+    /// by the middleware.
+    #[cfg(feature = "middleware")]
+    pub fn with_middleware_client(
+        mut self,
+        client: reqwest_middleware::ClientWithMiddleware,
+    ) -> Self {
+        self.middleware = Some(client);
+        self
+    }
+
+    /// Sends a request through the middleware client if one is set, or
+    /// this fetcher's client otherwise.
+    async fn send(&self, request: RequestBuilder) -> Result<reqwest::Response> {
+        #[cfg(feature = "middleware")]
+        if let Some(ref middleware) = self.middleware {
+            return middleware
+                .execute(request.build()?)
+                .await
+                .map_err(|e| match e {
+                    reqwest_middleware::Error::Reqwest(e) => SearchError::Http(e),
+                    reqwest_middleware::Error::Middleware(e) => {
+                        SearchError::Other(format!("Middleware error: {}", e))
+                    }
+                });
+        }
+        Ok(request.send().await?)
+    }
+
+    /// Starts a GET request, applying the user agent of the running
+    /// [`Search`](crate::Search) if it set one, or else the next rotated
+    /// user agent if rotation is enabled.
+    pub fn request(&self, url: &str) -> RequestBuilder {
+        self.get_as(url, self.user_agent())
+    }
+
+    /// Starts a GET request sending `user_agent` in place of the client's
+    /// default.
+    fn get_as(&self, url: &str, user_agent: Option<String>) -> RequestBuilder {
+        self.identify(self.client.get(url), user_agent, None, |_| false)
+    }
+
+    /// Adds `user_agent` (the one to send in place of the client's default)
+    /// unless `request_user_agent` is set, and the matching browser headers
+    /// if enabled, except those for which `is_set` returns true.
+    fn identify(
+        &self,
+        mut builder: RequestBuilder,
+        user_agent: Option<String>,
+        request_user_agent: Option<&str>,
+        is_set: impl Fn(&str) -> bool,
+    ) -> RequestBuilder {
+        let user_agent = match request_user_agent {
+            Some(_) => None,
+            None => user_agent,
+        };
+        if !self.browser_headers {
+            return match user_agent {
+                Some(user_agent) => builder.header(USER_AGENT, user_agent),
+                None => builder,
+            };
+        }
+
+        // Sent explicitly so the headers match it even with a custom client
+        let user_agent = user_agent
+            .or_else(|| self.default_user_agent.clone())
+            .unwrap_or_else(|| DEFAULT_USER_AGENT.to_string());
+        let effective = request_user_agent.unwrap_or(&user_agent);
+        for (name, value) in browser_headers(effective) {
+            if !is_set(name) && !self.default_headers.contains_key(name) {
+                builder = builder.header(name, value);
+            }
+        }
+        match request_user_agent {
+            Some(_) => builder,
+            None => builder.header(USER_AGENT, user_agent),
+        }
+    }
+
+    /// Returns the user agent to send in place of the client's default.
+    fn user_agent(&self) -> Option<String> {
+        current_user_agent().or_else(|| {
+            self.user_agents
+                .as_ref()
+                .map(|rotation| rotation.next().to_string())
+        })
     }
 
     /// Returns a reference to the underlying reqwest client.
@@ -56,6 +484,536 @@ impl HttpFetcher {
     pub fn client(&self) -> &Client {
         &self.client
     }
+
+    /// Resolves `hosts` into the DNS cache ahead of time, so the first
+    /// request to each does not wait for a lookup.
+    ///
+    /// Returns the number of hosts that resolved. Does nothing and returns
+    /// zero unless the fetcher was built with
+    /// [`dns_cache`](HttpFetcherBuilder::dns_cache).
+    pub async fn preresolve<I, S>(&self, hosts: I) -> usize
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        match self.dns {
+            Some(ref dns) => dns.preresolve(hosts).await,
+            None => 0,
+        }
+    }
+
+    /// Requests `url` and returns the absolute target of its redirect, if any.
+    ///
+    /// Only meaningful for fetchers built with [`RedirectPolicy::None`]:
+    /// engines can use it to resolve redirector links (e.g. Sogou's
+    /// `/link?url=...`) to their destination without fetching it. Returns
+    /// `None` when the response is not a redirect.
+    pub async fn redirect_location(&self, url: &str) -> Result<Option<String>> {
+        let _permits = self.acquire_permits(url).await?;
+        let response = self.send(self.request(url)).await?;
+        if !response.status().is_redirection() {
+            return Ok(None);
+        }
+        Ok(location_of(&response))
+    }
+
+    /// Reads a response body as text, enforcing the body size limit.
+    ///
+    /// The body is decoded using the `Content-Type` charset, or the page's
+    /// `<meta charset>` declaration when the header names none, falling back
+    /// to UTF-8. Invalid sequences are replaced rather than rejected.
+    pub async fn read_body(&self, response: reqwest::Response) -> Result<String> {
+        if let Some(length) = response.content_length() {
+            if length > self.max_body_size as u64 {
+                return Err(SearchError::ResponseTooLarge(self.max_body_size));
+            }
+        }
+
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+
+        let mut body = Vec::new();
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            if body.len() + chunk.len() > self.max_body_size {
+                return Err(SearchError::ResponseTooLarge(self.max_body_size));
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        Ok(decode_body(&body, content_type.as_deref()))
+    }
+
+    /// Converts a non-success response into a typed error.
+    ///
+    /// - 3xx (only seen when redirects are not followed) becomes
+    ///   [`SearchError::Redirected`] with the absolute `Location`
+    /// - 429 becomes [`SearchError::RateLimited`] with the `Retry-After` value
+    /// - 403 becomes [`SearchError::Blocked`]
+    /// - any other 4xx/5xx becomes [`SearchError::HttpStatus`]
+    ///
+    /// Successful responses, and all responses from a lenient fetcher, are
+    /// passed through unchanged.
+    pub async fn check_status(&self, response: reqwest::Response) -> Result<reqwest::Response> {
+        let status = response.status();
+        if self.lenient || status.is_success() || status.is_informational() {
+            return Ok(response);
+        }
+
+        if status.is_redirection() {
+            return match location_of(&response) {
+                Some(location) => Err(SearchError::Redirected(location)),
+                None => Err(SearchError::HttpStatus(
+                    status.as_u16(),
+                    "Redirect without Location header".to_string(),
+                )),
+            };
+        }
+
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_string());
+            return Err(SearchError::RateLimited(retry_after));
+        }
+
+        let body = self.read_body(response).await.unwrap_or_default();
+        let preview = body_preview(&body);
+        if status == reqwest::StatusCode::FORBIDDEN {
+            Err(SearchError::Blocked(preview))
+        } else {
+            Err(SearchError::HttpStatus(status.as_u16(), preview))
+        }
+    }
+}
+
+/// Returns the headers, other than `User-Agent`, that the browser named by
+/// `user_agent` sends when navigating to a page.
+///
+/// Chromium browsers get `Sec-CH-UA` client hints naming their brand,
+/// major version and platform; Firefox and Safari send none.
+fn browser_headers(user_agent: &str) -> Vec<(&'static str, String)> {
+    let major_version = |marker: &str| {
+        let version = user_agent.split(marker).nth(1)?.split(['.', ' ']).next()?;
+        (!version.is_empty() && version.bytes().all(|b| b.is_ascii_digit())).then_some(version)
+    };
+    let firefox = user_agent.contains("Firefox/");
+    let chromium = major_version("Chrome/").filter(|_| !firefox);
+
+    let mut headers = vec![
+        (
+            "Accept",
+            if chromium.is_some() {
+                CHROMIUM_ACCEPT
+            } else {
+                GECKO_ACCEPT
+            }
+            .to_string(),
+        ),
+        (
+            "Accept-Language",
+            if firefox {
+                "en-US,en;q=0.5"
+            } else {
+                "en-US,en;q=0.9"
+            }
+            .to_string(),
+        ),
+    ];
+    if let Some(version) = chromium {
+        let brand = match major_version("Edg/") {
+            Some(edge) => format!("\"Microsoft Edge\";v=\"{}\"", edge),
+            None => format!("\"Google Chrome\";v=\"{}\"", version),
+        };
+        let platform = if user_agent.contains("Android") {
+            "Android"
+        } else if user_agent.contains("Windows") {
+            "Windows"
+        } else if user_agent.contains("Macintosh") {
+            "macOS"
+        } else if user_agent.contains("Linux") || user_agent.contains("X11") {
+            "Linux"
+        } else {
+            "Unknown"
+        };
+        let mobile = if user_agent.contains("Mobile") {
+            "?1"
+        } else {
+            "?0"
+        };
+        headers.extend([
+            (
+                "Sec-CH-UA",
+                format!(
+                    "{}, \"Chromium\";v=\"{}\", \"Not_A Brand\";v=\"24\"",
+                    brand, version
+                ),
+            ),
+            ("Sec-CH-UA-Mobile", mobile.to_string()),
+            ("Sec-CH-UA-Platform", format!("\"{}\"", platform)),
+        ]);
+    }
+    headers.extend(
+        [
+            ("Sec-Fetch-Dest", "document"),
+            ("Sec-Fetch-Mode", "navigate"),
+            ("Sec-Fetch-Site", "none"),
+            ("Sec-Fetch-User", "?1"),
+            ("Upgrade-Insecure-Requests", "1"),
+        ]
+        .map(|(name, value)| (name, value.to_string())),
+    );
+    headers
+}
+
+/// Decodes a response body to UTF-8.
+///
+/// The charset comes from the `Content-Type` header, then from a
+/// `<meta charset>` or `<meta http-equiv="Content-Type">` tag near the start
+/// of the body, and defaults to UTF-8. A byte order mark overrides all of
+/// these.
+fn decode_body(body: &[u8], content_type: Option<&str>) -> String {
+    let encoding = content_type
+        .and_then(charset_from_content_type)
+        .and_then(|label| Encoding::for_label(label.as_bytes()))
+        .or_else(|| sniff_meta_charset(body))
+        .unwrap_or(UTF_8);
+    let (text, _, _) = encoding.decode(body);
+    text.into_owned()
+}
+
+/// Extracts the `charset` parameter of a `Content-Type` value.
+fn charset_from_content_type(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim().eq_ignore_ascii_case("charset").then(|| {
+            value
+                .trim()
+                .trim_matches(|c| c == '"' || c == '\'')
+                .to_string()
+        })
+    })
+}
+
+/// Finds the charset declared by a `<meta>` tag near the start of a page.
+fn sniff_meta_charset(body: &[u8]) -> Option<&'static Encoding> {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    let pattern = PATTERN.get_or_init(|| {
+        Regex::new(r#"(?i)<meta[^>]+charset\s*=\s*["']?([a-z0-9_\-:.]+)"#)
+            .expect("valid charset pattern")
+    });
+    let head = &body[..body.len().min(CHARSET_SNIFF_BYTES)];
+    let label = pattern.captures(head)?.get(1)?.as_bytes();
+    Encoding::for_label(label)
+}
+
+/// Returns the `Location` header of a response resolved against its URL.
+fn location_of(response: &reqwest::Response) -> Option<String> {
+    let location = response
+        .headers()
+        .get(reqwest::header::LOCATION)?
+        .to_str()
+        .ok()?;
+    response
+        .url()
+        .join(location)
+        .ok()
+        .map(|url| url.to_string())
+}
+
+/// Returns the first `ERROR_BODY_PREVIEW_BYTES` of a body, trimmed, cut at a
+/// valid UTF-8 char boundary.
+fn body_preview(body: &str) -> String {
+    let body = body.trim();
+    let mut end = body.len().min(ERROR_BODY_PREVIEW_BYTES);
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    body[..end].to_string()
+}
+
+/// Builder for [`HttpFetcher`].
+///
+/// Headers added with [`header`](Self::header) are sent with every request
+/// made through the fetcher. Headers set on an individual request built from
+/// [`HttpFetcher::client`] take precedence over these defaults.
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use a3s_search::HttpFetcher;
+///
+/// let fetcher = HttpFetcher::builder()
+///     .header("Accept-Language", "en-US,en;q=0.9")
+///     .timeout(Duration::from_secs(10))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpFetcherBuilder {
+    user_agent: String,
+    headers: Vec<(String, String)>,
+    timeout: Duration,
+    proxy_url: Option<String>,
+    gzip: bool,
+    brotli: bool,
+    max_idle_per_host: usize,
+    max_connections_per_host: usize,
+    max_connections: Option<usize>,
+    lenient: bool,
+    http2_prior_knowledge: bool,
+    tcp_keepalive: Option<Duration>,
+    http2_keep_alive_interval: Option<Duration>,
+    redirect_policy: RedirectPolicy,
+    dns_cache: Option<DnsCache>,
+}
+
+impl HttpFetcherBuilder {
+    /// Creates a builder with the default user agent and timeout.
+    pub fn new() -> Self {
+        Self {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            headers: Vec::new(),
+            timeout: DEFAULT_TIMEOUT,
+            proxy_url: None,
+            gzip: true,
+            brotli: true,
+            max_idle_per_host: DEFAULT_MAX_IDLE_PER_HOST,
+            max_connections_per_host: DEFAULT_MAX_CONNECTIONS_PER_HOST,
+            max_connections: None,
+            lenient: false,
+            http2_prior_knowledge: false,
+            tcp_keepalive: Some(DEFAULT_TCP_KEEPALIVE),
+            http2_keep_alive_interval: None,
+            redirect_policy: RedirectPolicy::Limited,
+            dns_cache: None,
+        }
+    }
+
+    /// Sets the user agent sent with every request.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Adds a default header sent with every request.
+    ///
+    /// May be called repeatedly. Adding the same header name twice sends
+    /// both values.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the connect and total request timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Routes all requests through the given proxy URL.
+    pub fn proxy(mut self, proxy_url: impl Into<String>) -> Self {
+        self.proxy_url = Some(proxy_url.into());
+        self
+    }
+
+    /// Enables or disables transparent gzip decompression (enabled by default).
+    pub fn gzip(mut self, enabled: bool) -> Self {
+        self.gzip = enabled;
+        self
+    }
+
+    /// Enables or disables transparent brotli decompression (enabled by
+    /// default).
+    pub fn brotli(mut self, enabled: bool) -> Self {
+        self.brotli = enabled;
+        self
+    }
+
+    /// Sets how many idle keep-alive connections are kept open per host
+    /// (default: 8).
+    ///
+    /// Connections beyond this number are closed once their request
+    /// completes instead of being returned to the pool.
+    pub fn max_idle_per_host(mut self, max: usize) -> Self {
+        self.max_idle_per_host = max;
+        self
+    }
+
+    /// Caps the number of requests this fetcher runs concurrently against
+    /// a single host (default: 8).
+    ///
+    /// Hosts are told apart by name and port. Additional requests to a
+    /// busy host wait until one of its requests completes, without holding
+    /// up requests to other hosts. A limit of zero is treated as one.
+    pub fn max_connections_per_host(mut self, max: usize) -> Self {
+        self.max_connections_per_host = max.max(1);
+        self
+    }
+
+    /// Caps the number of requests this fetcher runs concurrently across all
+    /// hosts (default: unlimited).
+    ///
+    /// Additional `fetch` calls wait until an in-flight request completes.
+    /// A limit of zero is treated as one.
+    pub fn max_connections(mut self, max: usize) -> Self {
+        self.max_connections = Some(max.max(1));
+        self
+    }
+
+    /// When enabled, `fetch` returns the body of 4xx/5xx responses instead of
+    /// an error (default: disabled).
+    pub fn lenient(mut self, enabled: bool) -> Self {
+        self.lenient = enabled;
+        self
+    }
+
+    /// Speaks HTTP/2 from the first byte instead of negotiating it
+    /// (default: disabled).
+    ///
+    /// Only enable this for servers known to accept cleartext HTTP/2 or
+    /// HTTP/2 over TLS; HTTP/1-only servers reject every request. Without
+    /// it, HTTP/2 is still used over TLS whenever the server offers it.
+    pub fn http2_prior_knowledge(mut self, enabled: bool) -> Self {
+        self.http2_prior_knowledge = enabled;
+        self
+    }
+
+    /// Sets the TCP keepalive interval for open connections, or `None` to
+    /// disable it (default: 60s).
+    ///
+    /// Keepalive probes stop idle pooled connections from being silently
+    /// dropped by NATs and firewalls between requests.
+    pub fn tcp_keepalive(mut self, interval: Option<Duration>) -> Self {
+        self.tcp_keepalive = interval;
+        self
+    }
+
+    /// Sets the interval for HTTP/2 PING frames on idle connections, or
+    /// `None` to disable them (default: disabled).
+    ///
+    /// Pings keep multiplexed HTTP/2 connections to frequently queried
+    /// engines warm between searches.
+    pub fn http2_keep_alive_interval(mut self, interval: Option<Duration>) -> Self {
+        self.http2_keep_alive_interval = interval;
+        self
+    }
+
+    /// Sets how 3xx redirects are handled (default: follow up to 10).
+    ///
+    /// With [`RedirectPolicy::None`], redirects surface as
+    /// [`SearchError::Redirected`] so block pages and tracking redirectors
+    /// are not followed silently.
+    pub fn redirect_policy(mut self, policy: RedirectPolicy) -> Self {
+        self.redirect_policy = policy;
+        self
+    }
+
+    /// Caches resolved host addresses for `ttl` (default: disabled).
+    ///
+    /// Saves a DNS lookup per connection to the same engine host, which adds
+    /// up on high-latency links. The system resolver does not report record
+    /// TTLs, so every host is kept for `ttl`. Combine with
+    /// [`HttpFetcher::preresolve`] to warm the cache before the first search.
+    pub fn dns_cache(mut self, ttl: Duration) -> Self {
+        self.dns_cache = Some(DnsCache::new(ttl));
+        self
+    }
+
+    /// Builds the `HttpFetcher`.
+    ///
+    /// Fails if a header name or value is invalid, or the proxy URL cannot
+    /// be parsed.
+    pub fn build(self) -> Result<HttpFetcher> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                SearchError::Other(format!("Invalid header name '{}': {}", name, e))
+            })?;
+            let value = HeaderValue::from_str(value).map_err(|e| {
+                SearchError::Other(format!("Invalid header value for '{}': {}", name, e))
+            })?;
+            headers.append(name, value);
+        }
+
+        let client = self.client(&headers, self.redirect_policy)?;
+        let no_redirect_client = match self.redirect_policy {
+            RedirectPolicy::None => OnceLock::from(Some(client.clone())),
+            _ => OnceLock::new(),
+        };
+        Ok(HttpFetcher {
+            client,
+            proxied: self.proxy_url.is_some(),
+            no_redirects: Some(Arc::new(NoRedirectClient {
+                config: self.clone(),
+                headers: headers.clone(),
+                client: no_redirect_client,
+            })),
+            limiter: self
+                .max_connections
+                .map(|max| Arc::new(Semaphore::new(max))),
+            host_limiter: Some(Arc::new(HostLimiter::new(self.max_connections_per_host))),
+            lenient: self.lenient,
+            user_agents: None,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            dns: self.dns_cache,
+            in_flight: InFlight::default(),
+            default_user_agent: Some(self.user_agent),
+            default_headers: headers,
+            browser_headers: false,
+            #[cfg(feature = "middleware")]
+            middleware: None,
+        })
+    }
+
+    /// Builds a reqwest client with these settings and `redirect_policy`.
+    fn client(&self, headers: &HeaderMap, redirect_policy: RedirectPolicy) -> Result<Client> {
+        let mut builder = Client::builder()
+            .user_agent(&self.user_agent)
+            .default_headers(headers.clone())
+            .connect_timeout(self.timeout)
+            .timeout(self.timeout)
+            .gzip(self.gzip)
+            .brotli(self.brotli)
+            .pool_max_idle_per_host(self.max_idle_per_host)
+            .tcp_keepalive(self.tcp_keepalive)
+            .redirect(redirect_policy.to_reqwest());
+
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+
+        if let Some(interval) = self.http2_keep_alive_interval {
+            builder = builder
+                .http2_keep_alive_interval(interval)
+                .http2_keep_alive_while_idle(true);
+        }
+
+        if let Some(ref dns) = self.dns_cache {
+            builder = builder.dns_resolver(Arc::new(dns.clone()));
+        }
+
+        if let Some(ref proxy_url) = self.proxy_url {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|e| SearchError::Other(format!("Failed to create proxy: {}", e)))?;
+            builder = builder.proxy(proxy);
+        }
+
+        builder
+            .build()
+            .map_err(|e| SearchError::Other(format!("Failed to create HTTP client: {}", e)))
+    }
+}
+
+impl Default for HttpFetcherBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Default for HttpFetcher {
@@ -64,18 +1022,172 @@ impl Default for HttpFetcher {
     }
 }
 
+impl HttpFetcher {
+    /// Acquires the permits a request to `url` needs under the per-host
+    /// and global limits.
+    ///
+    /// The host permit is taken first, so requests queued for a busy host
+    /// do not hold global permits other hosts could use.
+    async fn acquire_permits(&self, url: &str) -> Result<Permits<'_>> {
+        let closed = |e| SearchError::Other(format!("Connection limiter closed: {}", e));
+        let host = match self.host_limiter.as_ref().and_then(|l| l.semaphore(url)) {
+            Some(semaphore) => Some(semaphore.acquire_owned().await.map_err(closed)?),
+            None => None,
+        };
+        let global = match self.limiter {
+            Some(ref limiter) => Some(limiter.acquire().await.map_err(closed)?),
+            None => None,
+        };
+        Ok(Permits {
+            _host: host,
+            _global: global,
+        })
+    }
+
+    /// Sends a GET request for `url` as `user_agent` without coalescing.
+    async fn fetch_uncoalesced(&self, url: &str, user_agent: Option<String>) -> Result<String> {
+        let _permits = self.acquire_permits(url).await?;
+
+        let response = self.send(self.get_as(url, user_agent)).await?;
+        let response = self.check_status(response).await?;
+        self.read_body(response).await
+    }
+}
+
 #[async_trait]
 impl PageFetcher for HttpFetcher {
     async fn fetch(&self, url: &str) -> Result<String> {
-        let response = self.client.get(url).send().await?;
-        let html = response.text().await?;
-        Ok(html)
+        if self.proxied {
+            record_proxy_use();
+        }
+        let user_agent = self.user_agent();
+        let key = (user_agent.clone(), url.to_string());
+        let joined = {
+            let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+            match in_flight.get(&key) {
+                Some(receiver) => Err(receiver.clone()),
+                None => {
+                    let (sender, receiver) = watch::channel(None);
+                    in_flight.insert(key.clone(), receiver);
+                    Ok(sender)
+                }
+            }
+        };
+
+        let sender = match joined {
+            Ok(sender) => sender,
+            Err(mut receiver) => {
+                // A leader dropped before finishing leaves nothing to share
+                let shared = match receiver.wait_for(Option::is_some).await {
+                    Ok(outcome) => match outcome.as_ref() {
+                        Some(Ok(body)) => Some(Ok(body.clone())),
+                        Some(Err(e)) => Some(Err(e.duplicate())),
+                        None => None,
+                    },
+                    Err(_) => None,
+                };
+                return match shared {
+                    Some(result) => result,
+                    None => self.fetch_uncoalesced(url, user_agent).await,
+                };
+            }
+        };
+
+        let _guard = FlightGuard {
+            in_flight: &self.in_flight,
+            key,
+        };
+        let result = self.fetch_uncoalesced(url, user_agent).await;
+        let shared = match result {
+            Ok(ref body) => Ok(body.clone()),
+            Err(ref e) => Err(e.duplicate()),
+        };
+        sender.send_replace(Some(shared));
+        result
+    }
+
+    async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        if self.proxied {
+            record_proxy_use();
+        }
+        let _permits = self.acquire_permits(&request.url).await?;
+
+        let method = match request.method {
+            FetchMethod::Get => reqwest::Method::GET,
+            FetchMethod::Head => reqwest::Method::HEAD,
+            FetchMethod::Post => reqwest::Method::POST,
+            FetchMethod::Put => reqwest::Method::PUT,
+            FetchMethod::Patch => reqwest::Method::PATCH,
+            FetchMethod::Delete => reqwest::Method::DELETE,
+        };
+        let client = match self.no_redirects {
+            Some(ref no_redirects) if request.no_redirects => {
+                no_redirects.get().unwrap_or(&self.client)
+            }
+            _ => &self.client,
+        };
+        // Rotate only when the request sets no user agent of its own
+        let user_agent = match request.user_agent() {
+            Some(_) => None,
+            None => self.user_agent(),
+        };
+        let mut builder = self.identify(
+            client.request(method, &request.url),
+            user_agent,
+            request.user_agent(),
+            |name| {
+                request
+                    .headers
+                    .iter()
+                    .any(|(n, _)| n.eq_ignore_ascii_case(name))
+            },
+        );
+        for (name, value) in &request.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+        builder = match request.body {
+            Some(FetchBody::Form(ref fields)) => builder.form(fields),
+            Some(FetchBody::Raw(ref body)) => builder.body(body.clone()),
+            None => builder,
+        };
+
+        let response = self.send(builder).await?;
+        // A redirect the request asked to stop at is its answer, not an error
+        let response = if request.no_redirects && response.status().is_redirection() {
+            response
+        } else {
+            self.check_status(response).await?
+        };
+        let status = response.status().as_u16();
+        let final_url = response.url().to_string();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|v| (name.as_str().to_string(), v.to_string()))
+            })
+            .collect();
+        let body = self.read_body(response).await?;
+
+        Ok(FetchResponse {
+            status,
+            final_url,
+            headers,
+            body,
+        })
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_server::{
+        spawn_connection_server, spawn_echo_server, spawn_server, spawn_silent_server,
+        spawn_status_server, Response,
+    };
 
     #[test]
     fn test_http_fetcher_new() {
@@ -87,6 +1199,222 @@ mod tests {
         let _fetcher = HttpFetcher::default();
     }
 
+    /// Returns a DNS cache resolving every host to localhost, and its lookup
+    /// counter.
+    fn localhost_dns(ttl: Duration) -> (DnsCache, Arc<AtomicUsize>) {
+        let lookups = Arc::new(AtomicUsize::new(0));
+        let counter = lookups.clone();
+        let dns = DnsCache::with_lookup(
+            ttl,
+            Arc::new(move |_host: String| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(vec![std::net::SocketAddr::from(([127, 0, 0, 1], 0))]) })
+                    as futures::future::BoxFuture<'static, _>
+            }),
+        );
+        (dns, lookups)
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_dns_cache_skips_second_lookup() {
+        let (addr, server) = spawn_echo_server().await;
+        let (dns, lookups) = localhost_dns(Duration::from_secs(60));
+        let mut builder = HttpFetcher::builder();
+        builder.dns_cache = Some(dns);
+        let fetcher = builder.build().unwrap();
+
+        // The server closes every connection, so each fetch reconnects
+        let url = format!("http://engine.test:{}/", addr.port());
+        fetcher.fetch(&url).await.unwrap();
+        fetcher.fetch(&url).await.unwrap();
+
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_preresolve() {
+        let (addr, server) = spawn_echo_server().await;
+        let (dns, lookups) = localhost_dns(Duration::from_secs(60));
+        let mut builder = HttpFetcher::builder();
+        builder.dns_cache = Some(dns);
+        let fetcher = builder.build().unwrap();
+
+        assert_eq!(fetcher.preresolve(["engine.test", "other.test"]).await, 2);
+        fetcher
+            .fetch(&format!("http://engine.test:{}/", addr.port()))
+            .await
+            .unwrap();
+
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_preresolve_without_cache() {
+        assert_eq!(HttpFetcher::new().preresolve(["localhost"]).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_clones_share_connections() {
+        use std::sync::atomic::AtomicUsize;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Keep-alive server that counts accepted connections
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = accepted.clone();
+        let (addr, server) = spawn_connection_server(move |mut socket| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                let mut buf = [0u8; 2048];
+                while let Ok(n) = socket.read(&mut buf).await {
+                    if n == 0 {
+                        return;
+                    }
+                    let _ = socket
+                        .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok")
+                        .await;
+                }
+            }
+        })
+        .await;
+
+        let fetcher = HttpFetcher::new();
+        let clone = fetcher.clone();
+        let url = format!("http://{}/", addr);
+        assert_eq!(fetcher.fetch(&url).await.unwrap(), "ok");
+        assert_eq!(clone.fetch(&url).await.unwrap(), "ok");
+
+        assert_eq!(accepted.load(Ordering::SeqCst), 1);
+        server.abort();
+    }
+
+    /// Spawns a server that answers every request after `delay`, and its
+    /// request counter.
+    async fn spawn_slow_counting_server(
+        delay: Duration,
+    ) -> (
+        std::net::SocketAddr,
+        Arc<AtomicUsize>,
+        tokio::task::JoinHandle<()>,
+    ) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let (addr, server) = spawn_server(move |_| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                tokio::time::sleep(delay).await;
+                Response::ok("ok")
+            }
+        })
+        .await;
+        (addr, requests, server)
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_coalesces_concurrent_fetches() {
+        let (addr, requests, server) = spawn_slow_counting_server(Duration::from_millis(200)).await;
+        let fetcher = HttpFetcher::new();
+        let clone = fetcher.clone();
+        let url = format!("http://{}/search?q=rust", addr);
+
+        let (first, second) = tokio::join!(fetcher.fetch(&url), clone.fetch(&url));
+        assert_eq!(first.unwrap(), "ok");
+        assert_eq!(second.unwrap(), "ok");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // Once finished, the next fetch goes to the network again
+        fetcher.fetch(&url).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_does_not_coalesce_different_urls() {
+        let (addr, requests, server) = spawn_slow_counting_server(Duration::from_millis(100)).await;
+        let fetcher = HttpFetcher::new();
+
+        let (a, b) = (format!("http://{}/a", addr), format!("http://{}/b", addr));
+        let (first, second) = tokio::join!(fetcher.fetch(&a), fetcher.fetch(&b));
+        assert!(first.is_ok() && second.is_ok());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_does_not_coalesce_different_user_agents() {
+        let (addr, requests, server) = spawn_slow_counting_server(Duration::from_millis(100)).await;
+        let fetcher = HttpFetcher::new();
+        let url = format!("http://{}/", addr);
+
+        let (first, second) = tokio::join!(
+            crate::fetcher::USER_AGENT.scope(Arc::from("AgentA/1.0"), fetcher.fetch(&url)),
+            crate::fetcher::USER_AGENT.scope(Arc::from("AgentB/2.0"), fetcher.fetch(&url)),
+        );
+        assert!(first.is_ok() && second.is_ok());
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_cancelled_leader_does_not_block_followers() {
+        let (addr, requests, server) = spawn_slow_counting_server(Duration::from_millis(200)).await;
+        let fetcher = HttpFetcher::new();
+        let url = format!("http://{}/", addr);
+
+        let leader = {
+            let fetcher = fetcher.clone();
+            let url = url.clone();
+            tokio::spawn(async move { fetcher.fetch(&url).await })
+        };
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let follower = {
+            let fetcher = fetcher.clone();
+            let url = url.clone();
+            tokio::spawn(async move { fetcher.fetch(&url).await })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        leader.abort();
+
+        assert_eq!(follower.await.unwrap().unwrap(), "ok");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        server.abort();
+    }
+
+    #[cfg(feature = "middleware")]
+    #[tokio::test]
+    async fn test_http_fetcher_with_middleware_client() {
+        use reqwest_middleware::{ClientBuilder, Middleware, Next};
+
+        /// Passes requests through unchanged, counting them.
+        struct CountingMiddleware(Arc<AtomicUsize>);
+
+        #[async_trait]
+        impl Middleware for CountingMiddleware {
+            async fn handle(
+                &self,
+                req: reqwest::Request,
+                extensions: &mut http::Extensions,
+                next: Next<'_>,
+            ) -> reqwest_middleware::Result<reqwest::Response> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                next.run(req, extensions).await
+            }
+        }
+
+        let (addr, server) = spawn_status_server("200 OK", "", "<html>ok</html>").await;
+        let calls = Arc::new(AtomicUsize::new(0));
+        let client = ClientBuilder::new(Client::new())
+            .with(CountingMiddleware(calls.clone()))
+            .build();
+        let fetcher = HttpFetcher::new().with_middleware_client(client);
+
+        let body = fetcher.fetch(&format!("http://{}/", addr)).await.unwrap();
+        assert_eq!(body, "<html>ok</html>");
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        server.abort();
+    }
+
     #[test]
     fn test_http_fetcher_with_client() {
         let client = Client::builder().user_agent("test-agent").build().unwrap();
@@ -117,4 +1445,1078 @@ mod tests {
         let fetcher = HttpFetcher::new();
         let _client = fetcher.client();
     }
+
+    #[test]
+    fn test_http_fetcher_with_timeout() {
+        let _fetcher = HttpFetcher::with_timeout(Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_timeout_on_slow_server() {
+        // Accept connections but never write a response
+        let (addr, server) = spawn_silent_server().await;
+
+        let fetcher = HttpFetcher::with_timeout(Duration::from_millis(300));
+        let start = std::time::Instant::now();
+        let result = fetcher.fetch(&format!("http://{}/", addr)).await;
+        let elapsed = start.elapsed();
+
+        assert!(result.is_err());
+        assert!(
+            elapsed < Duration::from_secs(2),
+            "fetch should fail within the timeout budget, took {:?}",
+            elapsed
+        );
+        server.abort();
+    }
+
+    #[test]
+    fn test_http_fetcher_builder_defaults() {
+        let builder = HttpFetcher::builder();
+        assert_eq!(builder.user_agent, DEFAULT_USER_AGENT);
+        assert!(builder.headers.is_empty());
+        assert_eq!(builder.timeout, DEFAULT_TIMEOUT);
+        assert!(builder.proxy_url.is_none());
+        assert!(builder.gzip);
+        assert!(builder.brotli);
+        assert_eq!(builder.max_idle_per_host, DEFAULT_MAX_IDLE_PER_HOST);
+        assert!(builder.max_connections.is_none());
+        assert!(!builder.lenient);
+        assert!(!builder.http2_prior_knowledge);
+        assert_eq!(builder.tcp_keepalive, Some(DEFAULT_TCP_KEEPALIVE));
+        assert!(builder.http2_keep_alive_interval.is_none());
+        assert_eq!(builder.redirect_policy, RedirectPolicy::Limited);
+    }
+
+    #[test]
+    fn test_http_fetcher_builder_keep_alive_options() {
+        let builder = HttpFetcher::builder()
+            .http2_prior_knowledge(true)
+            .tcp_keepalive(None)
+            .http2_keep_alive_interval(Some(Duration::from_secs(15)));
+        assert!(builder.http2_prior_knowledge);
+        assert!(builder.tcp_keepalive.is_none());
+        assert_eq!(
+            builder.http2_keep_alive_interval,
+            Some(Duration::from_secs(15))
+        );
+        assert!(builder.build().is_ok());
+    }
+
+    /// Spawns a cleartext HTTP/2 server that answers every request with the
+    /// negotiated protocol version in the body.
+    async fn spawn_h2c_server() -> (std::net::SocketAddr, tokio::task::JoinHandle<()>) {
+        spawn_connection_server(|socket| async move {
+            let Ok(mut connection) = h2::server::handshake(socket).await else {
+                return;
+            };
+            while let Some(Ok((request, mut respond))) = connection.accept().await {
+                let body = format!("{:?}", request.version());
+                let response = http::Response::builder().status(200).body(()).unwrap();
+                if let Ok(mut stream) = respond.send_response(response, false) {
+                    let _ = stream.send_data(bytes::Bytes::from(body), true);
+                }
+            }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_http2_prior_knowledge_round_trip() {
+        let (addr, server) = spawn_h2c_server().await;
+        let fetcher = HttpFetcher::builder()
+            .http2_prior_knowledge(true)
+            .tcp_keepalive(Some(Duration::from_secs(30)))
+            .http2_keep_alive_interval(Some(Duration::from_secs(10)))
+            .build()
+            .unwrap();
+
+        let url = format!("http://{}/", addr);
+        // Two requests exercise reuse of the pooled HTTP/2 connection
+        assert_eq!(fetcher.fetch(&url).await.unwrap(), "HTTP/2.0");
+        assert_eq!(fetcher.fetch(&url).await.unwrap(), "HTTP/2.0");
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_keep_alive_options_http1_round_trip() {
+        let (addr, server) = spawn_echo_server().await;
+        let fetcher = HttpFetcher::builder()
+            .tcp_keepalive(Some(Duration::from_secs(30)))
+            .http2_keep_alive_interval(Some(Duration::from_secs(10)))
+            .build()
+            .unwrap();
+
+        let echoed = fetcher.fetch(&format!("http://{}/", addr)).await.unwrap();
+        assert!(echoed.starts_with("GET / HTTP/1.1"));
+        server.abort();
+    }
+
+    #[test]
+    fn test_http_fetcher_builder_pool_limits() {
+        let builder = HttpFetcher::builder()
+            .max_idle_per_host(2)
+            .max_connections_per_host(3)
+            .max_connections(4);
+        assert_eq!(builder.max_idle_per_host, 2);
+        assert_eq!(builder.max_connections_per_host, 3);
+        assert_eq!(builder.max_connections, Some(4));
+
+        let fetcher = builder.build().unwrap();
+        assert_eq!(fetcher.limiter.unwrap().available_permits(), 4);
+        let semaphore = fetcher
+            .host_limiter
+            .unwrap()
+            .semaphore("https://example.com/search")
+            .unwrap();
+        assert_eq!(semaphore.available_permits(), 3);
+    }
+
+    #[test]
+    fn test_http_fetcher_builder_zero_max_connections() {
+        let builder = HttpFetcher::builder()
+            .max_connections(0)
+            .max_connections_per_host(0);
+        assert_eq!(builder.max_connections, Some(1));
+        assert_eq!(builder.max_connections_per_host, 1);
+    }
+
+    #[test]
+    fn test_host_limiter_keys_by_host_and_port() {
+        let limiter = HostLimiter::new(1);
+        let a = limiter.semaphore("https://example.com/a").unwrap();
+        let b = limiter.semaphore("https://example.com:443/b?q=1").unwrap();
+        let other = limiter.semaphore("http://example.com/a").unwrap();
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &other));
+        assert!(limiter.semaphore("not a url").is_none());
+
+        // Idle hosts are forgotten once another host is added
+        drop((a, b, other));
+        limiter.semaphore("https://example.org/").unwrap();
+        assert_eq!(limiter.hosts.lock().unwrap().len(), 1);
+    }
+
+    /// Starts a server that answers each request after 100ms, and counts
+    /// the most connections it had open at once.
+    async fn spawn_peak_server() -> (
+        std::net::SocketAddr,
+        Arc<AtomicUsize>,
+        tokio::task::JoinHandle<()>,
+    ) {
+        let active = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let (addr, server) = {
+            let peak = Arc::clone(&peak);
+            spawn_server(move |_| {
+                let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                let active = Arc::clone(&active);
+                async move {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    Response::ok("ok")
+                }
+            })
+            .await
+        };
+        (addr, peak, server)
+    }
+
+    /// Fetches six different paths of `addr` at once through `fetcher`.
+    async fn fetch_six_paths(fetcher: HttpFetcher, addr: std::net::SocketAddr) {
+        let fetcher = Arc::new(fetcher);
+        let tasks: Vec<_> = (0..6)
+            .map(|i| {
+                let fetcher = Arc::clone(&fetcher);
+                let url = format!("http://{}/{}", addr, i);
+                tokio::spawn(async move { fetcher.fetch(&url).await })
+            })
+            .collect();
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), "ok");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_max_connections_bounds_concurrency() {
+        let (addr, peak, server) = spawn_peak_server().await;
+        let fetcher = HttpFetcher::builder().max_connections(2).build().unwrap();
+        fetch_six_paths(fetcher, addr).await;
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_max_connections_per_host_bounds_concurrency() {
+        let (addr, peak, server) = spawn_peak_server().await;
+        let fetcher = HttpFetcher::builder()
+            .max_connections_per_host(3)
+            .build()
+            .unwrap();
+        fetch_six_paths(fetcher, addr).await;
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+
+        // Another host is not held up by a busy one
+        let (other, other_peak, other_server) = spawn_peak_server().await;
+        let fetcher = HttpFetcher::builder()
+            .max_connections_per_host(1)
+            .build()
+            .unwrap();
+        let (url, other_url) = (format!("http://{}/", addr), format!("http://{}/", other));
+        let started = std::time::Instant::now();
+        let (a, b) = tokio::join!(fetcher.fetch(&url), fetcher.fetch(&other_url));
+        assert_eq!(
+            (a.unwrap(), b.unwrap()),
+            ("ok".to_string(), "ok".to_string())
+        );
+        assert!(started.elapsed() < Duration::from_millis(200));
+        assert_eq!(other_peak.load(Ordering::SeqCst), 1);
+        server.abort();
+        other_server.abort();
+    }
+
+    #[test]
+    fn test_http_fetcher_builder_all_options() {
+        let fetcher = HttpFetcher::builder()
+            .user_agent("TestBot/1.0")
+            .header("Accept-Language", "en-US")
+            .header("Referer", "https://example.com/")
+            .timeout(Duration::from_secs(3))
+            .proxy("http://127.0.0.1:8080")
+            .gzip(false)
+            .build();
+        assert!(fetcher.is_ok());
+    }
+
+    #[test]
+    fn test_http_fetcher_builder_invalid_header_name() {
+        let result = HttpFetcher::builder().header("bad header", "value").build();
+        let err = result.err().unwrap().to_string();
+        assert!(err.contains("Invalid header name"), "{}", err);
+    }
+
+    #[test]
+    fn test_http_fetcher_builder_invalid_header_value() {
+        let result = HttpFetcher::builder()
+            .header("X-Test", "line\nbreak")
+            .build();
+        let err = result.err().unwrap().to_string();
+        assert!(err.contains("Invalid header value"), "{}", err);
+    }
+
+    #[test]
+    fn test_http_fetcher_builder_invalid_proxy() {
+        let result = HttpFetcher::builder().proxy("").build();
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_sends_default_headers() {
+        let (addr, server) = spawn_echo_server().await;
+
+        let fetcher = HttpFetcher::builder()
+            .user_agent("EchoTest/1.0")
+            .header("Accept-Language", "zh-CN")
+            .header("X-Api-Token", "secret")
+            .build()
+            .unwrap();
+        let echoed = fetcher
+            .fetch(&format!("http://{}/", addr))
+            .await
+            .unwrap()
+            .to_lowercase();
+
+        assert!(echoed.contains("user-agent: echotest/1.0"), "{}", echoed);
+        assert!(echoed.contains("accept-language: zh-cn"), "{}", echoed);
+        assert!(echoed.contains("x-api-token: secret"), "{}", echoed);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_per_request_header_overrides_default() {
+        let (addr, server) = spawn_echo_server().await;
+
+        let fetcher = HttpFetcher::builder()
+            .header("Accept-Language", "zh-CN")
+            .header("Referer", "https://default.example/")
+            .build()
+            .unwrap();
+        let echoed = fetcher
+            .client()
+            .get(format!("http://{}/", addr))
+            .header("Accept-Language", "fr-FR")
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap()
+            .to_lowercase();
+
+        // The per-request value replaces the default rather than adding to it
+        assert!(echoed.contains("accept-language: fr-fr"), "{}", echoed);
+        assert!(!echoed.contains("accept-language: zh-cn"), "{}", echoed);
+        // Defaults not overridden are still sent
+        assert!(
+            echoed.contains("referer: https://default.example/"),
+            "{}",
+            echoed
+        );
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_success_status() {
+        let (addr, server) = spawn_status_server("200 OK", "", "<html>ok</html>").await;
+        let body = HttpFetcher::new()
+            .fetch(&format!("http://{}/", addr))
+            .await
+            .unwrap();
+        assert_eq!(body, "<html>ok</html>");
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_rate_limited() {
+        let (addr, server) =
+            spawn_status_server("429 Too Many Requests", "Retry-After: 120\r\n", "slow down").await;
+        let result = HttpFetcher::new().fetch(&format!("http://{}/", addr)).await;
+        match result {
+            Err(SearchError::RateLimited(retry_after)) => {
+                assert_eq!(retry_after.as_deref(), Some("120"))
+            }
+            other => panic!("Expected RateLimited, got {:?}", other),
+        }
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_rate_limited_without_retry_after() {
+        let (addr, server) = spawn_status_server("429 Too Many Requests", "", "").await;
+        let result = HttpFetcher::new().fetch(&format!("http://{}/", addr)).await;
+        assert!(matches!(result, Err(SearchError::RateLimited(None))));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_blocked() {
+        let (addr, server) = spawn_status_server("403 Forbidden", "", "Access denied").await;
+        let result = HttpFetcher::new().fetch(&format!("http://{}/", addr)).await;
+        match result {
+            Err(SearchError::Blocked(body)) => assert_eq!(body, "Access denied"),
+            other => panic!("Expected Blocked, got {:?}", other),
+        }
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_client_error_status() {
+        let (addr, server) = spawn_status_server("404 Not Found", "", "no such page").await;
+        let result = HttpFetcher::new().fetch(&format!("http://{}/", addr)).await;
+        match result {
+            Err(SearchError::HttpStatus(status, body)) => {
+                assert_eq!(status, 404);
+                assert_eq!(body, "no such page");
+            }
+            other => panic!("Expected HttpStatus, got {:?}", other),
+        }
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_server_error_status_truncates_body() {
+        const LONG_BODY: &str = concat!(
+            "0123456789012345678901234567890123456789012345678901234567890123456789",
+            "0123456789012345678901234567890123456789012345678901234567890123456789",
+            "0123456789012345678901234567890123456789012345678901234567890123456789",
+            "0123456789012345678901234567890123456789012345678901234567890123456789"
+        );
+        let (addr, server) = spawn_status_server("503 Service Unavailable", "", LONG_BODY).await;
+        let result = HttpFetcher::new().fetch(&format!("http://{}/", addr)).await;
+        match result {
+            Err(SearchError::HttpStatus(status, body)) => {
+                assert_eq!(status, 503);
+                assert_eq!(body.len(), ERROR_BODY_PREVIEW_BYTES);
+            }
+            other => panic!("Expected HttpStatus, got {:?}", other),
+        }
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_lenient_returns_error_body() {
+        let (addr, server) = spawn_status_server("404 Not Found", "", "not found page").await;
+        let fetcher = HttpFetcher::builder().lenient(true).build().unwrap();
+        let body = fetcher.fetch(&format!("http://{}/", addr)).await.unwrap();
+        assert_eq!(body, "not found page");
+        server.abort();
+    }
+
+    #[test]
+    fn test_body_preview_char_boundary() {
+        let body = "中".repeat(100); // 300 bytes
+        let preview = body_preview(&body);
+        assert!(preview.len() <= ERROR_BODY_PREVIEW_BYTES);
+        assert!(preview.chars().all(|c| c == '中'));
+    }
+
+    /// Extracts the echoed `User-Agent` header value.
+    fn echoed_user_agent(echoed: &str) -> String {
+        echoed
+            .lines()
+            .find_map(|line| line.strip_prefix("user-agent: "))
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    /// Returns the value of header `name` (lowercase) in an echoed request.
+    fn echoed_header<'a>(echoed: &'a str, name: &str) -> Option<&'a str> {
+        echoed
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(": "))
+    }
+
+    #[test]
+    fn test_browser_headers_chrome() {
+        let headers = browser_headers(DEFAULT_USER_AGENTS[0]);
+        let get = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v.as_str())
+        };
+        assert_eq!(get("Accept"), Some(CHROMIUM_ACCEPT));
+        assert_eq!(get("Accept-Language"), Some("en-US,en;q=0.9"));
+        assert_eq!(
+            get("Sec-CH-UA"),
+            Some("\"Google Chrome\";v=\"131\", \"Chromium\";v=\"131\", \"Not_A Brand\";v=\"24\"")
+        );
+        assert_eq!(get("Sec-CH-UA-Mobile"), Some("?0"));
+        assert_eq!(get("Sec-CH-UA-Platform"), Some("\"Windows\""));
+        assert_eq!(get("Sec-Fetch-Mode"), Some("navigate"));
+    }
+
+    #[test]
+    fn test_browser_headers_match_user_agent() {
+        let hint = |ua: &str, name: &str| {
+            browser_headers(ua)
+                .into_iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, v)| v)
+        };
+        // Edge names its own brand
+        let edge = DEFAULT_USER_AGENTS[5];
+        assert!(hint(edge, "Sec-CH-UA")
+            .unwrap()
+            .starts_with("\"Microsoft Edge\";v=\"131\""));
+        assert_eq!(
+            hint(DEFAULT_USER_AGENTS[1], "Sec-CH-UA-Platform").as_deref(),
+            Some("\"macOS\"")
+        );
+        assert_eq!(
+            hint(DEFAULT_USER_AGENTS[6], "Sec-CH-UA-Platform").as_deref(),
+            Some("\"Linux\"")
+        );
+        // Firefox and Safari send no client hints
+        for ua in [DEFAULT_USER_AGENTS[2], DEFAULT_USER_AGENTS[4]] {
+            assert_eq!(hint(ua, "Sec-CH-UA"), None, "{}", ua);
+            assert_eq!(hint(ua, "Accept").as_deref(), Some(GECKO_ACCEPT));
+            assert_eq!(hint(ua, "Sec-Fetch-Dest").as_deref(), Some("document"));
+        }
+        assert_eq!(
+            hint(DEFAULT_USER_AGENTS[2], "Accept-Language").as_deref(),
+            Some("en-US,en;q=0.5")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_browser_headers() {
+        let (addr, server) = spawn_echo_server().await;
+        let url = format!("http://{}/", addr);
+
+        // Without the preset, none are sent
+        let echoed = HttpFetcher::new().fetch(&url).await.unwrap().to_lowercase();
+        assert_eq!(echoed_header(&echoed, "sec-ch-ua"), None);
+
+        let fetcher = HttpFetcher::builder()
+            .user_agent(DEFAULT_USER_AGENTS[5])
+            .build()
+            .unwrap()
+            .with_browser_headers();
+        let echoed = fetcher.fetch(&url).await.unwrap().to_lowercase();
+        assert_eq!(
+            echoed_user_agent(&echoed),
+            DEFAULT_USER_AGENTS[5].to_lowercase()
+        );
+        assert_eq!(echoed.matches("user-agent:").count(), 1);
+        assert!(echoed_header(&echoed, "sec-ch-ua")
+            .unwrap()
+            .starts_with("\"microsoft edge\";v=\"131\""));
+        assert_eq!(
+            echoed_header(&echoed, "sec-ch-ua-platform"),
+            Some("\"windows\"")
+        );
+        assert_eq!(echoed_header(&echoed, "sec-fetch-site"), Some("none"));
+        assert_eq!(
+            echoed_header(&echoed, "upgrade-insecure-requests"),
+            Some("1")
+        );
+        assert_eq!(
+            echoed_header(&echoed, "accept"),
+            Some(CHROMIUM_ACCEPT.to_lowercase().as_str())
+        );
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_browser_headers_follow_request() {
+        let (addr, server) = spawn_echo_server().await;
+        let fetcher = HttpFetcher::builder()
+            .header("Accept-Language", "zh-CN")
+            .build()
+            .unwrap()
+            .with_user_agent_rotation(vec![DEFAULT_USER_AGENTS[2].to_string()])
+            .with_browser_headers();
+
+        // Derived from the rotated Firefox agent, keeping the builder default
+        let echoed = fetcher
+            .fetch(&format!("http://{}/", addr))
+            .await
+            .unwrap()
+            .to_lowercase();
+        assert!(echoed_user_agent(&echoed).contains("firefox/133.0"));
+        assert_eq!(echoed_header(&echoed, "sec-ch-ua"), None);
+        assert_eq!(echoed_header(&echoed, "accept-language"), Some("zh-cn"));
+        assert_eq!(echoed.matches("accept-language:").count(), 1);
+
+        // A request's own user agent and headers win
+        let request = FetchRequest::get(format!("http://{}/", addr))
+            .with_user_agent(DEFAULT_USER_AGENTS[6])
+            .with_header("Accept", "application/json");
+        let echoed = fetcher
+            .fetch_request(&request)
+            .await
+            .unwrap()
+            .body
+            .to_lowercase();
+        assert_eq!(echoed.matches("user-agent:").count(), 1);
+        assert_eq!(
+            echoed_header(&echoed, "sec-ch-ua-platform"),
+            Some("\"linux\"")
+        );
+        assert_eq!(echoed_header(&echoed, "accept"), Some("application/json"));
+        assert_eq!(echoed.matches("accept:").count(), 1);
+        server.abort();
+    }
+
+    #[test]
+    fn test_http_fetcher_user_agent_rotation_default_list() {
+        let fetcher = HttpFetcher::new().with_user_agent_rotation(Vec::new());
+        let rotation = fetcher.user_agents.as_ref().unwrap();
+        assert_eq!(rotation.agents.len(), DEFAULT_USER_AGENTS.len());
+        assert_eq!(rotation.strategy, UserAgentStrategy::RoundRobin);
+    }
+
+    #[test]
+    fn test_http_fetcher_user_agent_strategy_without_rotation() {
+        let fetcher = HttpFetcher::new().with_user_agent_strategy(UserAgentStrategy::Random);
+        assert!(fetcher.user_agents.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_user_agent_rotation_round_robin() {
+        let (addr, server) = spawn_echo_server().await;
+        let agents = vec![
+            "AgentA/1.0".to_string(),
+            "AgentB/2.0".to_string(),
+            "AgentC/3.0".to_string(),
+        ];
+        let fetcher = HttpFetcher::new().with_user_agent_rotation(agents.clone());
+
+        let url = format!("http://{}/", addr);
+        let mut seen = Vec::new();
+        for _ in 0..4 {
+            let echoed = fetcher.fetch(&url).await.unwrap().to_lowercase();
+            seen.push(echoed_user_agent(&echoed));
+        }
+
+        assert_eq!(seen[0], "agenta/1.0");
+        assert_eq!(seen[1], "agentb/2.0");
+        assert_eq!(seen[2], "agentc/3.0");
+        assert_eq!(seen[3], "agenta/1.0");
+        for pair in seen.windows(2) {
+            assert_ne!(pair[0], pair[1]);
+        }
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_user_agent_rotation_random() {
+        let (addr, server) = spawn_echo_server().await;
+        let agents = vec!["AgentA/1.0".to_string(), "AgentB/2.0".to_string()];
+        let fetcher = HttpFetcher::new()
+            .with_user_agent_rotation(agents)
+            .with_user_agent_strategy(UserAgentStrategy::Random);
+
+        let url = format!("http://{}/", addr);
+        for _ in 0..5 {
+            let echoed = fetcher.fetch(&url).await.unwrap().to_lowercase();
+            let agent = echoed_user_agent(&echoed);
+            assert!(agent == "agenta/1.0" || agent == "agentb/2.0", "{}", agent);
+            // The client-level default agent is replaced, not duplicated
+            assert_eq!(echoed.matches("user-agent:").count(), 1);
+        }
+        server.abort();
+    }
+
+    #[test]
+    fn test_user_agent_rotation_random_is_uniform() {
+        let agents = (0..3).map(|i| format!("Agent/{}", i)).collect();
+        let rotation = UserAgentRotation::new(agents, UserAgentStrategy::Random);
+
+        // Tight loop: selection must not depend on the clock
+        let mut counts = [0u32; 3];
+        for _ in 0..30_000 {
+            let agent = rotation.next();
+            counts[agent["Agent/".len()..].parse::<usize>().unwrap()] += 1;
+        }
+        // Expected 10,000 each; the standard deviation is about 82
+        for count in counts {
+            assert!((9_000..=11_000).contains(&count), "{:?}", counts);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_request_user_agent_overrides_rotation() {
+        let (addr, server) = spawn_echo_server().await;
+        let fetcher = HttpFetcher::new().with_user_agent_rotation(vec!["AgentA/1.0".to_string()]);
+
+        let request = FetchRequest::get(format!("http://{}/", addr)).with_user_agent("Custom/9.0");
+        let echoed = fetcher
+            .fetch_request(&request)
+            .await
+            .unwrap()
+            .body
+            .to_lowercase();
+        assert_eq!(echoed_user_agent(&echoed), "custom/9.0");
+        assert_eq!(echoed.matches("user-agent:").count(), 1);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_search_user_agent() {
+        let (addr, server) = spawn_echo_server().await;
+        let fetcher = HttpFetcher::new().with_user_agent_rotation(vec!["AgentA/1.0".to_string()]);
+        let url = format!("http://{}/", addr);
+
+        // The search's user agent replaces the rotated one...
+        let echoed = crate::fetcher::USER_AGENT
+            .scope(Arc::from("GlobalBot/1.0"), fetcher.fetch(&url))
+            .await
+            .unwrap()
+            .to_lowercase();
+        assert_eq!(echoed_user_agent(&echoed), "globalbot/1.0");
+        assert_eq!(echoed.matches("user-agent:").count(), 1);
+
+        // ...but not one set on the request
+        let request = FetchRequest::get(&url).with_user_agent("Custom/9.0");
+        let echoed = crate::fetcher::USER_AGENT
+            .scope(Arc::from("GlobalBot/1.0"), fetcher.fetch_request(&request))
+            .await
+            .unwrap()
+            .body
+            .to_lowercase();
+        assert_eq!(echoed_user_agent(&echoed), "custom/9.0");
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_user_agent_rotation_with_proxy() {
+        // The echo server acts as an HTTP proxy and reflects the forwarded request
+        let (addr, server) = spawn_echo_server().await;
+        let fetcher = HttpFetcher::with_proxy(&format!("http://{}", addr))
+            .unwrap()
+            .with_user_agent_rotation(vec!["AgentA/1.0".to_string(), "AgentB/2.0".to_string()]);
+
+        let first = fetcher
+            .fetch("http://search.invalid/")
+            .await
+            .unwrap()
+            .to_lowercase();
+        let second = fetcher
+            .fetch("http://search.invalid/")
+            .await
+            .unwrap()
+            .to_lowercase();
+
+        assert!(first.starts_with("get http://search.invalid/"), "{}", first);
+        assert_eq!(echoed_user_agent(&first), "agenta/1.0");
+        assert_eq!(echoed_user_agent(&second), "agentb/2.0");
+        server.abort();
+    }
+
+    /// Spawns a local HTTP server where `/hop/N` redirects to `/hop/N-1`,
+    /// `/hop/0` redirects to `/final`, and `/final` returns a page.
+    async fn spawn_redirect_server() -> (std::net::SocketAddr, tokio::task::JoinHandle<()>) {
+        spawn_server(|request| {
+            let response = match request.path().strip_prefix("/hop/") {
+                Some("0") => Response::redirect("302 Found", "/final"),
+                Some(hops) => {
+                    let next = hops.parse::<u32>().unwrap_or(1) - 1;
+                    Response::redirect("301 Moved Permanently", &format!("/hop/{}", next))
+                }
+                None => Response::ok("final page"),
+            };
+            async move { response }
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_follows_redirects_by_default() {
+        let (addr, server) = spawn_redirect_server().await;
+        let body = HttpFetcher::new()
+            .fetch(&format!("http://{}/hop/0", addr))
+            .await
+            .unwrap();
+        assert_eq!(body, "final page");
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_redirect_policy_none() {
+        let (addr, server) = spawn_redirect_server().await;
+        let fetcher = HttpFetcher::builder()
+            .redirect_policy(RedirectPolicy::None)
+            .build()
+            .unwrap();
+
+        let result = fetcher.fetch(&format!("http://{}/hop/0", addr)).await;
+        match result {
+            Err(SearchError::Redirected(location)) => {
+                assert_eq!(location, format!("http://{}/final", addr))
+            }
+            other => panic!("Expected Redirected, got {:?}", other),
+        }
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_redirect_policy_max() {
+        let (addr, server) = spawn_redirect_server().await;
+        let fetcher = HttpFetcher::builder()
+            .redirect_policy(RedirectPolicy::Max(2))
+            .build()
+            .unwrap();
+
+        // Two redirects are within the limit
+        let body = fetcher
+            .fetch(&format!("http://{}/hop/1", addr))
+            .await
+            .unwrap();
+        assert_eq!(body, "final page");
+
+        // Four redirects exceed it
+        let result = fetcher.fetch(&format!("http://{}/hop/3", addr)).await;
+        assert!(matches!(result, Err(SearchError::Http(_))));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_redirect_location() {
+        let (addr, server) = spawn_redirect_server().await;
+        let fetcher = HttpFetcher::builder()
+            .redirect_policy(RedirectPolicy::None)
+            .build()
+            .unwrap();
+
+        let location = fetcher
+            .redirect_location(&format!("http://{}/hop/0", addr))
+            .await
+            .unwrap();
+        assert_eq!(location, Some(format!("http://{}/final", addr)));
+
+        let location = fetcher
+            .redirect_location(&format!("http://{}/final", addr))
+            .await
+            .unwrap();
+        assert_eq!(location, None);
+        server.abort();
+    }
+
+    /// Spawns a local HTTP server that answers with `size` bytes of body,
+    /// declaring the length upfront only when `declare_length` is set.
+    async fn spawn_body_server(
+        size: usize,
+        declare_length: bool,
+    ) -> (std::net::SocketAddr, tokio::task::JoinHandle<()>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        spawn_connection_server(move |mut socket| async move {
+            let mut buf = [0u8; 1024];
+            let _ = socket.read(&mut buf).await;
+            let header = if declare_length {
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    size
+                )
+            } else {
+                "HTTP/1.1 200 OK\r\nConnection: close\r\n\r\n".to_string()
+            };
+            if socket.write_all(header.as_bytes()).await.is_err() {
+                return;
+            }
+            // Stream the body in small chunks like a slow, large page
+            let chunk = [b'a'; 512];
+            let mut sent = 0;
+            while sent < size {
+                let n = chunk.len().min(size - sent);
+                if socket.write_all(&chunk[..n]).await.is_err() {
+                    return;
+                }
+                sent += n;
+            }
+        })
+        .await
+    }
+
+    #[test]
+    fn test_http_fetcher_default_max_body_size() {
+        let fetcher = HttpFetcher::new();
+        assert_eq!(fetcher.max_body_size, DEFAULT_MAX_BODY_SIZE);
+        let fetcher = fetcher.with_max_body_size(1024);
+        assert_eq!(fetcher.max_body_size, 1024);
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_body_within_limit() {
+        let (addr, server) = spawn_body_server(4096, false).await;
+        let fetcher = HttpFetcher::new().with_max_body_size(4096);
+        let body = fetcher.fetch(&format!("http://{}/", addr)).await.unwrap();
+        assert_eq!(body.len(), 4096);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_rejects_oversized_content_length() {
+        let (addr, server) = spawn_body_server(64 * 1024, true).await;
+        let fetcher = HttpFetcher::new().with_max_body_size(1024);
+        let result = fetcher.fetch(&format!("http://{}/", addr)).await;
+        assert!(matches!(result, Err(SearchError::ResponseTooLarge(1024))));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_aborts_oversized_stream() {
+        let (addr, server) = spawn_body_server(64 * 1024, false).await;
+        let fetcher = HttpFetcher::new().with_max_body_size(1024);
+        let result = fetcher.fetch(&format!("http://{}/", addr)).await;
+        assert!(matches!(result, Err(SearchError::ResponseTooLarge(1024))));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_fetch_request_post_form() {
+        let (addr, server) = spawn_echo_server().await;
+        let request = FetchRequest::post_form(
+            format!("http://{}/html/", addr),
+            [("q", "rust lang"), ("s", "20")],
+        )
+        .with_header("Referer", "https://html.duckduckgo.com/");
+
+        let response = HttpFetcher::new().fetch_request(&request).await.unwrap();
+        let echoed = response.body.to_lowercase();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.final_url, format!("http://{}/html/", addr));
+        assert_eq!(response.header("X-Echo"), Some("yes"));
+        assert!(echoed.starts_with("post /html/ http/1.1"), "{}", echoed);
+        assert!(
+            echoed.contains("referer: https://html.duckduckgo.com/"),
+            "{}",
+            echoed
+        );
+        assert!(
+            echoed.contains("content-type: application/x-www-form-urlencoded"),
+            "{}",
+            echoed
+        );
+        assert!(echoed.ends_with("q=rust+lang&s=20"), "{}", echoed);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_fetch_request_custom_method_raw_body() {
+        let (addr, server) = spawn_echo_server().await;
+        let request = FetchRequest::get(format!("http://{}/api", addr))
+            .with_method(FetchMethod::Put)
+            .with_header("Content-Type", "application/json")
+            .with_body(FetchBody::Raw(r#"{"q":"rust"}"#.to_string()));
+
+        let response = HttpFetcher::new().fetch_request(&request).await.unwrap();
+        let echoed = response.body.to_lowercase();
+
+        assert!(echoed.starts_with("put /api http/1.1"), "{}", echoed);
+        assert!(
+            echoed.contains("content-type: application/json"),
+            "{}",
+            echoed
+        );
+        assert!(echoed.ends_with(r#"{"q":"rust"}"#), "{}", echoed);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_fetch_request_maps_status() {
+        let (addr, server) = spawn_status_server("403 Forbidden", "", "blocked").await;
+        let result = HttpFetcher::new()
+            .fetch_request(&FetchRequest::post_form(
+                format!("http://{}/", addr),
+                [("q", "x")],
+            ))
+            .await;
+        assert!(matches!(result, Err(SearchError::Blocked(_))));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_fetch_request_reports_final_url() {
+        let (addr, server) = spawn_redirect_server().await;
+        let response = HttpFetcher::new()
+            .fetch_request(&FetchRequest::get(format!("http://{}/hop/1", addr)))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.final_url, format!("http://{}/final", addr));
+        assert_eq!(response.body, "final page");
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_fetch_request_exposes_unfollowed_redirect() {
+        let (addr, server) = spawn_redirect_server().await;
+        let fetcher = HttpFetcher::builder()
+            .redirect_policy(RedirectPolicy::None)
+            .lenient(true)
+            .build()
+            .unwrap();
+        let response = fetcher
+            .fetch_request(&FetchRequest::get(format!("http://{}/hop/0", addr)))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 302);
+        assert_eq!(response.final_url, format!("http://{}/hop/0", addr));
+        assert_eq!(response.header("Location"), Some("/final"));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_fetch_request_without_redirects() {
+        let (addr, server) = spawn_redirect_server().await;
+        let fetcher = HttpFetcher::new();
+        let url = format!("http://{}/hop/0", addr);
+
+        let response = fetcher
+            .fetch_request(&FetchRequest::get(&url).without_redirects())
+            .await
+            .unwrap();
+        assert_eq!(response.status, 302);
+        assert_eq!(response.final_url, url);
+        assert_eq!(response.header("Location"), Some("/final"));
+        // Other requests still follow redirects
+        let response = fetcher
+            .fetch_request(&FetchRequest::get(&url))
+            .await
+            .unwrap();
+        assert_eq!(response.final_url, format!("http://{}/final", addr));
+        server.abort();
+    }
+
+    const GBK_PAGE: &[u8] = include_bytes!("../tests/fixtures/gbk_page.html");
+
+    #[test]
+    fn test_decode_body_content_type_charset() {
+        let text = decode_body(GBK_PAGE, Some("text/html; charset=GBK"));
+        assert!(text.contains("搜狗搜索结果：Rust 编程语言"), "{}", text);
+    }
+
+    #[test]
+    fn test_decode_body_gb2312_label() {
+        // GB2312 pages are decoded as GBK, its superset
+        let text = decode_body(GBK_PAGE, Some("text/html; charset=\"gb2312\""));
+        assert!(text.contains("中文搜索"), "{}", text);
+    }
+
+    #[test]
+    fn test_decode_body_sniffs_meta_charset() {
+        let text = decode_body(GBK_PAGE, Some("text/html"));
+        assert!(text.contains("搜狗搜索结果"), "{}", text);
+
+        let http_equiv = br#"<meta http-equiv="Content-Type" content="text/html; charset=gbk">"#;
+        let mut page = http_equiv.to_vec();
+        page.extend_from_slice(&GBK_PAGE[GBK_PAGE.len() - 40..]);
+        assert!(decode_body(&page, None).contains("编程语言"));
+    }
+
+    #[test]
+    fn test_decode_body_defaults_to_utf8() {
+        assert_eq!(decode_body("中文".as_bytes(), None), "中文");
+        assert_eq!(
+            decode_body("中文".as_bytes(), Some("text/html; charset=bogus")),
+            "中文"
+        );
+    }
+
+    #[test]
+    fn test_charset_from_content_type() {
+        assert_eq!(
+            charset_from_content_type("text/html; Charset=\"GBK\""),
+            Some("GBK".to_string())
+        );
+        assert_eq!(charset_from_content_type("text/html"), None);
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_decodes_gbk_response() {
+        let (addr, server) = spawn_status_server(
+            "200 OK",
+            "Content-Type: text/html; charset=gbk\r\n",
+            GBK_PAGE.to_vec(),
+        )
+        .await;
+        let body = HttpFetcher::new()
+            .fetch(&format!("http://{}/", addr))
+            .await
+            .unwrap();
+        assert!(body.contains("<title>中文搜索</title>"), "{}", body);
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_http_fetcher_gzip_round_trip() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+        use std::io::Write;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all("<p>压缩内容</p>".as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let (addr, server) = spawn_status_server(
+            "200 OK",
+            "Content-Type: text/html; charset=utf-8\r\nContent-Encoding: gzip\r\n",
+            compressed,
+        )
+        .await;
+        let body = HttpFetcher::new()
+            .fetch(&format!("http://{}/", addr))
+            .await
+            .unwrap();
+        assert_eq!(body, "<p>压缩内容</p>");
+        server.abort();
+    }
 }
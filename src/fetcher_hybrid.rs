@@ -0,0 +1,475 @@
+//! Page fetcher that falls back from plain HTTP to a headless browser.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tracing::debug;
+
+use crate::fetcher::{current_fetcher_hint, FetchRequest, FetchResponse, FetcherHint, PageFetcher};
+use crate::{Result, SearchError};
+
+/// Substrings of pages that are bot checks rather than the requested
+/// content: Cloudflare's challenge, DuckDuckGo's anomaly page, and generic
+/// CAPTCHA and JavaScript walls.
+pub const DEFAULT_BLOCK_MARKERS: &[&str] = &[
+    "challenge-platform",
+    "cf-browser-verification",
+    "<title>Just a moment...</title>",
+    "anomaly-modal",
+    "g-recaptcha",
+    "h-captcha",
+    "Please enable JavaScript to continue",
+];
+
+/// A predicate on a fetched page's HTML.
+type PageSignature = Box<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// A predicate on a fetch error.
+type ErrorSignature = Box<dyn Fn(&SearchError) -> bool + Send + Sync>;
+
+/// How many fetches each path of a [`HybridFetcher`] served.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HybridStats {
+    /// Fetches served over plain HTTP.
+    pub http: u64,
+    /// Fetches that fell back to the browser and succeeded there.
+    pub browser: u64,
+    /// Fetches that failed on both paths, or over HTTP without a fallback.
+    pub failed: u64,
+}
+
+/// A page fetcher that tries plain HTTP first and retries through a
+/// browser when the response looks like a block.
+///
+/// A fetch falls back if the HTTP fetch fails with a block error
+/// (`Blocked`, `RateLimited`, `Captcha`, or a 403, 429 or 503 status) or
+/// returns a page matching a block signature, by default one containing
+/// any of [`DEFAULT_BLOCK_MARKERS`]. Other HTTP errors are returned as they
+/// are. Only plain GETs can fall back, since a browser tab cannot send
+/// other requests.
+///
+/// Inside a [`Search`](crate::Search), the running engine's
+/// [`preferred_fetcher`](crate::Engine::preferred_fetcher) hint can skip
+/// this: with [`FetcherHint::Http`] fetches never fall back, and with
+/// [`FetcherHint::Browser`] plain GETs go straight to the browser.
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use a3s_search::{BrowserFetcher, BrowserPool, BrowserPoolConfig, HttpFetcher, HybridFetcher};
+/// use a3s_search::engines::Brave;
+///
+/// let pool = Arc::new(BrowserPool::new(BrowserPoolConfig::default()));
+/// let fetcher = HybridFetcher::new(
+///     Arc::new(HttpFetcher::new()),
+///     Arc::new(BrowserFetcher::new(pool)),
+/// );
+/// let engine = Brave::with_fetcher(Arc::new(fetcher));
+/// ```
+pub struct HybridFetcher {
+    http: Arc<dyn PageFetcher>,
+    browser: Arc<dyn PageFetcher>,
+    page_signatures: Vec<PageSignature>,
+    error_signatures: Vec<ErrorSignature>,
+    served_http: AtomicU64,
+    served_browser: AtomicU64,
+    failed: AtomicU64,
+}
+
+impl HybridFetcher {
+    /// Creates a fetcher that tries `http` first and falls back to
+    /// `browser`, with the default block signatures.
+    pub fn new(http: Arc<dyn PageFetcher>, browser: Arc<dyn PageFetcher>) -> Self {
+        Self {
+            http,
+            browser,
+            page_signatures: vec![Box::new(|html: &str| {
+                DEFAULT_BLOCK_MARKERS
+                    .iter()
+                    .any(|marker| html.contains(marker))
+            })],
+            error_signatures: vec![Box::new(is_block_error)],
+            served_http: AtomicU64::new(0),
+            served_browser: AtomicU64::new(0),
+            failed: AtomicU64::new(0),
+        }
+    }
+
+    /// Also falls back when an HTTP page matches `signature`.
+    pub fn with_block_signature<F>(mut self, signature: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.page_signatures.push(Box::new(signature));
+        self
+    }
+
+    /// Also falls back when the HTTP fetch fails with an error matching
+    /// `signature`.
+    pub fn with_error_signature<F>(mut self, signature: F) -> Self
+    where
+        F: Fn(&SearchError) -> bool + Send + Sync + 'static,
+    {
+        self.error_signatures.push(Box::new(signature));
+        self
+    }
+
+    /// Drops the default signatures, keeping only those added afterwards.
+    pub fn without_default_signatures(mut self) -> Self {
+        self.page_signatures.clear();
+        self.error_signatures.clear();
+        self
+    }
+
+    /// Returns how many fetches each path served so far.
+    pub fn stats(&self) -> HybridStats {
+        HybridStats {
+            http: self.served_http.load(Ordering::Relaxed),
+            browser: self.served_browser.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Returns whether an HTTP `result` should be retried in the browser.
+    fn is_blocked<T>(&self, result: &Result<T>, html: impl Fn(&T) -> &str) -> bool {
+        match result {
+            Ok(page) => {
+                let html = html(page);
+                self.page_signatures.iter().any(|matches| matches(html))
+            }
+            Err(e) => self.error_signatures.iter().any(|matches| matches(e)),
+        }
+    }
+
+    /// Counts the outcome of a fetch served by `browser` or over HTTP.
+    fn record<T>(&self, result: Result<T>, browser: bool) -> Result<T> {
+        let counter = match (&result, browser) {
+            (Err(_), _) => &self.failed,
+            (Ok(_), false) => &self.served_http,
+            (Ok(_), true) => &self.served_browser,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        result
+    }
+}
+
+/// Returns whether `err` means the site refused to serve the request.
+fn is_block_error(err: &SearchError) -> bool {
+    match err {
+        SearchError::Blocked(_) | SearchError::RateLimited(_) | SearchError::Captcha(_) => true,
+        SearchError::HttpStatus(status, _) => matches!(status, 403 | 429 | 503),
+        _ => false,
+    }
+}
+
+/// Describes the HTTP outcome that triggered a fallback, for logging.
+fn describe<T>(result: &Result<T>) -> String {
+    match result {
+        Ok(_) => "a block page".to_string(),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[async_trait]
+impl PageFetcher for HybridFetcher {
+    async fn fetch(&self, url: &str) -> Result<String> {
+        let hint = current_fetcher_hint();
+        if hint == FetcherHint::Browser {
+            return self.record(self.browser.fetch(url).await, true);
+        }
+        let result = self.http.fetch(url).await;
+        if hint == FetcherHint::Http || !self.is_blocked(&result, |html| html.as_str()) {
+            return self.record(result, false);
+        }
+        debug!(
+            "HTTP fetch of {} got {}, retrying in the browser",
+            url,
+            describe(&result)
+        );
+        self.record(self.browser.fetch(url).await, true)
+    }
+
+    async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        let hint = current_fetcher_hint();
+        if hint == FetcherHint::Browser && request.is_plain_get() {
+            return self.record(self.browser.fetch_request(request).await, true);
+        }
+        let result = self.http.fetch_request(request).await;
+        if hint == FetcherHint::Http
+            || !request.is_plain_get()
+            || !self.is_blocked(&result, |response| &response.body)
+        {
+            return self.record(result, false);
+        }
+        debug!(
+            "HTTP fetch of {} got {}, retrying in the browser",
+            request.url,
+            describe(&result)
+        );
+        self.record(self.browser.fetch_request(request).await, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Search;
+    use std::sync::Mutex;
+
+    /// Serves `page` for every URL, or fails with `error`, and records the
+    /// URLs it was asked for.
+    struct MockFetcher {
+        page: Option<String>,
+        error: Option<SearchError>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl MockFetcher {
+        fn page(html: &str) -> Arc<Self> {
+            Arc::new(Self {
+                page: Some(html.to_string()),
+                error: None,
+                calls: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn failing(error: SearchError) -> Arc<Self> {
+            Arc::new(Self {
+                page: None,
+                error: Some(error),
+                calls: Mutex::new(Vec::new()),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.lock().unwrap().len()
+        }
+    }
+
+    #[async_trait]
+    impl PageFetcher for MockFetcher {
+        async fn fetch(&self, url: &str) -> Result<String> {
+            self.calls.lock().unwrap().push(url.to_string());
+            match self.error {
+                Some(ref e) => Err(e.duplicate()),
+                None => Ok(self.page.clone().unwrap_or_default()),
+            }
+        }
+    }
+
+    fn hybrid(http: &Arc<MockFetcher>, browser: &Arc<MockFetcher>) -> HybridFetcher {
+        HybridFetcher::new(http.clone(), browser.clone())
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_fetcher_serves_over_http() {
+        let http = MockFetcher::page("<html>results</html>");
+        let browser = MockFetcher::page("<html>rendered</html>");
+        let fetcher = hybrid(&http, &browser);
+
+        let html = fetcher.fetch("https://a.example/").await.unwrap();
+
+        assert_eq!(html, "<html>results</html>");
+        assert_eq!(browser.calls(), 0);
+        assert_eq!(
+            fetcher.stats(),
+            HybridStats {
+                http: 1,
+                browser: 0,
+                failed: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_fetcher_falls_back_on_block_page() {
+        let http = MockFetcher::page("<html><title>Just a moment...</title></html>");
+        let browser = MockFetcher::page("<html>rendered</html>");
+        let fetcher = hybrid(&http, &browser);
+
+        let html = fetcher.fetch("https://a.example/").await.unwrap();
+
+        assert_eq!(html, "<html>rendered</html>");
+        assert_eq!(http.calls(), 1);
+        assert_eq!(browser.calls(), 1);
+        assert_eq!(fetcher.stats().browser, 1);
+        assert_eq!(fetcher.stats().http, 0);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_fetcher_falls_back_on_block_error() {
+        for error in [
+            SearchError::Blocked("blocked".to_string()),
+            SearchError::RateLimited(None),
+            SearchError::HttpStatus(403, String::new()),
+        ] {
+            let http = MockFetcher::failing(error);
+            let browser = MockFetcher::page("<html>rendered</html>");
+            let fetcher = hybrid(&http, &browser);
+
+            assert_eq!(
+                fetcher.fetch("https://a.example/").await.unwrap(),
+                "<html>rendered</html>"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_fetcher_keeps_other_errors() {
+        let http = MockFetcher::failing(SearchError::HttpStatus(404, String::new()));
+        let browser = MockFetcher::page("<html>rendered</html>");
+        let fetcher = hybrid(&http, &browser);
+
+        let err = fetcher.fetch("https://a.example/").await.unwrap_err();
+
+        assert!(matches!(err, SearchError::HttpStatus(404, _)), "{:?}", err);
+        assert_eq!(browser.calls(), 0);
+        assert_eq!(fetcher.stats().failed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_fetcher_both_fail() {
+        let http = MockFetcher::failing(SearchError::Blocked("http".to_string()));
+        let browser = MockFetcher::failing(SearchError::Browser("browser".to_string()));
+        let fetcher = hybrid(&http, &browser);
+
+        let err = fetcher.fetch("https://a.example/").await.unwrap_err();
+
+        // The browser's error is the one reported
+        assert!(matches!(err, SearchError::Browser(_)), "{:?}", err);
+        assert_eq!(http.calls(), 1);
+        assert_eq!(browser.calls(), 1);
+        assert_eq!(
+            fetcher.stats(),
+            HybridStats {
+                http: 0,
+                browser: 0,
+                failed: 1
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_fetcher_custom_signatures() {
+        let http = MockFetcher::page("<html>Verify you are human</html>");
+        let browser = MockFetcher::page("<html>rendered</html>");
+
+        // Not a default marker
+        let fetcher = hybrid(&http, &browser);
+        assert_eq!(
+            fetcher.fetch("https://a.example/").await.unwrap(),
+            "<html>Verify you are human</html>"
+        );
+
+        let fetcher = hybrid(&http, &browser)
+            .with_block_signature(|html| html.contains("Verify you are human"));
+        assert_eq!(
+            fetcher.fetch("https://a.example/").await.unwrap(),
+            "<html>rendered</html>"
+        );
+
+        // Without the defaults a block error is returned as is
+        let http = MockFetcher::failing(SearchError::Blocked("blocked".to_string()));
+        let fetcher = hybrid(&http, &browser).without_default_signatures();
+        assert!(fetcher.fetch("https://a.example/").await.is_err());
+
+        let fetcher = hybrid(&http, &browser)
+            .without_default_signatures()
+            .with_error_signature(|e| matches!(e, SearchError::Blocked(_)));
+        assert!(fetcher.fetch("https://a.example/").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_fetcher_request_falls_back_only_for_plain_get() {
+        let http = MockFetcher::page("<div class=\"anomaly-modal\"></div>");
+        let browser = MockFetcher::page("<html>rendered</html>");
+        let fetcher = hybrid(&http, &browser);
+
+        let response = fetcher
+            .fetch_request(&FetchRequest::get("https://a.example/"))
+            .await
+            .unwrap();
+        assert_eq!(response.body, "<html>rendered</html>");
+
+        // The mock serves POSTs through the default implementation, which
+        // rejects them; the HTTP error is returned without a retry
+        let post = FetchRequest::post_form("https://a.example/", [("q", "rust")]);
+        assert!(fetcher.fetch_request(&post).await.is_err());
+        assert_eq!(browser.calls(), 1);
+    }
+
+    /// Fetches one page through a shared fetcher and prefers the fetcher
+    /// named by the query text.
+    struct HintEngine {
+        config: crate::EngineConfig,
+        fetcher: Arc<HybridFetcher>,
+    }
+
+    #[async_trait]
+    impl crate::Engine for HintEngine {
+        fn config(&self) -> &crate::EngineConfig {
+            &self.config
+        }
+
+        async fn search(&self, _query: &crate::SearchQuery) -> Result<Vec<crate::SearchResult>> {
+            self.fetcher.fetch("https://a.example/").await?;
+            Ok(Vec::new())
+        }
+
+        fn preferred_fetcher(&self, query: &crate::SearchQuery) -> FetcherHint {
+            match query.query.as_str() {
+                "http" => FetcherHint::Http,
+                "browser" => FetcherHint::Browser,
+                _ => FetcherHint::Auto,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_fetcher_follows_engine_hint() {
+        let http = MockFetcher::page("<html><title>Just a moment...</title></html>");
+        let browser = MockFetcher::page("<html>rendered</html>");
+        let fetcher = Arc::new(hybrid(&http, &browser));
+        let mut search = Search::new();
+        search.add_engine(HintEngine {
+            config: crate::EngineConfig {
+                name: "hint".to_string(),
+                shortcut: "hint".to_string(),
+                ..Default::default()
+            },
+            fetcher: fetcher.clone(),
+        });
+
+        // HTTP only: the block page is served without a fallback
+        search
+            .search(crate::SearchQuery::new("http"))
+            .await
+            .unwrap();
+        assert_eq!((http.calls(), browser.calls()), (1, 0));
+
+        // Browser: HTTP is skipped
+        search
+            .search(crate::SearchQuery::new("browser"))
+            .await
+            .unwrap();
+        assert_eq!((http.calls(), browser.calls()), (1, 1));
+
+        // Auto: HTTP, then the browser on the block page
+        search
+            .search(crate::SearchQuery::new("auto"))
+            .await
+            .unwrap();
+        assert_eq!((http.calls(), browser.calls()), (2, 2));
+
+        assert_eq!(
+            fetcher.stats(),
+            HybridStats {
+                http: 1,
+                browser: 2,
+                failed: 0
+            }
+        );
+        // Outside a search the hint is Auto
+        assert_eq!(Search::current_fetcher_hint(), FetcherHint::Auto);
+    }
+}
@@ -0,0 +1,249 @@
+//! Page fetcher that rotates requests through a proxy pool.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::fetcher::{FetchRequest, FetchResponse, PageFetcher};
+use crate::fetcher_http::{HttpFetcher, HttpFetcherBuilder};
+use crate::proxy::{ProxyConfig, ProxyPool};
+use crate::{Result, SearchError};
+
+/// An HTTP page fetcher that sends each request through the next proxy of a
+/// [`ProxyPool`].
+///
+/// One client is built per proxy and reused for later requests through it.
+/// Outcomes are reported back to the pool so failing proxies get
+/// quarantined. When the pool is disabled or empty, requests go out
+/// directly.
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use a3s_search::PooledHttpFetcher;
+/// use a3s_search::engines::DuckDuckGo;
+/// use a3s_search::proxy::{ProxyConfig, ProxyPool};
+///
+/// let pool = Arc::new(ProxyPool::with_proxies(vec![
+///     ProxyConfig::new("10.0.0.1", 8080),
+///     ProxyConfig::new("10.0.0.2", 8080),
+/// ]));
+/// let engine = DuckDuckGo::with_fetcher(Arc::new(PooledHttpFetcher::new(pool)));
+/// ```
+pub struct PooledHttpFetcher {
+    pool: Arc<ProxyPool>,
+    /// Settings shared by every per-proxy client.
+    template: HttpFetcherBuilder,
+    /// Client for requests made without a proxy.
+    direct: Arc<HttpFetcher>,
+    /// Per-proxy clients, keyed by proxy URL.
+    clients: Mutex<HashMap<String, Arc<HttpFetcher>>>,
+}
+
+impl PooledHttpFetcher {
+    /// Creates a pooled fetcher with default HTTP settings.
+    pub fn new(pool: Arc<ProxyPool>) -> Self {
+        Self::with_builder(pool, HttpFetcher::builder()).expect("Failed to create HTTP client")
+    }
+
+    /// Creates a pooled fetcher whose clients use the settings of `builder`.
+    ///
+    /// The builder's own proxy, if any, is replaced by the pool's proxy for
+    /// each request and only used for direct requests.
+    pub fn with_builder(pool: Arc<ProxyPool>, builder: HttpFetcherBuilder) -> Result<Self> {
+        let direct = Arc::new(builder.clone().build()?);
+        Ok(Self {
+            pool,
+            template: builder,
+            direct,
+            clients: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Returns the proxy pool requests are rotated through.
+    pub fn pool(&self) -> &Arc<ProxyPool> {
+        &self.pool
+    }
+
+    /// Picks the next proxy and the client bound to it.
+    async fn select(&self) -> Result<(Option<ProxyConfig>, Arc<HttpFetcher>)> {
+        let proxy = match self.pool.get_proxy().await {
+            Some(proxy) => proxy,
+            None => return Ok((None, self.direct.clone())),
+        };
+
+        let url = proxy.url();
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let fetcher = match clients.get(&url) {
+            Some(fetcher) => fetcher.clone(),
+            None => {
+                let fetcher = Arc::new(self.template.clone().proxy(url.clone()).build()?);
+                clients.insert(url, fetcher.clone());
+                fetcher
+            }
+        };
+        Ok((Some(proxy), fetcher))
+    }
+
+    /// Reports the outcome of a request through `proxy` back to the pool.
+    fn report<T>(&self, proxy: Option<&ProxyConfig>, result: &Result<T>) {
+        let Some(proxy) = proxy else {
+            return;
+        };
+        match result {
+            Ok(_) => self.pool.report_success(proxy),
+            Err(e) if is_proxy_failure(e) => self.pool.report_failure(proxy),
+            // Errors such as a 404 or a parse failure say nothing about the proxy
+            Err(_) => {}
+        }
+    }
+}
+
+/// Returns true for errors that suggest the proxy itself is bad: connection
+/// failures and timeouts, or the target blocking or throttling its IP.
+fn is_proxy_failure(error: &SearchError) -> bool {
+    matches!(
+        error,
+        SearchError::Http(_) | SearchError::Blocked(_) | SearchError::RateLimited(_)
+    )
+}
+
+#[async_trait]
+impl PageFetcher for PooledHttpFetcher {
+    async fn fetch(&self, url: &str) -> Result<String> {
+        let (proxy, fetcher) = self.select().await?;
+        let result = fetcher.fetch(url).await;
+        self.report(proxy.as_ref(), &result);
+        result
+    }
+
+    async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        let (proxy, fetcher) = self.select().await?;
+        let result = fetcher.fetch_request(request).await;
+        self.report(proxy.as_ref(), &result);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use tokio::net::TcpListener;
+
+    use crate::test_server::{spawn_server, Response};
+
+    /// Spawns a fake HTTP proxy that answers every request with `name`.
+    async fn spawn_proxy(name: &'static str) -> (u16, tokio::task::JoinHandle<()>) {
+        let (addr, handle) = spawn_server(move |_| async move { Response::ok(name) }).await;
+        (addr.port(), handle)
+    }
+
+    /// Returns a local port with nothing listening on it.
+    async fn dead_port() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    fn pooled(pool: ProxyPool) -> PooledHttpFetcher {
+        let builder = HttpFetcher::builder().timeout(Duration::from_secs(2));
+        PooledHttpFetcher::with_builder(Arc::new(pool), builder).unwrap()
+    }
+
+    // The target host never resolves; only the proxies answer
+    const TARGET: &str = "http://search.invalid/";
+
+    #[tokio::test]
+    async fn test_pooled_fetcher_rotates_proxies() {
+        let (port_a, a) = spawn_proxy("proxy-a").await;
+        let (port_b, b) = spawn_proxy("proxy-b").await;
+        let fetcher = pooled(ProxyPool::with_proxies(vec![
+            ProxyConfig::new("127.0.0.1", port_a),
+            ProxyConfig::new("127.0.0.1", port_b),
+        ]));
+
+        let mut bodies = Vec::new();
+        for _ in 0..4 {
+            bodies.push(fetcher.fetch(TARGET).await.unwrap());
+        }
+
+        assert_eq!(bodies, vec!["proxy-a", "proxy-b", "proxy-a", "proxy-b"]);
+        // One client per proxy, reused across requests
+        assert_eq!(fetcher.clients.lock().unwrap().len(), 2);
+        a.abort();
+        b.abort();
+    }
+
+    #[tokio::test]
+    async fn test_pooled_fetcher_reports_failures() {
+        let dead = dead_port().await;
+        let (live, server) = spawn_proxy("live").await;
+        let fetcher = pooled(
+            ProxyPool::with_proxies(vec![
+                ProxyConfig::new("127.0.0.1", dead),
+                ProxyConfig::new("127.0.0.1", live),
+            ])
+            .with_quarantine(1, Duration::from_secs(60)),
+        );
+
+        let first = fetcher.fetch(TARGET).await;
+        assert!(matches!(first, Err(SearchError::Http(_))));
+        assert!(fetcher
+            .pool()
+            .is_quarantined(&ProxyConfig::new("127.0.0.1", dead)));
+
+        // The quarantined proxy is skipped from now on
+        for _ in 0..3 {
+            assert_eq!(fetcher.fetch(TARGET).await.unwrap(), "live");
+        }
+        assert!(!fetcher
+            .pool()
+            .is_quarantined(&ProxyConfig::new("127.0.0.1", live)));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_pooled_fetcher_fetch_request_uses_proxy() {
+        let (port, server) = spawn_proxy("proxied").await;
+        let fetcher = pooled(ProxyPool::with_proxies(vec![ProxyConfig::new(
+            "127.0.0.1",
+            port,
+        )]));
+
+        let response = fetcher
+            .fetch_request(&FetchRequest::post_form(TARGET, [("q", "rust")]))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, "proxied");
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_pooled_fetcher_direct_when_pool_empty() {
+        let (port, server) = spawn_proxy("direct").await;
+        let fetcher = pooled(ProxyPool::new());
+
+        let body = fetcher
+            .fetch(&format!("http://127.0.0.1:{}/", port))
+            .await
+            .unwrap();
+
+        assert_eq!(body, "direct");
+        assert!(fetcher.clients.lock().unwrap().is_empty());
+        server.abort();
+    }
+
+    #[test]
+    fn test_is_proxy_failure() {
+        assert!(is_proxy_failure(&SearchError::Blocked(String::new())));
+        assert!(is_proxy_failure(&SearchError::RateLimited(None)));
+        assert!(!is_proxy_failure(&SearchError::HttpStatus(
+            404,
+            String::new()
+        )));
+        assert!(!is_proxy_failure(&SearchError::Parse(String::new())));
+    }
+}
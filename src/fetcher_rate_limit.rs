@@ -0,0 +1,330 @@
+//! Per-host rate-limiting page fetcher decorator.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::fetcher::{FetchRequest, FetchResponse, PageFetcher};
+use crate::Result;
+
+/// A request rate: at most `requests` per `per` interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rate {
+    /// Requests allowed per interval (also the burst size).
+    pub requests: u32,
+    /// Length of the interval.
+    pub per: Duration,
+}
+
+impl Rate {
+    /// Creates a rate of `requests` per `per`. Zero requests is treated as one.
+    pub fn new(requests: u32, per: Duration) -> Self {
+        Self {
+            requests: requests.max(1),
+            per,
+        }
+    }
+
+    /// Creates a rate of `requests` per second.
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(requests, Duration::from_secs(1))
+    }
+
+    /// Time needed to earn one token.
+    fn interval(&self) -> Duration {
+        self.per / self.requests.max(1)
+    }
+}
+
+/// Token bucket for a single host.
+///
+/// Tokens may go negative: each request reserves a token immediately and
+/// waits until its reservation is covered, so concurrent requests to the
+/// same host are spaced out in arrival order.
+struct Bucket {
+    rate: Rate,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn new(rate: Rate, now: Instant) -> Self {
+        Self {
+            rate,
+            tokens: rate.requests.max(1) as f64,
+            updated_at: now,
+        }
+    }
+
+    /// Reserves one token and returns how long to wait before using it.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        let interval = self.rate.interval().as_secs_f64();
+        if interval == 0.0 {
+            return Duration::ZERO;
+        }
+
+        let earned = now.duration_since(self.updated_at).as_secs_f64() / interval;
+        self.tokens = (self.tokens + earned).min(self.rate.requests.max(1) as f64);
+        self.updated_at = now;
+        self.tokens -= 1.0;
+
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens * interval)
+        }
+    }
+}
+
+/// Rate configuration and bucket state shared by all clones.
+struct RateLimitState {
+    default_rate: Option<Rate>,
+    host_rates: HashMap<String, Rate>,
+    buckets: HashMap<String, Bucket>,
+}
+
+/// A page fetcher that paces requests per host with a token bucket.
+///
+/// Requests to a host wait until its budget allows them; requests to other
+/// hosts are never delayed. Clones share their budgets, so engines that hit
+/// the same host through clones of one `RateLimitedFetcher` stay within a
+/// single limit.
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use std::time::Duration;
+/// use a3s_search::{HttpFetcher, Rate, RateLimitedFetcher};
+///
+/// let fetcher = RateLimitedFetcher::new(Arc::new(HttpFetcher::new()), None);
+/// // Nominatim's usage policy allows one request per second
+/// fetcher.set_host_rate("nominatim.openstreetmap.org", 1, Duration::from_secs(1));
+/// ```
+#[derive(Clone)]
+pub struct RateLimitedFetcher {
+    inner: Arc<dyn PageFetcher>,
+    state: Arc<Mutex<RateLimitState>>,
+}
+
+impl RateLimitedFetcher {
+    /// Creates a rate-limited fetcher around `inner`.
+    ///
+    /// `default_rate` applies to every host without its own rate; `None`
+    /// leaves such hosts unlimited.
+    pub fn new(inner: Arc<dyn PageFetcher>, default_rate: Option<Rate>) -> Self {
+        Self {
+            inner,
+            state: Arc::new(Mutex::new(RateLimitState {
+                default_rate,
+                host_rates: HashMap::new(),
+                buckets: HashMap::new(),
+            })),
+        }
+    }
+
+    /// Limits `host` to `requests` per `per`, replacing any previous rate.
+    pub fn set_host_rate(&self, host: impl Into<String>, requests: u32, per: Duration) {
+        let host = host.into().to_lowercase();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.buckets.remove(&host);
+        state.host_rates.insert(host, Rate::new(requests, per));
+    }
+
+    /// Returns the rate applied to `host`, if any.
+    pub fn host_rate(&self, host: &str) -> Option<Rate> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .host_rates
+            .get(&host.to_lowercase())
+            .copied()
+            .or(state.default_rate)
+    }
+
+    /// Waits until the host of `url` has budget for one more request.
+    async fn wait_for(&self, url: &str) {
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+            .unwrap_or_default();
+
+        let wait = self.reserve(&host);
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Reserves a slot for `host` and returns how long to wait for it.
+    fn reserve(&self, host: &str) -> Duration {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let rate = match state.host_rates.get(host).copied().or(state.default_rate) {
+            Some(rate) => rate,
+            None => return Duration::ZERO,
+        };
+
+        let now = Instant::now();
+        state
+            .buckets
+            .entry(host.to_string())
+            .or_insert_with(|| Bucket::new(rate, now))
+            .reserve(now)
+    }
+}
+
+#[async_trait]
+impl PageFetcher for RateLimitedFetcher {
+    async fn fetch(&self, url: &str) -> Result<String> {
+        self.wait_for(url).await;
+        self.inner.fetch(url).await
+    }
+
+    async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        self.wait_for(&request.url).await;
+        self.inner.fetch_request(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records the (paused) time of every fetch, relative to creation.
+    struct RecordingFetcher {
+        start: Instant,
+        calls: Mutex<Vec<(String, Duration)>>,
+    }
+
+    impl RecordingFetcher {
+        fn new() -> Arc<Self> {
+            Arc::new(Self {
+                start: Instant::now(),
+                calls: Mutex::new(Vec::new()),
+            })
+        }
+
+        /// Returns call offsets in milliseconds for URLs containing `host`.
+        fn offsets_ms(&self, host: &str) -> Vec<u128> {
+            self.calls
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(url, _)| url.contains(host))
+                .map(|(_, at)| at.as_millis())
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl PageFetcher for RecordingFetcher {
+        async fn fetch(&self, url: &str) -> Result<String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((url.to_string(), self.start.elapsed()));
+            Ok(String::new())
+        }
+    }
+
+    #[test]
+    fn test_rate_new() {
+        let rate = Rate::new(2, Duration::from_secs(1));
+        assert_eq!(rate.requests, 2);
+        assert_eq!(rate.interval(), Duration::from_millis(500));
+        assert_eq!(Rate::new(0, Duration::from_secs(1)).requests, 1);
+        assert_eq!(Rate::per_second(5), Rate::new(5, Duration::from_secs(1)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limited_fetcher_spaces_same_host() {
+        let inner = RecordingFetcher::new();
+        let fetcher = RateLimitedFetcher::new(inner.clone(), None);
+        fetcher.set_host_rate("nominatim.example", 1, Duration::from_secs(1));
+
+        for _ in 0..3 {
+            fetcher
+                .fetch("https://nominatim.example/search?q=x")
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(inner.offsets_ms("nominatim"), vec![0, 1000, 2000]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limited_fetcher_allows_burst() {
+        let inner = RecordingFetcher::new();
+        let fetcher = RateLimitedFetcher::new(inner.clone(), Some(Rate::per_second(2)));
+
+        for _ in 0..3 {
+            fetcher.fetch("https://api.example/").await.unwrap();
+        }
+
+        // Two requests fit in the bucket, the third waits for a refill
+        assert_eq!(inner.offsets_ms("api.example"), vec![0, 0, 500]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limited_fetcher_other_hosts_not_blocked() {
+        let inner = RecordingFetcher::new();
+        let fetcher = RateLimitedFetcher::new(inner.clone(), None);
+        fetcher.set_host_rate("slow.example", 1, Duration::from_secs(10));
+
+        fetcher.fetch("https://slow.example/a").await.unwrap();
+        let slow = {
+            let fetcher = fetcher.clone();
+            tokio::spawn(async move { fetcher.fetch("https://slow.example/b").await })
+        };
+        fetcher.fetch("https://fast.example/a").await.unwrap();
+        fetcher.fetch("https://fast.example/b").await.unwrap();
+        slow.await.unwrap().unwrap();
+
+        assert_eq!(inner.offsets_ms("fast.example"), vec![0, 0]);
+        assert_eq!(inner.offsets_ms("slow.example"), vec![0, 10_000]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limited_fetcher_clones_share_budget() {
+        let inner = RecordingFetcher::new();
+        let fetcher = RateLimitedFetcher::new(inner.clone(), None);
+        fetcher.set_host_rate("shared.example", 1, Duration::from_secs(1));
+        let engine_a = fetcher.clone();
+        let engine_b = fetcher.clone();
+
+        let a = tokio::spawn(async move { engine_a.fetch("https://shared.example/a").await });
+        let b = tokio::spawn(async move { engine_b.fetch("https://shared.example/b").await });
+        a.await.unwrap().unwrap();
+        b.await.unwrap().unwrap();
+
+        assert_eq!(inner.offsets_ms("shared.example"), vec![0, 1000]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_rate_limited_fetcher_refills_after_idle() {
+        let inner = RecordingFetcher::new();
+        let fetcher = RateLimitedFetcher::new(inner.clone(), Some(Rate::per_second(1)));
+
+        fetcher.fetch("https://idle.example/").await.unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        fetcher.fetch("https://idle.example/").await.unwrap();
+        fetcher.fetch("https://idle.example/").await.unwrap();
+
+        // Idle time refills at most one token (the burst size)
+        assert_eq!(inner.offsets_ms("idle.example"), vec![0, 5000, 6000]);
+    }
+
+    #[test]
+    fn test_rate_limited_fetcher_host_rate() {
+        let fetcher = RateLimitedFetcher::new(RecordingFetcher::new(), Some(Rate::per_second(10)));
+        fetcher.set_host_rate("MusicBrainz.org", 1, Duration::from_secs(1));
+
+        assert_eq!(
+            fetcher.host_rate("musicbrainz.org"),
+            Some(Rate::per_second(1))
+        );
+        assert_eq!(fetcher.host_rate("other.org"), Some(Rate::per_second(10)));
+
+        let unlimited = RateLimitedFetcher::new(RecordingFetcher::new(), None);
+        assert_eq!(unlimited.host_rate("other.org"), None);
+    }
+}
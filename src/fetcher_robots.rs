@@ -0,0 +1,618 @@
+//! robots.txt-respecting page fetcher decorator.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tracing::debug;
+
+use crate::fetcher::{FetchRequest, FetchResponse, PageFetcher};
+use crate::{Result, SearchError};
+
+/// User-agent token matched against robots.txt groups by default.
+pub const DEFAULT_ROBOTS_USER_AGENT: &str = "a3s-search";
+
+/// How long a host's robots.txt is cached.
+const ROBOTS_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long an unreachable robots.txt disallows its host before it is
+/// fetched again.
+const ROBOTS_FAILURE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Longest `Crawl-delay` honoured; larger values are clamped to it.
+const MAX_CRAWL_DELAY: Duration = Duration::from_secs(60);
+
+/// A single `Allow` or `Disallow` rule.
+#[derive(Debug, Clone, PartialEq)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+impl Rule {
+    /// Returns true if the rule's path pattern matches `path`.
+    ///
+    /// Supports the `*` wildcard and the `$` end anchor.
+    fn matches(&self, path: &str) -> bool {
+        let (pattern, anchored) = match self.pattern.strip_suffix('$') {
+            Some(pattern) => (pattern, true),
+            None => (self.pattern.as_str(), false),
+        };
+
+        let mut parts = pattern.split('*');
+        let first = parts.next().unwrap_or_default();
+        let Some(mut rest) = path.strip_prefix(first) else {
+            return false;
+        };
+        let parts: Vec<&str> = parts.collect();
+        for (i, part) in parts.iter().enumerate() {
+            if anchored && i == parts.len() - 1 {
+                return rest.ends_with(part);
+            }
+            match rest.find(part) {
+                Some(at) => rest = &rest[at + part.len()..],
+                None => return false,
+            }
+        }
+        !anchored || rest.is_empty()
+    }
+}
+
+/// The rules of a robots.txt that apply to one user agent.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct RobotsRules {
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+impl RobotsRules {
+    /// Rules that disallow every path, used when robots.txt is unreachable.
+    fn disallow_all() -> Self {
+        Self {
+            rules: vec![Rule {
+                allow: false,
+                pattern: "/".to_string(),
+            }],
+            crawl_delay: None,
+        }
+    }
+
+    /// Parses robots.txt, keeping the groups naming one of the product
+    /// tokens of `user_agent` or, failing that, the `*` group.
+    fn parse(body: &str, user_agent: &str) -> Self {
+        let tokens = product_tokens(user_agent);
+        let mut specific: Option<RobotsRules> = None;
+        let mut wildcard: Option<RobotsRules> = None;
+
+        // Agents of the group being read, and whether its rules have started
+        let mut agents: Vec<String> = Vec::new();
+        let mut in_rules = false;
+        let mut current = RobotsRules::default();
+
+        let mut finish = |agents: &[String], group: RobotsRules| {
+            if agents.iter().any(|a| a != "*" && tokens.contains(a)) {
+                specific.get_or_insert_with(Default::default).extend(group);
+            } else if agents.iter().any(|a| a == "*") {
+                wildcard.get_or_insert_with(Default::default).extend(group);
+            }
+        };
+
+        for line in body.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let key = key.trim().to_lowercase();
+            let value = value.trim();
+
+            match key.as_str() {
+                "user-agent" => {
+                    if in_rules {
+                        finish(&agents, std::mem::take(&mut current));
+                        agents.clear();
+                        in_rules = false;
+                    }
+                    // `Googlebot/2.1` names the `googlebot` product token
+                    let agent = value.split('/').next().unwrap_or_default().trim();
+                    agents.push(agent.to_lowercase());
+                }
+                "allow" | "disallow" => {
+                    in_rules = true;
+                    // An empty Disallow allows everything
+                    if !value.is_empty() {
+                        current.rules.push(Rule {
+                            allow: key == "allow",
+                            pattern: value.to_string(),
+                        });
+                    }
+                }
+                "crawl-delay" => {
+                    in_rules = true;
+                    current.crawl_delay = value
+                        .parse::<f64>()
+                        .ok()
+                        .filter(|secs| *secs >= 0.0)
+                        .map(|secs| secs.min(MAX_CRAWL_DELAY.as_secs_f64()))
+                        .map(Duration::from_secs_f64);
+                }
+                _ => {}
+            }
+        }
+        finish(&agents, current);
+
+        specific.or(wildcard).unwrap_or_default()
+    }
+
+    fn extend(&mut self, other: RobotsRules) {
+        self.rules.extend(other.rules);
+        self.crawl_delay = self.crawl_delay.or(other.crawl_delay);
+    }
+
+    /// Returns whether `path` (including any query string) may be fetched.
+    ///
+    /// The longest matching rule wins; `Allow` wins ties.
+    fn is_allowed(&self, path: &str) -> bool {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(path))
+            .max_by_key(|rule| (rule.pattern.len(), rule.allow))
+            .is_none_or(|rule| rule.allow)
+    }
+}
+
+/// Cached robots.txt for one host.
+struct HostRobots {
+    rules: RobotsRules,
+    expires_at: Instant,
+    /// When the last page request to the host was let through.
+    last_request: Option<Instant>,
+}
+
+/// A page fetcher that honours robots.txt.
+///
+/// Before the first request to a host, its `/robots.txt` is fetched through
+/// the inner fetcher and cached for a day; concurrent first requests share
+/// one fetch. Requests to disallowed paths fail with
+/// [`SearchError::DisallowedByRobots`] without being sent, and a
+/// `Crawl-delay` spaces out requests to the same host. A missing robots.txt
+/// (any 4xx) allows everything; an unreachable one (a timeout or a 5xx)
+/// disallows everything for five minutes, then is fetched again.
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use a3s_search::{HttpFetcher, RobotsFetcher};
+/// use a3s_search::engines::DuckDuckGo;
+///
+/// let fetcher = RobotsFetcher::new(Arc::new(HttpFetcher::new()));
+/// let engine = DuckDuckGo::with_fetcher(Arc::new(fetcher));
+/// ```
+pub struct RobotsFetcher {
+    inner: Arc<dyn PageFetcher>,
+    user_agent: String,
+    hosts: Mutex<HashMap<String, HostRobots>>,
+    /// Per-origin locks held while a robots.txt is being fetched.
+    refreshes: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl RobotsFetcher {
+    /// Creates a robots.txt-respecting fetcher around `inner`.
+    pub fn new(inner: Arc<dyn PageFetcher>) -> Self {
+        Self {
+            inner,
+            user_agent: DEFAULT_ROBOTS_USER_AGENT.to_string(),
+            hosts: Mutex::new(HashMap::new()),
+            refreshes: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sets the user agent matched against robots.txt groups (default:
+    /// `a3s-search`).
+    ///
+    /// A group applies when its name equals one of the user agent's product
+    /// tokens, ignoring case: `Mozilla/5.0 (compatible; mybot/1.0)` is
+    /// matched by `mybot` groups, but a `mybot` group does not apply to
+    /// `mybot-images`.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Returns whether robots.txt allows fetching `url`.
+    pub async fn is_allowed(&self, url: &str) -> Result<bool> {
+        let parsed = url::Url::parse(url)?;
+        let Some(origin) = robots_origin(&parsed) else {
+            return Ok(true);
+        };
+        self.ensure_rules(&origin).await;
+
+        let hosts = self.hosts.lock().await;
+        Ok(hosts
+            .get(&origin)
+            .is_none_or(|host| host.rules.is_allowed(&path_of(&parsed))))
+    }
+
+    /// Returns true if the cached robots.txt of `origin` is still fresh.
+    async fn has_fresh_rules(&self, origin: &str) -> bool {
+        let hosts = self.hosts.lock().await;
+        hosts
+            .get(origin)
+            .is_some_and(|host| Instant::now() < host.expires_at)
+    }
+
+    /// Fetches and caches the robots.txt of `origin` if missing or stale.
+    ///
+    /// Concurrent callers for the same origin wait for a single fetch.
+    async fn ensure_rules(&self, origin: &str) {
+        if self.has_fresh_rules(origin).await {
+            return;
+        }
+        let refresh = {
+            let mut refreshes = self.refreshes.lock().await;
+            Arc::clone(refreshes.entry(origin.to_string()).or_default())
+        };
+        let _refreshing = refresh.lock().await;
+        // Another caller may have fetched it while this one waited
+        if self.has_fresh_rules(origin).await {
+            return;
+        }
+
+        let robots_url = format!("{}/robots.txt", origin);
+        let (rules, ttl) = match self.inner.fetch(&robots_url).await {
+            Ok(body) => (RobotsRules::parse(&body, &self.user_agent), ROBOTS_TTL),
+            Err(SearchError::HttpStatus(status, _)) if (400..500).contains(&status) => {
+                (RobotsRules::default(), ROBOTS_TTL)
+            }
+            Err(SearchError::Blocked(_)) | Err(SearchError::RateLimited(_)) => {
+                (RobotsRules::default(), ROBOTS_TTL)
+            }
+            Err(e) => {
+                debug!("robots.txt unreachable at {}: {}", robots_url, e);
+                (RobotsRules::disallow_all(), ROBOTS_FAILURE_TTL)
+            }
+        };
+
+        let mut hosts = self.hosts.lock().await;
+        let last_request = hosts.get(origin).and_then(|host| host.last_request);
+        hosts.insert(
+            origin.to_string(),
+            HostRobots {
+                rules,
+                expires_at: Instant::now() + ttl,
+                last_request,
+            },
+        );
+    }
+
+    /// Checks `url` against robots.txt and waits out any crawl delay.
+    async fn admit(&self, url: &str) -> Result<()> {
+        let parsed = url::Url::parse(url)?;
+        let Some(origin) = robots_origin(&parsed) else {
+            return Ok(());
+        };
+        self.ensure_rules(&origin).await;
+
+        let wait = {
+            let mut hosts = self.hosts.lock().await;
+            let Some(host) = hosts.get_mut(&origin) else {
+                return Ok(());
+            };
+            if !host.rules.is_allowed(&path_of(&parsed)) {
+                return Err(SearchError::DisallowedByRobots(url.to_string()));
+            }
+
+            // Reserve the next slot so concurrent requests queue up in order
+            let now = Instant::now();
+            let slot = match (host.rules.crawl_delay, host.last_request) {
+                (Some(delay), Some(last)) => last.checked_add(delay).map_or(now, |at| at.max(now)),
+                _ => now,
+            };
+            host.last_request = Some(slot);
+            slot - now
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the lowercased product tokens of a user agent string, such as
+/// `mozilla` and `mybot` for `Mozilla/5.0 (compatible; mybot/1.0)`.
+fn product_tokens(user_agent: &str) -> Vec<String> {
+    user_agent
+        .split(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | ';' | ','))
+        .filter_map(|part| part.split('/').next())
+        .filter(|token| !token.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Returns `scheme://host[:port]` for http(s) URLs.
+fn robots_origin(url: &url::Url) -> Option<String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    Some(url.origin().ascii_serialization())
+}
+
+/// Returns the path and query of `url`, as matched by robots.txt rules.
+fn path_of(url: &url::Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+#[async_trait]
+impl PageFetcher for RobotsFetcher {
+    async fn fetch(&self, url: &str) -> Result<String> {
+        self.admit(url).await?;
+        self.inner.fetch(url).await
+    }
+
+    async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        self.admit(&request.url).await?;
+        self.inner.fetch_request(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex as StdMutex;
+
+    /// Serves a fixed robots.txt (or an error for it) and records every URL.
+    struct MockSite {
+        robots: std::result::Result<String, u16>,
+        /// How long answering robots.txt takes.
+        robots_delay: Duration,
+        start: Instant,
+        calls: StdMutex<Vec<(String, Duration)>>,
+    }
+
+    impl MockSite {
+        fn new(robots: std::result::Result<&str, u16>) -> Arc<Self> {
+            Self::slow(robots, Duration::ZERO)
+        }
+
+        fn slow(robots: std::result::Result<&str, u16>, robots_delay: Duration) -> Arc<Self> {
+            Arc::new(Self {
+                robots: robots.map(str::to_string),
+                robots_delay,
+                start: Instant::now(),
+                calls: StdMutex::new(Vec::new()),
+            })
+        }
+
+        fn urls(&self) -> Vec<String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(url, _)| url.clone())
+                .collect()
+        }
+
+        fn robots_fetches(&self) -> usize {
+            self.urls()
+                .iter()
+                .filter(|url| url.ends_with("/robots.txt"))
+                .count()
+        }
+
+        fn page_offsets_ms(&self) -> Vec<u128> {
+            self.calls
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(url, _)| !url.ends_with("/robots.txt"))
+                .map(|(_, at)| at.as_millis())
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl PageFetcher for MockSite {
+        async fn fetch(&self, url: &str) -> Result<String> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push((url.to_string(), self.start.elapsed()));
+            if url.ends_with("/robots.txt") {
+                if !self.robots_delay.is_zero() {
+                    tokio::time::sleep(self.robots_delay).await;
+                }
+                return match self.robots {
+                    Ok(ref body) => Ok(body.clone()),
+                    Err(status) => Err(SearchError::HttpStatus(status, String::new())),
+                };
+            }
+            Ok(format!("page {}", url))
+        }
+    }
+
+    const ROBOTS: &str = "\
+# Example robots.txt
+User-agent: *
+Disallow: /search
+Allow: /search/about
+Disallow: /*.pdf$
+
+User-agent: a3s-search
+User-agent: otherbot
+Disallow: /private/
+Crawl-delay: 2
+";
+
+    #[test]
+    fn test_robots_rules_wildcard_group() {
+        let rules = RobotsRules::parse(ROBOTS, "somebot");
+        assert!(!rules.is_allowed("/search?q=rust"));
+        assert!(rules.is_allowed("/search/about"));
+        assert!(!rules.is_allowed("/files/doc.pdf"));
+        assert!(rules.is_allowed("/files/doc.pdf.html"));
+        assert!(rules.is_allowed("/"));
+        assert_eq!(rules.crawl_delay, None);
+    }
+
+    #[test]
+    fn test_robots_rules_specific_group() {
+        let rules = RobotsRules::parse(ROBOTS, "a3s-search");
+        assert!(rules.is_allowed("/search?q=rust"));
+        assert!(!rules.is_allowed("/private/page"));
+        assert_eq!(rules.crawl_delay, Some(Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_robots_rules_match_whole_product_tokens() {
+        let robots = "User-agent: Googlebot\nDisallow: /\n\nUser-agent: *\nDisallow: /search\n";
+        assert!(!RobotsRules::parse(robots, "googlebot").is_allowed("/"));
+        assert!(
+            !RobotsRules::parse(robots, "Mozilla/5.0 (compatible; Googlebot/2.1)").is_allowed("/")
+        );
+        // Longer agents and substrings fall back to the `*` group
+        assert!(RobotsRules::parse(robots, "Googlebot-Image").is_allowed("/"));
+        assert!(RobotsRules::parse(robots, "bot").is_allowed("/"));
+        assert!(!RobotsRules::parse(robots, "Googlebot-Image").is_allowed("/search"));
+
+        let versioned = "User-agent: a3s-search/1.0\nDisallow: /private\n";
+        assert!(!RobotsRules::parse(versioned, "a3s-search").is_allowed("/private"));
+    }
+
+    #[test]
+    fn test_robots_rules_clamp_crawl_delay() {
+        let delay = |value: &str| {
+            RobotsRules::parse(&format!("User-agent: *\nCrawl-delay: {}\n", value), "bot")
+                .crawl_delay
+        };
+        assert_eq!(delay("1e20"), Some(MAX_CRAWL_DELAY));
+        assert_eq!(delay("0.5"), Some(Duration::from_millis(500)));
+        assert_eq!(delay("-1"), None);
+        assert_eq!(delay("NaN"), None);
+        assert_eq!(delay("inf"), Some(MAX_CRAWL_DELAY));
+    }
+
+    #[test]
+    fn test_robots_rules_empty_disallow_allows_all() {
+        let rules = RobotsRules::parse("User-agent: *\nDisallow:\n", "a3s-search");
+        assert!(rules.is_allowed("/anything"));
+        assert!(RobotsRules::parse("", "a3s-search").is_allowed("/anything"));
+    }
+
+    #[test]
+    fn test_rule_matches() {
+        let rule = |pattern: &str| Rule {
+            allow: false,
+            pattern: pattern.to_string(),
+        };
+        assert!(rule("/a").matches("/a/b"));
+        assert!(!rule("/a").matches("/b"));
+        assert!(rule("/*/edit").matches("/page/edit"));
+        assert!(rule("/*.php$").matches("/index.php"));
+        assert!(!rule("/*.php$").matches("/index.php?x=1"));
+        assert!(rule("/exact$").matches("/exact"));
+        assert!(!rule("/exact$").matches("/exact/more"));
+    }
+
+    #[tokio::test]
+    async fn test_robots_fetcher_skips_disallowed_fetch() {
+        let site = MockSite::new(Ok(ROBOTS));
+        let fetcher = RobotsFetcher::new(site.clone()).with_user_agent("somebot");
+
+        let result = fetcher.fetch("https://site.example/search?q=rust").await;
+        assert!(matches!(result, Err(SearchError::DisallowedByRobots(_))));
+
+        let page = fetcher.fetch("https://site.example/about").await.unwrap();
+        assert_eq!(page, "page https://site.example/about");
+
+        // robots.txt is fetched once and the disallowed page never
+        assert_eq!(
+            site.urls(),
+            vec![
+                "https://site.example/robots.txt",
+                "https://site.example/about"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_robots_fetcher_missing_robots_allows_all() {
+        let site = MockSite::new(Err(404));
+        let fetcher = RobotsFetcher::new(site.clone());
+
+        assert!(fetcher
+            .is_allowed("https://site.example/search")
+            .await
+            .unwrap());
+        fetcher.fetch("https://site.example/search").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_robots_fetcher_unreachable_robots_disallows_all() {
+        let site = MockSite::new(Err(503));
+        let fetcher = RobotsFetcher::new(site.clone());
+
+        let result = fetcher.fetch("https://site.example/").await;
+        assert!(matches!(result, Err(SearchError::DisallowedByRobots(_))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_robots_fetcher_refetches_unreachable_robots_soon() {
+        let site = MockSite::new(Err(503));
+        let fetcher = RobotsFetcher::new(site.clone());
+        let url = "https://site.example/";
+
+        assert!(!fetcher.is_allowed(url).await.unwrap());
+        tokio::time::advance(ROBOTS_FAILURE_TTL - Duration::from_secs(1)).await;
+        assert!(!fetcher.is_allowed(url).await.unwrap());
+        assert_eq!(site.robots_fetches(), 1);
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(!fetcher.is_allowed(url).await.unwrap());
+        assert_eq!(site.robots_fetches(), 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_robots_fetcher_coalesces_concurrent_robots_fetches() {
+        let site = MockSite::slow(Ok(ROBOTS), Duration::from_millis(100));
+        let fetcher = RobotsFetcher::new(site.clone()).with_user_agent("somebot");
+
+        let (first, second, search) = tokio::join!(
+            fetcher.fetch("https://site.example/a"),
+            fetcher.fetch("https://site.example/b"),
+            fetcher.is_allowed("https://site.example/search"),
+        );
+        assert!(first.is_ok());
+        assert!(second.is_ok());
+        assert!(!search.unwrap());
+        assert_eq!(site.robots_fetches(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_robots_fetcher_checks_fetch_requests() {
+        let site = MockSite::new(Ok(ROBOTS));
+        let fetcher = RobotsFetcher::new(site.clone()).with_user_agent("somebot");
+
+        let request = FetchRequest::post_form("https://site.example/search", [("q", "x")]);
+        let result = fetcher.fetch_request(&request).await;
+        assert!(matches!(result, Err(SearchError::DisallowedByRobots(_))));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_robots_fetcher_honours_crawl_delay() {
+        let site = MockSite::new(Ok(ROBOTS));
+        let fetcher = RobotsFetcher::new(site.clone());
+
+        for page in ["a", "b", "c"] {
+            fetcher
+                .fetch(&format!("https://site.example/{}", page))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(site.page_offsets_ms(), vec![0, 2000, 4000]);
+    }
+}
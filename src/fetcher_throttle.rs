@@ -0,0 +1,379 @@
+//! Host throttle that backs off after `429 Too Many Requests` responses.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+
+use crate::fetcher::{FetchRequest, FetchResponse, PageFetcher};
+use crate::{Result, SearchError};
+
+/// Pause applied when a rate-limited response has no usable `Retry-After`.
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Longest a host is paused, whatever its `Retry-After` asks for.
+const MAX_PAUSE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Registry of hosts that asked clients to back off, shared by clones.
+///
+/// Give one `HostThrottle` to every [`ThrottledFetcher`] that may reach the
+/// same provider, so a `429` seen by one engine pauses all of them.
+#[derive(Clone)]
+pub struct HostThrottle {
+    paused_until: Arc<Mutex<HashMap<String, Instant>>>,
+    default_backoff: Duration,
+}
+
+impl HostThrottle {
+    /// Creates an empty throttle.
+    pub fn new() -> Self {
+        Self {
+            paused_until: Arc::new(Mutex::new(HashMap::new())),
+            default_backoff: DEFAULT_BACKOFF,
+        }
+    }
+
+    /// Sets how long to pause a host whose rate-limited response has no
+    /// parsable `Retry-After` (default: 60 seconds).
+    pub fn with_default_backoff(mut self, backoff: Duration) -> Self {
+        self.default_backoff = backoff;
+        self
+    }
+
+    /// Suppresses requests to `host` for `duration`, at most a day, unless
+    /// it is already paused for longer.
+    pub fn pause(&self, host: &str, duration: Duration) {
+        let until = Instant::now() + duration.min(MAX_PAUSE);
+        let mut paused = self.paused_until.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = paused.entry(host.to_lowercase()).or_insert(until);
+        if *entry < until {
+            *entry = until;
+        }
+    }
+
+    /// Returns how long `host` stays paused, if it is paused.
+    pub fn remaining(&self, host: &str) -> Option<Duration> {
+        let host = host.to_lowercase();
+        let mut paused = self.paused_until.lock().unwrap_or_else(|e| e.into_inner());
+        let until = *paused.get(&host)?;
+        let now = Instant::now();
+        if until <= now {
+            paused.remove(&host);
+            return None;
+        }
+        Some(until - now)
+    }
+
+    /// Pauses `host` for the window a `RateLimited` error asks for.
+    fn record(&self, host: &str, retry_after: Option<&str>) {
+        let duration = retry_after
+            .and_then(|value| parse_retry_after(value, SystemTime::now()))
+            .unwrap_or(self.default_backoff);
+        tracing::debug!("Throttling {} for {:?} after a rate limit", host, duration);
+        self.pause(host, duration);
+    }
+}
+
+impl Default for HostThrottle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A page fetcher that stops requesting a host once it rate-limits us.
+///
+/// When `inner` fails with [`SearchError::RateLimited`], the host is paused
+/// for its `Retry-After` window. Until the window passes, requests to that
+/// host fail immediately with `RateLimited` (carrying the seconds left)
+/// instead of reaching the network; other hosts are unaffected.
+///
+/// ```rust,no_run
+/// use std::sync::Arc;
+/// use a3s_search::{HostThrottle, HttpFetcher, ThrottledFetcher};
+///
+/// let throttle = HostThrottle::new();
+/// let http = Arc::new(HttpFetcher::new());
+/// // Both engines stop hitting a host as soon as either sees a 429
+/// let for_ddg = ThrottledFetcher::new(http.clone(), throttle.clone());
+/// let for_brave = ThrottledFetcher::new(http, throttle);
+/// ```
+#[derive(Clone)]
+pub struct ThrottledFetcher {
+    inner: Arc<dyn PageFetcher>,
+    throttle: HostThrottle,
+}
+
+impl ThrottledFetcher {
+    /// Creates a throttled fetcher around `inner`, sharing `throttle`.
+    pub fn new(inner: Arc<dyn PageFetcher>, throttle: HostThrottle) -> Self {
+        Self { inner, throttle }
+    }
+
+    /// Returns the shared throttle.
+    pub fn throttle(&self) -> &HostThrottle {
+        &self.throttle
+    }
+
+    /// Runs `fetch` unless the host of `url` is paused, and pauses the host
+    /// if the fetch is rate limited.
+    async fn guarded<T>(
+        &self,
+        url: &str,
+        fetch: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let host = url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_lowercase()))
+            .unwrap_or_default();
+
+        if let Some(remaining) = self.throttle.remaining(&host) {
+            // Round up so a caller never retries before the window ends
+            let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+            return Err(SearchError::RateLimited(Some(secs.to_string())));
+        }
+
+        let result = fetch.await;
+        if let Err(SearchError::RateLimited(ref retry_after)) = result {
+            self.throttle.record(&host, retry_after.as_deref());
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl PageFetcher for ThrottledFetcher {
+    async fn fetch(&self, url: &str) -> Result<String> {
+        self.guarded(url, self.inner.fetch(url)).await
+    }
+
+    async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        self.guarded(&request.url, self.inner.fetch_request(request))
+            .await
+    }
+}
+
+/// Parses a `Retry-After` value, either delay seconds or an HTTP date
+/// (`Wed, 21 Oct 2015 07:28:00 GMT`), into a delay from `now`.
+fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    let date = parse_http_date(value)?;
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Parses an IMF-fixdate, the HTTP date format servers must send.
+fn parse_http_date(value: &str) -> Option<SystemTime> {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let [_weekday, day, month, year, time, "GMT"] = parts[..] else {
+        return None;
+    };
+    let day: u64 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u64 + 1;
+    let year: u64 = year.parse().ok()?;
+    let mut clock = time.split(':').map(|part| part.parse::<u64>().ok());
+    let (hour, minute, second) = (clock.next()??, clock.next()??, clock.next()??);
+    if !(1970..=9999).contains(&year)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    // Days since the epoch of a proleptic Gregorian date
+    let (y, m) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = y / 400;
+    let year_of_era = y % 400;
+    let day_of_year = (153 * m + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = (era * 146_097 + day_of_era).checked_sub(719_468)?;
+
+    let secs = days * 86_400 + hour * 3_600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers requests to `limited.example` with a 429 once, counting
+    /// every request that reaches it.
+    struct RateLimitingHost {
+        retry_after: Option<&'static str>,
+        calls: AtomicUsize,
+    }
+
+    impl RateLimitingHost {
+        fn new(retry_after: Option<&'static str>) -> Arc<Self> {
+            Arc::new(Self {
+                retry_after,
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+    }
+
+    #[async_trait]
+    impl PageFetcher for RateLimitingHost {
+        async fn fetch(&self, url: &str) -> Result<String> {
+            let first = self.calls.fetch_add(1, Ordering::SeqCst) == 0;
+            if first && url.contains("limited.example") {
+                return Err(SearchError::RateLimited(
+                    self.retry_after.map(str::to_string),
+                ));
+            }
+            Ok("ok".to_string())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttled_fetcher_short_circuits_until_retry_after() {
+        let host = RateLimitingHost::new(Some("120"));
+        let fetcher = ThrottledFetcher::new(host.clone(), HostThrottle::new());
+
+        let first = fetcher.fetch("https://limited.example/search?q=a").await;
+        assert!(matches!(first, Err(SearchError::RateLimited(Some(ref s))) if s == "120"));
+        assert_eq!(host.calls(), 1);
+
+        // Paused: other paths on the host never reach the network
+        tokio::time::advance(Duration::from_secs(30)).await;
+        let paused = fetcher.fetch("https://limited.example/other").await;
+        assert!(matches!(paused, Err(SearchError::RateLimited(Some(ref s))) if s == "90"));
+        assert_eq!(host.calls(), 1);
+
+        // Other hosts are unaffected
+        assert_eq!(fetcher.fetch("https://fine.example/").await.unwrap(), "ok");
+        assert_eq!(host.calls(), 2);
+
+        tokio::time::advance(Duration::from_secs(90)).await;
+        assert_eq!(
+            fetcher
+                .fetch("https://limited.example/search?q=b")
+                .await
+                .unwrap(),
+            "ok"
+        );
+        assert_eq!(host.calls(), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle_shared_across_fetchers() {
+        let throttle = HostThrottle::new();
+        let host = RateLimitingHost::new(Some("10"));
+        let ddg = ThrottledFetcher::new(host.clone(), throttle.clone());
+        let other = RateLimitingHost::new(None);
+        let brave = ThrottledFetcher::new(other.clone(), throttle);
+
+        assert!(ddg.fetch("https://limited.example/a").await.is_err());
+        let result = brave
+            .fetch_request(&FetchRequest::get("https://LIMITED.example/b"))
+            .await;
+        assert!(matches!(result, Err(SearchError::RateLimited(_))));
+        assert_eq!(other.calls(), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle_default_backoff_without_retry_after() {
+        let host = RateLimitingHost::new(None);
+        let throttle = HostThrottle::new().with_default_backoff(Duration::from_secs(5));
+        let fetcher = ThrottledFetcher::new(host.clone(), throttle);
+
+        assert!(fetcher.fetch("https://limited.example/").await.is_err());
+        assert_eq!(
+            fetcher.throttle().remaining("limited.example"),
+            Some(Duration::from_secs(5))
+        );
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(fetcher.throttle().remaining("limited.example"), None);
+        assert!(fetcher.fetch("https://limited.example/").await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle_caps_huge_retry_after() {
+        let host = RateLimitingHost::new(Some("18446744073709551615"));
+        let fetcher = ThrottledFetcher::new(host.clone(), HostThrottle::new());
+
+        assert!(fetcher.fetch("https://limited.example/").await.is_err());
+        assert_eq!(
+            fetcher.throttle().remaining("limited.example"),
+            Some(MAX_PAUSE)
+        );
+
+        fetcher.throttle().pause("other.example", Duration::MAX);
+        assert_eq!(
+            fetcher.throttle().remaining("other.example"),
+            Some(MAX_PAUSE)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_throttle_pause_keeps_longest_window() {
+        let throttle = HostThrottle::new();
+        throttle.pause("api.example", Duration::from_secs(60));
+        throttle.pause("API.example", Duration::from_secs(10));
+        assert_eq!(
+            throttle.remaining("api.example"),
+            Some(Duration::from_secs(60))
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_445_412_400); // 2015-10-21 07:26:40 UTC
+        assert_eq!(
+            parse_retry_after(" 120 ", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT", now),
+            Some(Duration::from_secs(80))
+        );
+        // Dates in the past mean "retry now"
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 PST", now),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_http_date() {
+        assert_eq!(
+            parse_http_date("Thu, 01 Jan 1970 00:00:00 GMT"),
+            Some(UNIX_EPOCH)
+        );
+        assert_eq!(
+            parse_http_date("Tue, 29 Feb 2000 12:00:00 GMT"),
+            Some(UNIX_EPOCH + Duration::from_secs(951_825_600))
+        );
+        assert_eq!(parse_http_date("Thu, 01 Foo 1970 00:00:00 GMT"), None);
+        assert_eq!(parse_http_date("Thu, 01 Jan 1970 25:00:00 GMT"), None);
+        assert_eq!(
+            parse_http_date("Thu, 01 Jan 18446744073709551615 00:00:00 GMT"),
+            None
+        );
+    }
+}
@@ -0,0 +1,192 @@
+//! HTML parsing behind a small interface.
+//!
+//! Engines parse result pages through [`HtmlDocument`], [`HtmlSelector`] and
+//! [`HtmlElement`] instead of a parser crate, so the backend can be swapped
+//! (e.g. for a lighter one behind a feature flag) without touching them.
+//! The default backend is `scraper` (html5ever).
+
+use crate::{Result, SearchError};
+
+/// A parsed HTML document.
+pub struct HtmlDocument(scraper::Html);
+
+/// A compiled CSS selector.
+#[derive(Debug, Clone)]
+pub struct HtmlSelector(scraper::Selector);
+
+/// An element of an [`HtmlDocument`].
+#[derive(Debug, Clone, Copy)]
+pub struct HtmlElement<'a>(scraper::ElementRef<'a>);
+
+impl HtmlDocument {
+    /// Parses a full HTML document. Malformed markup is recovered from the
+    /// way browsers do, so parsing never fails.
+    pub fn parse(html: &str) -> Self {
+        Self(scraper::Html::parse_document(html))
+    }
+
+    /// Returns the elements matching `selector`, in document order.
+    pub fn select<'a>(
+        &'a self,
+        selector: &'a HtmlSelector,
+    ) -> impl Iterator<Item = HtmlElement<'a>> + 'a {
+        self.0.select(&selector.0).map(HtmlElement)
+    }
+}
+
+impl HtmlSelector {
+    /// Compiles a CSS selector, failing with [`SearchError::Parse`] if it is
+    /// invalid.
+    pub fn parse(css: &str) -> Result<Self> {
+        scraper::Selector::parse(css)
+            .map(Self)
+            .map_err(|e| SearchError::Parse(format!("Failed to parse selector: {:?}", e)))
+    }
+
+    /// Returns whether `element` matches the selector.
+    pub fn matches(&self, element: &HtmlElement<'_>) -> bool {
+        self.0.matches(&element.0)
+    }
+}
+
+impl<'a> HtmlElement<'a> {
+    /// Returns the descendants matching `selector`, in document order.
+    pub fn select<'s>(
+        &self,
+        selector: &'s HtmlSelector,
+    ) -> impl Iterator<Item = HtmlElement<'a>> + 's
+    where
+        'a: 's,
+    {
+        self.0.select(&selector.0).map(HtmlElement)
+    }
+
+    /// Returns the text of the element and its descendants, concatenated.
+    pub fn text(&self) -> String {
+        self.0.text().collect()
+    }
+
+    /// Returns the value of attribute `name`, if present.
+    pub fn attr(&self, name: &str) -> Option<&'a str> {
+        self.0.value().attr(name)
+    }
+
+    /// Returns the element's ancestors, from its parent up to the root.
+    pub fn ancestors(&self) -> impl Iterator<Item = HtmlElement<'a>> {
+        self.0
+            .ancestors()
+            .filter_map(scraper::ElementRef::wrap)
+            .map(HtmlElement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = r#"<!DOCTYPE html>
+<html><body>
+  <div class="result">
+    <h3><a href="https://a.example/">First <b>result</b></a></h3>
+    <p class="snippet">  Snippet one  </p>
+  </div>
+  <div class="result ad">
+    <h3><a href="https://ads.example/">Sponsored</a></h3>
+  </div>
+  <div class="result">
+    <h3><a data-url="https://b.example/">Second</a></h3>
+    <p class="snippet">Snippet <i>two</i></p>
+  </div>
+</body></html>"#;
+
+    /// Extracts (title, href, snippet) tuples through the abstraction.
+    fn extract(html: &str) -> Vec<(String, Option<String>, String)> {
+        let document = HtmlDocument::parse(html);
+        let result = HtmlSelector::parse("div.result").unwrap();
+        let title = HtmlSelector::parse("h3 a").unwrap();
+        let snippet = HtmlSelector::parse(".snippet").unwrap();
+        document
+            .select(&result)
+            .filter_map(|element| {
+                let link = element.select(&title).next()?;
+                Some((
+                    link.text(),
+                    link.attr("href").map(str::to_string),
+                    element
+                        .select(&snippet)
+                        .next()
+                        .map(|e| e.text())
+                        .unwrap_or_default(),
+                ))
+            })
+            .collect()
+    }
+
+    /// The same extraction written against `scraper` directly.
+    fn extract_with_scraper(html: &str) -> Vec<(String, Option<String>, String)> {
+        let document = scraper::Html::parse_document(html);
+        let result = scraper::Selector::parse("div.result").unwrap();
+        let title = scraper::Selector::parse("h3 a").unwrap();
+        let snippet = scraper::Selector::parse(".snippet").unwrap();
+        document
+            .select(&result)
+            .filter_map(|element| {
+                let link = element.select(&title).next()?;
+                Some((
+                    link.text().collect(),
+                    link.value().attr("href").map(str::to_string),
+                    element
+                        .select(&snippet)
+                        .next()
+                        .map(|e| e.text().collect())
+                        .unwrap_or_default(),
+                ))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_html_extraction_matches_scraper() {
+        let extracted = extract(FIXTURE);
+        assert_eq!(extracted, extract_with_scraper(FIXTURE));
+        assert_eq!(
+            extracted,
+            vec![
+                (
+                    "First result".to_string(),
+                    Some("https://a.example/".to_string()),
+                    "  Snippet one  ".to_string()
+                ),
+                (
+                    "Sponsored".to_string(),
+                    Some("https://ads.example/".to_string()),
+                    String::new()
+                ),
+                ("Second".to_string(), None, "Snippet two".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_html_selector_matches_and_ancestors() {
+        let document = HtmlDocument::parse(FIXTURE);
+        let ad = HtmlSelector::parse(".ad").unwrap();
+        let link = HtmlSelector::parse("a").unwrap();
+
+        let in_ad: Vec<bool> = document
+            .select(&link)
+            .map(|a| a.ancestors().any(|ancestor| ad.matches(&ancestor)))
+            .collect();
+        assert_eq!(in_ad, vec![false, true, false]);
+        assert_eq!(
+            document.select(&link).nth(2).unwrap().attr("data-url"),
+            Some("https://b.example/")
+        );
+    }
+
+    #[test]
+    fn test_html_selector_invalid() {
+        let err = HtmlSelector::parse("div[").unwrap_err();
+        assert!(matches!(err, SearchError::Parse(_)), "{:?}", err);
+    }
+}
@@ -33,8 +33,12 @@
 //!
 //! ## Using Proxy Pool
 //!
+//! Engines send requests through their fetcher, so a pool is used by
+//! handing each engine a `PooledHttpFetcher` rotating through it.
+//!
 //! ```rust,no_run
-//! use a3s_search::{Search, SearchQuery, engines::DuckDuckGo};
+//! use std::sync::Arc;
+//! use a3s_search::{PooledHttpFetcher, Search, SearchQuery, engines::DuckDuckGo};
 //! use a3s_search::proxy::{ProxyPool, ProxyConfig};
 //!
 //! #[tokio::main]
@@ -44,10 +48,10 @@
 //!         ProxyConfig::new("proxy1.example.com", 8080),
 //!         ProxyConfig::new("proxy2.example.com", 8080),
 //!     ]);
+//!     let fetcher = Arc::new(PooledHttpFetcher::new(Arc::new(proxy_pool)));
 //!
 //!     let mut search = Search::new();
-//!     search.set_proxy_pool(proxy_pool);
-//!     search.add_engine(DuckDuckGo::new());
+//!     search.add_engine(DuckDuckGo::with_fetcher(fetcher));
 //!
 //!     let query = SearchQuery::new("rust programming");
 //!     let results = search.search(query).await?;
@@ -56,32 +60,98 @@
 //! }
 //! ```
 
+//! ## WebAssembly
+//!
+//! On `wasm32` targets only the engine-independent core is compiled:
+//! [`SearchQuery`], [`SearchResult`], [`SearchResults`], [`Aggregator`], the
+//! [`Engine`] and [`PageFetcher`] traits and [`SearchError`]. Build it with
+//! `--no-default-features --features wasm`; the fetchers, engines, caches,
+//! proxy pool and [`Search`] need sockets, threads and a file system and are
+//! left out.
+
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+compile_error!("building for wasm32 requires `--no-default-features --features wasm`");
+
+#[cfg(all(
+    target_arch = "wasm32",
+    any(feature = "headless", feature = "middleware")
+))]
+compile_error!("the `headless` and `middleware` features are not available on wasm32");
+
+/// Compiles `items` only on targets with a native runtime (not `wasm32`).
+macro_rules! cfg_native {
+    ($($item:item)*) => {
+        $(
+            #[cfg(not(target_arch = "wasm32"))]
+            $item
+        )*
+    };
+}
+
 mod aggregator;
 mod engine;
 mod error;
 mod fetcher;
-mod fetcher_http;
-pub mod proxy;
+mod html;
 mod query;
 mod result;
-mod search;
 
-pub mod engines;
+cfg_native! {
+    mod cache;
+    mod dns;
+    mod fetcher_cache;
+    mod fetcher_http;
+    mod fetcher_hybrid;
+    mod fetcher_pool;
+    mod fetcher_rate_limit;
+    mod fetcher_robots;
+    mod fetcher_throttle;
+    pub mod proxy;
+    mod search;
+
+    #[cfg(feature = "test-utils")]
+    pub mod testing;
 
-#[cfg(feature = "headless")]
-pub mod browser;
+    #[cfg(any(test, feature = "test-utils"))]
+    pub mod test_server;
 
-#[cfg(feature = "headless")]
-pub mod browser_setup;
+    pub mod engines;
 
-pub use aggregator::Aggregator;
+    #[cfg(feature = "headless")]
+    pub mod browser;
+
+    #[cfg(feature = "headless")]
+    pub mod browser_setup;
+}
+
+pub use aggregator::{Aggregator, ConsensusMode};
 pub use engine::{Engine, EngineCategory, EngineConfig};
 pub use error::{Result, SearchError};
-pub use fetcher::{PageFetcher, WaitStrategy};
-pub use fetcher_http::HttpFetcher;
-pub use query::SearchQuery;
-pub use result::{ResultType, SearchResult, SearchResults};
-pub use search::Search;
+pub use fetcher::{
+    FetchBody, FetchMethod, FetchRequest, FetchResponse, FetcherHint, PageFetcher, WaitStrategy,
+};
+pub use html::{HtmlDocument, HtmlElement, HtmlSelector};
+pub use query::{SafeSearch, SearchQuery, SiteMatch, TimeRange};
+pub use result::{
+    DetailedResults, EngineStatus, ResultType, SearchResult, SearchResults, DEFAULT_RESULT_SCHEMES,
+};
+
+cfg_native! {
+    pub use cache::{CacheBackend, DiskCache, MemoryCache};
+    pub use fetcher_cache::CachingFetcher;
+    pub use fetcher_http::{
+        HttpFetcher, HttpFetcherBuilder, RedirectPolicy, UserAgentStrategy, DEFAULT_USER_AGENTS,
+    };
+    pub use fetcher_hybrid::{HybridFetcher, HybridStats, DEFAULT_BLOCK_MARKERS};
+    pub use fetcher_pool::PooledHttpFetcher;
+    pub use fetcher_rate_limit::{Rate, RateLimitedFetcher};
+    pub use fetcher_robots::{RobotsFetcher, DEFAULT_ROBOTS_USER_AGENT};
+    pub use fetcher_throttle::{HostThrottle, ThrottledFetcher};
+    pub use search::Search;
 
-#[cfg(feature = "headless")]
-pub use browser::{BrowserFetcher, BrowserPool, BrowserPoolConfig};
+    #[cfg(feature = "headless")]
+    pub use browser::{
+        BrowserFetcher, BrowserPool, BrowserPoolConfig, BrowserPoolStats, ConsentChoice,
+        ResourceBlockPolicy,
+    };
+}
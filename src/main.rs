@@ -8,18 +8,24 @@ use tracing::Level;
 use tracing_subscriber::FmtSubscriber;
 
 use a3s_search::{
-    engines::{Brave, DuckDuckGo, So360, Sogou, Wikipedia},
-    proxy::{ProxyConfig, ProxyPool, ProxyProtocol},
-    HttpFetcher, PageFetcher, Search, SearchQuery,
+    engines,
+    proxy::{ProxyConfig, ProxyProtocol},
+    Engine, EngineCategory, HttpFetcher, SafeSearch, Search, SearchQuery, SearchResult,
+    SearchResults, TimeRange,
 };
 
 #[cfg(feature = "headless")]
 use a3s_search::{
-    browser::{BrowserFetcher, BrowserPool, BrowserPoolConfig},
-    engines::{Baidu, BingChina, Google},
-    WaitStrategy,
+    browser::{BrowserPool, BrowserPoolConfig},
+    browser_setup::{self, DownloadPhase, DownloadProgress},
 };
 
+/// Highest page `--page` accepts.
+const MAX_PAGE: i64 = 100;
+
+/// Most pages `--pages` merges.
+const MAX_PAGES: i64 = 10;
+
 /// A3S Search - Embeddable meta search engine CLI
 #[derive(Parser)]
 #[command(name = "a3s-search")]
@@ -29,7 +35,7 @@ struct Cli {
     query: Option<String>,
 
     /// Search engines to use (comma-separated)
-    /// Available: ddg, brave, wiki, sogou, 360, g, baidu, bing_cn
+    /// Available: all, ddg, brave, wiki, wikt, wikinews, wikibooks, mar, yt, sogou, 360, g, baidu, bing_cn
     #[arg(short, long, value_delimiter = ',')]
     engines: Option<Vec<String>>,
 
@@ -49,6 +55,36 @@ struct Cli {
     #[arg(short, long)]
     proxy: Option<String>,
 
+    /// Result page to show (1-100)
+    #[arg(long, default_value = "1", value_parser = clap::value_parser!(u32).range(1..=MAX_PAGE))]
+    page: u32,
+
+    /// Fetch this many pages from --page on and merge them (1-10)
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..=MAX_PAGES))]
+    pages: Option<u32>,
+
+    /// Only show results from the past day, week, month or year.
+    /// Honored by ddg, brave and g; other engines ignore it and still run
+    #[arg(long, value_enum)]
+    time_range: Option<TimeRangeArg>,
+
+    /// Search vertical(s); without -e, searches every engine that serves them
+    #[arg(long = "category", value_enum, value_delimiter = ',')]
+    categories: Vec<CategoryArg>,
+
+    /// Safe search level, for engines that support it (see `a3s-search engines`)
+    #[arg(long, value_enum)]
+    safesearch: Option<SafeSearchArg>,
+
+    /// Result language as a BCP 47 tag (e.g. en, zh-CN); sent as Accept-Language
+    #[arg(short = 'L', long)]
+    language: Option<String>,
+
+    /// Never download Chrome; use a system or cached one (also A3S_NO_DOWNLOAD=1)
+    #[cfg(feature = "headless")]
+    #[arg(long)]
+    no_download: bool,
+
     /// Use headless browser for JS-rendered engines (default: auto-detected)
     #[arg(long, hide = true)]
     headless: bool,
@@ -67,6 +103,13 @@ enum Commands {
     Engines,
     /// Update a3s-search to the latest version
     Update,
+    /// Check the Chrome setup for headless engines and prune old cached versions
+    #[cfg(feature = "headless")]
+    Doctor {
+        /// Number of cached Chrome versions to keep
+        #[arg(long, default_value_t = 1)]
+        keep: usize,
+    },
 }
 
 #[derive(Clone, Copy, ValueEnum, Debug)]
@@ -77,6 +120,93 @@ enum OutputFormat {
     Json,
     /// Compact single-line output
     Compact,
+    /// Numbered Markdown list of links with snippets
+    Markdown,
+    /// CSV with a header row
+    Csv,
+    /// One JSON result object per line
+    Jsonl,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq, Eq)]
+enum TimeRangeArg {
+    /// Past 24 hours
+    Day,
+    /// Past week
+    Week,
+    /// Past month
+    Month,
+    /// Past year
+    Year,
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq, Eq)]
+enum CategoryArg {
+    /// Web pages
+    General,
+    /// Image search
+    Images,
+    /// Video search (yt; Brave and Bing China video snippets)
+    Videos,
+    /// News (Brave and Bing China news snippets)
+    News,
+    /// Places and maps
+    Maps,
+    /// Music
+    Music,
+    /// Scientific papers
+    Science,
+    /// Files and downloads
+    Files,
+    /// Social media posts
+    Social,
+}
+
+impl From<CategoryArg> for EngineCategory {
+    fn from(arg: CategoryArg) -> Self {
+        match arg {
+            CategoryArg::General => EngineCategory::General,
+            CategoryArg::Images => EngineCategory::Images,
+            CategoryArg::Videos => EngineCategory::Videos,
+            CategoryArg::News => EngineCategory::News,
+            CategoryArg::Maps => EngineCategory::Maps,
+            CategoryArg::Music => EngineCategory::Music,
+            CategoryArg::Science => EngineCategory::Science,
+            CategoryArg::Files => EngineCategory::Files,
+            CategoryArg::Social => EngineCategory::Social,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum, Debug, PartialEq, Eq)]
+enum SafeSearchArg {
+    /// No filtering
+    Off,
+    /// Filter explicit images and videos
+    Moderate,
+    /// Filter all explicit results
+    Strict,
+}
+
+impl From<SafeSearchArg> for SafeSearch {
+    fn from(arg: SafeSearchArg) -> Self {
+        match arg {
+            SafeSearchArg::Off => SafeSearch::Off,
+            SafeSearchArg::Moderate => SafeSearch::Moderate,
+            SafeSearchArg::Strict => SafeSearch::Strict,
+        }
+    }
+}
+
+impl From<TimeRangeArg> for TimeRange {
+    fn from(arg: TimeRangeArg) -> Self {
+        match arg {
+            TimeRangeArg::Day => TimeRange::Day,
+            TimeRangeArg::Week => TimeRange::Week,
+            TimeRangeArg::Month => TimeRange::Month,
+            TimeRangeArg::Year => TimeRange::Year,
+        }
+    }
 }
 
 #[tokio::main]
@@ -103,6 +233,8 @@ async fn main() -> Result<()> {
             })
             .await
         }
+        #[cfg(feature = "headless")]
+        Some(Commands::Doctor { keep }) => doctor(keep),
         None => {
             if let Some(query) = cli.query {
                 run_search(SearchArgs {
@@ -112,13 +244,24 @@ async fn main() -> Result<()> {
                     timeout: cli.timeout,
                     format: cli.format,
                     proxy: cli.proxy,
+                    page: cli.page,
+                    pages: cli.pages,
+                    time_range: cli.time_range,
+                    language: cli.language,
+                    safesearch: cli.safesearch,
+                    categories: cli.categories.into_iter().map(Into::into).collect(),
+                    #[cfg(feature = "headless")]
+                    no_download: cli.no_download,
                 })
                 .await
             } else {
                 // No query provided, show help
                 println!("A3S Search - Meta search engine CLI\n");
                 println!("Usage: a3s-search <QUERY> [OPTIONS]");
-                println!("       a3s-search engines\n");
+                println!("       a3s-search engines");
+                #[cfg(feature = "headless")]
+                println!("       a3s-search doctor [--keep N]");
+                println!();
                 println!("Examples:");
                 println!("  a3s-search \"Rust programming\"");
                 println!("  a3s-search \"Rust\" -e ddg,wiki -l 5");
@@ -126,12 +269,23 @@ async fn main() -> Result<()> {
                 println!("  a3s-search \"Rust\" -p http://127.0.0.1:8080\n");
                 println!("Options:");
                 println!(
-                    "  -e, --engines <ENGINES>  Engines: ddg,brave,wiki,sogou,360,g,baidu,bing_cn"
+                    "  -e, --engines <ENGINES>  Engines: all,ddg,brave,wiki,wikt,wikinews,wikibooks,mar,yt,sogou,360,g,baidu,bing_cn"
                 );
                 println!("  -l, --limit <N>          Max results (default: 10)");
+                println!("      --page <N>           Result page, 1-100 (default: 1)");
+                println!("      --pages <N>          Merge N pages from --page on, 1-10");
+                println!("      --time-range <RANGE> day, week, month or year (ddg, brave, g)");
+                println!("  -L, --language <LANG>    Result language, e.g. en, zh-CN");
+                println!("      --safesearch <LEVEL> off, moderate or strict");
+                println!("      --category <CAT>     general, images, videos, news, maps, music,");
+                println!("                           science, files or social (repeatable)");
                 println!("  -t, --timeout <SECS>     Timeout in seconds (default: 10)");
-                println!("  -f, --format <FORMAT>    Output: text, json, compact");
+                println!(
+                    "  -f, --format <FORMAT>    Output: text, json, compact, markdown, csv, jsonl"
+                );
                 println!("  -p, --proxy <URL>        Proxy URL (http/https/socks5)");
+                #[cfg(feature = "headless")]
+                println!("      --no-download        Never download Chrome");
                 println!("  -v, --verbose            Enable debug logging");
                 println!("  -h, --help               Show help");
                 println!("  -V, --version            Show version\n");
@@ -149,6 +303,14 @@ struct SearchArgs {
     timeout: u64,
     format: OutputFormat,
     proxy: Option<String>,
+    page: u32,
+    pages: Option<u32>,
+    time_range: Option<TimeRangeArg>,
+    language: Option<String>,
+    safesearch: Option<SafeSearchArg>,
+    categories: Vec<EngineCategory>,
+    #[cfg(feature = "headless")]
+    no_download: bool,
 }
 
 fn list_engines() -> Result<()> {
@@ -157,6 +319,11 @@ fn list_engines() -> Result<()> {
     println!("    ddg      - DuckDuckGo (privacy-focused search)");
     println!("    brave    - Brave Search");
     println!("    wiki     - Wikipedia");
+    println!("    wikt     - Wiktionary");
+    println!("    wikinews - Wikinews");
+    println!("    wikibooks - Wikibooks");
+    println!("    mar      - Marginalia (small/independent web)");
+    println!("    yt       - YouTube (videos via Invidious; instance from A3S_INVIDIOUS_INSTANCE)");
     println!();
     println!("  Chinese:");
     println!("    sogou    - Sogou (搜狗)");
@@ -171,20 +338,76 @@ fn list_engines() -> Result<()> {
         println!("    bing_cn  - Bing China (必应中国)");
     }
 
+    println!();
+    println!("  all      - All engines except the headless ones");
+    println!();
+    println!(
+        "  Safe search (--safesearch) is supported by: {}",
+        safesearch_engines().join(", ")
+    );
     println!();
     println!("Usage: a3s-search \"query\" -e ddg,wiki,sogou");
     Ok(())
 }
 
+/// Shortcuts of the engines whose config says they support safe search.
+fn safesearch_engines() -> Vec<String> {
+    engines::configs()
+        .into_iter()
+        .filter(|config| config.safesearch)
+        .map(|config| config.shortcut)
+        .collect()
+}
+
+/// Returns a warning when strict safe search is asked of engines that all
+/// ignore it.
+fn safesearch_warning(level: SafeSearch, engines: &[Box<dyn Engine>]) -> Option<String> {
+    if level != SafeSearch::Strict || engines.iter().any(|e| e.config().safesearch) {
+        return None;
+    }
+    Some(
+        "Warning: none of the selected engines support safe search; \
+         results are not filtered"
+            .to_string(),
+    )
+}
+
+/// Reports which Chrome headless engines would use and removes all but the
+/// newest `keep` cached Chrome versions.
+#[cfg(feature = "headless")]
+fn doctor(keep: usize) -> Result<()> {
+    match browser_setup::detect_chrome() {
+        Some(path) => println!("System Chrome: {}", path.display()),
+        None => println!("System Chrome: not found (Chrome for Testing is downloaded on demand)"),
+    }
+
+    let removed = browser_setup::cleanup_old_versions(keep)?;
+    if removed.is_empty() {
+        println!("No old cached Chrome versions to remove");
+    }
+    for dir in removed {
+        println!("Removed {}", dir.display());
+    }
+    Ok(())
+}
+
 async fn run_search(args: SearchArgs) -> Result<()> {
     let mut search = Search::new();
     search.set_timeout(Duration::from_secs(args.timeout));
 
-    // Setup proxy if provided
+    if let Some(language) = &args.language {
+        if !looks_like_language_tag(language) {
+            eprintln!(
+                "Warning: '{}' does not look like a language tag (e.g. en, zh-CN); using it anyway",
+                language
+            );
+        }
+    }
+
+    // The proxy is applied by the engines' HTTP fetcher and browser pool
+    // below; reject a malformed URL before either is built
     if let Some(proxy_url) = &args.proxy {
-        let proxy_config = parse_proxy_url(proxy_url)?;
-        let proxy_pool = ProxyPool::with_proxies(vec![proxy_config]);
-        search.set_proxy_pool(proxy_pool);
+        parse_proxy_url(proxy_url)?;
         if matches!(args.format, OutputFormat::Text) {
             eprintln!("Using proxy: {}", proxy_url);
         }
@@ -194,9 +417,8 @@ async fn run_search(args: SearchArgs) -> Result<()> {
     #[cfg(not(feature = "headless"))]
     {
         let engine_list = args.engines.as_deref().unwrap_or(&[]);
-        let headless_engines = ["g", "google", "baidu", "bing_cn", "bing"];
         for e in engine_list {
-            if headless_engines.contains(&e.as_str()) {
+            if HEADLESS_ENGINES.contains(&e.as_str()) {
                 eprintln!(
                     "Warning: '{}' engine requires the 'headless' feature. \
                      Rebuild with: cargo build --features headless",
@@ -209,80 +431,74 @@ async fn run_search(args: SearchArgs) -> Result<()> {
     // Lazily create browser pool when headless engines are needed
     #[cfg(feature = "headless")]
     let browser_pool: std::sync::Arc<BrowserPool> = {
+        // Install Chrome up front so a first-run download shows its progress
+        let needs_browser = args
+            .engines
+            .as_deref()
+            .unwrap_or(&[])
+            .iter()
+            .any(|e| HEADLESS_ENGINES.contains(&e.as_str()));
+        let chrome_path = if needs_browser {
+            let resolved = if args.no_download {
+                browser_setup::find_chrome()
+            } else {
+                browser_setup::ensure_chrome_with_progress(download_progress_printer()).await
+            };
+            match resolved {
+                Ok(path) => Some(path.to_string_lossy().into_owned()),
+                Err(e) => {
+                    eprintln!("Warning: Chrome setup failed: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
         let pool_config = BrowserPoolConfig {
             proxy_url: args.proxy.clone(),
+            chrome_path,
+            auto_download: !args.no_download,
             ..Default::default()
         };
         std::sync::Arc::new(BrowserPool::new(pool_config))
     };
 
-    // Create shared HTTP fetcher (with proxy if provided)
-    let http_fetcher: std::sync::Arc<dyn PageFetcher> = if let Some(proxy_url) = &args.proxy {
-        std::sync::Arc::new(
-            HttpFetcher::with_proxy(proxy_url)
-                .map_err(|e| anyhow::anyhow!("Failed to create HTTP fetcher with proxy: {}", e))?,
-        )
+    // Create one HTTP fetcher (with proxy if provided) shared by all HTTP engines
+    let shared_http = if let Some(proxy_url) = &args.proxy {
+        HttpFetcher::with_proxy(proxy_url)
+            .map_err(|e| anyhow::anyhow!("Failed to create HTTP fetcher with proxy: {}", e))?
     } else {
-        std::sync::Arc::new(HttpFetcher::new())
+        HttpFetcher::builder()
+            .dns_cache(DNS_CACHE_TTL)
+            .build()
+            .map_err(|e| anyhow::anyhow!("Failed to create HTTP fetcher: {}", e))?
     };
 
     // Add engines based on selection
-    let engine_shortcuts: Vec<String> = args
-        .engines
-        .unwrap_or_else(|| vec!["ddg".to_string(), "wiki".to_string()]);
+    // A category search without -e picks from every HTTP engine
+    let engine_shortcuts: Vec<String> = match &args.engines {
+        Some(engines) => engines.clone(),
+        None if !args.categories.is_empty() => vec!["all".to_string()],
+        None => vec!["ddg".to_string(), "wiki".to_string()],
+    };
+
+    let mut selected: Vec<Box<dyn Engine>> = Vec::new();
 
     for shortcut in &engine_shortcuts {
         match shortcut.as_str() {
-            "ddg" | "duckduckgo" => search.add_engine(DuckDuckGo::with_fetcher(
-                std::sync::Arc::clone(&http_fetcher),
-            )),
-            "brave" => search.add_engine(Brave::with_fetcher(std::sync::Arc::clone(&http_fetcher))),
-            "wiki" | "wikipedia" => {
-                // Wikipedia needs its own fetcher since it uses JSON API, not HTML
-                let fetcher = if let Some(proxy_url) = &args.proxy {
-                    HttpFetcher::with_proxy(proxy_url).map_err(|e| {
-                        anyhow::anyhow!("Failed to create HTTP fetcher with proxy: {}", e)
-                    })?
-                } else {
-                    HttpFetcher::new()
-                };
-                search.add_engine(Wikipedia::with_http_fetcher(fetcher))
-            }
-            "sogou" => search.add_engine(Sogou::with_fetcher(std::sync::Arc::clone(&http_fetcher))),
-            "360" | "so360" => {
-                search.add_engine(So360::with_fetcher(std::sync::Arc::clone(&http_fetcher)))
-            }
-            #[cfg(feature = "headless")]
-            "g" | "google" => {
-                let fetcher: std::sync::Arc<dyn PageFetcher> = std::sync::Arc::new(
-                    BrowserFetcher::new(std::sync::Arc::clone(&browser_pool)).with_wait(
-                        WaitStrategy::Selector {
-                            css: "div.g".to_string(),
-                            timeout_ms: 5000,
-                        },
-                    ),
-                );
-                search.add_engine(Google::new(fetcher));
-            }
-            #[cfg(feature = "headless")]
-            "baidu" => {
-                let fetcher: std::sync::Arc<dyn PageFetcher> = std::sync::Arc::new(
-                    BrowserFetcher::new(std::sync::Arc::clone(&browser_pool)).with_wait(
-                        WaitStrategy::Selector {
-                            css: "div.c-container".to_string(),
-                            timeout_ms: 5000,
-                        },
-                    ),
-                );
-                search.add_engine(Baidu::new(fetcher));
-            }
+            "all" => selected.extend(engines::all_http(shared_http.clone())),
             #[cfg(feature = "headless")]
-            "bing_cn" | "bing" => {
-                let fetcher: std::sync::Arc<dyn PageFetcher> = std::sync::Arc::new(
-                    BrowserFetcher::new(std::sync::Arc::clone(&browser_pool))
-                        .with_wait(WaitStrategy::Delay { ms: 2000 }),
+            "g" | "google" | "baidu" | "bing_cn" | "bing" => {
+                let wanted = match shortcut.as_str() {
+                    "google" => "g",
+                    "bing" => "bing_cn",
+                    other => other,
+                };
+                selected.extend(
+                    engines::all_headless(std::sync::Arc::clone(&browser_pool))
+                        .into_iter()
+                        .filter(|engine| engine.shortcut() == wanted),
                 );
-                search.add_engine(BingChina::new(fetcher));
             }
             #[cfg(not(feature = "headless"))]
             "g" | "google" | "baidu" | "bing_cn" | "bing" => {
@@ -292,19 +508,56 @@ async fn run_search(args: SearchArgs) -> Result<()> {
                     shortcut
                 );
             }
-            _ => {
-                eprintln!("Warning: Unknown engine '{}', skipping", shortcut);
-            }
+            _ => match engines::by_shortcut(shortcut, &shared_http) {
+                Some(engine) => selected.push(engine),
+                None => eprintln!("Warning: Unknown engine '{}', skipping", shortcut),
+            },
         }
     }
 
+    let categories = query_categories(&args.categories, &selected, args.engines.is_some());
+    for warning in category_warnings(&args.categories, &selected) {
+        eprintln!("{}", warning);
+    }
+    if let Some(level) = args.safesearch {
+        if let Some(warning) = safesearch_warning(level.into(), &selected) {
+            eprintln!("{}", warning);
+        }
+    }
+    search.add_engines(selected);
+    #[cfg(feature = "headless")]
+    search.set_browser_pool(std::sync::Arc::clone(&browser_pool));
+
     if search.engine_count() == 0 {
         anyhow::bail!("No valid engines specified");
     }
 
+    // Resolve all engine hosts at once instead of one lookup per engine
+    let hosts: Vec<String> = engine_shortcuts
+        .iter()
+        .flat_map(|shortcut| match shortcut.as_str() {
+            "all" => engines::HTTP_ENGINES.to_vec(),
+            shortcut => vec![shortcut],
+        })
+        .filter_map(engine_host)
+        .collect();
+    shared_http.preresolve(hosts).await;
+
     // Perform search
-    let query = SearchQuery::new(&args.query);
-    let results = search.search(query).await?;
+    let query = build_query(&args, categories);
+    tracing::debug!(
+        "Query {:?}: page {}, language {:?}, time range {:?}",
+        query.query,
+        query.page,
+        query.language,
+        query.time_range
+    );
+    let results = match args.pages {
+        Some(pages) => search.search_pages(query, pages).await,
+        None => search.search(query).await,
+    };
+    search.shutdown().await;
+    let results = results?;
 
     // Show engine errors to the user
     for (engine, error) in results.errors() {
@@ -315,8 +568,11 @@ async fn run_search(args: SearchArgs) -> Result<()> {
     match args.format {
         OutputFormat::Text => {
             println!(
-                "\nSearch results for \"{}\" ({} results in {}ms):\n",
-                args.query, results.count, results.duration_ms
+                "\nSearch results for \"{}\" ({}, {} results in {}ms):\n",
+                args.query,
+                page_label(args.page, args.pages),
+                results.len(),
+                results.duration_ms
             );
 
             for (i, result) in results.items().iter().take(args.limit).enumerate() {
@@ -342,11 +598,207 @@ async fn run_search(args: SearchArgs) -> Result<()> {
                 println!("{}\t{}", result.title, result.url);
             }
         }
+        OutputFormat::Markdown => print!("{}", format_markdown(&results, args.limit)),
+        OutputFormat::Csv => print!("{}", format_csv(&results, args.limit)),
+        OutputFormat::Jsonl => print!("{}", format_jsonl(&results, args.limit)?),
     }
 
     Ok(())
 }
 
+/// Builds the query `args` ask for over `categories`.
+fn build_query(args: &SearchArgs, categories: Vec<EngineCategory>) -> SearchQuery {
+    let mut query = SearchQuery::new(&args.query)
+        .with_categories(categories)
+        .with_page(args.page);
+    if let Some(range) = args.time_range {
+        query = query.with_time_range(range.into());
+    }
+    if let Some(language) = &args.language {
+        query = query.with_language(language.as_str());
+    }
+    if let Some(level) = args.safesearch {
+        query = query.with_safesearch(level.into());
+    }
+    query
+}
+
+/// Loosely checks for a BCP 47 tag: a 2-3 letter language, then
+/// alphanumeric subtags of up to 8 characters separated by `-` or `_`.
+fn looks_like_language_tag(tag: &str) -> bool {
+    let mut subtags = tag.split(['-', '_']);
+    let language = subtags.next().unwrap_or_default();
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && subtags.all(|subtag| {
+            (1..=8).contains(&subtag.len()) && subtag.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
+
+/// Describes the searched pages for the text output header, e.g.
+/// `page 2` or `pages 1-3`.
+fn page_label(page: u32, pages: Option<u32>) -> String {
+    match pages {
+        Some(pages) if pages > 1 => format!("pages {}-{}", page, page + pages - 1),
+        _ => format!("page {}", page),
+    }
+}
+
+/// Formats up to `limit` results as a numbered Markdown list of
+/// `[title](url)` links, each followed by its snippet on one line.
+fn format_markdown(results: &SearchResults, limit: usize) -> String {
+    let mut output = String::new();
+    for (i, result) in results.items().iter().take(limit).enumerate() {
+        let title = result.title.replace('[', "\\[").replace(']', "\\]");
+        let url = result
+            .url
+            .replace(' ', "%20")
+            .replace('(', "%28")
+            .replace(')', "%29");
+        output.push_str(&format!("{}. [{}]({})\n", i + 1, title, url));
+        let snippet = result
+            .content
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !snippet.is_empty() {
+            output.push_str(&format!("   {}\n", snippet));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Formats up to `limit` results as CSV with a header row.
+fn format_csv(results: &SearchResults, limit: usize) -> String {
+    let mut limited = SearchResults::new();
+    for result in results.items().iter().take(limit) {
+        limited.add_result(result.clone());
+    }
+    limited.to_csv()
+}
+
+/// Formats up to `limit` results as JSON Lines, one object per result.
+fn format_jsonl(results: &SearchResults, limit: usize) -> Result<String> {
+    let mut output = String::new();
+    for result in results.items().iter().take(limit) {
+        output.push_str(&serde_json::to_string::<SearchResult>(result)?);
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+/// Adds the categories of `engines` to the query's `categories`, so
+/// vertical engines such as YouTube are selected.
+fn add_categories(categories: &mut Vec<EngineCategory>, engines: &[Box<dyn Engine>]) {
+    for category in engines.iter().flat_map(|e| &e.config().categories) {
+        if !categories.contains(category) {
+            categories.push(*category);
+        }
+    }
+}
+
+/// Returns the categories to query `selected` engines for.
+///
+/// With explicitly chosen engines, every selected engine runs: the query
+/// covers `General`, the engines' own categories (so vertical engines such
+/// as YouTube are selected) and the requested ones. Without either, the
+/// query keeps its default `General` category. Otherwise only engines
+/// serving a requested category run.
+fn query_categories(
+    requested: &[EngineCategory],
+    selected: &[Box<dyn Engine>],
+    explicit_engines: bool,
+) -> Vec<EngineCategory> {
+    let mut categories = Vec::new();
+    if requested.is_empty() || explicit_engines {
+        categories.push(EngineCategory::General);
+    }
+    if explicit_engines {
+        add_categories(&mut categories, selected);
+    }
+    for category in requested {
+        if !categories.contains(category) {
+            categories.push(*category);
+        }
+    }
+    categories
+}
+
+/// Returns a warning for each requested category no selected engine serves.
+fn category_warnings(requested: &[EngineCategory], selected: &[Box<dyn Engine>]) -> Vec<String> {
+    requested
+        .iter()
+        .filter(|category| {
+            !selected
+                .iter()
+                .any(|engine| engine.config().categories.contains(category))
+        })
+        .map(|category| {
+            format!(
+                "Warning: no selected engine serves the '{}' category",
+                format!("{:?}", category).to_lowercase()
+            )
+        })
+        .collect()
+}
+
+/// Engines that render their pages in headless Chrome.
+const HEADLESS_ENGINES: &[&str] = &["g", "google", "baidu", "bing_cn", "bing"];
+
+/// Returns a progress callback drawing the Chrome download and extraction
+/// as a percentage on one stderr line.
+#[cfg(feature = "headless")]
+fn download_progress_printer() -> impl Fn(DownloadProgress) + Send + Sync {
+    let last = std::sync::Mutex::new(None);
+    move |progress: DownloadProgress| {
+        let Some(percent) = progress.percent() else {
+            return;
+        };
+        let step = (progress.phase, percent as u8);
+        let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+        if *last == Some(step) {
+            return;
+        }
+        *last = Some(step);
+        let label = match progress.phase {
+            DownloadPhase::Downloading => "Downloading",
+            DownloadPhase::Extracting => "Extracting",
+        };
+        eprint!("\r  {} Chrome: {:>3}%", label, step.1);
+        if step.1 == 100 {
+            eprintln!();
+        }
+    }
+}
+
+/// How long resolved engine hosts are cached.
+const DNS_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Returns the default host of an HTTP engine, for DNS pre-resolution.
+///
+/// YouTube's is that of its Invidious instance, which
+/// `A3S_INVIDIOUS_INSTANCE` may change.
+fn engine_host(shortcut: &str) -> Option<String> {
+    let host = match shortcut {
+        "ddg" | "duckduckgo" => "html.duckduckgo.com",
+        "brave" => "search.brave.com",
+        "wiki" | "wikipedia" => "en.wikipedia.org",
+        "wikt" | "wiktionary" => "en.wiktionary.org",
+        "wikinews" => "en.wikinews.org",
+        "wikibooks" => "en.wikibooks.org",
+        "mar" | "marginalia" => "api.marginalia.nu",
+        "yt" | "youtube" => {
+            let instance = url::Url::parse(engines::YouTube::new().instance()).ok()?;
+            return instance.host_str().map(str::to_string);
+        }
+        "sogou" => "www.sogou.com",
+        "360" | "so360" => "www.so.com",
+        _ => return None,
+    };
+    Some(host.to_string())
+}
+
 /// Truncates a string to at most `max_bytes` bytes at a valid UTF-8 char boundary.
 fn truncate_str(s: &str, max_bytes: usize) -> String {
     if s.len() <= max_bytes {
@@ -399,6 +851,17 @@ mod tests {
         Cli::command().debug_assert();
     }
 
+    #[test]
+    fn test_engine_host() {
+        assert_eq!(engine_host("ddg").as_deref(), Some("html.duckduckgo.com"));
+        assert_eq!(
+            engine_host("wikipedia").as_deref(),
+            Some("en.wikipedia.org")
+        );
+        assert_eq!(engine_host("wikt").as_deref(), Some("en.wiktionary.org"));
+        assert_eq!(engine_host("google"), None);
+    }
+
     #[test]
     fn test_parse_proxy_url_http() {
         let config = parse_proxy_url("http://127.0.0.1:8080").unwrap();
@@ -466,6 +929,73 @@ mod tests {
         let _text = OutputFormat::Text;
         let _json = OutputFormat::Json;
         let _compact = OutputFormat::Compact;
+        let _markdown = OutputFormat::Markdown;
+        let _csv = OutputFormat::Csv;
+        let _jsonl = OutputFormat::Jsonl;
+    }
+
+    /// Results with commas, quotes, brackets, line breaks and CJK text.
+    fn crafted_results() -> SearchResults {
+        let mut results = SearchResults::new();
+        results.add_result(
+            SearchResult::new(
+                "https://example.com/wiki/Rust_(language)",
+                "Rust [lang], \"fast\"",
+                "Fast,\nsafe  and \"fun\"",
+            )
+            .with_engine("ddg", 1),
+        );
+        results.add_result(SearchResult::new(
+            "https://例子.cn/页面",
+            "中文标题",
+            "内容，摘要",
+        ));
+        results.add_result(SearchResult::new("https://example.org/", "Third", ""));
+        results
+    }
+
+    #[test]
+    fn test_format_markdown() {
+        assert_eq!(
+            format_markdown(&crafted_results(), 10),
+            "1. [Rust \\[lang\\], \"fast\"](https://example.com/wiki/Rust_%28language%29)\n   \
+             Fast, safe and \"fun\"\n\n\
+             2. [中文标题](https://例子.cn/页面)\n   内容，摘要\n\n\
+             3. [Third](https://example.org/)\n\n"
+        );
+        assert!(format_markdown(&crafted_results(), 1).starts_with("1. "));
+        assert!(!format_markdown(&crafted_results(), 1).contains("2. "));
+    }
+
+    #[test]
+    fn test_format_csv() {
+        let csv = format_csv(&crafted_results(), 2);
+        assert_eq!(
+            csv,
+            "title,url,content,engines,score\n\
+             \"Rust [lang], \"\"fast\"\"\",https://example.com/wiki/Rust_(language),\"Fast,\nsafe  and \"\"fun\"\"\",ddg,0.00\n\
+             中文标题,https://例子.cn/页面,内容，摘要,,0.00\n"
+        );
+    }
+
+    #[test]
+    fn test_format_jsonl() {
+        let jsonl = format_jsonl(&crafted_results(), 2).unwrap();
+        let lines: Vec<&str> = jsonl.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: SearchResult = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first.title, "Rust [lang], \"fast\"");
+        assert_eq!(first.content, "Fast,\nsafe  and \"fun\"");
+        let second: SearchResult = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(second.title, "中文标题");
+    }
+
+    #[test]
+    fn test_cli_with_format_markdown_csv_jsonl() {
+        for (name, expected) in [("markdown", "Markdown"), ("csv", "Csv"), ("jsonl", "Jsonl")] {
+            let cli = Cli::parse_from(["a3s-search", "query", "-f", name]);
+            assert_eq!(format!("{:?}", cli.format), expected);
+        }
     }
 
     #[test]
@@ -494,6 +1024,185 @@ mod tests {
         assert_eq!(cli.limit, 5);
     }
 
+    #[test]
+    fn test_cli_with_page() {
+        let cli = Cli::parse_from(["a3s-search", "query"]);
+        assert_eq!(cli.page, 1);
+        assert_eq!(cli.pages, None);
+
+        let cli = Cli::parse_from(["a3s-search", "query", "--page", "3", "--pages", "2"]);
+        assert_eq!(cli.page, 3);
+        assert_eq!(cli.pages, Some(2));
+    }
+
+    #[test]
+    fn test_cli_rejects_out_of_range_pages() {
+        for args in [
+            ["a3s-search", "query", "--page", "0"],
+            ["a3s-search", "query", "--page", "101"],
+            ["a3s-search", "query", "--pages", "0"],
+            ["a3s-search", "query", "--pages", "11"],
+        ] {
+            let err = Cli::try_parse_from(args).err().unwrap().to_string();
+            assert!(err.contains("is not in 1..="), "{}", err);
+        }
+    }
+
+    #[test]
+    fn test_cli_time_range() {
+        let cli = Cli::parse_from(["a3s-search", "query"]);
+        assert_eq!(cli.time_range, None);
+
+        for (value, expected) in [
+            ("day", TimeRangeArg::Day),
+            ("week", TimeRangeArg::Week),
+            ("month", TimeRangeArg::Month),
+            ("year", TimeRangeArg::Year),
+        ] {
+            let cli = Cli::parse_from(["a3s-search", "query", "--time-range", value]);
+            assert_eq!(cli.time_range, Some(expected));
+        }
+
+        let err = Cli::try_parse_from(["a3s-search", "query", "--time-range", "decade"])
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(
+            err.contains("possible values: day, week, month, year"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_build_query() {
+        let mut args = SearchArgs {
+            query: "rust news".to_string(),
+            engines: None,
+            limit: 10,
+            timeout: 10,
+            format: OutputFormat::Text,
+            proxy: None,
+            page: 2,
+            pages: None,
+            time_range: None,
+            language: None,
+            safesearch: None,
+            categories: Vec::new(),
+            #[cfg(feature = "headless")]
+            no_download: false,
+        };
+        let query = build_query(&args, vec![EngineCategory::General]);
+        assert_eq!(query.query, "rust news");
+        assert_eq!(query.page, 2);
+        assert_eq!(query.categories, vec![EngineCategory::General]);
+        assert_eq!(query.time_range, None);
+
+        args.time_range = Some(TimeRangeArg::Week);
+        let query = build_query(&args, vec![EngineCategory::General]);
+        assert_eq!(query.time_range, Some(TimeRange::Week));
+        assert_eq!(query.language, None);
+
+        args.language = Some("zh-CN".to_string());
+        let query = build_query(&args, vec![EngineCategory::General]);
+        assert_eq!(query.language.as_deref(), Some("zh-CN"));
+        assert_eq!(query.accept_language().as_deref(), Some("zh-CN,zh;q=0.9"));
+    }
+
+    #[test]
+    fn test_cli_safesearch() {
+        let cli = Cli::parse_from(["a3s-search", "query"]);
+        assert_eq!(cli.safesearch, None);
+        for (value, expected) in [
+            ("off", SafeSearchArg::Off),
+            ("moderate", SafeSearchArg::Moderate),
+            ("strict", SafeSearchArg::Strict),
+        ] {
+            let cli = Cli::parse_from(["a3s-search", "query", "--safesearch", value]);
+            assert_eq!(cli.safesearch, Some(expected));
+        }
+        assert!(Cli::try_parse_from(["a3s-search", "query", "--safesearch", "high"]).is_err());
+    }
+
+    #[test]
+    fn test_build_query_safesearch() {
+        let args = SearchArgs {
+            query: "rust".to_string(),
+            engines: None,
+            limit: 10,
+            timeout: 10,
+            format: OutputFormat::Text,
+            proxy: None,
+            page: 1,
+            pages: None,
+            time_range: None,
+            language: None,
+            safesearch: Some(SafeSearchArg::Strict),
+            categories: Vec::new(),
+            #[cfg(feature = "headless")]
+            no_download: false,
+        };
+        let query = build_query(&args, vec![EngineCategory::General]);
+        assert_eq!(query.safesearch, SafeSearch::Strict);
+    }
+
+    #[test]
+    fn test_safesearch_warning() {
+        let fetcher = HttpFetcher::new();
+        let wiki_only = vec![engines::by_shortcut("wiki", &fetcher).unwrap()];
+        let with_ddg = vec![
+            engines::by_shortcut("wiki", &fetcher).unwrap(),
+            engines::by_shortcut("ddg", &fetcher).unwrap(),
+        ];
+
+        let warning = safesearch_warning(SafeSearch::Strict, &wiki_only).unwrap();
+        assert!(
+            warning.contains("none of the selected engines"),
+            "{}",
+            warning
+        );
+        assert_eq!(safesearch_warning(SafeSearch::Strict, &with_ddg), None);
+        assert_eq!(safesearch_warning(SafeSearch::Moderate, &wiki_only), None);
+        assert_eq!(safesearch_warning(SafeSearch::Off, &wiki_only), None);
+    }
+
+    #[test]
+    fn test_safesearch_engines() {
+        let shortcuts = safesearch_engines();
+        assert!(shortcuts.contains(&"ddg".to_string()));
+        assert!(shortcuts.contains(&"brave".to_string()));
+        assert!(!shortcuts.contains(&"wiki".to_string()));
+        #[cfg(feature = "headless")]
+        assert!(shortcuts.contains(&"g".to_string()));
+    }
+
+    #[test]
+    fn test_cli_language() {
+        let cli = Cli::parse_from(["a3s-search", "query"]);
+        assert_eq!(cli.language, None);
+        let cli = Cli::parse_from(["a3s-search", "query", "-L", "zh-CN"]);
+        assert_eq!(cli.language.as_deref(), Some("zh-CN"));
+        let cli = Cli::parse_from(["a3s-search", "query", "--language", "de"]);
+        assert_eq!(cli.language.as_deref(), Some("de"));
+    }
+
+    #[test]
+    fn test_looks_like_language_tag() {
+        for tag in ["en", "zh-CN", "zh_TW", "zh-Hant-TW", "es-419", "fil"] {
+            assert!(looks_like_language_tag(tag), "{}", tag);
+        }
+        for tag in ["", "e", "english", "en-", "zh CN", "12", "en-toolongsubtag"] {
+            assert!(!looks_like_language_tag(tag), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn test_page_label() {
+        assert_eq!(page_label(1, None), "page 1");
+        assert_eq!(page_label(2, Some(1)), "page 2");
+        assert_eq!(page_label(1, Some(3)), "pages 1-3");
+    }
+
     #[test]
     fn test_cli_with_timeout() {
         let cli = Cli::parse_from(["a3s-search", "query", "-t", "30"]);
@@ -563,6 +1272,116 @@ mod tests {
         assert!(matches!(cli.command, Some(Commands::Engines)));
     }
 
+    #[cfg(feature = "headless")]
+    #[test]
+    fn test_cli_doctor_subcommand() {
+        let cli = Cli::parse_from(["a3s-search", "doctor"]);
+        assert!(matches!(cli.command, Some(Commands::Doctor { keep: 1 })));
+
+        let cli = Cli::parse_from(["a3s-search", "doctor", "--keep", "3"]);
+        assert!(matches!(cli.command, Some(Commands::Doctor { keep: 3 })));
+    }
+
+    #[test]
+    fn test_http_engines_have_hosts() {
+        assert!(engines::HTTP_ENGINES
+            .iter()
+            .all(|s| engine_host(s).is_some()));
+    }
+
+    #[test]
+    fn test_cli_category() {
+        let cli = Cli::parse_from(["a3s-search", "query"]);
+        assert!(cli.categories.is_empty());
+
+        let cli = Cli::parse_from([
+            "a3s-search",
+            "query",
+            "--category",
+            "videos",
+            "--category",
+            "news,science",
+        ]);
+        assert_eq!(
+            cli.categories,
+            vec![CategoryArg::Videos, CategoryArg::News, CategoryArg::Science]
+        );
+        assert_eq!(
+            EngineCategory::from(CategoryArg::Social),
+            EngineCategory::Social
+        );
+
+        let err = Cli::try_parse_from(["a3s-search", "query", "--category", "recipes"])
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.contains("possible values"), "{}", err);
+    }
+
+    #[test]
+    fn test_query_categories_without_engines() {
+        let all = engines::all_http(HttpFetcher::new());
+
+        // No category: the default set, without vertical engines
+        assert_eq!(
+            query_categories(&[], &all, false),
+            vec![EngineCategory::General]
+        );
+
+        // Vertical engines run when chosen with -e
+        assert_eq!(
+            query_categories(&[], &all, true),
+            vec![EngineCategory::General, EngineCategory::Videos]
+        );
+
+        // A category alone narrows the search to engines serving it
+        let categories = query_categories(&[EngineCategory::Videos], &all, false);
+        assert_eq!(categories, vec![EngineCategory::Videos]);
+        assert!(category_warnings(&[EngineCategory::Videos], &all).is_empty());
+    }
+
+    #[test]
+    fn test_query_categories_explicit_engines_win() {
+        let fetcher = HttpFetcher::new();
+        let explicit = vec![
+            engines::by_shortcut("ddg", &fetcher).unwrap(),
+            engines::by_shortcut("yt", &fetcher).unwrap(),
+        ];
+
+        // Every chosen engine still runs, and the query asks for news too
+        let categories = query_categories(&[EngineCategory::News], &explicit, true);
+        assert_eq!(
+            categories,
+            vec![
+                EngineCategory::General,
+                EngineCategory::Videos,
+                EngineCategory::News
+            ]
+        );
+        for engine in &explicit {
+            assert!(engine
+                .config()
+                .categories
+                .iter()
+                .any(|c| categories.contains(c)));
+        }
+
+        assert_eq!(
+            category_warnings(&[EngineCategory::News, EngineCategory::Videos], &explicit),
+            vec!["Warning: no selected engine serves the 'news' category"]
+        );
+    }
+
+    #[test]
+    fn test_add_categories() {
+        let mut categories = vec![EngineCategory::General];
+        add_categories(&mut categories, &engines::all_http(HttpFetcher::new()));
+        assert_eq!(
+            categories,
+            vec![EngineCategory::General, EngineCategory::Videos]
+        );
+    }
+
     #[test]
     fn test_cli_no_args() {
         let cli = Cli::parse_from(["a3s-search"]);
@@ -570,6 +1389,15 @@ mod tests {
         assert!(cli.command.is_none());
     }
 
+    #[cfg(feature = "headless")]
+    #[test]
+    fn test_cli_no_download() {
+        let cli = Cli::parse_from(["a3s-search", "query", "-e", "g"]);
+        assert!(!cli.no_download);
+        let cli = Cli::parse_from(["a3s-search", "query", "-e", "g", "--no-download"]);
+        assert!(cli.no_download);
+    }
+
     #[test]
     fn test_cli_with_headless() {
         let cli = Cli::parse_from(["a3s-search", "query", "--headless"]);
@@ -621,7 +1449,7 @@ mod tests {
     #[test]
     fn test_truncate_str_mixed_cjk() {
         let s = "Hello世界！This is a test with 中文 and English mixed content that is long enough to be truncated at some point in the middle of the string somewhere around here.";
-        let result = truncate_str(&s, 150);
+        let result = truncate_str(s, 150);
         assert!(result.ends_with("..."));
         // Must not panic on mixed content
     }
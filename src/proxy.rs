@@ -4,11 +4,14 @@
 //! search engines to rotate through multiple proxy IPs to avoid being
 //! blocked by anti-crawler mechanisms.
 
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use reqwest::{Client, Proxy as ReqwestProxy};
 use tokio::sync::RwLock;
 use tracing::debug;
@@ -82,6 +85,11 @@ impl ProxyConfig {
             _ => format!("{}://{}:{}", scheme, self.host, self.port),
         }
     }
+
+    /// Returns the `host:port` key identifying this proxy in the pool.
+    fn key(&self) -> String {
+        format!("{}:{}", self.host, self.port)
+    }
 }
 
 /// Proxy selection strategy.
@@ -129,13 +137,144 @@ impl ProxyProvider for StaticProxyProvider {
     }
 }
 
+/// Consecutive failures before a proxy is quarantined by default.
+const DEFAULT_MAX_FAILURES: u32 = 3;
+
+/// How long a failing proxy is skipped by default.
+const DEFAULT_QUARANTINE: Duration = Duration::from_secs(60);
+
+/// Failure tracking for a single proxy.
+#[derive(Debug, Default)]
+struct ProxyHealth {
+    consecutive_failures: u32,
+    quarantined_until: Option<Instant>,
+}
+
+impl ProxyHealth {
+    fn is_quarantined(&self, now: Instant) -> bool {
+        self.quarantined_until.is_some_and(|until| until > now)
+    }
+}
+
+/// How often a proxy of a [`ProxyPool`] has been handed out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProxyStat {
+    /// Proxy host (IP or domain)
+    pub host: String,
+    /// Proxy port
+    pub port: u16,
+    /// Times [`ProxyPool::get_proxy`] returned this proxy.
+    pub uses: u64,
+    /// When the proxy was last returned, if ever.
+    pub last_used: Option<Instant>,
+}
+
+/// Usage counters for a single proxy, updated without taking a write lock.
+#[derive(Debug)]
+struct ProxyUsage {
+    host: String,
+    port: u16,
+    uses: AtomicU64,
+    /// Nanoseconds from the pool's creation to the last use, plus one;
+    /// zero if never used.
+    last_used: AtomicU64,
+}
+
+impl ProxyUsage {
+    fn new(proxy: &ProxyConfig) -> Self {
+        Self {
+            host: proxy.host.clone(),
+            port: proxy.port,
+            uses: AtomicU64::new(0),
+            last_used: AtomicU64::new(0),
+        }
+    }
+}
+
+/// Per-proxy usage counters, keyed like the health map.
+#[derive(Debug)]
+struct UsageStats {
+    created: Instant,
+    proxies: std::sync::RwLock<HashMap<String, ProxyUsage>>,
+}
+
+impl UsageStats {
+    fn new(proxies: &[ProxyConfig]) -> Self {
+        let stats = Self {
+            created: Instant::now(),
+            proxies: std::sync::RwLock::new(HashMap::new()),
+        };
+        stats.track(proxies);
+        stats
+    }
+
+    /// Keeps counters for exactly `proxies`, preserving existing counts.
+    fn track(&self, proxies: &[ProxyConfig]) {
+        let mut usage = self.proxies.write().unwrap_or_else(|e| e.into_inner());
+        usage.retain(|key, _| proxies.iter().any(|p| &p.key() == key));
+        for proxy in proxies {
+            usage
+                .entry(proxy.key())
+                .or_insert_with(|| ProxyUsage::new(proxy));
+        }
+    }
+
+    /// Counts one use of `proxy`.
+    fn record(&self, proxy: &ProxyConfig) {
+        let key = proxy.key();
+        let now = self.created.elapsed().as_nanos() as u64 + 1;
+        let bump = |usage: &ProxyUsage| {
+            usage.uses.fetch_add(1, Ordering::Relaxed);
+            usage.last_used.fetch_max(now, Ordering::Relaxed);
+        };
+        {
+            let usage = self.proxies.read().unwrap_or_else(|e| e.into_inner());
+            if let Some(entry) = usage.get(&key) {
+                bump(entry);
+                return;
+            }
+        }
+        let mut usage = self.proxies.write().unwrap_or_else(|e| e.into_inner());
+        bump(usage.entry(key).or_insert_with(|| ProxyUsage::new(proxy)));
+    }
+
+    fn snapshot(&self) -> Vec<ProxyStat> {
+        let usage = self.proxies.read().unwrap_or_else(|e| e.into_inner());
+        let mut stats: Vec<ProxyStat> = usage
+            .values()
+            .map(|usage| {
+                let last_used = usage.last_used.load(Ordering::Relaxed);
+                ProxyStat {
+                    host: usage.host.clone(),
+                    port: usage.port,
+                    uses: usage.uses.load(Ordering::Relaxed),
+                    last_used: (last_used > 0)
+                        .then(|| self.created + Duration::from_nanos(last_used - 1)),
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| a.host.cmp(&b.host).then(a.port.cmp(&b.port)));
+        stats
+    }
+}
+
 /// A proxy pool that manages multiple proxies with rotation.
+///
+/// Callers report request outcomes with [`report_success`](Self::report_success)
+/// and [`report_failure`](Self::report_failure). A proxy that fails
+/// `max_failures` times in a row is skipped for the quarantine period.
 pub struct ProxyPool {
     proxies: Arc<RwLock<Vec<ProxyConfig>>>,
     provider: Option<Arc<dyn ProxyProvider>>,
     strategy: ProxyStrategy,
     current_index: AtomicUsize,
     enabled: bool,
+    max_failures: u32,
+    quarantine: Duration,
+    health: Mutex<HashMap<String, ProxyHealth>>,
+    usage: UsageStats,
+    /// Source of `ProxyStrategy::Random` picks.
+    rng: Mutex<StdRng>,
 }
 
 impl ProxyPool {
@@ -147,18 +286,29 @@ impl ProxyPool {
             strategy: ProxyStrategy::RoundRobin,
             current_index: AtomicUsize::new(0),
             enabled: false,
+            max_failures: DEFAULT_MAX_FAILURES,
+            quarantine: DEFAULT_QUARANTINE,
+            health: Mutex::new(HashMap::new()),
+            usage: UsageStats::new(&[]),
+            rng: Mutex::new(StdRng::from_os_rng()),
         }
     }
 
     /// Creates a proxy pool with static proxies.
     pub fn with_proxies(proxies: Vec<ProxyConfig>) -> Self {
         let enabled = !proxies.is_empty();
+        let usage = UsageStats::new(&proxies);
         Self {
             proxies: Arc::new(RwLock::new(proxies)),
             provider: None,
             strategy: ProxyStrategy::RoundRobin,
             current_index: AtomicUsize::new(0),
             enabled,
+            max_failures: DEFAULT_MAX_FAILURES,
+            quarantine: DEFAULT_QUARANTINE,
+            health: Mutex::new(HashMap::new()),
+            usage,
+            rng: Mutex::new(StdRng::from_os_rng()),
         }
     }
 
@@ -170,6 +320,11 @@ impl ProxyPool {
             strategy: ProxyStrategy::RoundRobin,
             current_index: AtomicUsize::new(0),
             enabled: true,
+            max_failures: DEFAULT_MAX_FAILURES,
+            quarantine: DEFAULT_QUARANTINE,
+            health: Mutex::new(HashMap::new()),
+            usage: UsageStats::new(&[]),
+            rng: Mutex::new(StdRng::from_os_rng()),
         }
     }
 
@@ -179,6 +334,22 @@ impl ProxyPool {
         self
     }
 
+    /// Seeds the `ProxyStrategy::Random` selection, so that the same seed
+    /// picks the same sequence of proxies from the same pool. Without a
+    /// seed, the selection is seeded from the operating system.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Mutex::new(StdRng::seed_from_u64(seed));
+        self
+    }
+
+    /// Sets how many consecutive failures quarantine a proxy, and for how
+    /// long. Zero failures is treated as one.
+    pub fn with_quarantine(mut self, max_failures: u32, duration: Duration) -> Self {
+        self.max_failures = max_failures.max(1);
+        self.quarantine = duration;
+        self
+    }
+
     /// Enables or disables the proxy pool.
     pub fn set_enabled(&mut self, enabled: bool) {
         self.enabled = enabled;
@@ -195,6 +366,7 @@ impl ProxyPool {
             let new_proxies = provider.fetch_proxies().await?;
             debug!("Refreshed proxy pool with {} proxies", new_proxies.len());
             let mut proxies = self.proxies.write().await;
+            self.usage.track(&new_proxies);
             *proxies = new_proxies;
         }
         Ok(())
@@ -211,6 +383,8 @@ impl ProxyPool {
     }
 
     /// Gets the next proxy based on the selection strategy.
+    ///
+    /// Quarantined proxies are skipped unless every proxy is quarantined.
     pub async fn get_proxy(&self) -> Option<ProxyConfig> {
         if !self.enabled {
             return None;
@@ -221,40 +395,104 @@ impl ProxyPool {
             return None;
         }
 
+        let available: Vec<&ProxyConfig> = {
+            let now = Instant::now();
+            let health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+            proxies
+                .iter()
+                .filter(|p| {
+                    health
+                        .get(&p.key())
+                        .filter(|h| h.is_quarantined(now))
+                        .is_none()
+                })
+                .collect()
+        };
+        let candidates: Vec<&ProxyConfig> = if available.is_empty() {
+            proxies.iter().collect()
+        } else {
+            available
+        };
+
         let index = match self.strategy {
             ProxyStrategy::RoundRobin => {
-                self.current_index.fetch_add(1, Ordering::SeqCst) % proxies.len()
-            }
-            ProxyStrategy::Random => {
-                use std::time::{SystemTime, UNIX_EPOCH};
-                let seed = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_nanos() as usize;
-                seed % proxies.len()
+                self.current_index.fetch_add(1, Ordering::SeqCst) % candidates.len()
             }
+            ProxyStrategy::Random => self
+                .rng
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .random_range(0..candidates.len()),
         };
 
-        proxies.get(index).cloned()
+        let proxy = candidates.get(index).map(|p| (*p).clone());
+        if let Some(ref proxy) = proxy {
+            self.usage.record(proxy);
+        }
+        proxy
+    }
+
+    /// Returns how often each proxy in the pool has been handed out by
+    /// [`get_proxy`](Self::get_proxy), sorted by host and port.
+    ///
+    /// Counts survive [`refresh`](Self::refresh) for proxies that stay in
+    /// the pool, and are dropped with proxies that leave it.
+    pub fn stats(&self) -> Vec<ProxyStat> {
+        self.usage.snapshot()
+    }
+
+    /// Records a successful request through `proxy`, clearing its failures.
+    pub fn report_success(&self, proxy: &ProxyConfig) {
+        let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        health.remove(&proxy.key());
+    }
+
+    /// Records a failed request through `proxy`, quarantining it once it
+    /// reaches the consecutive failure limit.
+    pub fn report_failure(&self, proxy: &ProxyConfig) {
+        let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = health.entry(proxy.key()).or_default();
+        entry.consecutive_failures += 1;
+        if entry.consecutive_failures >= self.max_failures {
+            debug!(
+                "Quarantining proxy {}:{} for {:?}",
+                proxy.host, proxy.port, self.quarantine
+            );
+            entry.consecutive_failures = 0;
+            entry.quarantined_until = Some(Instant::now() + self.quarantine);
+        }
+    }
+
+    /// Returns whether `proxy` is currently quarantined.
+    pub fn is_quarantined(&self, proxy: &ProxyConfig) -> bool {
+        let health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        health
+            .get(&proxy.key())
+            .is_some_and(|h| h.is_quarantined(Instant::now()))
     }
 
     /// Adds a proxy to the pool.
     pub async fn add_proxy(&self, proxy: ProxyConfig) {
         let mut proxies = self.proxies.write().await;
         proxies.push(proxy);
+        self.usage.track(&proxies);
     }
 
     /// Removes a proxy from the pool by host and port.
     pub async fn remove_proxy(&self, host: &str, port: u16) {
         let mut proxies = self.proxies.write().await;
         proxies.retain(|p| !(p.host == host && p.port == port));
+        self.usage.track(&proxies);
     }
 
     /// Creates a reqwest Client configured with the next proxy.
-    pub async fn create_client(&self, user_agent: &str) -> Result<Client> {
+    ///
+    /// `timeout` bounds both connection establishment and the whole request.
+    pub async fn create_client(&self, user_agent: &str, timeout: Duration) -> Result<Client> {
         let mut builder = Client::builder()
             .user_agent(user_agent)
-            .timeout(Duration::from_secs(30));
+            .connect_timeout(timeout)
+            .timeout(timeout);
 
         if let Some(proxy_config) = self.get_proxy().await {
             let proxy_url = proxy_config.url();
@@ -450,6 +688,60 @@ mod tests {
         assert_eq!(p4.port, 8080); // Wraps around
     }
 
+    #[tokio::test]
+    async fn test_proxy_pool_quarantines_failing_proxy() {
+        let proxies = vec![
+            ProxyConfig::new("127.0.0.1", 8080),
+            ProxyConfig::new("127.0.0.1", 8081),
+        ];
+        let pool = ProxyPool::with_proxies(proxies).with_quarantine(2, Duration::from_secs(60));
+        let bad = ProxyConfig::new("127.0.0.1", 8080);
+
+        pool.report_failure(&bad);
+        assert!(!pool.is_quarantined(&bad));
+        pool.report_failure(&bad);
+        assert!(pool.is_quarantined(&bad));
+
+        for _ in 0..4 {
+            assert_eq!(pool.get_proxy().await.unwrap().port, 8081);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy_pool_success_resets_failures() {
+        let pool = ProxyPool::with_proxies(vec![ProxyConfig::new("127.0.0.1", 8080)])
+            .with_quarantine(2, Duration::from_secs(60));
+        let proxy = ProxyConfig::new("127.0.0.1", 8080);
+
+        pool.report_failure(&proxy);
+        pool.report_success(&proxy);
+        pool.report_failure(&proxy);
+        assert!(!pool.is_quarantined(&proxy));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_pool_all_quarantined_still_returns_proxy() {
+        let pool = ProxyPool::with_proxies(vec![ProxyConfig::new("127.0.0.1", 8080)])
+            .with_quarantine(1, Duration::from_secs(60));
+        let proxy = ProxyConfig::new("127.0.0.1", 8080);
+
+        pool.report_failure(&proxy);
+        assert!(pool.is_quarantined(&proxy));
+        assert_eq!(pool.get_proxy().await.unwrap().port, 8080);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_pool_quarantine_expires() {
+        let pool = ProxyPool::with_proxies(vec![ProxyConfig::new("127.0.0.1", 8080)])
+            .with_quarantine(1, Duration::from_millis(20));
+        let proxy = ProxyConfig::new("127.0.0.1", 8080);
+
+        pool.report_failure(&proxy);
+        assert!(pool.is_quarantined(&proxy));
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert!(!pool.is_quarantined(&proxy));
+    }
+
     #[tokio::test]
     async fn test_proxy_pool_get_proxy_random() {
         let proxies = vec![
@@ -463,6 +755,51 @@ mod tests {
         assert!(proxy.port == 8080 || proxy.port == 8081);
     }
 
+    #[tokio::test]
+    async fn test_proxy_pool_random_is_uniform() {
+        let pool = ProxyPool::with_proxies(vec![
+            ProxyConfig::new("127.0.0.1", 8080),
+            ProxyConfig::new("127.0.0.1", 8081),
+            ProxyConfig::new("127.0.0.1", 8082),
+        ])
+        .with_strategy(ProxyStrategy::Random);
+
+        // Tight loop: selection must not depend on the clock
+        let mut counts = [0u32; 3];
+        for _ in 0..30_000 {
+            counts[(pool.get_proxy().await.unwrap().port - 8080) as usize] += 1;
+        }
+        // Expected 10,000 each; the standard deviation is about 82
+        for count in counts {
+            assert!((9_000..=11_000).contains(&count), "{:?}", counts);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_proxy_pool_random_seeded_is_reproducible() {
+        let proxies: Vec<ProxyConfig> = (8080..8090)
+            .map(|port| ProxyConfig::new("127.0.0.1", port))
+            .collect();
+        let picks = |seed: u64| {
+            let pool = ProxyPool::with_proxies(proxies.clone())
+                .with_strategy(ProxyStrategy::Random)
+                .with_seed(seed);
+            async move {
+                let mut ports = Vec::new();
+                for _ in 0..20 {
+                    ports.push(pool.get_proxy().await.unwrap().port);
+                }
+                ports
+            }
+        };
+
+        let first = picks(42).await;
+        assert_eq!(first, picks(42).await);
+        assert_ne!(first, picks(7).await);
+        // Not stuck on one proxy
+        assert!(first.iter().any(|&port| port != first[0]));
+    }
+
     #[tokio::test]
     async fn test_proxy_pool_refresh_no_provider() {
         let pool = ProxyPool::new();
@@ -488,7 +825,10 @@ mod tests {
     #[tokio::test]
     async fn test_proxy_pool_create_client_no_proxy() {
         let pool = ProxyPool::new();
-        let client = pool.create_client("test-agent").await.unwrap();
+        let client = pool
+            .create_client("test-agent", Duration::from_secs(30))
+            .await
+            .unwrap();
         // Client should be created successfully without proxy
         drop(client);
     }
@@ -497,7 +837,10 @@ mod tests {
     async fn test_proxy_pool_create_client_with_proxy() {
         let proxies = vec![ProxyConfig::new("127.0.0.1", 8080)];
         let pool = ProxyPool::with_proxies(proxies);
-        let client = pool.create_client("test-agent").await.unwrap();
+        let client = pool
+            .create_client("test-agent", Duration::from_secs(30))
+            .await
+            .unwrap();
         // Client should be created with proxy configured
         drop(client);
     }
@@ -609,4 +952,75 @@ mod tests {
         let provider = CustomProvider;
         assert_eq!(provider.refresh_interval(), Duration::from_secs(300));
     }
+
+    #[tokio::test]
+    async fn test_proxy_pool_stats_count_uses() {
+        let pool = ProxyPool::with_proxies(vec![
+            ProxyConfig::new("10.0.0.1", 8080),
+            ProxyConfig::new("10.0.0.2", 8080),
+            ProxyConfig::new("10.0.0.3", 8080),
+        ]);
+        let stats = pool.stats();
+        assert_eq!(stats.len(), 3);
+        assert!(stats.iter().all(|s| s.uses == 0 && s.last_used.is_none()));
+
+        let before = Instant::now();
+        for _ in 0..7 {
+            pool.get_proxy().await.unwrap();
+        }
+        let stats = pool.stats();
+        assert_eq!(stats.iter().map(|s| s.uses).sum::<u64>(), 7);
+        // Round-robin spreads the calls evenly
+        let uses: Vec<u64> = stats.iter().map(|s| s.uses).collect();
+        assert_eq!(uses, vec![3, 2, 2]);
+        assert_eq!(stats[0].host, "10.0.0.1");
+        assert!(stats
+            .iter()
+            .all(|s| s.last_used.is_some_and(|at| at >= before)));
+    }
+
+    #[tokio::test]
+    async fn test_proxy_pool_stats_concurrent() {
+        let pool = Arc::new(ProxyPool::with_proxies(vec![
+            ProxyConfig::new("10.0.0.1", 8080),
+            ProxyConfig::new("10.0.0.2", 8080),
+        ]));
+        let tasks: Vec<_> = (0..8)
+            .map(|_| {
+                let pool = pool.clone();
+                tokio::spawn(async move {
+                    for _ in 0..25 {
+                        pool.get_proxy().await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+        assert_eq!(pool.stats().iter().map(|s| s.uses).sum::<u64>(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_proxy_pool_stats_follow_membership() {
+        let pool = ProxyPool::with_proxies(vec![ProxyConfig::new("10.0.0.1", 8080)]);
+        pool.get_proxy().await.unwrap();
+
+        pool.add_proxy(ProxyConfig::new("10.0.0.2", 8080)).await;
+        let stats = pool.stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].uses, 1);
+        assert_eq!(stats[1].uses, 0);
+
+        pool.remove_proxy("10.0.0.1", 8080).await;
+        let stats = pool.stats();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].host, "10.0.0.2");
+
+        // A disabled pool hands out nothing and counts nothing
+        let mut pool = ProxyPool::with_proxies(vec![ProxyConfig::new("10.0.0.1", 8080)]);
+        pool.set_enabled(false);
+        assert!(pool.get_proxy().await.is_none());
+        assert_eq!(pool.stats()[0].uses, 0);
+    }
 }
@@ -1,11 +1,27 @@
 //! Search query representation.
 
-use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::EngineCategory;
 
+/// Returns the 64-bit FNV-1a hash of `bytes`.
+///
+/// Unlike `DefaultHasher`, the value is stable across Rust versions, so it
+/// can name cache files and recordings that outlive the process.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
 /// Safe search level.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+///
+/// Serializes as lowercase (`"off"`, `"moderate"`, `"strict"`); any casing is
+/// accepted when deserializing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum SafeSearch {
     /// No filtering.
     #[default]
@@ -16,8 +32,35 @@ pub enum SafeSearch {
     Strict = 2,
 }
 
+impl FromStr for SafeSearch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "off" => Ok(SafeSearch::Off),
+            "moderate" => Ok(SafeSearch::Moderate),
+            "strict" => Ok(SafeSearch::Strict),
+            _ => Err(format!(
+                "unknown safe search level '{}', expected off, moderate, or strict",
+                s
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SafeSearch {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 /// Time range filter for search results.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// Serializes as lowercase (`"day"`, `"week"`, `"month"`, `"year"`); any
+/// casing is accepted when deserializing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum TimeRange {
     Day,
     Week,
@@ -25,6 +68,69 @@ pub enum TimeRange {
     Year,
 }
 
+impl FromStr for TimeRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "day" => Ok(TimeRange::Day),
+            "week" => Ok(TimeRange::Week),
+            "month" => Ok(TimeRange::Month),
+            "year" => Ok(TimeRange::Year),
+            _ => Err(format!(
+                "unknown time range '{}', expected day, week, month, or year",
+                s
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for TimeRange {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+/// How a `site:` operator matches result hosts.
+///
+/// Serializes as lowercase (`"loose"`, `"strict"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SiteMatch {
+    /// Any host under the same registrable domain, per the public suffix
+    /// list: `site:rust-lang.org` matches `blog.rust-lang.org`.
+    #[default]
+    Loose,
+    /// Only the exact host: `site:rust-lang.org` skips `blog.rust-lang.org`.
+    Strict,
+}
+
+impl SiteMatch {
+    /// Whether `host` satisfies a `site:` operator for `site`.
+    ///
+    /// Hosts without a registrable domain, such as IP addresses, are
+    /// always matched exactly.
+    pub fn matches(&self, site: &str, host: &str) -> bool {
+        let site = site.trim_end_matches('.');
+        let host = host.trim_end_matches('.');
+        if site.eq_ignore_ascii_case(host) {
+            return true;
+        }
+        match self {
+            SiteMatch::Strict => false,
+            SiteMatch::Loose => {
+                let site = site.to_ascii_lowercase();
+                let host = host.to_ascii_lowercase();
+                match (psl::domain_str(&site), psl::domain_str(&host)) {
+                    (Some(site), Some(host)) => site == host,
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
 /// A search query with all parameters.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchQuery {
@@ -42,6 +148,13 @@ pub struct SearchQuery {
     pub time_range: Option<TimeRange>,
     /// Specific engines to use (by shortcut).
     pub engines: Vec<String>,
+    /// How `site:` operators in the query match result hosts.
+    #[serde(default)]
+    pub site_match: SiteMatch,
+    /// Results to request from each engine, for engines with a count
+    /// parameter. `None` keeps each engine's default page size.
+    #[serde(default)]
+    pub results_per_engine: Option<u32>,
 }
 
 impl SearchQuery {
@@ -55,6 +168,8 @@ impl SearchQuery {
             page: 1,
             time_range: None,
             engines: Vec::new(),
+            site_match: SiteMatch::Loose,
+            results_per_engine: None,
         }
     }
 
@@ -70,6 +185,19 @@ impl SearchQuery {
         self
     }
 
+    /// Returns an `Accept-Language` header value for the query's language.
+    ///
+    /// A regional tag falls back to its base language, so `"en-US"` yields
+    /// `"en-US,en;q=0.9"`. Returns `None` when no language is set.
+    pub fn accept_language(&self) -> Option<String> {
+        let language = self.language.as_deref()?.trim().replace('_', "-");
+        match language.split_once('-') {
+            _ if language.is_empty() => None,
+            Some((base, _)) if !base.is_empty() => Some(format!("{},{};q=0.9", language, base)),
+            _ => Some(language),
+        }
+    }
+
     /// Sets the safe search level.
     pub fn with_safesearch(mut self, level: SafeSearch) -> Self {
         self.safesearch = level;
@@ -93,12 +221,117 @@ impl SearchQuery {
         self.engines = engines;
         self
     }
+
+    /// Sets how `site:` operators match result hosts.
+    pub fn with_site_match(mut self, site_match: SiteMatch) -> Self {
+        self.site_match = site_match;
+        self
+    }
+
+    /// Sets how many results each engine should return.
+    ///
+    /// Maps to Wikipedia's `srlimit`, Google's `num` and Bing's `count`,
+    /// clamped to each engine's maximum. Other engines ignore it.
+    pub fn with_results_per_engine(mut self, count: u32) -> Self {
+        self.results_per_engine = Some(count);
+        self
+    }
+
+    /// Returns the requested results per engine, clamped to `1..=max`.
+    pub(crate) fn result_count(&self, max: u32) -> Option<u32> {
+        self.results_per_engine.map(|count| count.clamp(1, max))
+    }
+
+    /// Returns how many results the pages before this one hold, at
+    /// `per_page` results a page.
+    pub(crate) fn page_offset(&self, per_page: u32) -> u32 {
+        self.page.saturating_sub(1).saturating_mul(per_page)
+    }
+
+    /// Returns the hosts named by `site:` operators in the query terms,
+    /// lowercased and without any scheme or path.
+    pub fn sites(&self) -> Vec<String> {
+        self.query
+            .split_whitespace()
+            .filter_map(|term| {
+                let (operator, site) = term.split_once(':')?;
+                if !operator.eq_ignore_ascii_case("site") {
+                    return None;
+                }
+                let site = site
+                    .split_once("://")
+                    .map_or(site, |(_, rest)| rest)
+                    .split(['/', '?', '#'])
+                    .next()
+                    .unwrap_or_default()
+                    .trim_end_matches('.')
+                    .to_ascii_lowercase();
+                (!site.is_empty()).then_some(site)
+            })
+            .collect()
+    }
+
+    /// Whether `url` satisfies the query's `site:` operators: its host must
+    /// match one of them under [`site_match`](Self::site_match). Always
+    /// true for queries without `site:` operators.
+    pub fn matches_sites(&self, url: &str) -> bool {
+        let sites = self.sites();
+        if sites.is_empty() {
+            return true;
+        }
+        let Some(host) = url::Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string))
+        else {
+            return false;
+        };
+        sites
+            .iter()
+            .any(|site| self.site_match.matches(site, &host))
+    }
+
+    /// Returns a stable hash of every parameter engines see, for keying
+    /// cached results.
+    ///
+    /// Queries differing in any of them get different keys; the order of
+    /// categories and engines does not matter. `site_match` is left out, as
+    /// it only filters the merged results.
+    pub fn cache_key(&self) -> String {
+        let mut categories: Vec<String> = self
+            .categories
+            .iter()
+            .map(|c| format!("{:?}", c).to_lowercase())
+            .collect();
+        categories.sort();
+        categories.dedup();
+        let mut engines = self.engines.clone();
+        engines.sort();
+        engines.dedup();
+
+        let canonical = serde_json::json!({
+            "query": self.query,
+            "categories": categories,
+            "language": self.language,
+            "safesearch": self.safesearch,
+            "page": self.page,
+            "time_range": self.time_range,
+            "engines": engines,
+            "results_per_engine": self.results_per_engine,
+        });
+        format!("{:016x}", fnv1a(canonical.to_string().as_bytes()))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+
     #[test]
     fn test_search_query_new() {
         let query = SearchQuery::new("test query");
@@ -109,6 +342,22 @@ mod tests {
         assert!(query.language.is_none());
         assert!(query.time_range.is_none());
         assert!(query.engines.is_empty());
+        assert!(query.results_per_engine.is_none());
+    }
+
+    #[test]
+    fn test_search_query_with_results_per_engine() {
+        let query = SearchQuery::new("test").with_results_per_engine(30);
+        assert_eq!(query.results_per_engine, Some(30));
+        assert_eq!(query.result_count(100), Some(30));
+        assert_eq!(query.result_count(20), Some(20));
+        assert_eq!(
+            SearchQuery::new("test")
+                .with_results_per_engine(0)
+                .result_count(100),
+            Some(1)
+        );
+        assert_eq!(SearchQuery::new("test").result_count(100), None);
     }
 
     #[test]
@@ -127,6 +376,20 @@ mod tests {
         assert_eq!(query.language, Some("en-US".to_string()));
     }
 
+    #[test]
+    fn test_search_query_accept_language() {
+        let accept = |language: &str| {
+            SearchQuery::new("t")
+                .with_language(language)
+                .accept_language()
+        };
+        assert_eq!(accept("en-US"), Some("en-US,en;q=0.9".to_string()));
+        assert_eq!(accept("zh_CN"), Some("zh-CN,zh;q=0.9".to_string()));
+        assert_eq!(accept("de"), Some("de".to_string()));
+        assert_eq!(accept("  "), None);
+        assert_eq!(SearchQuery::new("t").accept_language(), None);
+    }
+
     #[test]
     fn test_search_query_with_safesearch() {
         let query = SearchQuery::new("test").with_safesearch(SafeSearch::Strict);
@@ -139,6 +402,15 @@ mod tests {
         assert_eq!(query.page, 5);
     }
 
+    #[test]
+    fn test_search_query_page_offset() {
+        assert_eq!(SearchQuery::new("test").page_offset(10), 0);
+        assert_eq!(SearchQuery::new("test").with_page(3).page_offset(10), 20);
+        assert_eq!(SearchQuery::new("test").with_page(0).page_offset(10), 0);
+        let last = SearchQuery::new("test").with_page(u32::MAX);
+        assert_eq!(last.page_offset(10), u32::MAX);
+    }
+
     #[test]
     fn test_search_query_with_time_range() {
         let query = SearchQuery::new("test").with_time_range(TimeRange::Week);
@@ -152,6 +424,38 @@ mod tests {
         assert_eq!(query.engines, vec!["ddg", "wiki"]);
     }
 
+    #[test]
+    fn test_search_query_cache_key() {
+        let base = SearchQuery::new("rust");
+        assert_eq!(base.cache_key(), SearchQuery::new("rust").cache_key());
+        assert_eq!(base.cache_key().len(), 16);
+
+        let variants = [
+            SearchQuery::new("go"),
+            base.clone().with_page(2),
+            base.clone().with_safesearch(SafeSearch::Strict),
+            base.clone().with_language("de"),
+            base.clone().with_categories(vec![EngineCategory::News]),
+            base.clone().with_time_range(TimeRange::Week),
+            base.clone().with_engines(vec!["ddg".to_string()]),
+            base.clone().with_results_per_engine(30),
+        ];
+        for variant in &variants {
+            assert_ne!(variant.cache_key(), base.cache_key(), "{:?}", variant);
+        }
+    }
+
+    #[test]
+    fn test_search_query_cache_key_ignores_order() {
+        let a = SearchQuery::new("rust")
+            .with_categories(vec![EngineCategory::General, EngineCategory::News])
+            .with_engines(vec!["ddg".to_string(), "wiki".to_string()]);
+        let b = SearchQuery::new("rust")
+            .with_categories(vec![EngineCategory::News, EngineCategory::General])
+            .with_engines(vec!["wiki".to_string(), "ddg".to_string()]);
+        assert_eq!(a.cache_key(), b.cache_key());
+    }
+
     #[test]
     fn test_search_query_builder_chain() {
         let query = SearchQuery::new("rust programming")
@@ -206,5 +510,154 @@ mod tests {
         let json = r#"{"query":"test","categories":["general"],"language":null,"safesearch":"Off","page":1,"time_range":null,"engines":[]}"#;
         let query: SearchQuery = serde_json::from_str(json).unwrap();
         assert_eq!(query.query, "test");
+        assert!(query.results_per_engine.is_none());
+    }
+
+    #[test]
+    fn test_safe_search_serializes_lowercase() {
+        for (level, expected) in [
+            (SafeSearch::Off, "\"off\""),
+            (SafeSearch::Moderate, "\"moderate\""),
+            (SafeSearch::Strict, "\"strict\""),
+        ] {
+            let json = serde_json::to_string(&level).unwrap();
+            assert_eq!(json, expected);
+            let parsed: SafeSearch = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, level);
+        }
+    }
+
+    #[test]
+    fn test_safe_search_deserializes_any_case() {
+        for input in ["\"Moderate\"", "\"moderate\"", "\"MODERATE\""] {
+            let parsed: SafeSearch = serde_json::from_str(input).unwrap();
+            assert_eq!(parsed, SafeSearch::Moderate);
+        }
+        assert!(serde_json::from_str::<SafeSearch>("\"medium\"").is_err());
+    }
+
+    #[test]
+    fn test_time_range_serializes_lowercase() {
+        for (range, expected) in [
+            (TimeRange::Day, "\"day\""),
+            (TimeRange::Week, "\"week\""),
+            (TimeRange::Month, "\"month\""),
+            (TimeRange::Year, "\"year\""),
+        ] {
+            let json = serde_json::to_string(&range).unwrap();
+            assert_eq!(json, expected);
+            let parsed: TimeRange = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, range);
+        }
+    }
+
+    #[test]
+    fn test_time_range_deserializes_any_case() {
+        for input in ["\"Week\"", "\"week\"", "\"WEEK\""] {
+            let parsed: TimeRange = serde_json::from_str(input).unwrap();
+            assert_eq!(parsed, TimeRange::Week);
+        }
+        let err = serde_json::from_str::<TimeRange>("\"decade\"")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("unknown time range"), "{}", err);
+    }
+
+    #[test]
+    fn test_search_query_round_trip_lowercase() {
+        let query = SearchQuery::new("test")
+            .with_safesearch(SafeSearch::Strict)
+            .with_time_range(TimeRange::Month);
+        let json = serde_json::to_string(&query).unwrap();
+        assert!(json.contains("\"safesearch\":\"strict\""), "{}", json);
+        assert!(json.contains("\"time_range\":\"month\""), "{}", json);
+
+        let parsed: SearchQuery = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.safesearch, SafeSearch::Strict);
+        assert_eq!(parsed.time_range, Some(TimeRange::Month));
+    }
+
+    #[test]
+    fn test_search_query_sites() {
+        let query =
+            SearchQuery::new("async site:rust-lang.org SITE:https://Docs.rs/tokio site: -site");
+        assert_eq!(query.sites(), vec!["rust-lang.org", "docs.rs"]);
+        assert!(SearchQuery::new("rust").sites().is_empty());
+        assert_eq!(SearchQuery::new("rust").site_match, SiteMatch::Loose);
+    }
+
+    fn site_results() -> [(&'static str, bool, bool); 6] {
+        // (url, loose, strict)
+        [
+            ("https://rust-lang.org/learn", true, true),
+            (
+                "https://blog.rust-lang.org/2024/01/01/post.html",
+                true,
+                false,
+            ),
+            ("https://www.rust-lang.org/", true, false),
+            ("https://rust-lang.org.evil.example/", false, false),
+            ("https://notrust-lang.org/", false, false),
+            ("https://github.com/rust-lang/rust", false, false),
+        ]
+    }
+
+    #[test]
+    fn test_matches_sites_loose() {
+        let query = SearchQuery::new("editions site:rust-lang.org");
+        for (url, loose, _) in site_results() {
+            assert_eq!(query.matches_sites(url), loose, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_matches_sites_strict() {
+        let query =
+            SearchQuery::new("editions site:rust-lang.org").with_site_match(SiteMatch::Strict);
+        for (url, _, strict) in site_results() {
+            assert_eq!(query.matches_sites(url), strict, "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_matches_sites_public_suffixes() {
+        // The registrable domain spans the whole multi-label suffix
+        let query = SearchQuery::new("news site:bbc.co.uk");
+        assert!(query.matches_sites("https://www.bbc.co.uk/news"));
+        assert!(!query.matches_sites("https://itv.co.uk/"));
+
+        // Each github.io site is its own registrable domain
+        let query = SearchQuery::new("site:serde-rs.github.io");
+        assert!(query.matches_sites("https://serde-rs.github.io/serde/"));
+        assert!(!query.matches_sites("https://tokio-rs.github.io/"));
+    }
+
+    #[test]
+    fn test_matches_sites_edge_cases() {
+        let query = SearchQuery::new("rust");
+        assert!(query.matches_sites("not a url"));
+
+        let query = SearchQuery::new("site:rust-lang.org site:docs.rs");
+        assert!(query.matches_sites("https://docs.rs/serde"));
+        assert!(query.matches_sites("https://BLOG.Rust-Lang.org/"));
+        assert!(!query.matches_sites("mailto:someone@rust-lang.org"));
+        assert!(!query.matches_sites("/relative/path"));
+
+        let query = SearchQuery::new("site:127.0.0.1");
+        assert!(query.matches_sites("http://127.0.0.1:8080/"));
+        assert!(!query.matches_sites("http://127.0.0.2/"));
+    }
+
+    #[test]
+    fn test_site_match_serialization() {
+        assert_eq!(
+            serde_json::to_string(&SiteMatch::Strict).unwrap(),
+            "\"strict\""
+        );
+        let query: SearchQuery = serde_json::from_str(
+            r#"{"query":"t","categories":[],"language":null,"safesearch":"off","page":1,"time_range":null,"engines":[],"site_match":"strict"}"#,
+        )
+        .unwrap();
+        assert_eq!(query.site_match, SiteMatch::Strict);
     }
 }
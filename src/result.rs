@@ -1,7 +1,12 @@
 //! Search result types.
 
-use serde::{Deserialize, Serialize};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashSet;
+use std::fmt;
+use std::time::Duration;
+
+use crate::Aggregator;
 
 /// Type of search result.
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -28,6 +33,57 @@ pub enum ResultType {
     Suggestion,
 }
 
+/// URL schemes results may use by default.
+pub const DEFAULT_RESULT_SCHEMES: &[&str] = &["http", "https"];
+
+/// Returns true if `url` is absolute and its scheme is one of `schemes`,
+/// compared case-insensitively.
+pub(crate) fn url_has_scheme<S: AsRef<str>>(url: &str, schemes: &[S]) -> bool {
+    url::Url::parse(url.trim()).is_ok_and(|parsed| {
+        schemes
+            .iter()
+            .any(|scheme| scheme.as_ref().eq_ignore_ascii_case(parsed.scheme()))
+    })
+}
+
+/// Separators between a page title and a trailing site name, with the
+/// surrounding spaces that tell them apart from hyphenated words.
+const SITE_NAME_SEPARATORS: &[&str] = &[" | ", " - ", " — ", " – ", " · "];
+
+/// Characters trimmed from either end of a title, so "| Site Name" loses
+/// its dangling separator.
+const TITLE_EDGE_SEPARATORS: &[char] = &['|', '-', '—', '–', '·'];
+
+/// Normalizes a scraped title: collapses runs of whitespace, including
+/// newlines, into single spaces and trims whitespace and dangling
+/// separators from both ends.
+///
+/// With `strip_site_name`, the segment after the last separator (such as
+/// "Rust - Wikipedia" → "Rust") is also removed, unless nothing would be
+/// left.
+pub(crate) fn clean_title(title: &str, strip_site_name: bool) -> String {
+    let collapsed = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut cleaned = trim_title_edges(&collapsed);
+
+    if strip_site_name {
+        let cut = SITE_NAME_SEPARATORS
+            .iter()
+            .filter_map(|sep| cleaned.rfind(sep))
+            .max();
+        if let Some(cut) = cut {
+            let head = trim_title_edges(&cleaned[..cut]);
+            if !head.is_empty() {
+                cleaned = head;
+            }
+        }
+    }
+    cleaned.to_string()
+}
+
+fn trim_title_edges(title: &str) -> &str {
+    title.trim_matches(|c: char| c.is_whitespace() || TITLE_EDGE_SEPARATORS.contains(&c))
+}
+
 /// A single search result.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
@@ -49,6 +105,10 @@ pub struct SearchResult {
     pub thumbnail: Option<String>,
     /// Published date (for news).
     pub published_date: Option<String>,
+    /// Whether an engine showed this result as a featured snippet (e.g.
+    /// Google's answer box). Featured results rank above organic ones.
+    #[serde(default)]
+    pub is_featured: bool,
 }
 
 impl SearchResult {
@@ -68,6 +128,7 @@ impl SearchResult {
             score: 0.0,
             thumbnail: None,
             published_date: None,
+            is_featured: false,
         }
     }
 
@@ -96,6 +157,19 @@ impl SearchResult {
         self
     }
 
+    /// Marks the result as a featured snippet.
+    pub fn with_featured(mut self, featured: bool) -> Self {
+        self.is_featured = featured;
+        self
+    }
+
+    /// Returns true if the URL is absolute and uses one of `schemes`.
+    ///
+    /// Relative URLs never match.
+    pub fn has_scheme(&self, schemes: &[&str]) -> bool {
+        url_has_scheme(&self.url, schemes)
+    }
+
     /// Returns a normalized URL for deduplication (without scheme and trailing slash).
     pub fn normalized_url(&self) -> String {
         let url = self
@@ -105,10 +179,104 @@ impl SearchResult {
             .trim_end_matches('/');
         url.to_lowercase()
     }
+
+    /// Returns up to `max_chars` characters of the content around the first
+    /// occurrence of a `query` term, with `...` marking cut ends.
+    ///
+    /// Terms match case-insensitively; `site:`-style operators and excluded
+    /// (`-term`) terms are ignored. Without a match, returns the start of
+    /// the content. Content that already fits is returned whole.
+    pub fn snippet_with_context(&self, query: &str, max_chars: usize) -> String {
+        let chars: Vec<char> = self.content.chars().collect();
+        if chars.len() <= max_chars {
+            return self.content.clone();
+        }
+        if max_chars == 0 {
+            return String::new();
+        }
+
+        // One lowercase char per content char keeps indices aligned
+        let fold = |c: char| c.to_lowercase().next().unwrap_or(c);
+        let lower: Vec<char> = chars.iter().map(|&c| fold(c)).collect();
+        let found = query
+            .split_whitespace()
+            .filter(|term| !term.starts_with('-') && !term.contains(':'))
+            .map(|term| {
+                term.trim_matches('"')
+                    .chars()
+                    .map(fold)
+                    .collect::<Vec<char>>()
+            })
+            .filter(|term| !term.is_empty())
+            .filter_map(|term| {
+                lower
+                    .windows(term.len())
+                    .position(|window| window == term.as_slice())
+                    .map(|at| (at, term.len()))
+            })
+            .min();
+
+        let start = match found {
+            Some((at, len)) => {
+                let start = at.saturating_sub(max_chars.saturating_sub(len) / 2);
+                start.min(chars.len() - max_chars)
+            }
+            None => 0,
+        };
+        let end = start + max_chars;
+
+        let window: String = chars[start..end].iter().collect();
+        let mut snippet = String::new();
+        if start > 0 {
+            snippet.push_str("...");
+        }
+        snippet.push_str(window.trim());
+        if end < chars.len() {
+            snippet.push_str("...");
+        }
+        snippet
+    }
 }
 
+impl fmt::Display for SearchResult {
+    /// Formats as `title — url (score)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} — {} ({:.2})", self.title, self.url, self.score)
+    }
+}
+
+/// How one engine's part of a search ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EngineStatus {
+    /// The engine responded with results.
+    Ok,
+    /// The engine responded normally but had no results for the query.
+    Empty,
+    /// The engine refused the request: blocked, rate limited, or a CAPTCHA.
+    Blocked,
+    /// The engine responded but its page could not be parsed.
+    ParseFailed,
+    /// The engine failed some other way, e.g. a network error or timeout.
+    Failed,
+}
+
+impl EngineStatus {
+    /// Returns whether the engine responded normally, with or without
+    /// results.
+    pub fn is_ok(self) -> bool {
+        matches!(self, EngineStatus::Ok | EngineStatus::Empty)
+    }
+}
+
+/// Maximum number of results listed by `SearchResults`' `Display` output.
+const DISPLAY_MAX_ITEMS: usize = 10;
+
 /// Container for aggregated search results.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+///
+/// Serializes with a `count` field equal to [`len`](Self::len), so the
+/// reported count always matches the results, however they were mutated.
+#[derive(Debug, Clone, Default, Deserialize)]
 pub struct SearchResults {
     /// Main search results.
     results: Vec<SearchResult>,
@@ -118,10 +286,25 @@ pub struct SearchResults {
     answers: Vec<String>,
     /// Engine errors (engine name → error message).
     errors: Vec<(String, String)>,
-    /// Number of results.
-    pub count: usize,
+    /// How each queried engine's search ended (engine name → status).
+    #[serde(default)]
+    engines: Vec<(String, EngineStatus)>,
     /// Search duration in milliseconds.
     pub duration_ms: u64,
+    /// Whether every engine's results were served from the cache.
+    #[serde(default)]
+    pub from_cache: bool,
+    /// Age of the oldest cached engine results used, if any were; set even
+    /// when other engines' results were fresh. Serialized as `age_ms`.
+    #[serde(default, rename = "age_ms", deserialize_with = "deserialize_millis")]
+    pub age: Option<Duration>,
+}
+
+/// Reads an optional number of milliseconds as a `Duration`.
+fn deserialize_millis<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Duration>, D::Error> {
+    Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_millis))
 }
 
 impl SearchResults {
@@ -133,7 +316,6 @@ impl SearchResults {
     /// Adds a result.
     pub fn add_result(&mut self, result: SearchResult) {
         self.results.push(result);
-        self.count = self.results.len();
     }
 
     /// Adds a suggestion.
@@ -151,6 +333,21 @@ impl SearchResults {
         &self.results
     }
 
+    /// Returns the number of results.
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns true if there are no results.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Returns an iterator over the results.
+    pub fn iter(&self) -> std::slice::Iter<'_, SearchResult> {
+        self.results.iter()
+    }
+
     /// Returns mutable results.
     pub fn items_mut(&mut self) -> &mut Vec<SearchResult> {
         &mut self.results
@@ -176,16 +373,219 @@ impl SearchResults {
         &self.errors
     }
 
+    /// Records how an engine's search ended.
+    pub fn add_engine_status(&mut self, engine: impl Into<String>, status: EngineStatus) {
+        self.engines.push((engine.into(), status));
+    }
+
+    /// Returns how each queried engine's search ended (engine name,
+    /// status), telling engines that found nothing apart from those that
+    /// were blocked or failed.
+    pub fn engine_statuses(&self) -> &[(String, EngineStatus)] {
+        &self.engines
+    }
+
     /// Sets the search duration.
     pub fn set_duration(&mut self, duration_ms: u64) {
         self.duration_ms = duration_ms;
     }
+
+    /// Removes results whose content is empty or whitespace.
+    ///
+    /// If every result is empty, the first (highest ranked) one is kept so
+    /// the response is never emptied by this filter alone.
+    pub fn filter_empty_content(&mut self) {
+        if self.results.iter().all(|r| r.content.trim().is_empty()) {
+            self.results.truncate(1);
+        } else {
+            self.results.retain(|r| !r.content.trim().is_empty());
+        }
+    }
+
+    /// Removes results outside the `site:` operators of `query`; see
+    /// [`SearchQuery::matches_sites`](crate::SearchQuery::matches_sites).
+    pub fn filter_sites(&mut self, query: &crate::SearchQuery) {
+        if !query.sites().is_empty() {
+            self.results.retain(|r| query.matches_sites(&r.url));
+        }
+    }
+
+    /// Removes results whose URL is relative or uses a scheme not in
+    /// `schemes`, e.g. `javascript:` or `mailto:` links.
+    pub fn filter_schemes(&mut self, schemes: &[&str]) {
+        self.results.retain(|r| r.has_scheme(schemes));
+    }
+
+    /// Merges the results of another search into this one.
+    ///
+    /// The combined results are deduplicated, merged, and rescored with
+    /// `aggregator`, as if both searches had been a single run. Suggestions
+    /// and answers are combined without duplicates, errors and engine
+    /// statuses are appended, and durations are summed. The merged results
+    /// count as cached only if both were, and keep the older age.
+    ///
+    /// Intended for searches over different engine sets (e.g. an HTTP search
+    /// followed by a headless one); merging two runs of the same engine
+    /// counts that engine's positions twice.
+    pub fn merge(&mut self, other: SearchResults, aggregator: &Aggregator) {
+        let mut results = std::mem::take(&mut self.results);
+        results.extend(other.results);
+        self.results = aggregator.reaggregate(results);
+
+        for suggestion in other.suggestions {
+            if !self.suggestions.contains(&suggestion) {
+                self.suggestions.push(suggestion);
+            }
+        }
+        for answer in other.answers {
+            if !self.answers.contains(&answer) {
+                self.answers.push(answer);
+            }
+        }
+        self.errors.extend(other.errors);
+        self.engines.extend(other.engines);
+        self.duration_ms += other.duration_ms;
+        self.from_cache &= other.from_cache;
+        self.age = self.age.max(other.age);
+    }
+
+    /// Formats the results as CSV with a header row: title, url, content,
+    /// engines (sorted, `;`-separated) and score.
+    ///
+    /// Fields containing commas, quotes or line breaks are quoted, with
+    /// quotes doubled, as RFC 4180 describes. Rows end with `\n`.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("title,url,content,engines,score\n");
+        for result in &self.results {
+            let mut engines: Vec<&str> = result.engines.iter().map(String::as_str).collect();
+            engines.sort_unstable();
+            let fields = [
+                csv_field(&result.title),
+                csv_field(&result.url),
+                csv_field(&result.content),
+                csv_field(&engines.join(";")),
+                format!("{:.2}", result.score),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// Quotes a CSV field if it contains a comma, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl Serialize for SearchResults {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("SearchResults", 9)?;
+        state.serialize_field("results", &self.results)?;
+        state.serialize_field("suggestions", &self.suggestions)?;
+        state.serialize_field("answers", &self.answers)?;
+        state.serialize_field("errors", &self.errors)?;
+        state.serialize_field("engines", &self.engines)?;
+        state.serialize_field("count", &self.len())?;
+        state.serialize_field("duration_ms", &self.duration_ms)?;
+        state.serialize_field("from_cache", &self.from_cache)?;
+        state.serialize_field("age_ms", &self.age.map(|age| age.as_millis() as u64))?;
+        state.end()
+    }
+}
+
+impl IntoIterator for SearchResults {
+    type Item = SearchResult;
+    type IntoIter = std::vec::IntoIter<SearchResult>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a SearchResults {
+    type Item = &'a SearchResult;
+    type IntoIter = std::slice::Iter<'a, SearchResult>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.iter()
+    }
+}
+
+impl fmt::Display for SearchResults {
+    /// Formats a summary line followed by up to 10 numbered results.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} results in {}ms", self.len(), self.duration_ms)?;
+        for (i, result) in self.results.iter().take(DISPLAY_MAX_ITEMS).enumerate() {
+            write!(f, "\n{}. {}", i + 1, result)?;
+        }
+        if self.results.len() > DISPLAY_MAX_ITEMS {
+            write!(
+                f,
+                "\n... and {} more",
+                self.results.len() - DISPLAY_MAX_ITEMS
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Aggregated results together with what each engine returned on its own.
+///
+/// Returned by [`Search::search_detailed`](crate::Search::search_detailed).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DetailedResults {
+    /// The merged, ranked view, as returned by `Search::search`.
+    pub aggregated: SearchResults,
+    /// Each responding engine's results, in engine order and as returned.
+    pub by_engine: Vec<(String, Vec<SearchResult>)>,
+    /// Engine errors (engine name → error message).
+    pub errors: Vec<(String, String)>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_clean_title_collapses_whitespace() {
+        assert_eq!(
+            clean_title("  Rust\n  Programming\tLanguage  ", false),
+            "Rust Programming Language"
+        );
+        assert_eq!(clean_title("| Site Name", false), "Site Name");
+        assert_eq!(clean_title("Rust - Wikipedia", false), "Rust - Wikipedia");
+        assert_eq!(clean_title("", false), "");
+    }
+
+    #[test]
+    fn test_clean_title_strips_site_name() {
+        assert_eq!(clean_title("Rust - Wikipedia", true), "Rust");
+        assert_eq!(
+            clean_title("Install Rust | rust-lang.org", true),
+            "Install Rust"
+        );
+        assert_eq!(
+            clean_title("Release notes — The Rust Blog", true),
+            "Release notes"
+        );
+        assert_eq!(clean_title("Async Rust – Tokio", true), "Async Rust");
+        assert_eq!(clean_title("Docs · GitHub", true), "Docs");
+        // Only the last segment is removed
+        assert_eq!(clean_title("A | B | Site", true), "A | B");
+    }
+
+    #[test]
+    fn test_clean_title_keeps_words_and_lone_segments() {
+        assert_eq!(clean_title("Well-known crates", true), "Well-known crates");
+        assert_eq!(clean_title("| Site Name", true), "Site Name");
+        assert_eq!(clean_title("Rust\n-\nWikipedia", true), "Rust");
+    }
+
     #[test]
     fn test_result_type_default() {
         let default: ResultType = Default::default();
@@ -281,9 +681,9 @@ mod tests {
     #[test]
     fn test_search_results_new() {
         let results = SearchResults::new();
-        assert_eq!(results.count, 0);
+        assert_eq!(results.len(), 0);
         assert_eq!(results.duration_ms, 0);
-        assert!(results.items().is_empty());
+        assert!(results.is_empty());
         assert!(results.suggestions().is_empty());
         assert!(results.answers().is_empty());
     }
@@ -293,8 +693,8 @@ mod tests {
         let mut results = SearchResults::new();
         results.add_result(SearchResult::new("url1", "title1", "content1"));
         results.add_result(SearchResult::new("url2", "title2", "content2"));
-        assert_eq!(results.count, 2);
-        assert_eq!(results.items().len(), 2);
+        assert_eq!(results.len(), 2);
+        assert_eq!(serde_json::to_value(&results).unwrap()["count"], 2);
     }
 
     #[test]
@@ -382,7 +782,356 @@ mod tests {
         let mut results = SearchResults::new();
         results.add_result(SearchResult::new("url", "title", "content"));
         results.add_error("Google", "failed");
-        assert_eq!(results.count, 1);
+        assert_eq!(results.len(), 1);
         assert_eq!(results.errors().len(), 1);
     }
+
+    #[test]
+    fn test_search_results_merge_overlapping_url() {
+        let aggregator = Aggregator::new();
+
+        let mut http = aggregator.aggregate(vec![(
+            "duckduckgo".to_string(),
+            vec![
+                SearchResult::new("https://rust-lang.org/", "Rust", "Short"),
+                SearchResult::new("https://docs.rs", "Docs", "Crate docs"),
+            ],
+        )]);
+        http.add_suggestion("rust book");
+        http.set_duration(100);
+
+        let mut headless = aggregator.aggregate(vec![(
+            "google".to_string(),
+            vec![SearchResult::new(
+                "http://rust-lang.org",
+                "Rust Programming Language",
+                "A longer description",
+            )],
+        )]);
+        headless.add_suggestion("rust book");
+        headless.add_suggestion("rust async");
+        headless.add_error("baidu", "timeout");
+        headless.set_duration(250);
+
+        http.merge(headless, &aggregator);
+
+        assert_eq!(http.len(), 2);
+        let rust = &http.items()[0];
+        assert_eq!(rust.normalized_url(), "rust-lang.org");
+        assert!(rust.engines.contains("duckduckgo"));
+        assert!(rust.engines.contains("google"));
+        assert_eq!(rust.positions, vec![1, 1]);
+        assert_eq!(rust.title, "Rust Programming Language");
+        assert_eq!(rust.content, "A longer description");
+        // Two engines at position 1: weight 2 * (1/1 + 1/1)
+        assert_eq!(rust.score, 4.0);
+
+        assert_eq!(http.items()[1].url, "https://docs.rs");
+        assert_eq!(http.suggestions(), &["rust book", "rust async"]);
+        assert_eq!(http.errors().len(), 1);
+        assert_eq!(http.duration_ms, 350);
+    }
+
+    #[test]
+    fn test_search_results_merge_into_empty() {
+        let aggregator = Aggregator::new();
+        let other = aggregator.aggregate(vec![(
+            "brave".to_string(),
+            vec![SearchResult::new("https://example.com", "Example", "")],
+        )]);
+
+        let mut results = SearchResults::new();
+        results.merge(other, &aggregator);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results.items()[0].score, 1.0);
+    }
+
+    #[test]
+    fn test_search_results_to_csv() {
+        let mut results = SearchResults::new();
+        let mut quoted = SearchResult::new(
+            "https://example.com/a,b",
+            "Say \"hi\", world",
+            "line one\nline two",
+        )
+        .with_engine("wiki", 1)
+        .with_engine("brave", 2);
+        quoted.score = 1.5;
+        results.add_result(quoted);
+        results.add_result(SearchResult::new("https://例子.cn/", "中文标题", "内容"));
+
+        assert_eq!(
+            results.to_csv(),
+            "title,url,content,engines,score\n\
+             \"Say \"\"hi\"\", world\",\"https://example.com/a,b\",\"line one\nline two\",brave;wiki,1.50\n\
+             中文标题,https://例子.cn/,内容,,0.00\n"
+        );
+        assert_eq!(
+            SearchResults::new().to_csv(),
+            "title,url,content,engines,score\n"
+        );
+    }
+
+    #[test]
+    fn test_search_result_display() {
+        let mut result = SearchResult::new("https://www.rust-lang.org", "Rust", "content");
+        result.score = 1.5;
+        assert_eq!(
+            result.to_string(),
+            "Rust — https://www.rust-lang.org (1.50)"
+        );
+    }
+
+    #[test]
+    fn test_search_results_display() {
+        let mut results = SearchResults::new();
+        results.add_result(SearchResult::new("https://a.example", "A", ""));
+        results.add_result(SearchResult::new("https://b.example", "B", ""));
+        results.set_duration(42);
+
+        let display = results.to_string();
+        let lines: Vec<&str> = display.lines().collect();
+        assert_eq!(lines[0], "2 results in 42ms");
+        assert_eq!(lines[1], "1. A — https://a.example (0.00)");
+        assert_eq!(lines[2], "2. B — https://b.example (0.00)");
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_search_results_display_caps_items() {
+        let mut results = SearchResults::new();
+        for i in 0..12 {
+            results.add_result(SearchResult::new(
+                format!("https://{}.example", i),
+                format!("Result {}", i),
+                "",
+            ));
+        }
+
+        let display = results.to_string();
+        assert!(display.starts_with("12 results in 0ms"));
+        assert!(display.contains("10. Result 9"));
+        assert!(!display.contains("Result 10"));
+        assert!(display.ends_with("... and 2 more"));
+    }
+
+    #[test]
+    fn test_search_results_display_empty() {
+        assert_eq!(SearchResults::new().to_string(), "0 results in 0ms");
+    }
+
+    fn sample_results() -> SearchResults {
+        let mut results = SearchResults::new();
+        results.add_result(SearchResult::new("https://a.example", "A", ""));
+        results.add_result(SearchResult::new("https://b.example", "B", ""));
+        results.add_result(SearchResult::new("https://c.example", "C", ""));
+        results
+    }
+
+    #[test]
+    fn test_search_results_iter() {
+        let results = sample_results();
+        let titles: Vec<&str> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["A", "B", "C"]);
+    }
+
+    #[test]
+    fn test_search_results_iterate_by_reference() {
+        let results = sample_results();
+        let mut count = 0;
+        for result in &results {
+            assert!(result.url.starts_with("https://"));
+            count += 1;
+        }
+        assert_eq!(count, 3);
+        // Still usable after borrowing
+        assert_eq!(results.len(), 3);
+    }
+
+    #[test]
+    fn test_search_results_iterate_by_value() {
+        let results = sample_results();
+        let owned: Vec<SearchResult> = results.into_iter().collect();
+        assert_eq!(owned.len(), 3);
+        assert_eq!(owned[2].title, "C");
+    }
+
+    #[test]
+    fn test_search_results_filter_empty_content() {
+        let mut results = SearchResults::new();
+        results.add_result(SearchResult::new("https://a.example", "A", "Has content"));
+        results.add_result(SearchResult::new("https://b.example", "B", ""));
+        results.add_result(SearchResult::new("https://c.example", "C", "   "));
+        results.add_result(SearchResult::new("https://d.example", "D", "More"));
+
+        results.filter_empty_content();
+
+        assert_eq!(results.len(), 2);
+        let titles: Vec<&str> = results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["A", "D"]);
+    }
+
+    #[test]
+    fn test_search_results_filter_empty_content_keeps_one() {
+        let mut results = SearchResults::new();
+        results.add_result(SearchResult::new("https://a.example", "A", ""));
+        results.add_result(SearchResult::new("https://b.example", "B", ""));
+
+        results.filter_empty_content();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results.items()[0].title, "A");
+    }
+
+    #[test]
+    fn test_search_results_filter_empty_content_no_results() {
+        let mut results = SearchResults::new();
+        results.filter_empty_content();
+        assert_eq!(results.len(), 0);
+    }
+
+    #[test]
+    fn test_search_results_len_and_is_empty() {
+        let mut results = SearchResults::new();
+        assert!(results.is_empty());
+        assert_eq!(results.len(), 0);
+
+        results.add_result(SearchResult::new("https://a.com", "A", ""));
+        results.add_result(SearchResult::new("https://b.com", "B", ""));
+        assert!(!results.is_empty());
+        assert_eq!(results.len(), 2);
+
+        results.items_mut().pop();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_search_results_count_after_items_mut() {
+        let mut results = SearchResults::new();
+        results.add_result(SearchResult::new("https://a.com", "A", ""));
+        results.add_result(SearchResult::new("https://b.com", "B", ""));
+        results.items_mut().remove(0);
+
+        let json = serde_json::to_value(&results).unwrap();
+        assert_eq!(json["count"], 1);
+        assert_eq!(json["results"].as_array().unwrap().len(), 1);
+        assert!(results.to_string().starts_with("1 results"));
+
+        // `count` is derived on output and ignored on input
+        let parsed: SearchResults = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.len(), 1);
+    }
+
+    #[test]
+    fn test_search_results_cache_fields_serde() {
+        let mut results = SearchResults::new();
+        let json = serde_json::to_value(&results).unwrap();
+        assert_eq!(json["from_cache"], false);
+        assert!(json["age_ms"].is_null());
+
+        results.from_cache = true;
+        results.age = Some(Duration::from_millis(180_500));
+        let json = serde_json::to_value(&results).unwrap();
+        assert_eq!(json["from_cache"], true);
+        assert_eq!(json["age_ms"], 180_500);
+
+        let parsed: SearchResults = serde_json::from_value(json).unwrap();
+        assert!(parsed.from_cache);
+        assert_eq!(parsed.age, Some(Duration::from_millis(180_500)));
+
+        // Output of older versions has neither field
+        let parsed: SearchResults = serde_json::from_str(
+            r#"{"results": [], "suggestions": [], "answers": [], "errors": [], "duration_ms": 5}"#,
+        )
+        .unwrap();
+        assert!(!parsed.from_cache);
+        assert!(parsed.age.is_none());
+    }
+
+    #[test]
+    fn test_snippet_with_context_match_in_middle() {
+        let content = format!(
+            "{} Rust ownership rules {}",
+            "a".repeat(100),
+            "b".repeat(100)
+        );
+        let result = SearchResult::new("https://a.com/", "A", content);
+        let snippet = result.snippet_with_context("OWNERSHIP", 30);
+        assert_eq!(snippet, "...aaaa Rust ownership rules bbbb...");
+        assert!(snippet.starts_with("..."));
+        assert!(snippet.ends_with("..."));
+    }
+
+    #[test]
+    fn test_snippet_with_context_match_near_edges() {
+        let content = format!("Rust is fast. {}", "x".repeat(100));
+        let result = SearchResult::new("https://a.com/", "A", content.clone());
+        assert_eq!(result.snippet_with_context("rust", 10), "Rust is fa...");
+
+        let result = SearchResult::new(
+            "https://a.com/",
+            "A",
+            format!("{} the end", "x".repeat(100)),
+        );
+        assert_eq!(result.snippet_with_context("end", 10), "...xx the end");
+    }
+
+    #[test]
+    fn test_snippet_with_context_no_match() {
+        let result = SearchResult::new("https://a.com/", "A", "abcdefghijklmnop");
+        assert_eq!(result.snippet_with_context("zzz", 5), "abcde...");
+        // Operators and excluded terms never match
+        assert_eq!(result.snippet_with_context("site:ghi -klm", 5), "abcde...");
+    }
+
+    #[test]
+    fn test_snippet_with_context_short_content() {
+        let result = SearchResult::new("https://a.com/", "A", "short text");
+        assert_eq!(result.snippet_with_context("text", 50), "short text");
+        assert_eq!(result.snippet_with_context("text", 0), "");
+    }
+
+    #[test]
+    fn test_snippet_with_context_cjk() {
+        let content = format!(
+            "{}Rust 编程语言注重安全{}",
+            "前".repeat(40),
+            "后".repeat(40)
+        );
+        let result = SearchResult::new("https://a.com/", "A", content);
+        let snippet = result.snippet_with_context("编程", 10);
+        assert_eq!(snippet, "...ust 编程语言注重...");
+        assert_eq!(snippet.trim_matches('.').chars().count(), 10);
+    }
+
+    #[test]
+    fn test_search_result_has_scheme() {
+        let schemes = DEFAULT_RESULT_SCHEMES;
+        assert!(SearchResult::new("https://a.com/", "A", "").has_scheme(schemes));
+        assert!(SearchResult::new("HTTP://a.com/", "A", "").has_scheme(schemes));
+        assert!(!SearchResult::new("ftp://a.com/f", "A", "").has_scheme(schemes));
+        assert!(!SearchResult::new("/relative/path", "A", "").has_scheme(schemes));
+        assert!(!SearchResult::new("", "A", "").has_scheme(schemes));
+        assert!(SearchResult::new("ftp://a.com/f", "A", "").has_scheme(&["ftp"]));
+    }
+
+    #[test]
+    fn test_search_results_filter_schemes() {
+        let mut results = SearchResults::new();
+        for url in [
+            "https://a.com/",
+            "javascript:void(0)",
+            "mailto:someone@example.com",
+            "http://b.com/",
+            "/relative",
+        ] {
+            results.add_result(SearchResult::new(url, "T", ""));
+        }
+
+        results.filter_schemes(DEFAULT_RESULT_SCHEMES);
+
+        let urls: Vec<_> = results.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, vec!["https://a.com/", "http://b.com/"]);
+    }
 }
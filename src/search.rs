@@ -1,21 +1,175 @@
 //! Search orchestration.
 
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 use futures::future::join_all;
+use futures::stream::{FuturesUnordered, StreamExt};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use tokio::time::{timeout, Duration};
 use tracing::{debug, warn};
 
-use crate::proxy::ProxyPool;
-use crate::{Aggregator, Engine, Result, SearchError, SearchQuery, SearchResults};
+use crate::fetcher::with_fetch_context;
+#[cfg(feature = "headless")]
+use crate::BrowserPool;
+use crate::{
+    Aggregator, CacheBackend, ConsensusMode, DetailedResults, Engine, EngineStatus, FetcherHint,
+    HttpFetcher, Result, SearchError, SearchQuery, SearchResult, SearchResults,
+};
+
+/// How one engine's part of a search ended.
+///
+/// An engine that responds with no results is a success, not an error:
+/// `results` is empty and `error` is `None`. `blocked` and `parse_failed`
+/// tell the two common failures apart from other errors.
+#[derive(Debug)]
+struct EngineOutcome {
+    name: String,
+    results: Vec<SearchResult>,
+    /// Age of the results if they came from the cache.
+    age: Option<Duration>,
+    /// Whether the engine refused the request (blocked, rate limited, or a
+    /// CAPTCHA).
+    blocked: bool,
+    /// Whether the engine's response could not be parsed.
+    parse_failed: bool,
+    /// What went wrong, if the engine failed.
+    error: Option<String>,
+}
+
+impl EngineOutcome {
+    /// An engine's results, fresh or, with an `age`, from the cache.
+    fn results(name: String, results: Vec<SearchResult>, age: Option<Duration>) -> Self {
+        Self {
+            name,
+            results,
+            age,
+            blocked: false,
+            parse_failed: false,
+            error: None,
+        }
+    }
+
+    /// An engine's failure with `err`.
+    fn failed(name: String, err: &SearchError) -> Self {
+        Self {
+            blocked: is_blocked(err),
+            parse_failed: matches!(err, SearchError::Parse(_)),
+            error: Some(err.to_string()),
+            ..Self::results(name, Vec::new(), None)
+        }
+    }
+
+    /// An engine that did not respond within its timeout.
+    fn timed_out(name: String) -> Self {
+        Self {
+            error: Some("timed out".to_string()),
+            ..Self::results(name, Vec::new(), None)
+        }
+    }
+
+    fn status(&self) -> EngineStatus {
+        if self.blocked {
+            EngineStatus::Blocked
+        } else if self.parse_failed {
+            EngineStatus::ParseFailed
+        } else if self.error.is_some() {
+            EngineStatus::Failed
+        } else if self.results.is_empty() {
+            EngineStatus::Empty
+        } else {
+            EngineStatus::Ok
+        }
+    }
+}
+
+/// Returns whether `err` means the engine refused to serve the request.
+fn is_blocked(err: &SearchError) -> bool {
+    match err {
+        SearchError::Blocked(_) | SearchError::RateLimited(_) | SearchError::Captcha(_) => true,
+        SearchError::HttpStatus(status, _) => matches!(status, 403 | 429),
+        _ => false,
+    }
+}
+
+/// What the engines of one search returned.
+#[derive(Default)]
+struct EngineRun {
+    /// Results of the engines that responded.
+    results: Vec<(String, Vec<SearchResult>)>,
+    /// Errors of the engines that did not.
+    errors: Vec<(String, String)>,
+    /// How each engine's search ended, in the order they finished.
+    statuses: Vec<(String, EngineStatus)>,
+    /// Age of each result list served from the cache.
+    cached: Vec<Duration>,
+    /// Number of result lists fetched fresh rather than from the cache.
+    uncached: usize,
+}
+
+impl EngineRun {
+    fn record(&mut self, outcome: EngineOutcome) {
+        let status = outcome.status();
+        self.statuses.push((outcome.name.clone(), status));
+        match outcome.error {
+            Some(error) => self.errors.push((outcome.name, error)),
+            None => {
+                self.results.push((outcome.name, outcome.results));
+                match outcome.age {
+                    Some(age) => self.cached.push(age),
+                    None => self.uncached += 1,
+                }
+            }
+        }
+    }
+
+    /// Adds the run of a later page, appending each engine's results to
+    /// its earlier ones so they keep ranking behind them.
+    fn append_page(&mut self, page: EngineRun) {
+        for (engine, results) in page.results {
+            match self.results.iter_mut().find(|(name, _)| *name == engine) {
+                Some((_, earlier)) => earlier.extend(results),
+                None => self.results.push((engine, results)),
+            }
+        }
+        self.errors.extend(page.errors);
+        self.statuses.extend(page.statuses);
+        self.cached.extend(page.cached);
+        self.uncached += page.uncached;
+    }
+}
+
+/// An engine's results as stored in the cache.
+#[derive(Serialize, Deserialize)]
+struct CachedResults {
+    /// When the results were stored, in milliseconds since the Unix epoch.
+    stored_at_ms: u64,
+    results: Vec<SearchResult>,
+}
+
+/// Returns the current time in milliseconds since the Unix epoch.
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// Upper bound for the dispatch jitter between two engines.
+const MAX_DISPATCH_JITTER: Duration = Duration::from_millis(500);
 
 /// Meta search engine that orchestrates searches across multiple engines.
 pub struct Search {
     engines: Vec<Arc<dyn Engine>>,
     aggregator: Aggregator,
-    default_timeout: Duration,
-    proxy_pool: Option<Arc<ProxyPool>>,
+    default_timeout: Option<Duration>,
+    require_content: bool,
+    dispatch_jitter: Duration,
+    cache: Option<Arc<dyn CacheBackend>>,
+    user_agent: Option<Arc<str>>,
+    #[cfg(feature = "headless")]
+    browser_pool: Option<Arc<BrowserPool>>,
 }
 
 impl Search {
@@ -24,32 +178,172 @@ impl Search {
         Self {
             engines: Vec::new(),
             aggregator: Aggregator::new(),
-            default_timeout: Duration::from_secs(5),
-            proxy_pool: None,
+            default_timeout: None,
+            require_content: false,
+            dispatch_jitter: Duration::ZERO,
+            cache: None,
+            user_agent: None,
+            #[cfg(feature = "headless")]
+            browser_pool: None,
         }
     }
 
+    /// Creates a search instance with the HTTP engines named by
+    /// `shortcuts`, e.g. `&["ddg", "wiki", "brave"]`, sharing `fetcher`.
+    ///
+    /// Shortcuts are resolved by [`engines::by_shortcut`](crate::engines::by_shortcut).
+    /// Fails with [`SearchError::NoEngines`] if `shortcuts` is empty, and
+    /// with [`SearchError::UnknownEngines`] listing every shortcut that
+    /// names no engine. Vertical engines such as `yt` still only run for
+    /// queries in their category.
+    pub fn from_catalog<S: AsRef<str>>(shortcuts: &[S], fetcher: HttpFetcher) -> Result<Self> {
+        if shortcuts.is_empty() {
+            return Err(SearchError::NoEngines);
+        }
+
+        let mut engines = Vec::new();
+        let mut unknown = Vec::new();
+        for shortcut in shortcuts {
+            let shortcut = shortcut.as_ref();
+            match crate::engines::by_shortcut(shortcut, &fetcher) {
+                Some(engine) => engines.push(engine),
+                None => unknown.push(shortcut.to_string()),
+            }
+        }
+        if !unknown.is_empty() {
+            return Err(SearchError::UnknownEngines(unknown));
+        }
+
+        let mut search = Self::new();
+        search.add_engines(engines);
+        Ok(search)
+    }
+
     /// Adds a search engine.
     pub fn add_engine<E: Engine + 'static>(&mut self, engine: E) {
+        self.push_engine(Arc::new(engine));
+    }
+
+    /// Adds boxed search engines, such as those of
+    /// [`engines::all_http`](crate::engines::all_http).
+    pub fn add_engines(&mut self, engines: impl IntoIterator<Item = Box<dyn Engine>>) {
+        for engine in engines {
+            self.push_engine(Arc::from(engine));
+        }
+    }
+
+    fn push_engine(&mut self, engine: Arc<dyn Engine>) {
         let config = engine.config();
         self.aggregator
             .set_engine_weight(&config.name, config.weight);
-        self.engines.push(Arc::new(engine));
+        self.engines.push(engine);
     }
 
-    /// Sets the default timeout for searches.
+    /// Caps every engine's timeout at `timeout` (default: none, each engine
+    /// gets its [`EngineConfig::timeout`](crate::EngineConfig::timeout)).
+    ///
+    /// An engine with a shorter timeout of its own keeps it.
     pub fn set_timeout(&mut self, timeout: Duration) {
-        self.default_timeout = timeout;
+        self.default_timeout = Some(timeout);
+    }
+
+    /// Returns how long `engine` may take: its own timeout, capped by
+    /// [`set_timeout`](Self::set_timeout).
+    fn engine_timeout(&self, engine: &dyn Engine) -> Duration {
+        let own = Duration::from_secs(engine.config().timeout);
+        self.default_timeout.map_or(own, |cap| own.min(cap))
+    }
+
+    /// Drops results without a snippet after aggregation (default: disabled).
+    ///
+    /// See [`SearchResults::filter_empty_content`]; the top result is kept if
+    /// none have content.
+    pub fn set_require_content(&mut self, require: bool) {
+        self.require_content = require;
+    }
+
+    /// Staggers engine dispatch instead of starting every engine at once
+    /// (default: disabled).
+    ///
+    /// Each engine starts a random delay between half and the full `jitter`
+    /// after the previous one, so the last of `n` engines starts at most
+    /// `(n - 1) * jitter` late. `jitter` is capped at 500ms.
+    pub fn set_dispatch_jitter(&mut self, jitter: Duration) {
+        self.dispatch_jitter = jitter.min(MAX_DISPATCH_JITTER);
+    }
+
+    /// Sets the URL schemes results must use (default: `http` and `https`).
+    ///
+    /// See [`Aggregator::set_allowed_schemes`].
+    pub fn set_allowed_schemes(&mut self, schemes: &[&str]) {
+        self.aggregator.set_allowed_schemes(schemes);
+    }
+
+    /// Strips trailing site names from result titles (default: disabled).
+    ///
+    /// See [`Aggregator::set_strip_site_names`].
+    pub fn set_strip_site_names(&mut self, strip: bool) {
+        self.aggregator.set_strip_site_names(strip);
     }
 
-    /// Sets the proxy pool for anti-crawler protection.
-    pub fn set_proxy_pool(&mut self, proxy_pool: ProxyPool) {
-        self.proxy_pool = Some(Arc::new(proxy_pool));
+    /// Sets how results found by several engines are boosted (default:
+    /// [`ConsensusMode::Linear`]).
+    ///
+    /// See [`Aggregator::set_consensus`].
+    pub fn set_consensus(&mut self, mode: ConsensusMode) {
+        self.aggregator.set_consensus(mode);
     }
 
-    /// Returns a reference to the proxy pool if configured.
-    pub fn proxy_pool(&self) -> Option<&Arc<ProxyPool>> {
-        self.proxy_pool.as_ref()
+    /// Caches each engine's results per query in `cache` (default: disabled).
+    ///
+    /// Engines with a cached, unexpired result list for a query are not
+    /// queried again. Only successful, non-empty result lists are cached.
+    pub fn set_cache<C: CacheBackend + 'static>(&mut self, cache: C) {
+        self.cache = Some(Arc::new(cache));
+    }
+
+    /// Sets the user agent of every engine request, in place of the
+    /// fetchers' own (default: each fetcher's).
+    ///
+    /// `HttpFetcher` (and the fetchers wrapping it) and `BrowserFetcher`
+    /// send it while one of this search's engines runs; an explicit
+    /// [`FetchRequest::with_user_agent`](crate::FetchRequest::with_user_agent)
+    /// still wins. Custom fetchers can read it with
+    /// [`current_user_agent`](Self::current_user_agent).
+    pub fn set_user_agent(&mut self, user_agent: impl Into<String>) {
+        self.user_agent = Some(Arc::from(user_agent.into()));
+    }
+
+    /// Returns the user agent set with [`set_user_agent`](Self::set_user_agent)
+    /// of the search whose engine is running on the current task, if any.
+    pub fn current_user_agent() -> Option<String> {
+        crate::fetcher::current_user_agent()
+    }
+
+    /// Returns the [`Engine::preferred_fetcher`] hint of the engine running
+    /// on the current task, or [`FetcherHint::Auto`] outside a search.
+    pub fn current_fetcher_hint() -> FetcherHint {
+        crate::fetcher::current_fetcher_hint()
+    }
+
+    /// Hands the browser pool shared by the headless engines to this search,
+    /// so that [`shutdown`](Self::shutdown) closes its browsers.
+    #[cfg(feature = "headless")]
+    pub fn set_browser_pool(&mut self, pool: Arc<BrowserPool>) {
+        self.browser_pool = Some(pool);
+    }
+
+    /// Releases what the search holds in the background: closes the
+    /// browsers of the pool set with `set_browser_pool` and stops its idle
+    /// reaper.
+    ///
+    /// The search stays usable afterwards; a later search relaunches
+    /// browsers on demand.
+    pub async fn shutdown(&self) {
+        #[cfg(feature = "headless")]
+        if let Some(pool) = &self.browser_pool {
+            pool.shutdown().await;
+        }
     }
 
     /// Returns the number of configured engines.
@@ -59,6 +353,112 @@ impl Search {
 
     /// Performs a search across all configured engines.
     pub async fn search(&self, query: SearchQuery) -> Result<SearchResults> {
+        let start = Instant::now();
+        let run = self.run_engines(query.clone()).await?;
+        Ok(self.finish(run, &query, start))
+    }
+
+    /// Searches `pages` consecutive result pages, starting at the query's
+    /// page, and merges them into one ranked list.
+    ///
+    /// Pages are searched one after another, and pages after the first
+    /// only query the selected engines whose config supports paging. Each
+    /// engine's pages are joined before aggregation, so its results on
+    /// later pages rank behind those on earlier ones. Errors and engine
+    /// statuses of every page are kept. A `pages` of zero searches one page.
+    pub async fn search_pages(&self, query: SearchQuery, pages: u32) -> Result<SearchResults> {
+        let start = Instant::now();
+        let mut run = self.run_engines(query.clone()).await?;
+
+        let paging: Vec<String> = self
+            .select_engines(&query)
+            .iter()
+            .filter(|engine| engine.config().paging)
+            .map(|engine| engine.shortcut().to_string())
+            .collect();
+        if !paging.is_empty() {
+            for page in query.page.saturating_add(1)..query.page.saturating_add(pages.max(1)) {
+                let page_query = query.clone().with_page(page).with_engines(paging.clone());
+                run.append_page(self.run_engines(page_query).await?);
+            }
+        }
+
+        Ok(self.finish(run, &query, start))
+    }
+
+    /// Performs a search and returns both the aggregated results and each
+    /// engine's raw results, from a single run of the engines.
+    ///
+    /// Raw lists are kept exactly as the engines returned them, before
+    /// deduplication, scheme filtering, and the content requirement.
+    pub async fn search_detailed(&self, query: SearchQuery) -> Result<DetailedResults> {
+        let start = Instant::now();
+        let run = self.run_engines(query.clone()).await?;
+        let by_engine = run.results.clone();
+        let errors = run.errors.clone();
+        Ok(DetailedResults {
+            aggregated: self.finish(run, &query, start),
+            by_engine,
+            errors,
+        })
+    }
+
+    /// Performs a search that returns early once it has enough results.
+    ///
+    /// Until `soft_deadline` passes, this waits for every engine like
+    /// [`search`](Self::search). After it, the search returns as soon as the
+    /// engines that have responded add up to at least `min_results`
+    /// aggregated results, dropping the engines still running. If that never
+    /// happens, it returns once every engine has finished or timed out.
+    pub async fn search_until(
+        &self,
+        query: SearchQuery,
+        min_results: usize,
+        soft_deadline: Duration,
+    ) -> Result<SearchResults> {
+        let start = Instant::now();
+        let deadline = tokio::time::Instant::now() + soft_deadline;
+        let mut pending: FuturesUnordered<_> = self.dispatch(query.clone())?.into_iter().collect();
+
+        let mut run = EngineRun::default();
+        let mut past_deadline = false;
+        loop {
+            tokio::select! {
+                outcome = pending.next() => match outcome {
+                    Some(outcome) => run.record(outcome),
+                    None => break,
+                },
+                _ = tokio::time::sleep_until(deadline), if !past_deadline => past_deadline = true,
+            }
+            if past_deadline && self.aggregate(run.results.clone(), &query).len() >= min_results {
+                debug!(
+                    "Returning {} engines' results without {} slower engines",
+                    run.results.len(),
+                    pending.len()
+                );
+                break;
+            }
+        }
+
+        Ok(self.finish(run, &query, start))
+    }
+
+    /// Runs the selected engines and returns the results of those that
+    /// responded and the errors of those that did not.
+    async fn run_engines(&self, query: SearchQuery) -> Result<EngineRun> {
+        let mut run = EngineRun::default();
+        for outcome in join_all(self.dispatch(query)?).await {
+            run.record(outcome);
+        }
+        Ok(run)
+    }
+
+    /// Validates `query` and returns one future per selected engine, each
+    /// serving from the cache or running the engine with its timeout.
+    fn dispatch(
+        &self,
+        query: SearchQuery,
+    ) -> Result<Vec<impl std::future::Future<Output = EngineOutcome> + Send + 'static>> {
         if self.engines.is_empty() {
             return Err(SearchError::NoEngines);
         }
@@ -67,60 +467,132 @@ impl Search {
             return Err(SearchError::InvalidQuery("Query cannot be empty".into()));
         }
 
-        let start = Instant::now();
         let query = Arc::new(query);
 
         let engines_to_use = self.select_engines(&query);
         debug!("Searching {} engines", engines_to_use.len());
 
-        let futures: Vec<_> = engines_to_use
+        let offsets = dispatch_offsets(engines_to_use.len(), self.dispatch_jitter);
+        let futures = engines_to_use
             .iter()
-            .map(|engine| {
+            .zip(offsets)
+            .map(|(engine, delay)| {
                 let engine = Arc::clone(engine);
                 let query = Arc::clone(&query);
-                let timeout_duration = Duration::from_secs(engine.config().timeout);
+                let timeout_duration = self.engine_timeout(engine.as_ref());
+                let cache = self.cache.clone();
+                let user_agent = self.user_agent.clone();
+                #[cfg(feature = "otel")]
+                let span = engine_span(engine.as_ref());
 
-                async move {
+                let run = async move {
                     let name = engine.name().to_string();
-                    match timeout(timeout_duration, engine.search(&query)).await {
+                    let key = cache_key(&name, &query);
+                    if let Some(ref cache) = cache {
+                        let cached = cache.get(&key).await;
+                        if let Some(cached) = cached
+                            .and_then(|json| serde_json::from_str::<CachedResults>(&json).ok())
+                        {
+                            let age = unix_millis().saturating_sub(cached.stored_at_ms);
+                            debug!(
+                                "Engine {} served {} cached results ({}ms old)",
+                                name,
+                                cached.results.len(),
+                                age
+                            );
+                            return EngineOutcome::results(
+                                name,
+                                cached.results,
+                                Some(Duration::from_millis(age)),
+                            );
+                        }
+                    }
+
+                    // The engine's timeout starts once it is dispatched
+                    if !delay.is_zero() {
+                        tokio::time::sleep(delay).await;
+                    }
+                    let search = with_fetch_context(
+                        engine.preferred_fetcher(&query),
+                        user_agent,
+                        engine.search(&query),
+                    );
+                    match timeout(timeout_duration, search).await {
                         Ok(Ok(results)) => {
-                            debug!("Engine {} returned {} results", name, results.len());
-                            Ok((name, results))
+                            if results.is_empty() {
+                                debug!("Engine {} returned no results", name);
+                            } else {
+                                debug!("Engine {} returned {} results", name, results.len());
+                            }
+                            if let Some(ref cache) = cache {
+                                if !results.is_empty() {
+                                    let cached = CachedResults {
+                                        stored_at_ms: unix_millis(),
+                                        results,
+                                    };
+                                    if let Ok(json) = serde_json::to_string(&cached) {
+                                        cache.insert(&key, json).await;
+                                    }
+                                    return EngineOutcome::results(name, cached.results, None);
+                                }
+                            }
+                            EngineOutcome::results(name, results, None)
                         }
                         Ok(Err(e)) => {
-                            warn!("Engine {} failed: {}", name, e);
-                            Err((name, e.to_string()))
+                            if is_blocked(&e) {
+                                warn!("Engine {} was blocked: {}", name, e);
+                            } else {
+                                warn!("Engine {} failed: {}", name, e);
+                            }
+                            EngineOutcome::failed(name, &e)
                         }
                         Err(_) => {
                             warn!("Engine {} timed out", name);
-                            Err((name, "timed out".to_string()))
+                            EngineOutcome::timed_out(name)
                         }
                     }
-                }
+                };
+                #[cfg(feature = "otel")]
+                let run = traced(span, run);
+                run
             })
             .collect();
 
-        let all_results: Vec<_> = join_all(futures).await;
-
-        let mut engine_errors = Vec::new();
-        let results: Vec<_> = all_results
-            .into_iter()
-            .filter_map(|r| match r {
-                Ok(pair) => Some(pair),
-                Err(err) => {
-                    engine_errors.push(err);
-                    None
-                }
-            })
-            .collect();
+        Ok(futures)
+    }
 
+    /// Aggregates engine results and applies the query's `site:` operators
+    /// and the content requirement.
+    fn aggregate(
+        &self,
+        results: Vec<(String, Vec<SearchResult>)>,
+        query: &SearchQuery,
+    ) -> SearchResults {
         let mut search_results = self.aggregator.aggregate(results);
-        for (engine, error) in engine_errors {
+        search_results.filter_sites(query);
+        if self.require_content {
+            search_results.filter_empty_content();
+        }
+        search_results
+    }
+
+    /// Turns an engine run into the final `SearchResults`.
+    ///
+    /// The results count as cached if every engine that responded was
+    /// served from the cache; the age is that of the oldest cached list.
+    fn finish(&self, run: EngineRun, query: &SearchQuery, start: Instant) -> SearchResults {
+        let from_cache = !run.results.is_empty() && run.uncached == 0;
+        let mut search_results = self.aggregate(run.results, query);
+        for (engine, error) in run.errors {
             search_results.add_error(engine, error);
         }
+        for (engine, status) in run.statuses {
+            search_results.add_engine_status(engine, status);
+        }
+        search_results.from_cache = from_cache;
+        search_results.age = run.cached.into_iter().max();
         search_results.set_duration(start.elapsed().as_millis() as u64);
-
-        Ok(search_results)
+        search_results
     }
 
     /// Selects engines based on query parameters.
@@ -147,6 +619,78 @@ impl Search {
     }
 }
 
+/// Creates the span an engine's search runs in.
+///
+/// Field names follow `tracing-opentelemetry` conventions: `otel.name`,
+/// `otel.kind` and `otel.status_code` set the exported span's name, kind
+/// and status, and the other fields become span attributes. `proxy_used`
+/// is set by the fetchers that send the engine's requests through a proxy.
+#[cfg(feature = "otel")]
+fn engine_span(engine: &dyn Engine) -> tracing::Span {
+    tracing::info_span!(
+        "search.engine",
+        otel.name = %format_args!("search {}", engine.name()),
+        otel.kind = "client",
+        otel.status_code = tracing::field::Empty,
+        engine = engine.name(),
+        engine.shortcut = engine.shortcut(),
+        proxy_used = false,
+        result_count = tracing::field::Empty,
+        cache_hit = tracing::field::Empty,
+        error = tracing::field::Empty,
+    )
+}
+
+/// Runs an engine's search in `span` and records its outcome there.
+#[cfg(feature = "otel")]
+async fn traced(
+    span: tracing::Span,
+    run: impl std::future::Future<Output = EngineOutcome>,
+) -> EngineOutcome {
+    use tracing::Instrument;
+
+    let outcome = run.instrument(span.clone()).await;
+    span.record("result_count", outcome.results.len());
+    span.record("cache_hit", outcome.age.is_some());
+    match outcome.error {
+        Some(ref error) => {
+            span.record("otel.status_code", "ERROR");
+            span.record("error", error.as_str());
+        }
+        None => {
+            span.record("otel.status_code", "OK");
+        }
+    }
+    outcome
+}
+
+/// Returns the cache key of `engine`'s results for `query`.
+fn cache_key(engine: &str, query: &SearchQuery) -> String {
+    format!("{}:{}", engine, query.cache_key())
+}
+
+/// Returns the start delay of each of `count` engines: the first starts at
+/// once and each later one between half and the full `jitter` after the one
+/// before it.
+fn dispatch_offsets(count: usize, jitter: Duration) -> Vec<Duration> {
+    if jitter.is_zero() {
+        return vec![Duration::ZERO; count];
+    }
+
+    let mut rng = StdRng::from_os_rng();
+    let half = jitter / 2;
+
+    let mut at = Duration::ZERO;
+    (0..count)
+        .map(|i| {
+            if i > 0 {
+                at += rng.random_range(half..=jitter);
+            }
+            at
+        })
+        .collect()
+}
+
 impl Default for Search {
     fn default() -> Self {
         Self::new()
@@ -156,12 +700,13 @@ impl Default for Search {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{EngineCategory, EngineConfig, SearchResult};
+    use crate::{EngineCategory, EngineConfig, SearchResult, SiteMatch};
     use async_trait::async_trait;
 
     struct MockEngine {
         config: EngineConfig,
         results: Vec<SearchResult>,
+        delay: Duration,
     }
 
     impl MockEngine {
@@ -174,9 +719,15 @@ mod tests {
                     ..Default::default()
                 },
                 results,
+                delay: Duration::ZERO,
             }
         }
 
+        fn with_delay(mut self, delay: Duration) -> Self {
+            self.delay = delay;
+            self
+        }
+
         fn with_category(mut self, category: EngineCategory) -> Self {
             self.config.categories = vec![category];
             self
@@ -200,12 +751,16 @@ mod tests {
         }
 
         async fn search(&self, _query: &SearchQuery) -> Result<Vec<SearchResult>> {
+            if !self.delay.is_zero() {
+                tokio::time::sleep(self.delay).await;
+            }
             Ok(self.results.clone())
         }
     }
 
     struct FailingEngine {
         config: EngineConfig,
+        error: SearchError,
     }
 
     impl FailingEngine {
@@ -217,8 +772,14 @@ mod tests {
                     categories: vec![EngineCategory::General],
                     ..Default::default()
                 },
+                error: SearchError::Other("Engine failed".to_string()),
             }
         }
+
+        fn with_error(mut self, error: SearchError) -> Self {
+            self.error = error;
+            self
+        }
     }
 
     #[async_trait]
@@ -228,7 +789,258 @@ mod tests {
         }
 
         async fn search(&self, _query: &SearchQuery) -> Result<Vec<SearchResult>> {
-            Err(SearchError::Other("Engine failed".to_string()))
+            Err(self.error.duplicate())
+        }
+    }
+
+    #[cfg(feature = "otel")]
+    mod otel {
+        use super::*;
+        use std::collections::HashMap;
+
+        /// Collects the fields of every `search.engine` span.
+        #[derive(Clone, Default)]
+        struct EngineSpans(Arc<std::sync::Mutex<Vec<HashMap<String, String>>>>);
+
+        impl EngineSpans {
+            fn for_engine(&self, engine: &str) -> Vec<HashMap<String, String>> {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .filter(|fields| fields.get("engine").map(String::as_str) == Some(engine))
+                    .cloned()
+                    .collect()
+            }
+        }
+
+        struct FieldMap<'a>(&'a mut HashMap<String, String>);
+
+        impl tracing::field::Visit for FieldMap<'_> {
+            fn record_str(&mut self, field: &tracing::field::Field, value: &str) {
+                self.0.insert(field.name().to_string(), value.to_string());
+            }
+
+            fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+                self.0
+                    .insert(field.name().to_string(), format!("{:?}", value));
+            }
+        }
+
+        impl<S> tracing_subscriber::Layer<S> for EngineSpans
+        where
+            S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+        {
+            fn on_new_span(
+                &self,
+                attrs: &tracing::span::Attributes<'_>,
+                id: &tracing::span::Id,
+                ctx: tracing_subscriber::layer::Context<'_, S>,
+            ) {
+                if attrs.metadata().name() != "search.engine" {
+                    return;
+                }
+                let mut fields = HashMap::new();
+                attrs.record(&mut FieldMap(&mut fields));
+                if let Some(span) = ctx.span(id) {
+                    span.extensions_mut().insert(self.0.lock().unwrap().len());
+                }
+                self.0.lock().unwrap().push(fields);
+            }
+
+            fn on_record(
+                &self,
+                id: &tracing::span::Id,
+                values: &tracing::span::Record<'_>,
+                ctx: tracing_subscriber::layer::Context<'_, S>,
+            ) {
+                let Some(index) = ctx
+                    .span(id)
+                    .and_then(|s| s.extensions().get::<usize>().copied())
+                else {
+                    return;
+                };
+                values.record(&mut FieldMap(&mut self.0.lock().unwrap()[index]));
+            }
+        }
+
+        #[tokio::test]
+        async fn test_engine_spans_carry_otel_attributes() {
+            use tracing_subscriber::layer::SubscriberExt;
+
+            let spans = EngineSpans::default();
+            let subscriber = tracing_subscriber::registry().with(spans.clone());
+            let _guard = tracing::subscriber::set_default(subscriber);
+
+            let mut search = Search::new();
+            search.set_cache(crate::MemoryCache::new(Duration::from_secs(60), 10));
+            search.add_engine(MockEngine::new(
+                "Fresh",
+                vec![
+                    SearchResult::new("https://a.com/", "A", "a"),
+                    SearchResult::new("https://b.com/", "B", "b"),
+                ],
+            ));
+            search.add_engine(FailingEngine::new("Broken"));
+
+            search.search(SearchQuery::new("rust")).await.unwrap();
+            search.search(SearchQuery::new("rust")).await.unwrap();
+
+            let fresh = spans.for_engine("Fresh");
+            assert_eq!(fresh.len(), 2);
+            for (fields, cache_hit) in fresh.iter().zip(["false", "true"]) {
+                assert_eq!(fields["otel.name"], "search Fresh");
+                assert_eq!(fields["otel.kind"], "client");
+                assert_eq!(fields["otel.status_code"], "OK");
+                assert_eq!(fields["engine.shortcut"], "Fresh");
+                assert_eq!(fields["proxy_used"], "false");
+                assert_eq!(fields["result_count"], "2");
+                assert_eq!(fields["cache_hit"], cache_hit);
+            }
+
+            let broken = &spans.for_engine("Broken")[0];
+            assert_eq!(broken["otel.status_code"], "ERROR");
+            assert_eq!(broken["error"], "Engine failed");
+            assert_eq!(broken["result_count"], "0");
+            assert_eq!(broken["cache_hit"], "false");
+        }
+
+        #[tokio::test]
+        async fn test_engine_spans_report_proxied_fetches() {
+            use tracing_subscriber::layer::SubscriberExt;
+
+            let spans = EngineSpans::default();
+            let subscriber = tracing_subscriber::registry().with(spans.clone());
+            let _guard = tracing::subscriber::set_default(subscriber);
+
+            let engine = |name: &str, fetcher: crate::HttpFetcher| FetchingEngine {
+                config: EngineConfig {
+                    name: name.to_string(),
+                    shortcut: name.to_string(),
+                    ..Default::default()
+                },
+                fetcher: Arc::new(fetcher),
+            };
+            let mut search = Search::new();
+            // Nothing listens on port 1, so the proxied fetch fails fast
+            search.add_engine(engine(
+                "Proxied",
+                crate::HttpFetcher::with_proxy("http://127.0.0.1:1").unwrap(),
+            ));
+            search.add_engine(engine(
+                "Direct",
+                crate::HttpFetcher::with_timeout(Duration::from_millis(1)),
+            ));
+
+            search.search(SearchQuery::new("rust")).await.unwrap();
+
+            assert_eq!(spans.for_engine("Proxied")[0]["proxy_used"], "true");
+            assert_eq!(spans.for_engine("Direct")[0]["proxy_used"], "false");
+        }
+    }
+
+    /// Records the user agent it sees when fetching.
+    struct AgentFetcher {
+        seen: std::sync::Mutex<Vec<Option<String>>>,
+    }
+
+    #[async_trait]
+    impl crate::PageFetcher for AgentFetcher {
+        async fn fetch(&self, _url: &str) -> Result<String> {
+            self.seen.lock().unwrap().push(Search::current_user_agent());
+            Ok(String::new())
+        }
+    }
+
+    /// Fetches one page through an injected fetcher.
+    struct FetchingEngine {
+        config: EngineConfig,
+        fetcher: Arc<dyn crate::PageFetcher>,
+    }
+
+    #[async_trait]
+    impl Engine for FetchingEngine {
+        fn config(&self) -> &EngineConfig {
+            &self.config
+        }
+
+        async fn search(&self, _query: &SearchQuery) -> Result<Vec<SearchResult>> {
+            self.fetcher.fetch("https://example.com/").await?;
+            Ok(Vec::new())
+        }
+    }
+
+    /// Records when its search started.
+    struct TimedEngine {
+        config: EngineConfig,
+        starts: Arc<std::sync::Mutex<Vec<(String, tokio::time::Instant)>>>,
+    }
+
+    impl TimedEngine {
+        fn new(
+            name: &str,
+            starts: &Arc<std::sync::Mutex<Vec<(String, tokio::time::Instant)>>>,
+        ) -> Self {
+            Self {
+                config: EngineConfig {
+                    name: name.to_string(),
+                    shortcut: name.to_string(),
+                    categories: vec![EngineCategory::General],
+                    ..Default::default()
+                },
+                starts: starts.clone(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Engine for TimedEngine {
+        fn config(&self) -> &EngineConfig {
+            &self.config
+        }
+
+        async fn search(&self, _query: &SearchQuery) -> Result<Vec<SearchResult>> {
+            self.starts
+                .lock()
+                .unwrap()
+                .push((self.config.name.clone(), tokio::time::Instant::now()));
+            Ok(Vec::new())
+        }
+    }
+
+    /// Counts its searches and returns one result per query.
+    struct CountingEngine {
+        config: EngineConfig,
+        calls: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl CountingEngine {
+        fn new(name: &str, calls: &Arc<std::sync::atomic::AtomicUsize>) -> Self {
+            Self {
+                config: EngineConfig {
+                    name: name.to_string(),
+                    shortcut: name.to_string(),
+                    categories: vec![EngineCategory::General],
+                    ..Default::default()
+                },
+                calls: calls.clone(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Engine for CountingEngine {
+        fn config(&self) -> &EngineConfig {
+            &self.config
+        }
+
+        async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(vec![SearchResult::new(
+                format!("https://example.com/{}", query.query),
+                query.query.clone(),
+                "",
+            )])
         }
     }
 
@@ -251,11 +1063,102 @@ mod tests {
         assert_eq!(search.engine_count(), 1);
     }
 
+    #[test]
+    fn test_search_from_catalog() {
+        let search = Search::from_catalog(&["ddg", "wiki", "brave"], HttpFetcher::new()).unwrap();
+        let shortcuts: Vec<&str> = search.engines.iter().map(|e| e.shortcut()).collect();
+        assert_eq!(shortcuts, vec!["ddg", "wiki", "brave"]);
+    }
+
+    #[test]
+    fn test_search_from_catalog_empty() {
+        let result = Search::from_catalog::<&str>(&[], HttpFetcher::new());
+        assert!(matches!(result, Err(SearchError::NoEngines)));
+    }
+
+    #[test]
+    fn test_search_from_catalog_unknown() {
+        let shortcuts = vec!["ddg".to_string(), "nope".to_string(), "g".to_string()];
+        let err = match Search::from_catalog(&shortcuts, HttpFetcher::new()) {
+            Err(err) => err,
+            Ok(_) => panic!("unknown shortcuts accepted"),
+        };
+        assert!(
+            matches!(&err, SearchError::UnknownEngines(names) if names == &["nope", "g"]),
+            "{:?}",
+            err
+        );
+        assert_eq!(err.to_string(), "Unknown engines: nope, g");
+    }
+
+    #[tokio::test]
+    async fn test_search_add_engines() {
+        let mut search = Search::new();
+        search.add_engine(MockEngine::new("test", vec![]));
+        search.add_engines(crate::engines::all_http(HttpFetcher::new()));
+        assert_eq!(search.engine_count(), 8);
+        assert_eq!(search.engines[0].shortcut(), "test");
+        assert_eq!(search.engines[3].shortcut(), "wiki");
+    }
+
     #[tokio::test]
     async fn test_search_set_timeout() {
         let mut search = Search::new();
         search.set_timeout(Duration::from_secs(10));
-        assert_eq!(search.default_timeout, Duration::from_secs(10));
+        assert_eq!(search.default_timeout, Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_search_effective_engine_timeout() {
+        let engine = |timeout| {
+            let mut engine = MockEngine::new("engine", vec![]);
+            engine.config.timeout = timeout;
+            engine
+        };
+        let mut search = Search::new();
+        assert_eq!(search.engine_timeout(&engine(10)), Duration::from_secs(10));
+
+        search.set_timeout(Duration::from_secs(2));
+        assert_eq!(search.engine_timeout(&engine(10)), Duration::from_secs(2));
+        // The ceiling never extends a shorter engine timeout
+        assert_eq!(search.engine_timeout(&engine(1)), Duration::from_secs(1));
+        assert_eq!(search.engine_timeout(&engine(2)), Duration::from_secs(2));
+
+        search.set_timeout(Duration::from_millis(500));
+        assert_eq!(
+            search.engine_timeout(&engine(5)),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_search_timeout_caps_engine_timeout() {
+        let slow = || {
+            let mut engine = MockEngine::new(
+                "slow",
+                vec![SearchResult::new("https://a.example/", "A", "")],
+            )
+            .with_delay(Duration::from_secs(5));
+            engine.config.timeout = 30;
+            engine
+        };
+        let mut search = Search::new();
+        search.add_engine(slow());
+        search.set_timeout(Duration::from_secs(1));
+
+        let started = tokio::time::Instant::now();
+        let results = search.search(SearchQuery::new("test")).await.unwrap();
+
+        assert_eq!(started.elapsed(), Duration::from_secs(1));
+        assert!(results.items().is_empty());
+        assert_eq!(results.errors()[0].1, "timed out");
+
+        // Without a cap the engine's own timeout applies
+        let mut search = Search::new();
+        search.add_engine(slow());
+        let results = search.search(SearchQuery::new("test")).await.unwrap();
+        assert_eq!(results.items().len(), 1);
+        assert!(results.errors().is_empty());
     }
 
     #[tokio::test]
@@ -284,6 +1187,83 @@ mod tests {
         assert!(matches!(result, Err(SearchError::InvalidQuery(_))));
     }
 
+    #[tokio::test]
+    async fn test_search_filters_site_operator() {
+        let mut search = Search::new();
+        search.add_engine(MockEngine::new(
+            "test",
+            vec![
+                SearchResult::new("https://rust-lang.org/learn", "Learn", "Learn Rust"),
+                SearchResult::new("https://blog.rust-lang.org/", "Blog", "Rust blog"),
+                SearchResult::new("https://example.com/rust", "Example", "Unrelated"),
+            ],
+        ));
+        let urls = |results: &SearchResults| {
+            let mut urls: Vec<String> = results.items().iter().map(|r| r.url.clone()).collect();
+            urls.sort();
+            urls
+        };
+
+        let loose = search
+            .search(SearchQuery::new("rust site:rust-lang.org"))
+            .await
+            .unwrap();
+        assert_eq!(
+            urls(&loose),
+            vec!["https://blog.rust-lang.org/", "https://rust-lang.org/learn"]
+        );
+
+        let strict = search
+            .search(SearchQuery::new("rust site:rust-lang.org").with_site_match(SiteMatch::Strict))
+            .await
+            .unwrap();
+        assert_eq!(urls(&strict), vec!["https://rust-lang.org/learn"]);
+
+        let unscoped = search.search(SearchQuery::new("rust")).await.unwrap();
+        assert_eq!(unscoped.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_search_usable_after_shutdown() {
+        let mut search = Search::new();
+        search.add_engine(MockEngine::new(
+            "engine",
+            vec![SearchResult::new("https://example.com", "Example", "")],
+        ));
+
+        search.shutdown().await;
+        let results = search.search(SearchQuery::new("test")).await.unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[cfg(feature = "headless")]
+    #[tokio::test]
+    async fn test_search_shutdown_closes_browser_pool() {
+        use crate::BrowserPoolConfig;
+
+        let pool = Arc::new(BrowserPool::new(BrowserPoolConfig {
+            idle_shutdown: Some(Duration::from_secs(60)),
+            ..Default::default()
+        }));
+        let mut search = Search::new();
+        search.add_engine(MockEngine::new(
+            "engine",
+            vec![SearchResult::new("https://example.com", "Example", "")],
+        ));
+        search.set_browser_pool(Arc::clone(&pool));
+
+        // Twice, as the pool stays usable after a shutdown
+        timeout(Duration::from_secs(5), search.shutdown())
+            .await
+            .unwrap();
+        timeout(Duration::from_secs(5), search.shutdown())
+            .await
+            .unwrap();
+        assert_eq!(pool.stats().browsers_running, 0);
+        let results = search.search(SearchQuery::new("test")).await.unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
     #[tokio::test]
     async fn test_search_aggregates_results() {
         let mut search = Search::new();
@@ -307,7 +1287,7 @@ mod tests {
         let query = SearchQuery::new("test");
         let results = search.search(query).await.unwrap();
 
-        assert_eq!(results.items().len(), 2);
+        assert_eq!(results.len(), 2);
 
         let example = results
             .items()
@@ -355,7 +1335,7 @@ mod tests {
         let query = SearchQuery::new("test");
         let results = search.search(query).await.unwrap();
 
-        assert_eq!(results.items().len(), 1);
+        assert_eq!(results.len(), 1);
         assert_eq!(results.items()[0].url, "https://enabled.com");
     }
 
@@ -384,7 +1364,7 @@ mod tests {
         let query = SearchQuery::new("test").with_categories(vec![EngineCategory::Images]);
         let results = search.search(query).await.unwrap();
 
-        assert_eq!(results.items().len(), 1);
+        assert_eq!(results.len(), 1);
         assert_eq!(results.items()[0].url, "https://images.com");
     }
 
@@ -409,7 +1389,7 @@ mod tests {
         let query = SearchQuery::new("test").with_engines(vec!["e1".to_string()]);
         let results = search.search(query).await.unwrap();
 
-        assert_eq!(results.items().len(), 1);
+        assert_eq!(results.len(), 1);
         assert_eq!(results.items()[0].url, "https://one.com");
     }
 
@@ -430,7 +1410,7 @@ mod tests {
         let results = search.search(query).await.unwrap();
 
         // Should still return results from working engine
-        assert_eq!(results.items().len(), 1);
+        assert_eq!(results.len(), 1);
         assert_eq!(results.items()[0].url, "https://working.com");
 
         // Should record the engine error
@@ -439,6 +1419,142 @@ mod tests {
         assert!(results.errors()[0].1.contains("Engine failed"));
     }
 
+    #[tokio::test]
+    async fn test_search_engine_statuses() {
+        let mut search = Search::new();
+        search.add_engine(MockEngine::new(
+            "full",
+            vec![SearchResult::new("https://full.com", "Full", "Content")],
+        ));
+        search.add_engine(MockEngine::new("empty", vec![]));
+        search.add_engine(
+            FailingEngine::new("blocked").with_error(SearchError::Blocked("403".to_string())),
+        );
+        search.add_engine(
+            FailingEngine::new("captcha").with_error(SearchError::Captcha("CAPTCHA".to_string())),
+        );
+        search.add_engine(
+            FailingEngine::new("limited").with_error(SearchError::HttpStatus(429, String::new())),
+        );
+        search.add_engine(
+            FailingEngine::new("broken").with_error(SearchError::Parse("no results".to_string())),
+        );
+        search.add_engine(FailingEngine::new("failing"));
+
+        let results = search.search(SearchQuery::new("test")).await.unwrap();
+
+        let status = |name: &str| {
+            results
+                .engine_statuses()
+                .iter()
+                .find(|(engine, _)| engine == name)
+                .map(|(_, status)| *status)
+        };
+        assert_eq!(results.engine_statuses().len(), 7);
+        assert_eq!(status("full"), Some(EngineStatus::Ok));
+        assert_eq!(status("empty"), Some(EngineStatus::Empty));
+        assert_eq!(status("blocked"), Some(EngineStatus::Blocked));
+        assert_eq!(status("captcha"), Some(EngineStatus::Blocked));
+        assert_eq!(status("limited"), Some(EngineStatus::Blocked));
+        assert_eq!(status("broken"), Some(EngineStatus::ParseFailed));
+        assert_eq!(status("failing"), Some(EngineStatus::Failed));
+
+        // An engine that found nothing is not an error
+        assert_eq!(results.errors().len(), 5);
+        assert!(results.errors().iter().all(|(engine, _)| engine != "empty"));
+        assert!(EngineStatus::Empty.is_ok());
+        assert!(!EngineStatus::Blocked.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_search_engine_timeout_status() {
+        let mut search = Search::new();
+        let mut engine = MockEngine::new("slow", vec![]).with_delay(Duration::from_secs(2));
+        engine.config.timeout = 1;
+        search.add_engine(engine);
+
+        let results = search.search(SearchQuery::new("test")).await.unwrap();
+
+        assert_eq!(
+            results.engine_statuses(),
+            &[("slow".to_string(), EngineStatus::Failed)]
+        );
+        assert_eq!(results.errors()[0].1, "timed out");
+    }
+
+    #[tokio::test]
+    async fn test_search_user_agent_reaches_fetcher() {
+        let fetcher = Arc::new(AgentFetcher {
+            seen: std::sync::Mutex::new(Vec::new()),
+        });
+        let mut search = Search::new();
+        search.add_engine(FetchingEngine {
+            config: EngineConfig {
+                name: "fetching".to_string(),
+                shortcut: "fetching".to_string(),
+                ..Default::default()
+            },
+            fetcher: fetcher.clone(),
+        });
+
+        search.search(SearchQuery::new("test")).await.unwrap();
+        search.set_user_agent("GlobalBot/1.0");
+        search.search(SearchQuery::new("test")).await.unwrap();
+
+        assert_eq!(
+            *fetcher.seen.lock().unwrap(),
+            vec![None, Some("GlobalBot/1.0".to_string())]
+        );
+        // Outside an engine search there is none
+        assert!(Search::current_user_agent().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_detailed() {
+        let mut search = Search::new();
+        search.add_engine(MockEngine::new(
+            "engine1",
+            vec![SearchResult::new(
+                "https://example.com",
+                "Example",
+                "Content",
+            )],
+        ));
+        search.add_engine(MockEngine::new(
+            "engine2",
+            vec![
+                SearchResult::new("https://example.com", "Example Site", "More content"),
+                SearchResult::new("https://other.com", "Other", "Other content"),
+            ],
+        ));
+        search.add_engine(FailingEngine::new("failing"));
+
+        let detailed = search
+            .search_detailed(SearchQuery::new("test"))
+            .await
+            .unwrap();
+
+        assert_eq!(detailed.aggregated.len(), 2);
+        assert_eq!(detailed.aggregated.errors().len(), 1);
+
+        assert_eq!(detailed.by_engine.len(), 2);
+        assert_eq!(detailed.by_engine[0].0, "engine1");
+        assert_eq!(detailed.by_engine[0].1.len(), 1);
+        assert_eq!(detailed.by_engine[1].0, "engine2");
+        assert_eq!(detailed.by_engine[1].1.len(), 2);
+        assert_eq!(detailed.by_engine[1].1[0].title, "Example Site");
+
+        assert_eq!(detailed.errors.len(), 1);
+        assert_eq!(detailed.errors[0].0, "failing");
+    }
+
+    #[tokio::test]
+    async fn test_search_detailed_no_engines() {
+        let search = Search::new();
+        let result = search.search_detailed(SearchQuery::new("test")).await;
+        assert!(matches!(result, Err(SearchError::NoEngines)));
+    }
+
     #[tokio::test]
     async fn test_search_all_engines_fail() {
         let mut search = Search::new();
@@ -449,7 +1565,7 @@ mod tests {
         let results = search.search(query).await.unwrap();
 
         // Should return empty results, not error
-        assert_eq!(results.items().len(), 0);
+        assert_eq!(results.len(), 0);
 
         // Should record both engine errors
         assert_eq!(results.errors().len(), 2);
@@ -488,34 +1604,405 @@ mod tests {
             .with_categories(vec![EngineCategory::General, EngineCategory::News]);
         let results = search.search(query).await.unwrap();
 
-        assert_eq!(results.items().len(), 2);
+        assert_eq!(results.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_require_content() {
+        let mut search = Search::new();
+        search.add_engine(MockEngine::new(
+            "engine1",
+            vec![
+                SearchResult::new("https://a.example", "A", "Snippet"),
+                SearchResult::new("https://b.example", "B", ""),
+            ],
+        ));
+
+        let results = search.search(SearchQuery::new("test")).await.unwrap();
+        assert_eq!(results.len(), 2);
+
+        search.set_require_content(true);
+        let results = search.search(SearchQuery::new("test")).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results.items()[0].url, "https://a.example");
+    }
+
+    #[tokio::test]
+    async fn test_search_require_content_keeps_only_result() {
+        let mut search = Search::new();
+        search.set_require_content(true);
+        search.add_engine(MockEngine::new(
+            "engine1",
+            vec![SearchResult::new("https://a.example", "A", "")],
+        ));
+
+        let results = search.search(SearchQuery::new("test")).await.unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    fn numbered_results(prefix: &str, count: usize) -> Vec<SearchResult> {
+        (0..count)
+            .map(|i| {
+                SearchResult::new(
+                    format!("https://{}.example.com/{}", prefix, i),
+                    format!("{} {}", prefix, i),
+                    "content",
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_search_until_returns_before_slow_engine() {
+        let mut search = Search::new();
+        search.add_engine(MockEngine::new("fast", numbered_results("fast", 10)));
+        search.add_engine(
+            MockEngine::new("slow", numbered_results("slow", 10))
+                .with_delay(Duration::from_secs(3)),
+        );
+
+        let begin = tokio::time::Instant::now();
+        let results = search
+            .search_until(SearchQuery::new("test"), 10, Duration::from_millis(200))
+            .await
+            .unwrap();
+
+        assert_eq!(begin.elapsed(), Duration::from_millis(200));
+        assert_eq!(results.len(), 10);
+        assert!(results
+            .items()
+            .iter()
+            .all(|r| r.engines.contains("fast") && !r.engines.contains("slow")));
+        assert!(results.errors().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_search_until_waits_for_engines_before_deadline() {
+        let mut search = Search::new();
+        search.add_engine(MockEngine::new("fast", numbered_results("fast", 10)));
+        search.add_engine(
+            MockEngine::new("medium", numbered_results("medium", 5))
+                .with_delay(Duration::from_millis(100)),
+        );
+
+        let begin = tokio::time::Instant::now();
+        let results = search
+            .search_until(SearchQuery::new("test"), 10, Duration::from_millis(200))
+            .await
+            .unwrap();
+
+        assert_eq!(begin.elapsed(), Duration::from_millis(100));
+        assert_eq!(results.len(), 15);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_search_until_waits_for_all_without_enough_results() {
+        let mut search = Search::new();
+        search.add_engine(MockEngine::new("fast", numbered_results("fast", 3)));
+        search.add_engine(
+            MockEngine::new("slow", numbered_results("slow", 4)).with_delay(Duration::from_secs(1)),
+        );
+        search.add_engine(FailingEngine::new("failing"));
+
+        let begin = tokio::time::Instant::now();
+        let results = search
+            .search_until(SearchQuery::new("test"), 10, Duration::from_millis(200))
+            .await
+            .unwrap();
+
+        assert_eq!(begin.elapsed(), Duration::from_secs(1));
+        assert_eq!(results.len(), 7);
+        assert_eq!(results.errors().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_until_no_engines() {
+        let search = Search::new();
+        let result = search
+            .search_until(SearchQuery::new("test"), 10, Duration::from_millis(200))
+            .await;
+        assert!(matches!(result, Err(SearchError::NoEngines)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_search_dispatch_jitter_staggers_engines() {
+        let starts = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut search = Search::new();
+        for name in ["a", "b", "c", "d"] {
+            search.add_engine(TimedEngine::new(name, &starts));
+        }
+        search.set_dispatch_jitter(Duration::from_millis(100));
+
+        let begin = tokio::time::Instant::now();
+        search.search(SearchQuery::new("test")).await.unwrap();
+
+        let starts = starts.lock().unwrap();
+        let names: Vec<&str> = starts.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["a", "b", "c", "d"]);
+        assert_eq!(starts[0].1, begin);
+        for pair in starts.windows(2) {
+            let gap = pair[1].1 - pair[0].1;
+            assert!(
+                gap >= Duration::from_millis(50) && gap <= Duration::from_millis(100),
+                "gap {:?}",
+                gap
+            );
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_search_without_jitter_starts_together() {
+        let starts = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let mut search = Search::new();
+        for name in ["a", "b", "c"] {
+            search.add_engine(TimedEngine::new(name, &starts));
+        }
+
+        let begin = tokio::time::Instant::now();
+        search.search(SearchQuery::new("test")).await.unwrap();
+
+        assert!(starts.lock().unwrap().iter().all(|(_, at)| *at == begin));
+    }
+
+    #[test]
+    fn test_search_dispatch_jitter_capped() {
+        let mut search = Search::new();
+        search.set_dispatch_jitter(Duration::from_secs(10));
+        assert_eq!(search.dispatch_jitter, MAX_DISPATCH_JITTER);
+    }
+
+    #[test]
+    fn test_dispatch_offsets() {
+        assert_eq!(dispatch_offsets(3, Duration::ZERO), vec![Duration::ZERO; 3]);
+        let offsets = dispatch_offsets(5, Duration::from_millis(200));
+        assert_eq!(offsets[0], Duration::ZERO);
+        assert!(*offsets.last().unwrap() <= Duration::from_millis(800));
+        assert!(offsets
+            .windows(2)
+            .all(|w| w[1] - w[0] >= Duration::from_millis(100)));
+    }
+
+    #[tokio::test]
+    async fn test_search_cache_skips_engine() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut search = Search::new();
+        search.add_engine(CountingEngine::new("counting", &calls));
+        search.set_cache(crate::MemoryCache::new(Duration::from_secs(60), 10));
+
+        let first = search.search(SearchQuery::new("rust")).await.unwrap();
+        let second = search.search(SearchQuery::new("rust")).await.unwrap();
+        search.search(SearchQuery::new("go")).await.unwrap();
+
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(second.items()[0].url, first.items()[0].url);
+        assert!(second.items()[0].engines.contains("counting"));
+
+        assert!(!first.from_cache);
+        assert!(first.age.is_none());
+        assert!(second.from_cache);
+        assert!(second.age.unwrap() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn test_search_cache_reports_age() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let cache = Arc::new(crate::MemoryCache::new(Duration::from_secs(600), 10));
+        let mut search = Search::new();
+        search.add_engine(CountingEngine::new("counting", &calls));
+        search.add_engine(MockEngine::new(
+            "fresh",
+            vec![SearchResult::new(
+                "https://fresh.example.com",
+                "Fresh",
+                "content",
+            )],
+        ));
+        search.cache = Some(cache.clone());
+
+        // Stored three minutes ago
+        let query = SearchQuery::new("rust");
+        let cached = CachedResults {
+            stored_at_ms: unix_millis() - 180_000,
+            results: vec![SearchResult::new(
+                "https://cached.example.com",
+                "Cached",
+                "content",
+            )],
+        };
+        cache
+            .insert(
+                &cache_key("counting", &query),
+                serde_json::to_string(&cached).unwrap(),
+            )
+            .await;
+        cache
+            .insert(
+                &cache_key("fresh", &query),
+                serde_json::to_string(&cached).unwrap(),
+            )
+            .await;
+
+        let results = search.search(query).await.unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+        assert!(results.from_cache);
+        let age = results.age.unwrap();
+        assert!(
+            age >= Duration::from_secs(180) && age < Duration::from_secs(185),
+            "{:?}",
+            age
+        );
+
+        // A fresh search leaves both unset
+        let results = search.search(SearchQuery::new("go")).await.unwrap();
+        assert!(!results.from_cache);
+        assert!(results.age.is_none());
     }
 
     #[tokio::test]
-    async fn test_search_set_proxy_pool() {
-        use crate::proxy::{ProxyConfig, ProxyPool};
+    async fn test_search_partly_cached_results() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut search = Search::new();
+        search.add_engine(CountingEngine::new("counting", &calls));
+        search.set_cache(crate::MemoryCache::new(Duration::from_secs(60), 10));
+        search.search(SearchQuery::new("rust")).await.unwrap();
+
+        search.add_engine(MockEngine::new(
+            "uncached",
+            vec![SearchResult::new("https://fresh.example.com", "Fresh", "")],
+        ));
+        let results = search.search(SearchQuery::new("rust")).await.unwrap();
+        assert!(!results.from_cache);
+        assert!(results.age.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_search_disk_cache_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("a3s-search-disk-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        for _ in 0..2 {
+            let mut search = Search::new();
+            search.add_engine(CountingEngine::new("counting", &calls));
+            search.set_cache(crate::DiskCache::new(&dir, Duration::from_secs(60)).unwrap());
+            let results = search.search(SearchQuery::new("rust")).await.unwrap();
+            assert_eq!(results.items()[0].url, "https://example.com/rust");
+        }
 
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Returns two results per page, paging only if configured to.
+    struct PagedEngine {
+        config: EngineConfig,
+        pages: Arc<std::sync::Mutex<Vec<u32>>>,
+    }
+
+    impl PagedEngine {
+        fn new(name: &str, paging: bool) -> Self {
+            Self {
+                config: EngineConfig {
+                    name: name.to_string(),
+                    shortcut: name.to_string(),
+                    categories: vec![EngineCategory::General],
+                    paging,
+                    ..Default::default()
+                },
+                pages: Arc::new(std::sync::Mutex::new(Vec::new())),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Engine for PagedEngine {
+        fn config(&self) -> &EngineConfig {
+            &self.config
+        }
+
+        async fn search(&self, query: &SearchQuery) -> Result<Vec<SearchResult>> {
+            self.pages.lock().unwrap().push(query.page);
+            Ok((1..=2)
+                .map(|i| {
+                    let url = format!(
+                        "https://{}.example.com/{}/{}",
+                        self.config.name, query.page, i
+                    );
+                    SearchResult::new(url, format!("Page {} #{}", query.page, i), "")
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_pages_merges_paging_engines() {
+        let paged = PagedEngine::new("paged", true);
+        let paged_calls = paged.pages.clone();
+        let single = PagedEngine::new("single", false);
+        let single_calls = single.pages.clone();
         let mut search = Search::new();
-        assert!(search.proxy_pool().is_none());
+        search.add_engine(paged);
+        search.add_engine(single);
 
-        let proxy_pool = ProxyPool::with_proxies(vec![ProxyConfig::new("127.0.0.1", 8080)]);
-        search.set_proxy_pool(proxy_pool);
+        let results = search
+            .search_pages(SearchQuery::new("rust"), 3)
+            .await
+            .unwrap();
 
-        assert!(search.proxy_pool().is_some());
+        assert_eq!(*paged_calls.lock().unwrap(), vec![1, 2, 3]);
+        assert_eq!(*single_calls.lock().unwrap(), vec![1]);
+        assert_eq!(results.len(), 8);
+        // Later pages rank behind earlier ones
+        let last = results.items().last().unwrap();
+        assert_eq!(last.url, "https://paged.example.com/3/2");
+        assert_eq!(last.positions, vec![6]);
+        assert_eq!(results.engine_statuses().len(), 4);
     }
 
     #[tokio::test]
-    async fn test_search_proxy_pool_reference() {
-        use crate::proxy::{ProxyConfig, ProxyPool};
+    async fn test_search_pages_starts_at_query_page() {
+        let paged = PagedEngine::new("paged", true);
+        let calls = paged.pages.clone();
+        let mut search = Search::new();
+        search.add_engine(paged);
+
+        let results = search
+            .search_pages(SearchQuery::new("rust").with_page(4), 2)
+            .await
+            .unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec![4, 5]);
+        assert_eq!(results.len(), 4);
 
+        // Zero pages still searches the query's page
+        search
+            .search_pages(SearchQuery::new("rust"), 0)
+            .await
+            .unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec![4, 5, 1]);
+
+        // The last page has no pages after it
+        search
+            .search_pages(SearchQuery::new("rust").with_page(u32::MAX), 3)
+            .await
+            .unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec![4, 5, 1, u32::MAX]);
+    }
+
+    #[tokio::test]
+    async fn test_search_cache_separates_pages() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let cache = Arc::new(crate::MemoryCache::new(Duration::from_secs(60), 10));
         let mut search = Search::new();
-        let proxy_pool = ProxyPool::with_proxies(vec![
-            ProxyConfig::new("127.0.0.1", 8080),
-            ProxyConfig::new("127.0.0.1", 8081),
-        ]);
-        search.set_proxy_pool(proxy_pool);
+        search.add_engine(CountingEngine::new("counting", &calls));
+        search.cache = Some(cache.clone());
+
+        search.search(SearchQuery::new("rust")).await.unwrap();
+        search
+            .search(SearchQuery::new("rust").with_page(2))
+            .await
+            .unwrap();
 
-        let pool_ref = search.proxy_pool().unwrap();
-        assert!(pool_ref.is_enabled());
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(cache.len().await, 2);
     }
 }
@@ -0,0 +1,213 @@
+//! Local HTTP/1.1 servers for tests.
+//!
+//! Available with the `test-utils` feature, so integration tests can serve
+//! fixtures through the same servers the unit tests use.
+//!
+//! [`spawn_server`] answers each request with the response its handler
+//! builds; [`spawn_connection_server`] hands over raw connections for tests
+//! that need to control the wire. Every server binds a free local port and
+//! runs until its handle is aborted.
+
+use std::future::Future;
+use std::net::SocketAddr;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// A request read off the wire.
+#[derive(Debug, Clone)]
+pub struct Request {
+    /// The request line and headers, including the blank line ending them.
+    pub head: String,
+    /// The body announced by `Content-Length`.
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Returns the request method, such as `GET`.
+    pub fn method(&self) -> &str {
+        self.head.split_whitespace().next().unwrap_or_default()
+    }
+
+    /// Returns the request target, such as `/search?q=rust`.
+    pub fn path(&self) -> &str {
+        self.head.split_whitespace().nth(1).unwrap_or("/")
+    }
+
+    /// Returns the first value of header `name`, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.head.lines().skip(1).find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    /// Returns the request as it was sent.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.head.clone().into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+/// A response written back with `Content-Length` and `Connection: close`.
+pub struct Response {
+    status: String,
+    headers: String,
+    body: Vec<u8>,
+}
+
+impl Response {
+    /// Creates an empty response with the given status line, such as
+    /// `404 Not Found`.
+    pub fn new(status: &str) -> Self {
+        Self {
+            status: status.to_string(),
+            headers: String::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Creates a `200 OK` response with `body`.
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self::new("200 OK").body(body)
+    }
+
+    /// Creates a redirect with the given status line to `location`.
+    pub fn redirect(status: &str, location: &str) -> Self {
+        Self::new(status).header("Location", location)
+    }
+
+    /// Adds a header.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push_str(&format!("{}: {}\r\n", name, value));
+        self
+    }
+
+    /// Adds raw header lines, each ending in `\r\n`.
+    pub fn headers(mut self, lines: &str) -> Self {
+        self.headers.push_str(lines);
+        self
+    }
+
+    /// Sets the body.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        let mut bytes = format!(
+            "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.headers,
+            self.body.len()
+        )
+        .into_bytes();
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+}
+
+/// Spawns a server that hands every accepted connection to `handle` on its
+/// own task.
+pub async fn spawn_connection_server<F, Fut>(handle: F) -> (SocketAddr, JoinHandle<()>)
+where
+    F: Fn(TcpStream) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        while let Ok((socket, _)) = listener.accept().await {
+            tokio::spawn(handle(socket));
+        }
+    });
+    (addr, server)
+}
+
+/// Spawns a server that answers every request with the response `respond`
+/// builds for it.
+pub async fn spawn_server<F, Fut>(respond: F) -> (SocketAddr, JoinHandle<()>)
+where
+    F: Fn(Request) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Response> + Send + 'static,
+{
+    let respond = std::sync::Arc::new(respond);
+    spawn_connection_server(move |mut socket| {
+        let respond = std::sync::Arc::clone(&respond);
+        async move {
+            let Some(request) = read_request(&mut socket).await else {
+                return;
+            };
+            let response = respond(request).await;
+            let _ = socket.write_all(&response.into_bytes()).await;
+        }
+    })
+    .await
+}
+
+/// Spawns a server that answers every request with `status`, the raw
+/// header lines in `headers`, and `body`.
+pub async fn spawn_status_server(
+    status: &'static str,
+    headers: &'static str,
+    body: impl Into<Vec<u8>>,
+) -> (SocketAddr, JoinHandle<()>) {
+    let body = body.into();
+    spawn_server(move |_| {
+        let response = Response::new(status).headers(headers).body(body.clone());
+        async move { response }
+    })
+    .await
+}
+
+/// Spawns a server that echoes each request, head and body, as the
+/// response body, marked with an `X-Echo: yes` header.
+pub async fn spawn_echo_server() -> (SocketAddr, JoinHandle<()>) {
+    spawn_server(|request| async move { Response::ok(request.to_bytes()).header("X-Echo", "yes") })
+        .await
+}
+
+/// Spawns a server that accepts connections and never answers them.
+pub async fn spawn_silent_server() -> (SocketAddr, JoinHandle<()>) {
+    spawn_connection_server(|socket| async move {
+        std::future::pending::<()>().await;
+        drop(socket);
+    })
+    .await
+}
+
+/// Reads one request: its head, then the body its `Content-Length`
+/// announces. Returns `None` when the connection closes first.
+async fn read_request(socket: &mut TcpStream) -> Option<Request> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        match socket.read(&mut chunk).await {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    };
+    let head = String::from_utf8_lossy(&buf[..head_end]).into_owned();
+    let mut request = Request {
+        head,
+        body: Vec::new(),
+    };
+    let length = request
+        .header("Content-Length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    while buf.len() < head_end + length {
+        match socket.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    }
+    request.body = buf.split_off(head_end);
+    Some(request)
+}
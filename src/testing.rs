@@ -0,0 +1,328 @@
+//! Record-and-replay fetchers for offline engine regression tests.
+//!
+//! Available with the `test-utils` feature. A [`RecordingFetcher`] wraps a
+//! real fetcher and saves every response into a fixture directory; a
+//! [`ReplayFetcher`] serves those saved responses without network access.
+//! [`replay_search`] ties both together so an engine test can be re-recorded
+//! by setting the `A3S_SEARCH_RECORD` environment variable:
+//!
+//! ```rust,no_run
+//! use a3s_search::engines::DuckDuckGo;
+//! use a3s_search::testing::replay_search;
+//! use a3s_search::SearchQuery;
+//!
+//! # async fn run() -> a3s_search::Result<()> {
+//! let results = replay_search(
+//!     "tests/fixtures/replay/duckduckgo",
+//!     &SearchQuery::new("rust programming"),
+//!     DuckDuckGo::with_fetcher,
+//! )
+//! .await?;
+//! assert!(!results.is_empty());
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A fixture directory holds one body file per response plus an
+//! `index.json` mapping request keys to file names. The key of a plain GET
+//! is its URL; other requests are keyed by method, URL, and body.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::fetcher::{FetchBody, FetchRequest, FetchResponse, PageFetcher};
+use crate::query::fnv1a;
+use crate::{Engine, HttpFetcher, Result, SearchError, SearchQuery, SearchResult};
+
+/// Environment variable that switches [`replay_search`] to recording mode.
+pub const RECORD_ENV: &str = "A3S_SEARCH_RECORD";
+
+/// Name of the index file inside a fixture directory.
+const INDEX_FILE: &str = "index.json";
+
+/// Returns the fixture key for a request.
+fn request_key(request: &FetchRequest) -> String {
+    if request.is_plain_get() {
+        return request.url.clone();
+    }
+    let body = match request.body {
+        Some(FetchBody::Form(ref fields)) => url::form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(fields)
+            .finish(),
+        Some(FetchBody::Raw(ref raw)) => raw.clone(),
+        None => String::new(),
+    };
+    format!("{} {} {}", request.method.as_str(), request.url, body)
+        .trim_end()
+        .to_string()
+}
+
+/// Returns a stable, readable file name for a fixture key.
+fn fixture_file_name(key: &str) -> String {
+    let hash = fnv1a(key.as_bytes());
+    let host = url::Url::parse(key.split(' ').find(|p| p.contains("://")).unwrap_or(key))
+        .ok()
+        .and_then(|u| u.host_str().map(|h| h.replace('.', "_")))
+        .unwrap_or_else(|| "response".to_string());
+    format!("{}-{:016x}.html", host, hash)
+}
+
+fn read_index(dir: &Path) -> Result<BTreeMap<String, String>> {
+    let path = dir.join(INDEX_FILE);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let data = fs::read_to_string(&path)
+        .map_err(|e| SearchError::Other(format!("Failed to read {}: {}", path.display(), e)))?;
+    serde_json::from_str(&data)
+        .map_err(|e| SearchError::Parse(format!("Invalid {}: {}", path.display(), e)))
+}
+
+/// A page fetcher that records the responses of another fetcher to disk.
+pub struct RecordingFetcher {
+    inner: Arc<dyn PageFetcher>,
+    dir: PathBuf,
+    index: Mutex<BTreeMap<String, String>>,
+}
+
+impl RecordingFetcher {
+    /// Creates a recording fetcher writing into `dir`, which is created if
+    /// missing. Existing recordings in `dir` are kept and overwritten per key.
+    pub fn new(inner: Arc<dyn PageFetcher>, dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| {
+            SearchError::Other(format!("Failed to create {}: {}", dir.display(), e))
+        })?;
+        let index = read_index(&dir)?;
+        Ok(Self {
+            inner,
+            dir,
+            index: Mutex::new(index),
+        })
+    }
+
+    fn record(&self, key: String, body: &str) -> Result<()> {
+        let file = fixture_file_name(&key);
+        let io_err = |e: std::io::Error| SearchError::Other(format!("Failed to record: {}", e));
+
+        fs::write(self.dir.join(&file), body).map_err(io_err)?;
+
+        let mut index = self.index.lock().unwrap_or_else(|e| e.into_inner());
+        index.insert(key, file);
+        let json = serde_json::to_string_pretty(&*index)
+            .map_err(|e| SearchError::Other(format!("Failed to encode index: {}", e)))?;
+        fs::write(self.dir.join(INDEX_FILE), json + "\n").map_err(io_err)
+    }
+}
+
+#[async_trait]
+impl PageFetcher for RecordingFetcher {
+    async fn fetch(&self, url: &str) -> Result<String> {
+        let body = self.inner.fetch(url).await?;
+        self.record(url.to_string(), &body)?;
+        Ok(body)
+    }
+
+    async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        let response = self.inner.fetch_request(request).await?;
+        self.record(request_key(request), &response.body)?;
+        Ok(response)
+    }
+}
+
+/// A page fetcher that serves responses recorded by [`RecordingFetcher`].
+///
+/// Requests without a recording fail instead of reaching the network.
+pub struct ReplayFetcher {
+    dir: PathBuf,
+    bodies: BTreeMap<String, String>,
+}
+
+impl ReplayFetcher {
+    /// Loads all recordings from `dir`.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        let index = read_index(&dir)?;
+        if index.is_empty() {
+            return Err(SearchError::Other(format!(
+                "No recordings in {}; record them with {}=1",
+                dir.display(),
+                RECORD_ENV
+            )));
+        }
+
+        let mut bodies = BTreeMap::new();
+        for (key, file) in index {
+            let path = dir.join(&file);
+            let body = fs::read_to_string(&path).map_err(|e| {
+                SearchError::Other(format!("Failed to read {}: {}", path.display(), e))
+            })?;
+            bodies.insert(key, body);
+        }
+        Ok(Self { dir, bodies })
+    }
+
+    fn replay(&self, key: &str) -> Result<String> {
+        self.bodies.get(key).cloned().ok_or_else(|| {
+            SearchError::Other(format!(
+                "No recorded response for '{}' in {}",
+                key,
+                self.dir.display()
+            ))
+        })
+    }
+}
+
+#[async_trait]
+impl PageFetcher for ReplayFetcher {
+    async fn fetch(&self, url: &str) -> Result<String> {
+        self.replay(url)
+    }
+
+    async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+        let body = self.replay(&request_key(request))?;
+        Ok(FetchResponse {
+            status: 200,
+            final_url: request.url.clone(),
+            headers: Vec::new(),
+            body,
+        })
+    }
+}
+
+/// Runs `query` against an engine whose fetcher replays recordings from
+/// `fixture_dir`.
+///
+/// With the `A3S_SEARCH_RECORD` environment variable set, the engine uses a
+/// live [`HttpFetcher`] instead and its responses are recorded into
+/// `fixture_dir`, refreshing the fixtures.
+pub async fn replay_search<E, F>(
+    fixture_dir: impl AsRef<Path>,
+    query: &SearchQuery,
+    make_engine: F,
+) -> Result<Vec<SearchResult>>
+where
+    E: Engine,
+    F: FnOnce(Arc<dyn PageFetcher>) -> E,
+{
+    let dir = fixture_dir.as_ref();
+    let fetcher: Arc<dyn PageFetcher> = if std::env::var_os(RECORD_ENV).is_some() {
+        Arc::new(RecordingFetcher::new(Arc::new(HttpFetcher::new()), dir)?)
+    } else {
+        Arc::new(ReplayFetcher::open(dir)?)
+    };
+    make_engine(fetcher).search(query).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticFetcher;
+
+    #[async_trait]
+    impl PageFetcher for StaticFetcher {
+        async fn fetch(&self, url: &str) -> Result<String> {
+            Ok(format!("<html>{}</html>", url))
+        }
+
+        async fn fetch_request(&self, request: &FetchRequest) -> Result<FetchResponse> {
+            Ok(FetchResponse {
+                status: 200,
+                final_url: request.url.clone(),
+                headers: Vec::new(),
+                body: format!("<html>{}</html>", request_key(request)),
+            })
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("a3s-search-replay-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_request_key() {
+        assert_eq!(
+            request_key(&FetchRequest::get("https://a.example/?q=x")),
+            "https://a.example/?q=x"
+        );
+        assert_eq!(
+            request_key(&FetchRequest::post_form(
+                "https://a.example/html/",
+                [("q", "rust lang")]
+            )),
+            "POST https://a.example/html/ q=rust+lang"
+        );
+    }
+
+    #[test]
+    fn test_fixture_file_name_is_stable() {
+        let name = fixture_file_name("https://www.sogou.com/web?query=rust");
+        assert!(name.starts_with("www_sogou_com-"), "{}", name);
+        assert!(name.ends_with(".html"));
+        assert_eq!(
+            name,
+            fixture_file_name("https://www.sogou.com/web?query=rust")
+        );
+        assert_ne!(
+            name,
+            fixture_file_name("https://www.sogou.com/web?query=go")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_record_then_replay() {
+        let dir = temp_dir("roundtrip");
+        let recorder = RecordingFetcher::new(Arc::new(StaticFetcher), &dir).unwrap();
+        recorder.fetch("https://a.example/").await.unwrap();
+        recorder
+            .fetch_request(&FetchRequest::post_form("https://b.example/", [("q", "x")]))
+            .await
+            .unwrap();
+        assert!(dir.join(INDEX_FILE).exists());
+
+        let replay = ReplayFetcher::open(&dir).unwrap();
+        assert_eq!(
+            replay.fetch("https://a.example/").await.unwrap(),
+            "<html>https://a.example/</html>"
+        );
+        let response = replay
+            .fetch_request(&FetchRequest::post_form("https://b.example/", [("q", "x")]))
+            .await
+            .unwrap();
+        assert_eq!(response.body, "<html>POST https://b.example/ q=x</html>");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_replay_unknown_url_errors() {
+        let dir = temp_dir("unknown");
+        let recorder = RecordingFetcher::new(Arc::new(StaticFetcher), &dir).unwrap();
+        recorder.fetch("https://a.example/").await.unwrap();
+
+        let replay = ReplayFetcher::open(&dir).unwrap();
+        let err = replay
+            .fetch("https://other.example/")
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("No recorded response"), "{}", err);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_replay_open_empty_dir_errors() {
+        let dir = temp_dir("empty");
+        let err = ReplayFetcher::open(&dir).err().unwrap().to_string();
+        assert!(err.contains(RECORD_ENV), "{}", err);
+    }
+}
@@ -0,0 +1,98 @@
+//! In-process HTTP server serving saved fixtures, so engine tests can run
+//! offline by pointing engines at [`FixtureServer::base_url`].
+
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+use a3s_search::test_server::{spawn_server, Response};
+use tokio::task::JoinHandle;
+
+/// A request received by a [`FixtureServer`].
+#[derive(Debug, Clone)]
+pub struct Received {
+    /// Request method, e.g. `GET`.
+    pub method: String,
+    /// Path and query string, e.g. `/search?q=rust`.
+    pub target: String,
+    /// Request body, decoded lossily.
+    pub body: String,
+}
+
+/// Serves fixture files at fixed paths and records every request.
+///
+/// Query strings are ignored when matching paths; unknown paths get a 404.
+/// The server stops when dropped.
+pub struct FixtureServer {
+    addr: SocketAddr,
+    received: Arc<Mutex<Vec<Received>>>,
+    handle: JoinHandle<()>,
+}
+
+impl FixtureServer {
+    /// Starts a server answering each `(path, fixture)` pair with the file
+    /// `tests/fixtures/<fixture>`.
+    pub async fn start(routes: &[(&str, &str)]) -> Self {
+        let routes: Arc<Vec<(String, &'static str, Vec<u8>)>> = Arc::new(
+            routes
+                .iter()
+                .map(|(path, fixture)| {
+                    let file = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+                    let body = std::fs::read(&file).unwrap_or_else(|e| panic!("{}: {}", file, e));
+                    (path.to_string(), content_type(fixture), body)
+                })
+                .collect(),
+        );
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = Arc::clone(&received);
+        let (addr, handle) = spawn_server(move |request| {
+            let path = request.path().split('?').next().unwrap_or_default();
+            let response = match routes.iter().find(|(p, _, _)| p == path) {
+                Some((_, content_type, body)) => {
+                    Response::ok(body.clone()).header("Content-Type", content_type)
+                }
+                None => Response::new("404 Not Found")
+                    .header("Content-Type", "text/plain")
+                    .body("not found"),
+            };
+            log.lock().unwrap().push(Received {
+                method: request.method().to_string(),
+                target: request.path().to_string(),
+                body: String::from_utf8_lossy(&request.body).into_owned(),
+            });
+            async move { response }
+        })
+        .await;
+
+        Self {
+            addr,
+            received,
+            handle,
+        }
+    }
+
+    /// Returns the server's origin, e.g. `http://127.0.0.1:41234`.
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Returns the requests received so far.
+    pub fn received(&self) -> Vec<Received> {
+        self.received.lock().unwrap().clone()
+    }
+}
+
+impl Drop for FixtureServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Content type of a fixture file, from its extension.
+fn content_type(fixture: &str) -> &'static str {
+    if fixture.ends_with(".json") {
+        "application/json"
+    } else {
+        "text/html; charset=utf-8"
+    }
+}
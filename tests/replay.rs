@@ -0,0 +1,54 @@
+//! Offline regression tests that replay recorded engine responses.
+//!
+//! Fixtures live in `tests/fixtures/replay/<engine>`. Refresh them against
+//! the live engines with:
+//! `A3S_SEARCH_RECORD=1 cargo test -p a3s-search --test replay`
+
+use a3s_search::engines::{DuckDuckGo, Sogou};
+use a3s_search::testing::replay_search;
+use a3s_search::SearchQuery;
+
+fn fixture_dir(engine: &str) -> String {
+    format!(
+        "{}/tests/fixtures/replay/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        engine
+    )
+}
+
+#[tokio::test]
+async fn test_replay_duckduckgo() {
+    let query = SearchQuery::new("rust programming");
+    let results = replay_search(fixture_dir("duckduckgo"), &query, DuckDuckGo::with_fetcher)
+        .await
+        .unwrap();
+
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| !r.title.is_empty()));
+    assert!(results.iter().all(|r| r.url.starts_with("http")));
+    // Redirect links are unwrapped to their destination
+    assert!(results.iter().all(|r| !r.url.contains("duckduckgo.com/l/")));
+}
+
+#[tokio::test]
+async fn test_replay_sogou() {
+    let query = SearchQuery::new("rust programming");
+    let results = replay_search(fixture_dir("sogou"), &query, Sogou::with_fetcher)
+        .await
+        .unwrap();
+
+    assert!(!results.is_empty());
+    assert!(results.iter().all(|r| !r.title.is_empty()));
+    assert!(results.iter().all(|r| r.url.starts_with("https://")));
+}
+
+#[tokio::test]
+async fn test_replay_unrecorded_query_fails() {
+    if std::env::var_os(a3s_search::testing::RECORD_ENV).is_some() {
+        return;
+    }
+    let query = SearchQuery::new("never recorded");
+    let result = replay_search(fixture_dir("sogou"), &query, Sogou::with_fetcher).await;
+
+    assert!(result.is_err());
+}